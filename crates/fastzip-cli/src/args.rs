@@ -1,6 +1,7 @@
 //! CLI 参数解析
 
 use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use clap::{Parser, Subcommand};
 
@@ -18,10 +19,284 @@ pub enum Command {
     Extract(ExtractArgs),
     /// 解压压缩文件（extract 的简写）
     X(ExtractArgs),
-    /// 压缩为 ZIP 或 7z
+    /// 压缩为 ZIP、7z、tar.* 或单文件 gz/xz/bz2/zst
     Compress(CompressArgs),
     /// 压缩（compress 的简写）
     C(CompressArgs),
+    /// 为 tar.* 建立随机访问索引（<归档>.fzidx），可列出条目或读取单个条目
+    Index(IndexArgs),
+    /// 向已有 ZIP 加入新文件并替换有变化的文件（归档不存在时新建）
+    Add(UpdateArgs),
+    /// 只替换 ZIP 中已有且有变化的文件，不加入新文件
+    Update(UpdateArgs),
+    /// 删除 ZIP / tar.* 中匹配的条目
+    Delete(DeleteArgs),
+    /// 重命名 ZIP / tar.* 中的条目或目录
+    Rename(RenameArgs),
+    /// 转换归档格式（ZIP、7z、tar.* 之间，RAR 需启用 full），逐条目流式转换
+    Convert(ConvertArgs),
+    /// 查看归档注释与条目注释（暂不读取 7z 注释），或修改 ZIP 归档注释（不重新压缩）
+    Comment(CommentArgs),
+    /// 比较两个归档，或归档与目录：新增、删除、内容修改与仅元数据变化
+    Diff(DiffArgs),
+    /// 计算归档或目录中每个文件的 SHA-256 / BLAKE3，输出 sha256sum 兼容的清单
+    Hash(HashArgs),
+    /// 用清单校验归档或解压出的目录
+    Verify(VerifyArgs),
+    /// 在归档条目中搜索正则（类似 zgrep），不解压到磁盘
+    Grep(GrepArgs),
+    /// 把 ZIP、7z 或 tar.* 只读挂载到目录（Linux FUSE），前台运行直到卸载或 Ctrl+C
+    ///
+    /// 请求逐个处理：打开 7z 条目（及加密或非 Deflate 的 ZIP 条目）时先整体解压，期间挂载点上的其他访问都要等待；大型固实 7z 归档建议先解压再浏览。
+    Mount(MountArgs),
+}
+
+#[derive(Parser, Debug)]
+pub struct MountArgs {
+    /// 归档路径
+    pub archive: PathBuf,
+
+    /// 挂载点（已存在的目录）
+    pub mountpoint: PathBuf,
+
+    /// 加密归档的密码（也可通过 FASTZIP_PASSWORD 环境变量设置）
+    #[arg(short, long)]
+    pub password: Option<String>,
+
+    /// 允许其他用户访问挂载点
+    #[arg(long)]
+    pub allow_other: bool,
+
+    /// 静默模式
+    #[arg(short, long)]
+    pub quiet: bool,
+}
+
+#[derive(Parser, Debug)]
+pub struct GrepArgs {
+    /// 正则表达式（-F 时为普通字符串）
+    pub pattern: String,
+
+    /// 归档、单文件压缩或普通文件（可指定多个）
+    #[arg(required = true)]
+    pub archives: Vec<PathBuf>,
+
+    /// 忽略大小写
+    #[arg(short, long)]
+    pub ignore_case: bool,
+
+    /// 把模式当作普通字符串
+    #[arg(short = 'F', long)]
+    pub fixed_strings: bool,
+
+    /// 只匹配完整单词
+    #[arg(short, long)]
+    pub word_regexp: bool,
+
+    /// 只列出有匹配的条目
+    #[arg(short = 'l', long, conflicts_with = "count")]
+    pub files_with_matches: bool,
+
+    /// 只输出每个条目的匹配行数（含没有匹配的条目）
+    #[arg(short, long)]
+    pub count: bool,
+
+    /// 二进制条目也按文本搜索
+    #[arg(short = 'a', long)]
+    pub text: bool,
+
+    /// 始终在输出前加归档路径（多个归档时默认加）
+    #[arg(short = 'H', long)]
+    pub with_filename: bool,
+
+    /// 只搜索匹配的条目（glob，可多次指定）
+    #[arg(long, value_name = "GLOB")]
+    pub include: Vec<String>,
+
+    /// 跳过匹配的条目（glob，可多次指定）
+    #[arg(long, value_name = "GLOB")]
+    pub exclude: Vec<String>,
+
+    /// 加密归档的密码（也可通过 FASTZIP_PASSWORD 环境变量设置）
+    #[arg(short, long)]
+    pub password: Option<String>,
+
+    /// 不输出，只以退出状态表示是否有匹配；找到第一处匹配即停止
+    #[arg(short, long)]
+    pub quiet: bool,
+}
+
+#[derive(Parser, Debug)]
+pub struct HashArgs {
+    /// 归档或目录
+    pub target: PathBuf,
+
+    /// 哈希算法：sha256（默认）、blake3
+    #[arg(short, long, default_value = "sha256", value_name = "ALGO")]
+    pub algo: String,
+
+    /// 把清单写入 FILE（默认输出到标准输出）
+    #[arg(short, long, value_name = "FILE")]
+    pub output: Option<PathBuf>,
+
+    /// 加密归档的密码（也可通过 FASTZIP_PASSWORD 环境变量设置）
+    #[arg(short, long)]
+    pub password: Option<String>,
+}
+
+#[derive(Parser, Debug)]
+pub struct VerifyArgs {
+    /// 归档或目录
+    pub target: PathBuf,
+
+    /// sha256sum 格式的清单
+    #[arg(short, long, value_name = "FILE")]
+    pub manifest: PathBuf,
+
+    /// 哈希算法：sha256（默认）、blake3
+    #[arg(short, long, default_value = "sha256", value_name = "ALGO")]
+    pub algo: String,
+
+    /// 加密归档的密码（也可通过 FASTZIP_PASSWORD 环境变量设置）
+    #[arg(short, long)]
+    pub password: Option<String>,
+
+    /// 只输出失败的条目与汇总
+    #[arg(short, long)]
+    pub quiet: bool,
+}
+
+#[derive(Parser, Debug)]
+pub struct DiffArgs {
+    /// 旧的一侧：归档或目录
+    pub old: PathBuf,
+
+    /// 新的一侧：归档或目录
+    pub new: PathBuf,
+
+    /// 加密归档的密码，两侧共用（也可通过 FASTZIP_PASSWORD 环境变量设置）
+    #[arg(short, long)]
+    pub password: Option<String>,
+
+    /// 以 JSON 输出
+    #[arg(long)]
+    pub json: bool,
+}
+
+#[derive(Parser, Debug)]
+pub struct CommentArgs {
+    /// 归档路径
+    pub archive: PathBuf,
+
+    /// 把 ZIP 归档注释设为 TEXT
+    #[arg(long, value_name = "TEXT", conflicts_with_all = ["file", "clear"])]
+    pub set: Option<String>,
+
+    /// 把 ZIP 归档注释设为文件内容（UTF-8）
+    #[arg(long, value_name = "FILE", conflicts_with = "clear")]
+    pub file: Option<PathBuf>,
+
+    /// 清除 ZIP 归档注释
+    #[arg(long)]
+    pub clear: bool,
+
+    /// 静默模式
+    #[arg(short, long)]
+    pub quiet: bool,
+}
+
+#[derive(Parser, Debug)]
+pub struct ConvertArgs {
+    /// 源归档路径
+    pub source: PathBuf,
+
+    /// 输出路径，按扩展名选择格式：.zip、.7z、.tar、.tar.gz、.tar.zst 等
+    #[arg(short, long, value_name = "FILE")]
+    pub output: PathBuf,
+
+    /// 源归档密码（也可通过 FASTZIP_PASSWORD 环境变量设置）
+    #[arg(short, long)]
+    pub password: Option<String>,
+
+    /// 输出归档的加密密码（ZIP 默认 AES-256，7z AES-256）
+    #[arg(long, value_name = "PASSWORD")]
+    pub new_password: Option<String>,
+
+    /// 输出使用传统 ZipCrypto 加密
+    #[arg(long, requires = "new_password")]
+    pub zip_crypto: bool,
+
+    /// 7z：同时加密文件名（需设置 --new-password）
+    #[arg(long, requires = "new_password")]
+    pub encrypt_header: bool,
+
+    /// 压缩预设：store、fastest、normal、max、ultra
+    #[arg(long, value_name = "PRESET")]
+    pub preset: Option<String>,
+
+    /// ZIP 压缩方法：stored、deflate、deflate64、bzip2、zstd、xz、lzma
+    #[arg(short, long, value_name = "METHOD")]
+    pub method: Option<String>,
+
+    /// 压缩级别，覆盖预设
+    #[arg(short, long, value_name = "N")]
+    pub level: Option<u32>,
+
+    /// 分卷大小，如 100m、2g（规则同 compress --volume-size）
+    #[arg(long, value_name = "SIZE", value_parser = parse_size)]
+    pub volume_size: Option<u64>,
+
+    /// 只转换匹配的条目（可多次指定）
+    #[arg(long, value_name = "GLOB")]
+    pub include: Vec<String>,
+
+    /// 排除匹配的条目或目录（可多次指定）
+    #[arg(short = 'x', long, value_name = "GLOB")]
+    pub exclude: Vec<String>,
+
+    /// 静默模式
+    #[arg(short, long)]
+    pub quiet: bool,
+}
+
+#[derive(Parser, Debug)]
+pub struct DeleteArgs {
+    /// ZIP 或 tar.* 归档路径
+    pub archive: PathBuf,
+
+    /// 条目路径或 glob（可多个）；含 / 的模式匹配完整路径，否则匹配任意层级的名称，目录连同内容删除
+    #[arg(required = true, value_name = "PATTERN")]
+    pub patterns: Vec<String>,
+
+    /// 静默模式
+    #[arg(short, long)]
+    pub quiet: bool,
+}
+
+#[derive(Parser, Debug)]
+pub struct RenameArgs {
+    /// ZIP 或 tar.* 归档路径
+    pub archive: PathBuf,
+
+    /// 原条目路径（目录时其下条目一并移动）
+    pub from: String,
+
+    /// 新条目路径
+    pub to: String,
+
+    /// 静默模式
+    #[arg(short, long)]
+    pub quiet: bool,
+}
+
+#[derive(Parser, Debug)]
+pub struct UpdateArgs {
+    /// ZIP 归档路径
+    pub archive: PathBuf,
+
+    /// 压缩参数（新条目的压缩方法、密码、筛选条件等），不支持 -o 与 --format
+    #[command(flatten)]
+    pub compress: CompressArgs,
 }
 
 #[derive(Parser, Debug)]
@@ -30,7 +305,8 @@ pub struct CompressArgs {
     #[arg(required = true)]
     pub sources: Vec<String>,
 
-    /// 输出压缩包路径（.zip 或 .7z）
+    /// 输出路径，按扩展名选择格式：.zip、.7z、.tar、.tar.gz、.tar.zst 等；
+    /// 单个文件也可输出为 .gz、.xz、.bz2、.zst
     #[arg(short, long, value_name = "FILE")]
     pub output: Option<PathBuf>,
 
@@ -38,15 +314,141 @@ pub struct CompressArgs {
     #[arg(short, long, default_value_t = true)]
     pub recursive: bool,
 
-    /// 启用 Deflate 压缩（较慢，体积更小；默认仅存储不压缩）
-    #[arg(long)]
+    /// 启用 Deflate 压缩（较慢，体积更小；默认仅存储不压缩），等同 --preset normal
+    #[arg(long, conflicts_with = "preset")]
     pub no_fast: bool,
 
+    /// 压缩预设：store、fastest、normal、max、ultra
+    #[arg(long, value_name = "PRESET")]
+    pub preset: Option<String>,
+
+    /// ZIP 压缩方法：stored、deflate、deflate64、bzip2、zstd、xz、lzma
+    #[arg(short, long, value_name = "METHOD")]
+    pub method: Option<String>,
+
+    /// 批量原地压缩：每个文件压缩为 <文件>.<格式>（gz、xz、bz2、zst），并行执行
+    #[arg(long, value_name = "FORMAT", conflicts_with = "output")]
+    pub format: Option<String>,
+
+    /// 压缩级别，覆盖预设（Deflate/gzip/xz/7z 0-9，bzip2 1-9，zstd 1-22）
+    #[arg(short, long, value_name = "N")]
+    pub level: Option<u32>,
+
+    /// 单文件压缩成功后删除原文件
+    #[arg(long)]
+    pub delete_original: bool,
+
+    /// 单文件压缩时覆盖已存在的 .gz/.xz/.bz2/.zst 输出
+    #[arg(long)]
+    pub overwrite: bool,
+
+    /// ZIP 加密密码（也可通过 FASTZIP_PASSWORD 环境变量设置），默认 AES-256
+    #[arg(short, long)]
+    pub password: Option<String>,
+
+    /// 使用传统 ZipCrypto 加密（强度很弱，仅用于兼容旧解压工具）
+    #[arg(long)]
+    pub zip_crypto: bool,
+
+    /// 7z：同时加密文件名（需设置密码）
+    #[arg(long)]
+    pub encrypt_header: bool,
+
+    /// 7z：关闭固实压缩（每个文件单独压缩）
+    #[arg(long)]
+    pub no_solid: bool,
+
+    /// 7z：固实块大小上限，如 64m、1g
+    #[arg(long, value_name = "SIZE", value_parser = parse_size, conflicts_with = "no_solid")]
+    pub solid_block_size: Option<u64>,
+
+    /// 7z：LZMA2 字典大小，如 16m、64m
+    #[arg(long, value_name = "SIZE", value_parser = parse_size)]
+    pub dict_size: Option<u64>,
+
+    /// 只包含匹配的文件（可多次指定）；含 / 的模式匹配路径，否则匹配文件名，如 '*.rs'、'src/**'
+    #[arg(long, value_name = "GLOB")]
+    pub include: Vec<String>,
+
+    /// 排除匹配的文件或目录（可多次指定），如 target、'*.log'
+    #[arg(short = 'x', long, value_name = "GLOB")]
+    pub exclude: Vec<String>,
+
+    /// 只包含不小于该大小的文件，如 1k
+    #[arg(long, value_name = "SIZE", value_parser = parse_size)]
+    pub min_size: Option<u64>,
+
+    /// 只包含不大于该大小的文件，如 100m
+    #[arg(long, value_name = "SIZE", value_parser = parse_size)]
+    pub max_size: Option<u64>,
+
+    /// 只包含此后修改的文件：日期 2024-01-31、时间戳 @1706659200 或相对时长 7d、12h
+    #[arg(long, value_name = "TIME", value_parser = parse_time)]
+    pub newer_than: Option<SystemTime>,
+
+    /// 只包含此前修改的文件（格式同 --newer-than）
+    #[arg(long, value_name = "TIME", value_parser = parse_time)]
+    pub older_than: Option<SystemTime>,
+
+    /// 遵循 .gitignore / .ignore 并跳过 .git 目录
+    #[arg(long)]
+    pub gitignore: bool,
+
+    /// ZIP 与批量单文件压缩的并行线程数（0 = 自动，1 = 单线程；输出与线程数无关）
+    #[arg(short = 'j', long, default_value_t = 0, value_name = "N")]
+    pub threads: usize,
+
+    /// ZIP 条目不记录扩展时间戳（默认记录秒级精度的 UTC 修改时间；否则只有本地时间的 DOS 时间）
+    #[arg(long)]
+    pub no_extended_time: bool,
+
+    /// 符号链接处理：store（存储链接本身，默认）、follow（跟随，跳过循环链接）、skip
+    #[arg(long, value_name = "POLICY")]
+    pub symlinks: Option<String>,
+
+    /// 可复现输出（ZIP、tar.*）：条目排序、固定时间戳、规范化权限与属主
+    #[arg(long)]
+    pub reproducible: bool,
+
+    /// 可复现模式使用的时间（格式同 --newer-than；默认取 SOURCE_DATE_EPOCH，否则 1980-01-01）
+    #[arg(long, value_name = "TIME", value_parser = parse_time, requires = "reproducible")]
+    pub mtime: Option<SystemTime>,
+
+    /// 分卷大小，如 100m、2g（不小于 64k）：ZIP 输出 .z01… 与 .zip，7z 与 tar.* 输出 .001、.002…
+    #[arg(long, value_name = "SIZE", value_parser = parse_size, conflicts_with = "format")]
+    pub volume_size: Option<u64>,
+
+    /// ZIP 归档注释
+    #[arg(long, value_name = "TEXT", conflicts_with = "format")]
+    pub comment: Option<String>,
+
+    /// 从文件读取 ZIP 归档注释（UTF-8）
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["comment", "format"])]
+    pub comment_file: Option<PathBuf>,
+
     /// 静默模式
     #[arg(short, long)]
     pub quiet: bool,
 }
 
+#[derive(Parser, Debug)]
+pub struct IndexArgs {
+    /// tar / tar.gz / tar.xz / tar.bz2 / tar.zst 路径
+    pub archive: PathBuf,
+
+    /// 列出全部条目（大小与路径）
+    #[arg(short, long)]
+    pub list: bool,
+
+    /// 读取单个条目
+    #[arg(short, long, value_name = "PATH")]
+    pub entry: Option<String>,
+
+    /// 条目输出文件（默认写到标准输出）
+    #[arg(short, long, value_name = "FILE", requires = "entry")]
+    pub output: Option<PathBuf>,
+}
+
 #[derive(Parser, Debug)]
 pub struct ExtractArgs {
     /// 压缩文件路径（可指定多个）
@@ -73,6 +475,18 @@ pub struct ExtractArgs {
     #[arg(short, long)]
     pub password: Option<String>,
 
+    /// gzip/xz/zstd 解码线程数（0 = 自动，1 = 单线程）
+    #[arg(short = 'j', long, default_value_t = 0, value_name = "N")]
+    pub threads: usize,
+
+    /// 解压后把归档中每个文件的哈希写入 sha256sum 兼容的清单（仅限单个归档）
+    #[arg(long, value_name = "FILE")]
+    pub manifest: Option<PathBuf>,
+
+    /// 清单的哈希算法：sha256（默认）、blake3
+    #[arg(long, default_value = "sha256", value_name = "ALGO", requires = "manifest")]
+    pub algo: String,
+
     /// 静默模式，不输出进度和路径
     #[arg(short, long)]
     pub quiet: bool,
@@ -87,3 +501,67 @@ impl ExtractArgs {
         }
     }
 }
+
+/// 解析字节大小：纯数字为字节，支持 k/m/g 后缀（1024 进制，可带 b/ib）
+fn parse_size(s: &str) -> Result<u64, String> {
+    let lower = s.trim().to_lowercase();
+    let digits = lower.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+    let unit = &lower[digits.len()..];
+    let shift = match unit {
+        "" | "b" => 0,
+        "k" | "kb" | "kib" => 10,
+        "m" | "mb" | "mib" => 20,
+        "g" | "gb" | "gib" => 30,
+        _ => return Err(format!("无法识别的大小单位: {}", s)),
+    };
+    let n: u64 = digits.trim().parse().map_err(|_| format!("无效的大小: {}", s))?;
+    n.checked_mul(1 << shift).ok_or_else(|| format!("大小超出范围: {}", s))
+}
+
+/// 解析时间：`YYYY-MM-DD`（UTC 零点）、`@<Unix 秒>`，或相对当前时间的 `<N>s/m/h/d/w`
+fn parse_time(s: &str) -> Result<SystemTime, String> {
+    let s = s.trim();
+    let invalid = || format!("无效的时间: {}（示例：2024-01-31、@1706659200、7d）", s);
+    if let Some(secs) = s.strip_prefix('@') {
+        let secs: u64 = secs.parse().map_err(|_| invalid())?;
+        return Ok(UNIX_EPOCH + Duration::from_secs(secs));
+    }
+    let parts: Vec<&str> = s.split('-').collect();
+    if let [y, m, d] = parts[..] {
+        let (y, m, d): (i64, u32, u32) = (
+            y.parse().map_err(|_| invalid())?,
+            m.parse().map_err(|_| invalid())?,
+            d.parse().map_err(|_| invalid())?,
+        );
+        if !(1..=12).contains(&m) || !(1..=31).contains(&d) {
+            return Err(invalid());
+        }
+        let days = days_from_civil(y, m, d);
+        let secs = u64::try_from(days * 86_400).map_err(|_| invalid())?;
+        return Ok(UNIX_EPOCH + Duration::from_secs(secs));
+    }
+    let unit_at = s.find(|c: char| !c.is_ascii_digit()).ok_or_else(invalid)?;
+    let n: u64 = s[..unit_at].parse().map_err(|_| invalid())?;
+    let unit = match &s[unit_at..] {
+        "s" => 1,
+        "m" => 60,
+        "h" => 3_600,
+        "d" => 86_400,
+        "w" => 7 * 86_400,
+        _ => return Err(invalid()),
+    };
+    SystemTime::now()
+        .checked_sub(Duration::from_secs(n.saturating_mul(unit)))
+        .ok_or_else(invalid)
+}
+
+/// 公历日期距 1970-01-01 的天数
+fn days_from_civil(y: i64, m: u32, d: u32) -> i64 {
+    let y = if m <= 2 { y - 1 } else { y };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let mp = (m as i64 + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + d as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
@@ -1,17 +1,25 @@
 //! FastZip CLI - 跨平台快速解压缩工具
 
+use std::io::Write;
 use std::path::PathBuf;
+use std::process::ExitCode;
 
 use clap::Parser;
 use indicatif::{ProgressBar, ProgressStyle};
 
+use fastzip_core::formats::TarExtractor;
 use fastzip_core::{
-    compress_to_7z, compress_to_zip, extract_many, CompressOptions, ExtractOptions, FastZipError,
+    compress_single, compress_single_many, compress_to_7z, compress_to_tar, compress_to_zip,
+    convert_archive, delete_entries, detect_format, diff_archives, extract_many, extract_with_hashes, grep_archives,
+    hash_entries, list_archive_top_level, mount_archive, rename_entry, set_zip_comment, update_zip, verify_manifest,
+    write_manifest,
+    ArchiveFormat, ChangeKind, Codec, CompressOptions, CompressionPreset, DiffEntryInfo, DiffEntryKind, ExtractOptions,
+    FastZipError, GrepOptions, HashAlgorithm, MountOptions, SymlinkPolicy, UpdateMode, ZipEncryption, ZipMethod,
 };
 
 mod args;
 
-fn main() {
+fn main() -> ExitCode {
     tracing_subscriber::fmt()
         .with_env_filter(
             tracing_subscriber::EnvFilter::try_from_default_env()
@@ -21,19 +29,35 @@ fn main() {
         .try_init()
         .ok();
 
-    if let Err(e) = run() {
-        eprintln!("错误: {}", e);
-        std::process::exit(1);
+    match run() {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!("错误: {}", e);
+            ExitCode::FAILURE
+        }
     }
 }
 
-fn run() -> Result<(), FastZipError> {
+fn run() -> Result<ExitCode, FastZipError> {
     let cli = args::Cli::parse();
 
     match &cli.command {
         args::Command::Extract(a) | args::Command::X(a) => run_extract(a),
         args::Command::Compress(a) | args::Command::C(a) => run_compress(a),
-    }
+        args::Command::Index(a) => run_index(a),
+        args::Command::Add(a) => run_update(a, UpdateMode::Update),
+        args::Command::Update(a) => run_update(a, UpdateMode::Freshen),
+        args::Command::Delete(a) => run_delete(a),
+        args::Command::Rename(a) => run_rename(a),
+        args::Command::Convert(a) => run_convert(a),
+        args::Command::Comment(a) => run_comment(a),
+        args::Command::Diff(a) => run_diff(a),
+        args::Command::Hash(a) => run_hash(a),
+        args::Command::Verify(a) => run_verify(a),
+        args::Command::Grep(a) => return run_grep(a),
+        args::Command::Mount(a) => run_mount(a),
+    }?;
+    Ok(ExitCode::SUCCESS)
 }
 
 
@@ -46,6 +70,7 @@ fn run_extract(extract_args: &args::ExtractArgs) -> Result<(), FastZipError> {
             .password
             .clone()
             .or_else(|| std::env::var("FASTZIP_PASSWORD").ok()),
+        threads: extract_args.threads,
     };
 
     let archives: Vec<PathBuf> = extract_args
@@ -65,6 +90,10 @@ fn run_extract(extract_args: &args::ExtractArgs) -> Result<(), FastZipError> {
     if archives.is_empty() {
         return Err(FastZipError::Other("没有有效的压缩文件".into()));
     }
+    let algorithm = hash_algorithm(&extract_args.algo)?;
+    if extract_args.manifest.is_some() && archives.len() > 1 {
+        return Err(FastZipError::Other("--manifest 只支持单个归档".into()));
+    }
 
     let pb = if !extract_args.quiet && archives.len() > 1 {
         Some(
@@ -78,7 +107,14 @@ fn run_extract(extract_args: &args::ExtractArgs) -> Result<(), FastZipError> {
         None
     };
 
-    let results = extract_many(&archives, &options)?;
+    // --manifest 时在解压写出的同时计算条目哈希，不再读一遍归档
+    let (results, mut hashes) = match &extract_args.manifest {
+        Some(_) => match extract_with_hashes(&archives[0], &options, algorithm) {
+            Ok((dest, hashes)) => (vec![Ok(dest)], Some(hashes)),
+            Err(e) => (vec![Err(e)], None),
+        },
+        None => (extract_many(&archives, &options)?, None),
+    };
 
     for (i, result) in results.into_iter().enumerate() {
         if let Some(ref p) = pb {
@@ -90,6 +126,12 @@ fn run_extract(extract_args: &args::ExtractArgs) -> Result<(), FastZipError> {
                 if !extract_args.quiet {
                     println!("已解压到: {}", dest.display());
                 }
+                if let (Some(manifest), Some(hashes)) = (&extract_args.manifest, hashes.take()) {
+                    write_manifest(&mut std::fs::File::create(manifest)?, &hashes)?;
+                    if !extract_args.quiet {
+                        println!("已写入清单: {}（{} 个文件）", manifest.display(), hashes.len());
+                    }
+                }
             }
             Err(e) => {
                 eprintln!("解压失败 {}: {}", archives[i].display(), e);
@@ -105,10 +147,6 @@ fn run_extract(extract_args: &args::ExtractArgs) -> Result<(), FastZipError> {
 }
 
 fn run_compress(compress_args: &args::CompressArgs) -> Result<(), FastZipError> {
-    let output = compress_args.output.as_ref().ok_or_else(|| {
-        FastZipError::Other("压缩请指定输出文件：-o/--output <文件.zip 或 文件.7z>".into())
-    })?;
-
     let sources: Vec<PathBuf> = compress_args
         .sources
         .iter()
@@ -127,31 +165,44 @@ fn run_compress(compress_args: &args::CompressArgs) -> Result<(), FastZipError>
         return Err(FastZipError::Other("没有有效的源路径".into()));
     }
 
-    let ext = output
-        .extension()
-        .and_then(|e| e.to_str())
-        .unwrap_or("")
-        .to_lowercase();
+    let options = compress_options(compress_args)?;
 
-    let options = CompressOptions {
-        recursive: compress_args.recursive,
-        password: None,
-        fast: !compress_args.no_fast,
-    };
+    if let Some(name) = &compress_args.format {
+        let codec = Codec::from_name(name).ok_or_else(|| {
+            FastZipError::Other(format!("不支持的单文件压缩格式: {}（可选 gz、xz、bz2、zst）", name))
+        })?;
+        return run_compress_in_place(compress_args, &sources, codec, &options);
+    }
 
-    if ext == "7z" {
-        if sources.len() > 1 {
+    let output = compress_args.output.as_ref().ok_or_else(|| {
+        FastZipError::Other(
+            "压缩请指定输出文件 -o/--output <文件.zip 等>，或使用 --format 原地压缩每个文件".into(),
+        )
+    })?;
+
+    match ArchiveFormat::from_extension(output) {
+        Some(ArchiveFormat::SevenZ) => compress_to_7z(&sources, output, &options)?,
+        Some(ArchiveFormat::Zip) => compress_to_zip(&sources, output, &options)?,
+        Some(format) if format.is_tar() => {
+            compress_to_tar(&sources, output, format.codec(), &options)?;
+        }
+        Some(format) if format.is_single_compressed() => {
+            if options.volume_size.is_some() {
+                return Err(FastZipError::Other("单文件压缩不支持 --volume-size".into()));
+            }
+            if sources.len() > 1 || !sources[0].is_file() {
+                return Err(FastZipError::Other(
+                    "单文件压缩仅支持一个文件；批量压缩请使用 --format".into(),
+                ));
+            }
+            let codec = format.codec().expect("单文件压缩格式均有编码");
+            compress_single(&sources[0], output, codec, &options)?;
+        }
+        _ => {
             return Err(FastZipError::Other(
-                "7z 格式仅支持单一路径，请指定一个目录或文件".into(),
+                "仅支持 .zip、.7z、.tar、.tar.gz/.tgz、.tar.xz/.txz、.tar.bz2/.tbz2、.tar.zst/.tzst 及 .gz/.xz/.bz2/.zst 输出，请使用 -o 指定扩展名".into(),
             ));
         }
-        compress_to_7z(&sources[0], output)?;
-    } else if ext == "zip" {
-        compress_to_zip(&sources, output, &options)?;
-    } else {
-        return Err(FastZipError::Other(
-            "仅支持 .zip 或 .7z 输出，请使用 -o 指定扩展名".into(),
-        ));
     }
 
     if !compress_args.quiet {
@@ -159,3 +210,511 @@ fn run_compress(compress_args: &args::CompressArgs) -> Result<(), FastZipError>
     }
     Ok(())
 }
+
+fn run_update(update_args: &args::UpdateArgs, mode: UpdateMode) -> Result<(), FastZipError> {
+    let compress_args = &update_args.compress;
+    if compress_args.output.is_some() || compress_args.format.is_some() {
+        return Err(FastZipError::Other("add/update 直接修改归档，不支持 -o/--output 与 --format".into()));
+    }
+    if compress_args.volume_size.is_some() {
+        return Err(FastZipError::Other("add/update 不支持 --volume-size".into()));
+    }
+    if ArchiveFormat::from_extension(&update_args.archive) != Some(ArchiveFormat::Zip) {
+        return Err(FastZipError::UnsupportedFormat("add/update 仅支持 .zip 归档".into()));
+    }
+    let sources: Vec<PathBuf> = compress_args.sources.iter().map(PathBuf::from).collect();
+    let options = compress_options(compress_args)?;
+    let summary = update_zip(&update_args.archive, &sources, mode, &options)?;
+
+    if !compress_args.quiet {
+        for name in &summary.added {
+            println!("  加入: {}", name);
+        }
+        for name in &summary.replaced {
+            println!("  替换: {}", name);
+        }
+        println!(
+            "已更新 {}: 加入 {} 个，替换 {} 个，未变化 {} 个",
+            update_args.archive.display(),
+            summary.added.len(),
+            summary.replaced.len(),
+            summary.unchanged
+        );
+    }
+    Ok(())
+}
+
+fn run_delete(delete_args: &args::DeleteArgs) -> Result<(), FastZipError> {
+    let deleted = delete_entries(&delete_args.archive, &delete_args.patterns)?;
+    if !delete_args.quiet {
+        for name in &deleted {
+            println!("  删除: {}", name);
+        }
+        println!("已从 {} 删除 {} 个条目", delete_args.archive.display(), deleted.len());
+    }
+    Ok(())
+}
+
+fn run_rename(rename_args: &args::RenameArgs) -> Result<(), FastZipError> {
+    let renamed = rename_entry(&rename_args.archive, &rename_args.from, &rename_args.to)?;
+    if !rename_args.quiet {
+        for (old, new) in &renamed {
+            println!("  {} -> {}", old, new);
+        }
+        println!("已重命名 {} 个条目", renamed.len());
+    }
+    Ok(())
+}
+
+fn run_convert(convert_args: &args::ConvertArgs) -> Result<(), FastZipError> {
+    let mut options = base_options(
+        convert_args.preset.as_deref(),
+        false,
+        convert_args.method.as_deref(),
+    )?;
+    options.level = convert_args.level;
+    options.password = convert_args.new_password.clone();
+    if convert_args.zip_crypto {
+        options.encryption = ZipEncryption::ZipCrypto;
+    }
+    options.encrypt_header = convert_args.encrypt_header;
+    options.include = convert_args.include.clone();
+    options.exclude = convert_args.exclude.clone();
+    options.volume_size = convert_args.volume_size;
+    let password = convert_args
+        .password
+        .clone()
+        .or_else(|| std::env::var("FASTZIP_PASSWORD").ok());
+
+    let count = convert_archive(&convert_args.source, &convert_args.output, password.as_deref(), &options)?;
+    if !convert_args.quiet {
+        println!("已转换 {} 个条目到: {}", count, convert_args.output.display());
+    }
+    Ok(())
+}
+
+fn run_comment(comment_args: &args::CommentArgs) -> Result<(), FastZipError> {
+    let comment = match (&comment_args.set, &comment_args.file) {
+        (Some(text), _) => Some(text.clone()),
+        (None, Some(file)) => Some(std::fs::read_to_string(file)?),
+        (None, None) if comment_args.clear => Some(String::new()),
+        (None, None) => None,
+    };
+    if let Some(comment) = comment {
+        set_zip_comment(&comment_args.archive, &comment)?;
+        if !comment_args.quiet {
+            println!("已更新注释: {}", comment_args.archive.display());
+        }
+        return Ok(());
+    }
+
+    let (format, entries) = list_archive_top_level(&comment_args.archive)?;
+    match &entries.comment {
+        Some(comment) => println!("{}", comment),
+        None if !comment_args.quiet && format == ArchiveFormat::SevenZ => println!("（暂不支持读取 7z 注释）"),
+        None if !comment_args.quiet => println!("（无归档注释）"),
+        None => {}
+    }
+    for (name, comment) in &entries.entry_comments {
+        println!("  {}: {}", name, comment);
+    }
+    Ok(())
+}
+
+fn run_diff(diff_args: &args::DiffArgs) -> Result<(), FastZipError> {
+    let password = diff_args
+        .password
+        .clone()
+        .or_else(|| std::env::var("FASTZIP_PASSWORD").ok());
+    let diff = diff_archives(&diff_args.old, &diff_args.new, password.as_deref())?;
+
+    if diff_args.json {
+        let info = |info: &Option<DiffEntryInfo>| {
+            info.as_ref().map(|i| {
+                serde_json::json!({
+                    "type": match i.kind {
+                        DiffEntryKind::File => "file",
+                        DiffEntryKind::Dir => "dir",
+                        DiffEntryKind::Symlink => "symlink",
+                    },
+                    "size": i.size,
+                    "crc32": format!("{:08x}", i.crc32),
+                    "target": i.target,
+                    "mtime": i.mtime,
+                    "mode": i.mode.map(|m| format!("{:04o}", m)),
+                })
+            })
+        };
+        let changes: Vec<_> = diff
+            .changes
+            .iter()
+            .map(|c| {
+                serde_json::json!({
+                    "path": c.path,
+                    "change": c.change.name(),
+                    "old": info(&c.old),
+                    "new": info(&c.new),
+                })
+            })
+            .collect();
+        let report = serde_json::json!({
+            "old": diff_args.old.display().to_string(),
+            "new": diff_args.new.display().to_string(),
+            "changes": changes,
+            "unchanged": diff.unchanged,
+        });
+        println!("{}", serde_json::to_string_pretty(&report).expect("JSON 值可序列化"));
+        return Ok(());
+    }
+
+    for c in &diff.changes {
+        match (c.change, &c.old, &c.new) {
+            (ChangeKind::Added, _, Some(new)) => println!("+ {}{}", c.path, kind_suffix(new)),
+            (ChangeKind::Removed, Some(old), _) => println!("- {}{}", c.path, kind_suffix(old)),
+            (ChangeKind::Modified, Some(old), Some(new)) => {
+                let detail = if old.kind != new.kind {
+                    format!("{} -> {}", kind_name(old.kind), kind_name(new.kind))
+                } else if old.target != new.target {
+                    format!("-> {}", new.target.as_deref().unwrap_or_default())
+                } else if old.size != new.size {
+                    format!("{} -> {} 字节", old.size, new.size)
+                } else {
+                    format!("CRC32 {:08x} -> {:08x}", old.crc32, new.crc32)
+                };
+                println!("M {}  ({})", c.path, detail);
+            }
+            (ChangeKind::Metadata, Some(old), Some(new)) => {
+                let mut detail = Vec::new();
+                match (old.mode, new.mode) {
+                    (Some(a), Some(b)) if a != b => detail.push(format!("权限 {:04o} -> {:04o}", a, b)),
+                    _ => {}
+                }
+                match (old.mtime, new.mtime) {
+                    (Some(a), Some(b)) if (a - b).abs() >= 2 && old.kind != DiffEntryKind::Dir => {
+                        detail.push(format!("修改时间 {:+} 秒", b - a))
+                    }
+                    _ => {}
+                }
+                println!("m {}  ({})", c.path, detail.join("，"));
+            }
+            _ => {}
+        }
+    }
+    println!(
+        "新增 {}，删除 {}，修改 {}，仅元数据 {}，相同 {}",
+        diff.count(ChangeKind::Added),
+        diff.count(ChangeKind::Removed),
+        diff.count(ChangeKind::Modified),
+        diff.count(ChangeKind::Metadata),
+        diff.unchanged
+    );
+    Ok(())
+}
+
+fn run_hash(hash_args: &args::HashArgs) -> Result<(), FastZipError> {
+    let password = hash_args
+        .password
+        .clone()
+        .or_else(|| std::env::var("FASTZIP_PASSWORD").ok());
+    let hashes = hash_entries(&hash_args.target, hash_algorithm(&hash_args.algo)?, password.as_deref())?;
+    match &hash_args.output {
+        Some(path) => write_manifest(&mut std::io::BufWriter::new(std::fs::File::create(path)?), &hashes)?,
+        None => write_manifest(&mut std::io::stdout().lock(), &hashes)?,
+    }
+    Ok(())
+}
+
+fn run_verify(verify_args: &args::VerifyArgs) -> Result<(), FastZipError> {
+    let password = verify_args
+        .password
+        .clone()
+        .or_else(|| std::env::var("FASTZIP_PASSWORD").ok());
+    let algorithm = hash_algorithm(&verify_args.algo)?;
+    let report = verify_manifest(&verify_args.target, &verify_args.manifest, algorithm, password.as_deref())?;
+
+    for path in &report.mismatched {
+        println!("{}: 失败", path);
+    }
+    for path in &report.missing {
+        println!("{}: 缺失", path);
+    }
+    if !verify_args.quiet {
+        for path in &report.extra {
+            println!("{}: 不在清单中", path);
+        }
+    }
+    println!(
+        "一致 {}，不一致 {}，缺失 {}，清单外 {}",
+        report.ok,
+        report.mismatched.len(),
+        report.missing.len(),
+        report.extra.len()
+    );
+    if !report.is_ok() {
+        return Err(FastZipError::Other("校验失败".into()));
+    }
+    Ok(())
+}
+
+/// 退出状态同 grep：有匹配为 0，没有匹配为 1，出错为 2（`-q` 找到匹配时仍为 0）
+fn run_grep(grep_args: &args::GrepArgs) -> Result<ExitCode, FastZipError> {
+    let options = GrepOptions {
+        ignore_case: grep_args.ignore_case,
+        fixed_strings: grep_args.fixed_strings,
+        word: grep_args.word_regexp,
+        include: grep_args.include.clone(),
+        exclude: grep_args.exclude.clone(),
+        binary: grep_args.text,
+        password: grep_args
+            .password
+            .clone()
+            .or_else(|| std::env::var("FASTZIP_PASSWORD").ok()),
+        stop_on_match: grep_args.quiet,
+    };
+    let with_archive = grep_args.with_filename || grep_args.archives.len() > 1;
+    let prefix = |archive: &std::path::Path, entry: &str| {
+        if with_archive {
+            format!("{}:{}", archive.display(), entry)
+        } else {
+            entry.to_string()
+        }
+    };
+    let per_match = !(grep_args.quiet || grep_args.files_with_matches || grep_args.count);
+
+    let stdout = std::io::stdout();
+    let mut out = stdout.lock();
+    let results = grep_archives(&grep_args.archives, &grep_args.pattern, &options, |archive, m| {
+        if per_match {
+            writeln!(out, "{}:{}:{}", prefix(archive, &m.entry), m.line_number, m.line)?;
+        }
+        Ok(())
+    })?;
+
+    let mut matched = false;
+    let mut failed = false;
+    for (archive, result) in grep_args.archives.iter().zip(results) {
+        match result {
+            Ok(summary) => {
+                matched |= summary.matches > 0;
+                // -l / -c：由每个条目的匹配行数输出
+                for (entry, n) in &summary.entry_matches {
+                    if grep_args.quiet {
+                        break;
+                    } else if grep_args.count {
+                        writeln!(out, "{}:{}", prefix(archive, entry), n)?;
+                    } else if grep_args.files_with_matches && *n > 0 {
+                        writeln!(out, "{}", prefix(archive, entry))?;
+                    }
+                }
+            }
+            Err(e) => {
+                eprintln!("搜索失败 {}: {}", archive.display(), e);
+                failed = true;
+            }
+        }
+    }
+    out.flush()?;
+    Ok(match (failed, matched) {
+        (_, true) if grep_args.quiet => ExitCode::SUCCESS,
+        (true, _) => ExitCode::from(2),
+        (false, false) => ExitCode::from(1),
+        (false, true) => ExitCode::SUCCESS,
+    })
+}
+
+fn run_mount(mount_args: &args::MountArgs) -> Result<(), FastZipError> {
+    let options = MountOptions {
+        password: mount_args
+            .password
+            .clone()
+            .or_else(|| std::env::var("FASTZIP_PASSWORD").ok()),
+        allow_other: mount_args.allow_other,
+    };
+    if !mount_args.quiet {
+        println!(
+            "挂载 {} 到 {}（按 Ctrl+C 或 umount 卸载）",
+            mount_args.archive.display(),
+            mount_args.mountpoint.display()
+        );
+    }
+    mount_archive(&mount_args.archive, &mount_args.mountpoint, &options)
+}
+
+fn hash_algorithm(name: &str) -> Result<HashAlgorithm, FastZipError> {
+    HashAlgorithm::from_name(name)
+        .ok_or_else(|| FastZipError::Other(format!("未知的哈希算法: {}（可选 sha256、blake3）", name)))
+}
+
+fn kind_name(kind: DiffEntryKind) -> &'static str {
+    match kind {
+        DiffEntryKind::File => "文件",
+        DiffEntryKind::Dir => "目录",
+        DiffEntryKind::Symlink => "链接",
+    }
+}
+
+/// 目录加 `/`，链接附上目标
+fn kind_suffix(info: &DiffEntryInfo) -> String {
+    match info.kind {
+        DiffEntryKind::File => String::new(),
+        DiffEntryKind::Dir => "/".to_string(),
+        DiffEntryKind::Symlink => format!(" -> {}", info.target.as_deref().unwrap_or_default()),
+    }
+}
+
+/// 由预设与压缩方法得到初始选项
+fn base_options(preset: Option<&str>, no_fast: bool, method: Option<&str>) -> Result<CompressOptions, FastZipError> {
+    let preset = match preset {
+        Some(name) => Some(CompressionPreset::from_name(name).ok_or_else(|| {
+            FastZipError::Other(format!(
+                "未知的压缩预设: {}（可选 store、fastest、normal、max、ultra）",
+                name
+            ))
+        })?),
+        None if no_fast => Some(CompressionPreset::Normal),
+        None => None,
+    };
+
+    let mut options = match preset {
+        Some(preset) => CompressOptions::default().with_preset(preset),
+        None => CompressOptions::default(),
+    };
+    if let Some(name) = method {
+        options.method = ZipMethod::from_name(name).ok_or_else(|| {
+            FastZipError::Other(format!(
+                "未知的压缩方法: {}（可选 stored、deflate、deflate64、bzip2、zstd、xz、lzma）",
+                name
+            ))
+        })?;
+    }
+    Ok(options)
+}
+
+fn compress_options(compress_args: &args::CompressArgs) -> Result<CompressOptions, FastZipError> {
+    let mut options = base_options(
+        compress_args.preset.as_deref(),
+        compress_args.no_fast,
+        compress_args.method.as_deref(),
+    )?;
+    if let Some(name) = &compress_args.symlinks {
+        options.symlinks = SymlinkPolicy::from_name(name).ok_or_else(|| {
+            FastZipError::Other(format!("未知的符号链接处理方式: {}（可选 store、follow、skip）", name))
+        })?;
+    }
+    options.recursive = compress_args.recursive;
+    options.level = compress_args.level;
+    options.keep_original = !compress_args.delete_original;
+    options.overwrite = compress_args.overwrite;
+    options.password = compress_args
+        .password
+        .clone()
+        .or_else(|| std::env::var("FASTZIP_PASSWORD").ok());
+    if compress_args.zip_crypto {
+        options.encryption = ZipEncryption::ZipCrypto;
+    }
+    options.include = compress_args.include.clone();
+    options.exclude = compress_args.exclude.clone();
+    options.min_size = compress_args.min_size;
+    options.max_size = compress_args.max_size;
+    options.newer_than = compress_args.newer_than;
+    options.older_than = compress_args.older_than;
+    options.respect_ignore = compress_args.gitignore;
+    options.threads = compress_args.threads;
+    options.extended_timestamp = !compress_args.no_extended_time;
+    options.reproducible = compress_args.reproducible;
+    options.fixed_mtime = compress_args.mtime;
+    options.volume_size = compress_args.volume_size;
+    options.comment = match &compress_args.comment_file {
+        Some(file) => Some(std::fs::read_to_string(file)?),
+        None => compress_args.comment.clone(),
+    };
+    options.encrypt_header = compress_args.encrypt_header;
+    options.solid = !compress_args.no_solid;
+    options.solid_block_size = compress_args.solid_block_size;
+    options.dict_size = match compress_args.dict_size {
+        Some(size) => Some(
+            u32::try_from(size).map_err(|_| FastZipError::Other("字典大小不能超过 4 GiB".into()))?,
+        ),
+        None => None,
+    };
+    Ok(options)
+}
+
+fn run_compress_in_place(
+    compress_args: &args::CompressArgs,
+    sources: &[PathBuf],
+    codec: Codec,
+    options: &CompressOptions,
+) -> Result<(), FastZipError> {
+    let files: Vec<&PathBuf> = sources
+        .iter()
+        .filter(|p| {
+            if p.is_file() {
+                true
+            } else {
+                eprintln!("警告: 不是文件，已跳过: {}", p.display());
+                false
+            }
+        })
+        .collect();
+
+    let mut failed = 0;
+    for (src, result) in compress_single_many(&files, codec, options)? {
+        match result {
+            Ok(dest) => {
+                if !compress_args.quiet {
+                    println!("已压缩到: {}", dest.display());
+                }
+            }
+            Err(e) => {
+                failed += 1;
+                eprintln!("压缩失败 {}: {}", src.display(), e);
+            }
+        }
+    }
+
+    if failed > 0 {
+        return Err(FastZipError::Other(format!("{} 个文件压缩失败", failed)));
+    }
+    Ok(())
+}
+
+fn run_index(index_args: &args::IndexArgs) -> Result<(), FastZipError> {
+    let archive = &index_args.archive;
+    let format = detect_format(archive)?;
+    if !format.is_tar() {
+        return Err(FastZipError::UnsupportedFormat(format!(
+            "索引仅支持 tar 系列格式: {:?}",
+            format
+        )));
+    }
+
+    let index = TarExtractor::index(archive, format)?;
+
+    if let Some(entry) = &index_args.entry {
+        match &index_args.output {
+            Some(path) => {
+                let mut out = std::io::BufWriter::new(std::fs::File::create(path)?);
+                index.read_entry(archive, entry, &mut out)?;
+                out.flush()?;
+            }
+            None => {
+                let mut out = std::io::stdout().lock();
+                index.read_entry(archive, entry, &mut out)?;
+                out.flush()?;
+            }
+        }
+    } else if index_args.list {
+        for e in &index.entries {
+            println!("{:>12}  {}", e.size, e.path);
+        }
+    } else {
+        println!(
+            "已建立索引: {}（{} 个条目，{} 个重启点）",
+            fastzip_core::formats::TarIndex::sidecar_path(archive).display(),
+            index.entries.len(),
+            index.checkpoints.len()
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,168 @@
+//! 异步接口（`async` feature）：AsyncRead 解压、AsyncWrite 压缩、进度快照与丢弃取消
+
+use std::fs;
+use std::future::Future;
+use std::io::{Cursor, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use fastzip_core::{
+    compress_async, extract_async, ArchiveFormat, AsyncTask, CompressOptions, ExtractOptions, Progress,
+};
+use tempfile::TempDir;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use zip::write::SimpleFileOptions;
+
+mod common;
+use common::create_tree;
+
+fn block_on<F: Future>(future: F) -> F::Output {
+    tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap().block_on(future)
+}
+
+/// [`create_source`] 中文件的总字节数
+const SOURCE_LEN: u64 = 3 + 1_600_000;
+
+fn create_source(dir: &Path) -> PathBuf {
+    let big: Vec<u8> = (0..400_000u32).flat_map(|i| i.wrapping_mul(2_654_435_761).to_le_bytes()).collect();
+    create_tree(&dir.join("src"), &["sub"], [("abc.txt", &b"abc"[..]), ("sub/big.bin", &big)])
+}
+
+/// 取到最终进度后等待结果
+async fn finish<T>(mut task: AsyncTask<T>) -> (fastzip_core::Result<T>, Progress) {
+    let mut last = Progress::default();
+    while let Some(progress) = task.progress().await {
+        last = progress;
+    }
+    (task.await, last)
+}
+
+/// 压缩到内存管道的另一端，返回压缩结果与最终进度
+async fn compress(src: &Path, format: ArchiveFormat, options: CompressOptions) -> (Vec<u8>, Progress) {
+    let (writer, mut reader) = tokio::io::duplex(16 * 1024);
+    let task = compress_async(vec![src.to_path_buf()], format, writer, options);
+    let collect = tokio::spawn(async move {
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data).await.unwrap();
+        data
+    });
+    let (written, progress) = finish(task).await;
+    let data = collect.await.unwrap();
+    assert_eq!(written.unwrap(), data.len() as u64);
+    (data, progress)
+}
+
+#[test]
+fn test_async_round_trip() {
+    let dir = TempDir::new().unwrap();
+    let src = create_source(dir.path());
+    block_on(async {
+        for (name, format) in [
+            ("out.tar.gz", ArchiveFormat::TarGz),
+            ("out.tar.zst", ArchiveFormat::TarZst),
+            ("out.zip", ArchiveFormat::Zip),
+            ("out.7z", ArchiveFormat::SevenZ),
+        ] {
+            let (data, progress) = compress(&src, format, CompressOptions::default()).await;
+            assert_eq!(progress.written, data.len() as u64, "{name}");
+            // ZIP、7z 逐条目报告源数据
+            if !format.is_tar() {
+                assert_eq!(progress.read, SOURCE_LEN, "{name}");
+                assert!(progress.entries >= 2, "{name}");
+            }
+
+            let dest = dir.path().join(format!("dest-{name}"));
+            let options = ExtractOptions { dest: Some(dest.clone()), smart: false, ..Default::default() };
+            let (result, progress) = finish(extract_async(Cursor::new(data.clone()), name, options)).await;
+            let out = result.unwrap();
+            assert_eq!(out, dest.join("out"), "{name}");
+            assert_eq!(fs::read(out.join("abc.txt")).unwrap(), b"abc", "{name}");
+            assert_eq!(fs::read(out.join("sub/big.bin")).unwrap(), fs::read(src.join("sub/big.bin")).unwrap());
+            assert_eq!(progress.read, data.len() as u64, "{name}");
+            assert!(progress.entries >= 2 && progress.entry.is_some(), "{name}");
+            // tar.* 计入解码后的整个 tar 流，ZIP、7z 只计条目数据
+            if !format.is_tar() {
+                assert_eq!(progress.written, SOURCE_LEN, "{name}");
+            }
+        }
+
+        // 单文件压缩
+        let file = src.join("abc.txt");
+        let (writer, mut reader) = tokio::io::duplex(1024);
+        let task = compress_async(vec![file.clone()], ArchiveFormat::Zst, writer, CompressOptions::default());
+        let (result, _) = finish(task).await;
+        result.unwrap();
+        assert!(file.exists());
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data).await.unwrap();
+        let dest = dir.path().join("single");
+        let options = ExtractOptions { dest: Some(dest.clone()), ..Default::default() };
+        finish(extract_async(Cursor::new(data), "abc.txt.zst", options)).await.0.unwrap();
+        assert_eq!(fs::read(dest.join("abc.txt")).unwrap(), b"abc");
+    });
+}
+
+#[test]
+fn test_async_errors_and_cancel() {
+    let dir = TempDir::new().unwrap();
+    let src = create_source(dir.path());
+    block_on(async {
+        // 未指定目标目录、损坏的输入、单文件压缩多个输入
+        let (result, _) = finish(extract_async(Cursor::new(b"x".to_vec()), "a.zip", ExtractOptions::default())).await;
+        assert!(result.is_err());
+        let options = ExtractOptions { dest: Some(dir.path().join("bad")), ..Default::default() };
+        assert!(finish(extract_async(Cursor::new(b"garbage".to_vec()), "a.tar.gz", options)).await.0.is_err());
+        let (writer, _reader) = tokio::io::duplex(1024);
+        let sources = vec![src.join("abc.txt"), src.join("sub/big.bin")];
+        let (result, _) = finish(compress_async(sources, ArchiveFormat::Gz, writer, CompressOptions::default())).await;
+        assert!(result.is_err());
+
+        // 丢弃解压任务后不再读取输入：写端很快因对端关闭而失败
+        let (data, _) = compress(&src, ArchiveFormat::TarGz, CompressOptions::default()).await;
+        let (mut writer, reader) = tokio::io::duplex(4096);
+        let options = ExtractOptions { dest: Some(dir.path().join("cancelled")), ..Default::default() };
+        let mut task = extract_async(reader, "out.tar.gz", options);
+        writer.write_all(&data[..8192]).await.unwrap();
+        assert!(task.progress().await.is_some_and(|progress| progress.read > 0));
+        drop(task);
+        let mut failed = false;
+        for _ in 0..1000 {
+            if writer.write_all(&data[..4096]).await.is_err() {
+                failed = true;
+                break;
+            }
+        }
+        assert!(failed);
+    });
+}
+
+#[test]
+fn test_async_drop_stops_zip_extract() {
+    // 32 个 8 MiB 的全零条目：归档很小，解压要写出 256 MiB
+    let mut zip = zip::ZipWriter::new(Cursor::new(Vec::new()));
+    zip.start_file("0.bin", SimpleFileOptions::default()).unwrap();
+    for _ in 0..8 {
+        zip.write_all(&[0u8; 1024 * 1024]).unwrap();
+    }
+    for i in 1..32 {
+        zip.deep_copy_file("0.bin", &format!("{i}.bin")).unwrap();
+    }
+    let data = zip.finish().unwrap().into_inner();
+    const TOTAL: u64 = 32 * 8 * 1024 * 1024;
+
+    let dir = TempDir::new().unwrap();
+    let out = dir.path().join("zeros");
+    let written = || -> u64 { fs::read_dir(&out).unwrap().map(|e| e.unwrap().metadata().unwrap().len()).sum() };
+    block_on(async {
+        let options = ExtractOptions { dest: Some(dir.path().to_path_buf()), smart: false, ..Default::default() };
+        let mut task = extract_async(Cursor::new(data), "zeros.zip", options);
+        while task.progress().await.is_some_and(|progress| progress.written == 0) {}
+        drop(task);
+        // 阻塞端在下一个数据块处停止，之后不再写出
+        std::thread::sleep(Duration::from_millis(200));
+        let size = written();
+        std::thread::sleep(Duration::from_millis(300));
+        assert_eq!(written(), size);
+        assert!(size < TOTAL, "{size}");
+    });
+}
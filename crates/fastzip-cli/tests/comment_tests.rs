@@ -0,0 +1,184 @@
+//! 归档与条目注释：压缩时写入 ZIP 注释，列表接口读出注释，原地修改 ZIP 注释不重新压缩
+
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use fastzip_core::{
+    compress_to_7z, compress_to_tar, compress_to_zip, list_archive_top_level, set_zip_comment, update_zip,
+    CompressOptions, UpdateMode, ZipEncryption, ZipMethod,
+};
+use tempfile::TempDir;
+
+mod common;
+use common::create_tree;
+
+fn create_source(dir: &Path) -> PathBuf {
+    create_tree(&dir.join("src"), &[], [("a.txt", "alpha\n".repeat(100).as_str()), ("docs/b.txt", "beta")])
+}
+
+fn zip_comment(path: &Path) -> Vec<u8> {
+    zip::ZipArchive::new(fs::File::open(path).unwrap()).unwrap().comment().to_vec()
+}
+
+/// 条目名 -> 原始压缩数据
+fn raw_entries(path: &Path) -> Vec<(String, Vec<u8>)> {
+    let bytes = fs::read(path).unwrap();
+    let mut zip = zip::ZipArchive::new(fs::File::open(path).unwrap()).unwrap();
+    (0..zip.len())
+        .map(|i| {
+            let entry = zip.by_index_raw(i).unwrap();
+            let start = entry.data_start() as usize;
+            (entry.name().to_string(), bytes[start..start + entry.compressed_size() as usize].to_vec())
+        })
+        .collect()
+}
+
+/// 只含一个条目的 ZIP，条目带 UTF-8 注释（zip 库写入时不支持条目注释，直接改写中央目录）
+fn zip_with_entry_comment(path: &Path, name: &str, comment: &str) {
+    let mut zip = zip::ZipWriter::new(fs::File::create(path).unwrap());
+    zip.start_file(name, zip::write::SimpleFileOptions::default()).unwrap();
+    zip.write_all(b"data").unwrap();
+    zip.finish().unwrap();
+
+    let mut bytes = fs::read(path).unwrap();
+    let end = bytes.len() - 22;
+    let u16_at = |b: &[u8], i: usize| u16::from_le_bytes([b[i], b[i + 1]]) as usize;
+    let u32_at = |b: &[u8], i: usize| u32::from_le_bytes(b[i..i + 4].try_into().unwrap()) as usize;
+    let (cd_size, cd_start) = (u32_at(&bytes, end + 12), u32_at(&bytes, end + 16));
+    let record_end = cd_start + 46 + u16_at(&bytes, cd_start + 28) + u16_at(&bytes, cd_start + 30);
+    bytes.splice(record_end..record_end, comment.bytes());
+    // 通用标志 bit 11：名称与注释为 UTF-8
+    bytes[cd_start + 9] |= 1 << 3;
+    bytes[cd_start + 32..cd_start + 34].copy_from_slice(&(comment.len() as u16).to_le_bytes());
+    let end = end + comment.len();
+    bytes[end + 12..end + 16].copy_from_slice(&((cd_size + comment.len()) as u32).to_le_bytes());
+    fs::write(path, bytes).unwrap();
+}
+
+#[test]
+fn test_compress_with_comment() {
+    let dir = TempDir::new().unwrap();
+    let src = create_source(dir.path());
+    let comment = "构建 #42\nrelease";
+    for (i, (password, encryption)) in
+        [(None, ZipEncryption::Aes256), (Some("pw"), ZipEncryption::Aes256), (Some("pw"), ZipEncryption::ZipCrypto)]
+            .into_iter()
+            .enumerate()
+    {
+        let archive = dir.path().join(format!("out{i}.zip"));
+        let options = CompressOptions {
+            comment: Some(comment.into()),
+            password: password.map(String::from),
+            encryption,
+            ..Default::default()
+        };
+        compress_to_zip(&[&src], &archive, &options).unwrap();
+        assert_eq!(zip_comment(&archive), comment.as_bytes(), "{i}");
+    }
+
+    let (_, entries) = list_archive_top_level(&dir.path().join("out0.zip")).unwrap();
+    assert_eq!(entries.comment.as_deref(), Some(comment));
+    assert!(entries.entry_comments.is_empty());
+
+    // 不支持注释的格式、超长注释
+    let options = CompressOptions { comment: Some("x".into()), ..Default::default() };
+    assert!(compress_to_7z(&[&src], &dir.path().join("out.7z"), &options).is_err());
+    assert!(compress_to_tar(&[&src], &dir.path().join("out.tar"), None, &options).is_err());
+    let options = CompressOptions { comment: Some("x".repeat(70_000)), ..Default::default() };
+    assert!(compress_to_zip(&[&src], &dir.path().join("long.zip"), &options).is_err());
+}
+
+#[test]
+fn test_list_entry_comments() {
+    let dir = TempDir::new().unwrap();
+    let archive = dir.path().join("entry.zip");
+    zip_with_entry_comment(&archive, "a.txt", "第一个条目");
+    let (_, entries) = list_archive_top_level(&archive).unwrap();
+    assert_eq!(entries.comment, None);
+    assert_eq!(entries.entry_comments, [("a.txt".to_string(), "第一个条目".to_string())]);
+
+    // 修改归档注释时保留条目注释
+    set_zip_comment(&archive, "归档").unwrap();
+    let (_, entries) = list_archive_top_level(&archive).unwrap();
+    assert_eq!(entries.comment.as_deref(), Some("归档"));
+    assert_eq!(entries.entry_comments.len(), 1);
+}
+
+#[test]
+fn test_set_zip_comment_keeps_entries() {
+    let dir = TempDir::new().unwrap();
+    let src = create_source(dir.path());
+    let archive = dir.path().join("out.zip");
+    let options = CompressOptions { method: ZipMethod::Deflate, password: Some("pw".into()), ..Default::default() };
+    compress_to_zip(&[&src], &archive, &options).unwrap();
+    let before = raw_entries(&archive);
+
+    set_zip_comment(&archive, "hello").unwrap();
+    assert_eq!(zip_comment(&archive), b"hello");
+    assert_eq!(raw_entries(&archive), before);
+
+    set_zip_comment(&archive, "").unwrap();
+    assert!(zip_comment(&archive).is_empty());
+    assert_eq!(list_archive_top_level(&archive).unwrap().1.comment, None);
+
+    assert!(set_zip_comment(&archive, &"x".repeat(70_000)).is_err());
+    let sz = dir.path().join("out.7z");
+    compress_to_7z(&[&src], &sz, &CompressOptions::default()).unwrap();
+    assert!(set_zip_comment(&sz, "x").is_err());
+}
+
+#[test]
+fn test_update_replaces_comment() {
+    let dir = TempDir::new().unwrap();
+    let src = create_source(dir.path());
+    let archive = dir.path().join("out.zip");
+    let options = CompressOptions { comment: Some("v1".into()), ..Default::default() };
+    compress_to_zip(&[&src], &archive, &options).unwrap();
+
+    // 未设置注释时保留原注释
+    fs::write(src.join("c.txt"), "gamma").unwrap();
+    update_zip(&archive, &[&src], UpdateMode::Update, &CompressOptions::default()).unwrap();
+    assert_eq!(zip_comment(&archive), b"v1");
+
+    // 没有变化的文件时也写入新注释
+    let options = CompressOptions { comment: Some("v2".into()), ..Default::default() };
+    let summary = update_zip(&archive, &[&src], UpdateMode::Update, &options).unwrap();
+    assert!(summary.added.is_empty() && summary.replaced.is_empty());
+    assert_eq!(zip_comment(&archive), b"v2");
+}
+
+#[test]
+fn test_cli_comment() {
+    let dir = TempDir::new().unwrap();
+    let src = create_source(dir.path());
+    let archive = dir.path().join("cli.zip");
+    let note = dir.path().join("note.txt");
+    fs::write(&note, "from file").unwrap();
+    let fastzip = || Command::new(env!("CARGO_BIN_EXE_fastzip"));
+
+    let mut compress = fastzip();
+    compress.args(["compress", "-q", "--comment-file"]).arg(&note).arg(&src).arg("-o").arg(&archive);
+    assert!(compress.status().unwrap().success());
+    let output = fastzip().arg("comment").arg(&archive).output().unwrap();
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "from file\n");
+
+    let status = fastzip().args(["comment", "-q", "--set", "new"]).arg(&archive).status().unwrap();
+    assert!(status.success());
+    assert_eq!(zip_comment(&archive), b"new");
+    let status = fastzip().args(["comment", "-q", "--clear"]).arg(&archive).status().unwrap();
+    assert!(status.success());
+    assert!(zip_comment(&archive).is_empty());
+
+    // 注释仅支持 ZIP 输出
+    let mut compress = fastzip();
+    compress.args(["compress", "-q", "--comment", "x"]).arg(&src).arg("-o").arg(dir.path().join("a.7z"));
+    assert!(!compress.status().unwrap().success());
+
+    // 7z 注释暂不读取，输出中注明
+    let sz = dir.path().join("b.7z");
+    compress_to_7z(&[&src], &sz, &CompressOptions::default()).unwrap();
+    let output = fastzip().arg("comment").arg(&sz).output().unwrap();
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "（暂不支持读取 7z 注释）\n");
+}
@@ -0,0 +1,38 @@
+//! 集成测试共用的辅助函数：按条目列表建源目录、平铺解压
+
+#![allow(dead_code)]
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use fastzip_core::{extract_one, ExtractOptions};
+
+/// 在 `root` 下创建空目录 `dirs`，写入 `files`（相对路径与内容，按需创建上级目录），返回 `root`
+pub fn create_tree<P: AsRef<Path>, C: AsRef<[u8]>>(
+    root: &Path,
+    dirs: &[&str],
+    files: impl IntoIterator<Item = (P, C)>,
+) -> PathBuf {
+    fs::create_dir_all(root).unwrap();
+    for dir in dirs {
+        fs::create_dir_all(root.join(dir)).unwrap();
+    }
+    for (name, content) in files {
+        let path = root.join(name);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+    root.to_path_buf()
+}
+
+/// 不用智能解压，覆盖解压到 `dest`，返回实际的解压目录
+pub fn extract(archive: &Path, dest: &Path, password: Option<&str>) -> fastzip_core::Result<PathBuf> {
+    let options = ExtractOptions {
+        dest: Some(dest.to_path_buf()),
+        smart: false,
+        overwrite: true,
+        password: password.map(String::from),
+        ..Default::default()
+    };
+    extract_one(archive, &options)
+}
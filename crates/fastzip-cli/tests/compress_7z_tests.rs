@@ -0,0 +1,141 @@
+//! 7z 压缩测试：多源、密码与头加密、固实块、字典大小
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use fastzip_core::{compress_to_7z, CompressOptions};
+use tempfile::TempDir;
+
+mod common;
+use common::{create_tree, extract};
+
+fn create_sources(dir: &Path) -> (PathBuf, PathBuf) {
+    let files = (0..6).map(|i| {
+        let text: String = (0..2_000).map(|n| format!("file {} line {}\n", i, n)).collect();
+        (format!("sub/f{i}.txt"), text)
+    });
+    let src = create_tree(&dir.join("src"), &["sub/empty"], files.chain([("zero.bin".into(), String::new())]));
+    let single = dir.join("notes-unique-name.txt");
+    fs::write(&single, b"standalone").unwrap();
+    (src, single)
+}
+
+fn assert_same(out: &Path, src: &Path) {
+    for i in 0..6 {
+        let name = format!("sub/f{i}.txt");
+        assert_eq!(fs::read(out.join(&name)).unwrap(), fs::read(src.join(&name)).unwrap());
+    }
+    assert_eq!(fs::read(out.join("zero.bin")).unwrap(), b"");
+    assert!(out.join("sub/empty").is_dir());
+    assert_eq!(fs::read(out.join("notes-unique-name.txt")).unwrap(), b"standalone");
+}
+
+fn read_archive(path: &Path, password: &str) -> Result<sevenz_rust::Archive, sevenz_rust::Error> {
+    let mut file = fs::File::open(path).unwrap();
+    let len = file.metadata().unwrap().len();
+    sevenz_rust::Archive::read(&mut file, len, sevenz_rust::Password::from(password).as_slice())
+}
+
+#[test]
+fn test_7z_multi_source_roundtrip() {
+    let dir = TempDir::new().unwrap();
+    let (src, single) = create_sources(dir.path());
+    let archive = dir.path().join("multi.7z");
+    compress_to_7z(&[&src, &single], &archive, &CompressOptions::default()).unwrap();
+
+    let out = extract(&archive, &dir.path().join("x"), None).unwrap();
+    assert_same(&out, &src);
+    // 默认固实：所有非空文件在同一个块内
+    assert_eq!(read_archive(&archive, "").unwrap().folders.len(), 1);
+}
+
+#[test]
+fn test_7z_solid_blocks() {
+    let dir = TempDir::new().unwrap();
+    let (src, single) = create_sources(dir.path());
+
+    let non_solid = dir.path().join("non-solid.7z");
+    let options = CompressOptions { solid: false, ..Default::default() };
+    compress_to_7z(&[&src, &single], &non_solid, &options).unwrap();
+    assert_eq!(read_archive(&non_solid, "").unwrap().folders.len(), 7);
+    assert_same(&extract(&non_solid, &dir.path().join("a"), None).unwrap(), &src);
+
+    // 每个文件约 30 KB，块上限 64 KB 时每块两个文件
+    let blocks = dir.path().join("blocks.7z");
+    let options = CompressOptions { solid_block_size: Some(64 * 1024), ..Default::default() };
+    compress_to_7z(&[&src, &single], &blocks, &options).unwrap();
+    let folders = read_archive(&blocks, "").unwrap().folders.len();
+    assert!((3..7).contains(&folders), "块数 {folders}");
+    assert_same(&extract(&blocks, &dir.path().join("b"), None).unwrap(), &src);
+}
+
+#[test]
+fn test_7z_password_and_header_encryption() {
+    let dir = TempDir::new().unwrap();
+    let (src, single) = create_sources(dir.path());
+
+    let plain_header = dir.path().join("enc.7z");
+    let options = CompressOptions { password: Some("s3cret".into()), ..Default::default() };
+    compress_to_7z(&[&src, &single], &plain_header, &options).unwrap();
+    // 未加密头：不需要密码即可列出文件名，但无法解压内容
+    let listed = read_archive(&plain_header, "").unwrap();
+    assert!(listed.files.iter().any(|f| f.name() == "notes-unique-name.txt"));
+    assert!(extract(&plain_header, &dir.path().join("none"), None).is_err());
+    assert_same(&extract(&plain_header, &dir.path().join("ok"), Some("s3cret")).unwrap(), &src);
+
+    let hidden = dir.path().join("hidden.7z");
+    let options = CompressOptions { encrypt_header: true, ..options };
+    compress_to_7z(&[&src, &single], &hidden, &options).unwrap();
+    assert!(read_archive(&hidden, "").is_err());
+    let bytes = fs::read(&hidden).unwrap();
+    let needle: Vec<u8> = "notes-unique-name".encode_utf16().flat_map(u16::to_le_bytes).collect();
+    assert!(!bytes.windows(needle.len()).any(|w| w == needle), "文件名不应以明文出现");
+    assert!(read_archive(&hidden, "s3cret").is_ok());
+    assert_same(&extract(&hidden, &dir.path().join("ok2"), Some("s3cret")).unwrap(), &src);
+
+    // 头加密必须配合密码
+    let options = CompressOptions { encrypt_header: true, ..Default::default() };
+    assert!(compress_to_7z(&[&src], &dir.path().join("bad.7z"), &options).is_err());
+}
+
+#[test]
+fn test_7z_dict_size() {
+    let dir = TempDir::new().unwrap();
+    let (src, _) = create_sources(dir.path());
+    let archive = dir.path().join("dict.7z");
+    let options = CompressOptions { dict_size: Some(16 << 20), ..Default::default() };
+    compress_to_7z(&[&src], &archive, &options).unwrap();
+
+    // LZMA2 属性字节 24 表示 16 MiB 字典
+    let archive = read_archive(&archive, "").unwrap();
+    assert_eq!(archive.folders[0].coders[0].properties, vec![24]);
+}
+
+#[test]
+fn test_cli_7z_options() {
+    let dir = TempDir::new().unwrap();
+    let (src, single) = create_sources(dir.path());
+    let archive = dir.path().join("cli.7z");
+
+    let status = Command::new(env!("CARGO_BIN_EXE_fastzip"))
+        .args(["c", "-q", "-p", "pw", "--encrypt-header", "--solid-block-size", "64k", "--dict-size", "1m", "-o"])
+        .arg(&archive)
+        .arg(&src)
+        .arg(&single)
+        .env_remove("FASTZIP_PASSWORD")
+        .status()
+        .unwrap();
+    assert!(status.success());
+    assert!(read_archive(&archive, "").is_err());
+    assert!(read_archive(&archive, "pw").unwrap().folders.len() >= 3);
+    assert_same(&extract(&archive, &dir.path().join("x"), Some("pw")).unwrap(), &src);
+
+    let status = Command::new(env!("CARGO_BIN_EXE_fastzip"))
+        .args(["c", "-q", "--dict-size", "12q", "-o"])
+        .arg(dir.path().join("bad.7z"))
+        .arg(&src)
+        .status()
+        .unwrap();
+    assert!(!status.success());
+}
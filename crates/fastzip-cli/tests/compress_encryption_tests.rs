@@ -0,0 +1,145 @@
+//! ZIP 加密压缩测试（AES-256 / ZipCrypto）
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use fastzip_core::{compress_to_tar, compress_to_zip, CompressOptions, ZipEncryption, ZipMethod};
+use tempfile::TempDir;
+
+mod common;
+use common::{create_tree, extract};
+
+fn create_source(dir: &Path) -> PathBuf {
+    let text: String = (0..5_000).map(|i| format!("secret line {}\n", i)).collect();
+    create_tree(&dir.join("src"), &["sub/empty"], [("a.txt", text.as_bytes()), ("sub/b.txt", b"tiny")])
+}
+
+fn assert_same(out: &Path, src: &Path) {
+    assert_eq!(fs::read(out.join("a.txt")).unwrap(), fs::read(src.join("a.txt")).unwrap());
+    assert_eq!(fs::read(out.join("sub/b.txt")).unwrap(), b"tiny");
+    assert!(out.join("sub/empty").is_dir());
+}
+
+/// 所有文件条目加密，目录条目不加密
+fn assert_files_encrypted(archive: &Path) {
+    let mut zip = zip::ZipArchive::new(fs::File::open(archive).unwrap()).unwrap();
+    for i in 0..zip.len() {
+        let entry = zip.by_index_raw(i).unwrap();
+        assert_eq!(entry.encrypted(), entry.is_file(), "{}", entry.name());
+    }
+}
+
+fn count(haystack: &[u8], needle: &[u8]) -> usize {
+    haystack.windows(needle.len()).filter(|w| *w == needle).count()
+}
+
+/// AES 扩展字段（AE-1 或 AE-2）出现次数
+fn aes_extras(bytes: &[u8]) -> usize {
+    count(bytes, b"\x01\x99\x07\x00\x01\x00AE") + count(bytes, b"\x01\x99\x07\x00\x02\x00AE")
+}
+
+#[test]
+fn test_aes256_roundtrip_ae2() {
+    let dir = TempDir::new().unwrap();
+    let src = create_source(dir.path());
+    let archive = dir.path().join("aes.zip");
+    let options = CompressOptions {
+        method: ZipMethod::Deflate,
+        password: Some("p@ss 密码".into()),
+        ..Default::default()
+    };
+    compress_to_zip(&[&src], &archive, &options).unwrap();
+    assert_files_encrypted(&archive);
+
+    // AES 扩展字段（0x9901，长度 7）：每个文件在本地头与中央目录各一处，均为 AE-2、AES-256
+    let bytes = fs::read(&archive).unwrap();
+    assert_eq!(count(&bytes, b"\x01\x99\x07\x00\x02\x00AE\x03"), 4);
+    assert_eq!(count(&bytes, b"\x01\x99\x07\x00\x01\x00AE"), 0);
+    let mut zip = zip::ZipArchive::new(fs::File::open(&archive).unwrap()).unwrap();
+    for i in 0..zip.len() {
+        assert_eq!(zip.by_index_raw(i).unwrap().crc32(), 0, "AE-2 不存储 CRC");
+    }
+
+    let out = extract(&archive, &dir.path().join("ok"), Some("p@ss 密码")).unwrap();
+    assert_same(&out, &src);
+    assert!(extract(&archive, &dir.path().join("none"), None).is_err());
+    assert!(extract(&archive, &dir.path().join("wrong"), Some("wrong")).is_err());
+}
+
+#[test]
+fn test_zipcrypto_roundtrip() {
+    let dir = TempDir::new().unwrap();
+    let src = create_source(dir.path());
+
+    for method in [ZipMethod::Stored, ZipMethod::Deflate] {
+        let archive = dir.path().join(format!("legacy-{:?}.zip", method));
+        let options = CompressOptions {
+            method,
+            password: Some("legacy".into()),
+            encryption: ZipEncryption::ZipCrypto,
+            ..Default::default()
+        };
+        compress_to_zip(&[&src], &archive, &options).unwrap();
+        assert_files_encrypted(&archive);
+        assert_eq!(aes_extras(&fs::read(&archive).unwrap()), 0, "不应包含 AES 扩展字段");
+
+        let out = extract(&archive, &dir.path().join(format!("ok-{:?}", method)), Some("legacy")).unwrap();
+        assert_same(&out, &src);
+        assert!(extract(&archive, &dir.path().join(format!("none-{:?}", method)), None).is_err());
+    }
+}
+
+#[test]
+fn test_zipcrypto_rejects_modern_methods() {
+    let dir = TempDir::new().unwrap();
+    let src = create_source(dir.path());
+    let archive = dir.path().join("legacy.zip");
+    let options = CompressOptions {
+        method: ZipMethod::Zstd,
+        password: Some("legacy".into()),
+        encryption: ZipEncryption::ZipCrypto,
+        ..Default::default()
+    };
+    assert!(compress_to_zip(&[&src], &archive, &options).is_err());
+    assert!(!archive.exists(), "失败时应删除不完整的输出");
+}
+
+#[test]
+fn test_password_rejected_for_tar() {
+    let dir = TempDir::new().unwrap();
+    let src = create_source(dir.path());
+    let options = CompressOptions { password: Some("x".into()), ..Default::default() };
+    assert!(compress_to_tar(&[&src], &dir.path().join("out.tar.gz"), None, &options).is_err());
+}
+
+#[test]
+fn test_cli_password_from_env() {
+    let dir = TempDir::new().unwrap();
+    let src = create_source(dir.path());
+    let archive = dir.path().join("cli.zip");
+
+    let status = Command::new(env!("CARGO_BIN_EXE_fastzip"))
+        .args(["compress", "-q", "--preset", "normal", "-o"])
+        .arg(&archive)
+        .arg(&src)
+        .env("FASTZIP_PASSWORD", "from-env")
+        .status()
+        .unwrap();
+    assert!(status.success());
+    assert_files_encrypted(&archive);
+    let out = extract(&archive, &dir.path().join("x"), Some("from-env")).unwrap();
+    assert_same(&out, &src);
+
+    let legacy = dir.path().join("legacy.zip");
+    let status = Command::new(env!("CARGO_BIN_EXE_fastzip"))
+        .args(["compress", "-q", "--zip-crypto", "--password", "pw", "-o"])
+        .arg(&legacy)
+        .arg(&src)
+        .env_remove("FASTZIP_PASSWORD")
+        .status()
+        .unwrap();
+    assert!(status.success());
+    assert_eq!(aes_extras(&fs::read(&legacy).unwrap()), 0);
+    assert!(extract(&legacy, &dir.path().join("y"), Some("pw")).is_ok());
+}
@@ -0,0 +1,178 @@
+//! 压缩筛选测试：包含/排除 glob、大小与修改时间、.gitignore/.ignore
+
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{Duration, SystemTime};
+
+use fastzip_core::{compress_to_7z, compress_to_tar, compress_to_zip, Codec, CompressOptions};
+use tempfile::TempDir;
+
+mod common;
+use common::create_tree;
+
+/// 类似项目目录的源树
+fn create_project(dir: &Path) -> PathBuf {
+    let big = vec![7u8; 64 * 1024];
+    create_tree(
+        &dir.join("project"),
+        &[".git/objects"],
+        [
+            ("src/main.rs", &b"fn main() {}"[..]),
+            ("src/nested/lib.rs", b"pub fn f() {}"),
+            ("src/scratch.tmp", b"tmp"),
+            ("target/debug/app", &[0u8; 4096]),
+            (".git/HEAD", b"ref: refs/heads/main"),
+            ("node_modules/pkg/index.js", b"module.exports = 1"),
+            ("docs/big.bin", &big),
+            ("docs/secret.key", b"k"),
+            (".gitignore", b"target/\nnode_modules/\n*.tmp\n"),
+            ("docs/.ignore", b"*.key\n"),
+        ],
+    )
+}
+
+fn zip_names(path: &Path) -> BTreeSet<String> {
+    let archive = zip::ZipArchive::new(fs::File::open(path).unwrap()).unwrap();
+    archive.file_names().map(|n| n.trim_end_matches('/').to_string()).collect()
+}
+
+fn tar_gz_names(path: &Path) -> BTreeSet<String> {
+    let gz = flate2::read::GzDecoder::new(fs::File::open(path).unwrap());
+    let mut archive = tar::Archive::new(gz);
+    archive
+        .entries()
+        .unwrap()
+        .map(|e| e.unwrap().path().unwrap().to_string_lossy().trim_end_matches('/').to_string())
+        .collect()
+}
+
+fn sevenz_names(path: &Path) -> BTreeSet<String> {
+    let mut file = fs::File::open(path).unwrap();
+    let len = file.metadata().unwrap().len();
+    let archive = sevenz_rust::Archive::read(&mut file, len, &[]).unwrap();
+    archive.files.iter().map(|f| f.name().to_string()).collect()
+}
+
+/// 三种格式分别压缩并返回条目名集合（仅文件，忽略目录条目）
+fn names_for_all_formats(dir: &Path, src: &Path, options: &CompressOptions) -> Vec<BTreeSet<String>> {
+    let zip = dir.join("out.zip");
+    let tgz = dir.join("out.tar.gz");
+    let sz = dir.join("out.7z");
+    compress_to_zip(&[src], &zip, options).unwrap();
+    compress_to_tar(&[src], &tgz, Some(Codec::Gzip), options).unwrap();
+    compress_to_7z(&[src], &sz, options).unwrap();
+    let files_only = |names: BTreeSet<String>| -> BTreeSet<String> {
+        names.into_iter().filter(|n| src.join(n).is_file()).collect()
+    };
+    vec![
+        files_only(zip_names(&zip)),
+        files_only(tar_gz_names(&tgz)),
+        files_only(sevenz_names(&sz)),
+    ]
+}
+
+fn set(names: &[&str]) -> BTreeSet<String> {
+    names.iter().map(|s| s.to_string()).collect()
+}
+
+#[test]
+fn test_exclude_globs_all_formats() {
+    let dir = TempDir::new().unwrap();
+    let src = create_project(dir.path());
+    let options = CompressOptions {
+        exclude: vec!["target".into(), ".git".into(), "node_modules".into(), "*.tmp".into(), "docs/*.bin".into()],
+        ..Default::default()
+    };
+    let expected = set(&["src/main.rs", "src/nested/lib.rs", ".gitignore", "docs/.ignore", "docs/secret.key"]);
+    for names in names_for_all_formats(dir.path(), &src, &options) {
+        assert_eq!(names, expected);
+    }
+    // 被排除的目录本身也不写入
+    assert!(!zip_names(&dir.path().join("out.zip")).contains("target"));
+}
+
+#[test]
+fn test_include_globs() {
+    let dir = TempDir::new().unwrap();
+    let src = create_project(dir.path());
+    let options = CompressOptions { include: vec!["*.rs".into()], ..Default::default() };
+    for names in names_for_all_formats(dir.path(), &src, &options) {
+        assert_eq!(names, set(&["src/main.rs", "src/nested/lib.rs"]));
+    }
+}
+
+#[test]
+fn test_respect_gitignore() {
+    let dir = TempDir::new().unwrap();
+    let src = create_project(dir.path());
+    let options = CompressOptions { respect_ignore: true, ..Default::default() };
+    let expected = set(&["src/main.rs", "src/nested/lib.rs", ".gitignore", "docs/.ignore", "docs/big.bin"]);
+    for names in names_for_all_formats(dir.path(), &src, &options) {
+        assert_eq!(names, expected);
+    }
+}
+
+#[test]
+fn test_size_and_mtime_filters() {
+    let dir = TempDir::new().unwrap();
+    let src = create_project(dir.path());
+    let old = SystemTime::now() - Duration::from_secs(30 * 86_400);
+    fs::File::options()
+        .write(true)
+        .open(src.join("src/main.rs"))
+        .unwrap()
+        .set_modified(old)
+        .unwrap();
+
+    let options = CompressOptions { min_size: Some(4096), ..Default::default() };
+    for names in names_for_all_formats(dir.path(), &src, &options) {
+        assert_eq!(names, set(&["target/debug/app", "docs/big.bin"]));
+    }
+
+    let options = CompressOptions {
+        max_size: Some(20),
+        older_than: Some(SystemTime::now() - Duration::from_secs(86_400)),
+        ..Default::default()
+    };
+    for names in names_for_all_formats(dir.path(), &src, &options) {
+        assert_eq!(names, set(&["src/main.rs"]));
+    }
+
+    let options = CompressOptions {
+        newer_than: Some(SystemTime::now() - Duration::from_secs(86_400)),
+        include: vec!["*.rs".into()],
+        ..Default::default()
+    };
+    for names in names_for_all_formats(dir.path(), &src, &options) {
+        assert_eq!(names, set(&["src/nested/lib.rs"]));
+    }
+}
+
+#[test]
+fn test_cli_filters() {
+    let dir = TempDir::new().unwrap();
+    let src = create_project(dir.path());
+    let archive = dir.path().join("cli.zip");
+    let status = Command::new(env!("CARGO_BIN_EXE_fastzip"))
+        .args(["c", "-q", "--gitignore", "-x", "docs", "--max-size", "1k", "--newer-than", "7d", "-o"])
+        .arg(&archive)
+        .arg(&src)
+        .status()
+        .unwrap();
+    assert!(status.success());
+    let files: BTreeSet<String> = zip_names(&archive).into_iter().filter(|n| src.join(n).is_file()).collect();
+    assert_eq!(files, set(&["src/main.rs", "src/nested/lib.rs", ".gitignore"]));
+
+    // 批量原地压缩同样按文件名筛选
+    let status = Command::new(env!("CARGO_BIN_EXE_fastzip"))
+        .args(["c", "-q", "--format", "gz", "--include", "*.rs"])
+        .arg(src.join("src/main.rs"))
+        .arg(src.join("src/scratch.tmp"))
+        .status()
+        .unwrap();
+    assert!(status.success());
+    assert!(src.join("src/main.rs.gz").exists());
+    assert!(!src.join("src/scratch.tmp.gz").exists());
+}
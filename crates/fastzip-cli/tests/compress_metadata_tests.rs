@@ -0,0 +1,161 @@
+//! ZIP 元数据：修改时间、Unix 权限与扩展时间戳的记录与解压还原
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use fastzip_core::{compress_to_zip, CompressOptions, ZipEncryption};
+use tempfile::TempDir;
+
+mod common;
+use common::{create_tree, extract};
+
+/// 2020-09-13 12:26:40 UTC
+const MTIME: u64 = 1_600_000_000;
+
+fn at(secs: u64) -> SystemTime {
+    UNIX_EPOCH + Duration::from_secs(secs)
+}
+
+fn set_mtime(path: &Path, secs: u64) {
+    filetime::set_file_mtime(path, filetime::FileTime::from_system_time(at(secs))).unwrap();
+}
+
+fn mtime(path: &Path) -> u64 {
+    fs::metadata(path).unwrap().modified().unwrap().duration_since(UNIX_EPOCH).unwrap().as_secs()
+}
+
+#[cfg(unix)]
+fn set_mode(path: &Path, mode: u32) {
+    use std::os::unix::fs::PermissionsExt;
+    fs::set_permissions(path, fs::Permissions::from_mode(mode)).unwrap();
+}
+
+#[cfg(unix)]
+fn mode(path: &Path) -> u32 {
+    use std::os::unix::fs::PermissionsExt;
+    fs::metadata(path).unwrap().permissions().mode() & 0o7777
+}
+
+/// 含可执行脚本、私有文件与子目录的源树；`run.sh` 的修改时间为奇数秒
+fn create_source(dir: &Path) -> PathBuf {
+    let src = create_tree(&dir.join("src"), &[], [("run.sh", "#!/bin/sh\necho hi\n"), ("sub/secret.txt", "s")]);
+    #[cfg(unix)]
+    {
+        set_mode(&src.join("run.sh"), 0o755);
+        set_mode(&src.join("sub/secret.txt"), 0o600);
+        set_mode(&src.join("sub"), 0o750);
+    }
+    set_mtime(&src.join("run.sh"), MTIME + 1);
+    set_mtime(&src.join("sub/secret.txt"), MTIME);
+    set_mtime(&src.join("sub"), MTIME - 86_400);
+    src
+}
+
+fn assert_metadata(out: &Path, run_sh_mtime: u64) {
+    assert_eq!(mtime(&out.join("run.sh")), run_sh_mtime);
+    assert_eq!(mtime(&out.join("sub/secret.txt")), MTIME);
+    assert_eq!(mtime(&out.join("sub")), MTIME - 86_400);
+    #[cfg(unix)]
+    {
+        assert_eq!(mode(&out.join("run.sh")), 0o755);
+        assert_eq!(mode(&out.join("sub/secret.txt")), 0o600);
+        assert_eq!(mode(&out.join("sub")), 0o750);
+    }
+}
+
+#[test]
+fn test_zip_records_mtime_and_permissions() {
+    let dir = TempDir::new().unwrap();
+    let src = create_source(dir.path());
+    let archive = dir.path().join("meta.zip");
+    let options = CompressOptions { extended_timestamp: false, ..Default::default() };
+    compress_to_zip(&[&src], &archive, &options).unwrap();
+
+    let mut zip = zip::ZipArchive::new(fs::File::open(&archive).unwrap()).unwrap();
+    #[cfg(unix)]
+    assert_eq!(zip.by_name("sub/secret.txt").unwrap().unix_mode().unwrap() & 0o7777, 0o600);
+    assert_eq!(zip.by_name("run.sh").unwrap().extra_data().map_or(0, |d| d.len()), 0);
+
+    // DOS 时间为 2 秒精度：奇数秒向下取整
+    assert_metadata(&extract(&archive, &dir.path().join("x"), None).unwrap(), MTIME);
+}
+
+#[test]
+fn test_dos_time_is_local_time() {
+    let dir = TempDir::new().unwrap();
+    let src = create_source(dir.path());
+    let archive = dir.path().join("local.zip");
+    // 子进程固定时区为东八区（POSIX TZ 写法，无需时区数据库）
+    let run = |args: &[&str]| {
+        let status = Command::new(env!("CARGO_BIN_EXE_fastzip")).env("TZ", "CST-8").args(args).status().unwrap();
+        assert!(status.success());
+    };
+    let (archive_arg, src_arg) = (archive.to_str().unwrap(), src.to_str().unwrap());
+    run(&["c", "-q", "--no-extended-time", "-o", archive_arg, src_arg]);
+
+    // 2020-09-13 12:26:40 UTC 记为本地时间 20:26:40
+    let mut zip = zip::ZipArchive::new(fs::File::open(&archive).unwrap()).unwrap();
+    let t = zip.by_name("sub/secret.txt").unwrap().last_modified().unwrap();
+    assert_eq!((t.year(), t.month(), t.day(), t.hour(), t.minute(), t.second()), (2020, 9, 13, 20, 26, 40));
+
+    // 同一时区解压按本地时间解释，还原原来的时刻
+    let out = dir.path().join("x");
+    run(&["x", "-q", "-f", archive_arg, "-d", out.to_str().unwrap()]);
+    assert_metadata(&out.join("local"), MTIME);
+}
+
+#[test]
+fn test_extended_timestamp() {
+    let dir = TempDir::new().unwrap();
+    let src = create_source(dir.path());
+    // 默认写入；ZipCrypto 条目由本库自行写出，同样带扩展时间戳
+    let cases = [("ext", None, ZipEncryption::Aes256), ("crypto", Some("pw"), ZipEncryption::ZipCrypto)];
+    for (name, password, encryption) in cases {
+        let archive = dir.path().join(format!("{name}.zip"));
+        let options = CompressOptions { password: password.map(String::from), encryption, ..Default::default() };
+        compress_to_zip(&[&src], &archive, &options).unwrap();
+
+        // 0x5455，长度 5，仅修改时间；本地头与中央目录各一处
+        let mut needle = b"\x55\x54\x05\x00\x01".to_vec();
+        needle.extend_from_slice(&((MTIME + 1) as u32).to_le_bytes());
+        let bytes = fs::read(&archive).unwrap();
+        assert_eq!(bytes.windows(needle.len()).filter(|w| *w == needle).count(), 2, "{name}");
+
+        assert_metadata(&extract(&archive, &dir.path().join(name), password).unwrap(), MTIME + 1);
+    }
+}
+
+#[test]
+fn test_encrypted_zip_metadata_roundtrip() {
+    let dir = TempDir::new().unwrap();
+    let src = create_source(dir.path());
+    for encryption in [ZipEncryption::Aes256, ZipEncryption::ZipCrypto] {
+        let archive = dir.path().join(format!("{encryption:?}.zip"));
+        let options = CompressOptions { password: Some("pw".into()), encryption, ..Default::default() };
+        compress_to_zip(&[&src], &archive, &options).unwrap();
+        let out = extract(&archive, &dir.path().join(format!("{encryption:?}")), Some("pw")).unwrap();
+        assert_metadata(&out, MTIME + 1);
+    }
+}
+
+#[test]
+fn test_cli_extended_time() {
+    let dir = TempDir::new().unwrap();
+    let src = create_source(dir.path());
+    // 默认记录扩展时间戳（保留奇数秒），--no-extended-time 只有 DOS 时间
+    for (name, args, expected) in [("on", &[][..], MTIME + 1), ("off", &["--no-extended-time"][..], MTIME)] {
+        let archive = dir.path().join(format!("{name}.zip"));
+        let status = Command::new(env!("CARGO_BIN_EXE_fastzip"))
+            .args(["c", "-q"])
+            .args(args)
+            .arg("-o")
+            .arg(&archive)
+            .arg(&src)
+            .status()
+            .unwrap();
+        assert!(status.success());
+        assert_metadata(&extract(&archive, &dir.path().join(name), None).unwrap(), expected);
+    }
+}
@@ -0,0 +1,148 @@
+//! 压缩方法、级别与预设测试
+
+use std::fs;
+use std::path::Path;
+
+use fastzip_core::{compress_to_7z, compress_to_zip, CompressOptions, CompressionPreset, ZipMethod};
+use tempfile::TempDir;
+
+mod common;
+use common::{create_tree, extract};
+
+fn create_source(dir: &Path) -> std::path::PathBuf {
+    let text: String = (0..20_000).map(|i| format!("row {} value {}\n", i, i % 13)).collect();
+    create_tree(&dir.join("src"), &[], [("a.txt", text.clone()), ("sub/b.txt", text.repeat(2))])
+}
+
+fn zip_methods(path: &Path) -> Vec<zip::CompressionMethod> {
+    let mut archive = zip::ZipArchive::new(fs::File::open(path).unwrap()).unwrap();
+    let mut methods = Vec::new();
+    for i in 0..archive.len() {
+        let file = archive.by_index_raw(i).unwrap();
+        if file.is_file() {
+            methods.push(file.compression());
+        }
+    }
+    methods
+}
+
+fn extract_and_check(archive: &Path, src: &Path, dest: &Path) {
+    let out = extract(archive, dest, None).unwrap();
+    assert_eq!(fs::read(out.join("a.txt")).unwrap(), fs::read(src.join("a.txt")).unwrap());
+    assert_eq!(fs::read(out.join("sub/b.txt")).unwrap(), fs::read(src.join("sub/b.txt")).unwrap());
+}
+
+#[test]
+fn test_zip_methods_roundtrip() {
+    let dir = TempDir::new().unwrap();
+    let src = create_source(dir.path());
+
+    for (method, expected) in [
+        (ZipMethod::Stored, zip::CompressionMethod::Stored),
+        (ZipMethod::Deflate, zip::CompressionMethod::Deflated),
+        (ZipMethod::Bzip2, zip::CompressionMethod::Bzip2),
+        (ZipMethod::Zstd, zip::CompressionMethod::Zstd),
+        (ZipMethod::Xz, zip::CompressionMethod::Xz),
+        (ZipMethod::Lzma, zip::CompressionMethod::Lzma),
+    ] {
+        let archive = dir.path().join(format!("{:?}.zip", method));
+        let options = CompressOptions { method, level: Some(3), ..Default::default() };
+        compress_to_zip(&[&src], &archive, &options).unwrap();
+        assert!(zip_methods(&archive).iter().all(|m| *m == expected), "{:?}", method);
+        extract_and_check(&archive, &src, &dir.path().join(format!("x-{:?}", method)));
+    }
+    assert_eq!(ZipMethod::from_name("xz"), Some(ZipMethod::Xz));
+    assert_eq!(ZipMethod::from_name("lzma"), Some(ZipMethod::Lzma));
+}
+
+#[test]
+fn test_zip_lzma_entry_layout() {
+    let dir = TempDir::new().unwrap();
+    let src = create_source(dir.path());
+    let archive = dir.path().join("lzma.zip");
+    let options = CompressOptions { method: ZipMethod::Lzma, ..Default::default() };
+    compress_to_zip(&[src.join("a.txt")], &archive, &options).unwrap();
+
+    // 本地头：方法 14，置结束标记标志；数据以 SDK 版本与 5 字节属性长度开头
+    let data = fs::read(&archive).unwrap();
+    let name_len = u16::from_le_bytes([data[26], data[27]]) as usize;
+    let extra_len = u16::from_le_bytes([data[28], data[29]]) as usize;
+    assert_eq!(u16::from_le_bytes([data[8], data[9]]), 14);
+    assert_eq!(u16::from_le_bytes([data[6], data[7]]) & 2, 2);
+    assert_eq!(&data[30 + name_len + extra_len..][2..4], &[5, 0]);
+
+    // 中央目录同样标记
+    let central = data.windows(4).position(|w| w == b"PK\x01\x02").unwrap();
+    assert_eq!(u16::from_le_bytes([data[central + 10], data[central + 11]]), 14);
+    assert_eq!(u16::from_le_bytes([data[central + 8], data[central + 9]]) & 2, 2);
+
+    let password = CompressOptions { password: Some("pw".into()), ..options };
+    assert!(compress_to_zip(&[src.join("a.txt")], &dir.path().join("aes.zip"), &password).is_err());
+}
+
+#[test]
+fn test_zip_deflate64_write_unsupported() {
+    let dir = TempDir::new().unwrap();
+    let src = create_source(dir.path());
+    let options = CompressOptions { method: ZipMethod::Deflate64, ..Default::default() };
+    assert!(compress_to_zip(&[&src], &dir.path().join("d64.zip"), &options).is_err());
+}
+
+#[test]
+fn test_presets_map_to_method_and_size() {
+    let dir = TempDir::new().unwrap();
+    let src = create_source(dir.path());
+
+    let mut sizes = Vec::new();
+    for preset in [CompressionPreset::Store, CompressionPreset::Fastest, CompressionPreset::Max] {
+        let options = CompressOptions::default().with_preset(preset);
+        assert_eq!(options.method, preset.zip_method());
+        let archive = dir.path().join(format!("{:?}.zip", preset));
+        compress_to_zip(&[&src], &archive, &options).unwrap();
+        sizes.push(fs::metadata(&archive).unwrap().len());
+    }
+    assert!(sizes[0] > sizes[1] && sizes[0] > sizes[2], "{:?}", sizes);
+
+    // 默认仍为仅存储
+    assert_eq!(CompressOptions::default().method, ZipMethod::Stored);
+}
+
+#[test]
+fn test_7z_levels_roundtrip() {
+    let dir = TempDir::new().unwrap();
+    let src = create_source(dir.path());
+
+    let fast = dir.path().join("fast.7z");
+    let best = dir.path().join("best.7z");
+    compress_to_7z(&[&src], &fast, &CompressOptions::default().with_preset(CompressionPreset::Store)).unwrap();
+    compress_to_7z(&[&src], &best, &CompressOptions::default().with_preset(CompressionPreset::Max)).unwrap();
+    assert!(fs::metadata(&best).unwrap().len() <= fs::metadata(&fast).unwrap().len());
+    extract_and_check(&best, &src, &dir.path().join("x7"));
+}
+
+#[test]
+fn test_cli_no_fast_uses_deflate() {
+    let dir = TempDir::new().unwrap();
+    let src = create_source(dir.path());
+    let archive = dir.path().join("out.zip");
+
+    let status = std::process::Command::new(env!("CARGO_BIN_EXE_fastzip"))
+        .args(["c", "-q", "--no-fast"])
+        .arg(&src)
+        .arg("-o")
+        .arg(&archive)
+        .status()
+        .unwrap();
+    assert!(status.success());
+    assert!(zip_methods(&archive).iter().all(|m| *m == zip::CompressionMethod::Deflated));
+
+    let status = std::process::Command::new(env!("CARGO_BIN_EXE_fastzip"))
+        .args(["c", "-q", "--preset", "max", "-m", "zstd"])
+        .arg(&src)
+        .arg("-o")
+        .arg(&archive)
+        .status()
+        .unwrap();
+    assert!(status.success());
+    assert!(zip_methods(&archive).iter().all(|m| *m == zip::CompressionMethod::Zstd));
+}
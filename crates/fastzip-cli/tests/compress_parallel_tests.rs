@@ -0,0 +1,89 @@
+//! ZIP 并行压缩测试：输出与线程数无关、往返一致
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use fastzip_core::{compress_to_zip, CompressOptions, ZipMethod};
+use tempfile::TempDir;
+
+mod common;
+use common::{create_tree, extract};
+
+fn create_source(dir: &Path) -> PathBuf {
+    let files = (0..4).flat_map(|d| {
+        (0..12).map(move |f| {
+            let text: String = (0..(f * 300 + 1)).map(|n| format!("dir {d} file {f} line {n}\n")).collect();
+            (format!("d{d}/f{f}.txt"), text)
+        })
+    });
+    create_tree(&dir.join("src"), &["empty"], files.chain([("zero.bin".into(), String::new())]))
+}
+
+#[test]
+fn test_output_independent_of_threads() {
+    let dir = TempDir::new().unwrap();
+    let src = create_source(dir.path());
+    for method in [ZipMethod::Deflate, ZipMethod::Zstd, ZipMethod::Stored] {
+        let outputs: Vec<Vec<u8>> = [1, 2, 8, 0]
+            .iter()
+            .map(|&threads| {
+                let archive = dir.path().join(format!("{method:?}-{threads}.zip"));
+                let options = CompressOptions { method, threads, ..Default::default() };
+                compress_to_zip(&[&src], &archive, &options).unwrap();
+                fs::read(&archive).unwrap()
+            })
+            .collect();
+        for bytes in &outputs[1..] {
+            assert!(bytes == &outputs[0], "{method:?} 输出随线程数变化");
+        }
+    }
+}
+
+#[test]
+fn test_parallel_roundtrip_preserves_order() {
+    let dir = TempDir::new().unwrap();
+    let src = create_source(dir.path());
+    let archive = dir.path().join("par.zip");
+    let options = CompressOptions { threads: 4, ..Default::default() };
+    compress_to_zip(&[&src], &archive, &options).unwrap();
+
+    // 父目录条目先于其中的文件
+    let zip = zip::ZipArchive::new(fs::File::open(&archive).unwrap()).unwrap();
+    let names: Vec<&str> = zip.file_names().collect();
+    let mut sorted = names.clone();
+    sorted.sort_by_key(|n| zip.index_for_name(n).unwrap());
+    assert_eq!(names.len(), 4 * 12 + 4 + 2);
+    for d in 0..4 {
+        let dir_pos = sorted.iter().position(|n| *n == format!("d{d}/")).unwrap();
+        let file_pos = sorted.iter().position(|n| *n == format!("d{d}/f0.txt")).unwrap();
+        assert!(dir_pos < file_pos);
+    }
+
+    let out = extract(&archive, &dir.path().join("x"), None).unwrap();
+    for d in 0..4 {
+        for f in 0..12 {
+            let name = format!("d{d}/f{f}.txt");
+            assert_eq!(fs::read(out.join(&name)).unwrap(), fs::read(src.join(&name)).unwrap());
+        }
+    }
+    assert!(out.join("empty").is_dir());
+    assert_eq!(fs::read(out.join("zero.bin")).unwrap(), b"");
+}
+
+#[test]
+fn test_cli_threads() {
+    let dir = TempDir::new().unwrap();
+    let src = create_source(dir.path());
+    for threads in ["1", "3"] {
+        let archive = dir.path().join(format!("cli-{threads}.zip"));
+        let status = Command::new(env!("CARGO_BIN_EXE_fastzip"))
+            .args(["c", "-q", "-j", threads, "-o"])
+            .arg(&archive)
+            .arg(&src)
+            .status()
+            .unwrap();
+        assert!(status.success());
+        assert_eq!(zip::ZipArchive::new(fs::File::open(&archive).unwrap()).unwrap().len(), 54);
+    }
+}
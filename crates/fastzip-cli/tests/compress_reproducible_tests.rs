@@ -0,0 +1,140 @@
+//! 可复现压缩测试：排序、固定时间戳、规范化权限与属主
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use fastzip_core::{compress_to_tar, compress_to_zip, Codec, CompressOptions, ZipMethod};
+use tempfile::TempDir;
+
+const FILES: [&str; 5] = ["b.txt", "a/z.txt", "a/c/d.txt", "A.txt", "tool.sh"];
+
+/// 同样内容的源树；`reverse` 时以相反顺序创建，并使用不同的修改时间与权限
+fn create_tree(root: &Path, reverse: bool) -> PathBuf {
+    fs::create_dir_all(root.join("a/c")).unwrap();
+    fs::create_dir_all(root.join("empty")).unwrap();
+    let mut files = FILES.to_vec();
+    if reverse {
+        files.reverse();
+    }
+    for name in files {
+        fs::write(root.join(name), format!("content of {name}\n").repeat(50)).unwrap();
+        let age = if reverse { 3 * 86_400 } else { 10 };
+        let file = fs::File::options().write(true).open(root.join(name)).unwrap();
+        file.set_modified(SystemTime::now() - Duration::from_secs(age)).unwrap();
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = if reverse { 0o600 } else { 0o664 };
+        fs::set_permissions(root.join("b.txt"), fs::Permissions::from_mode(mode)).unwrap();
+        fs::set_permissions(root.join("tool.sh"), fs::Permissions::from_mode(0o750)).unwrap();
+    }
+    root.to_path_buf()
+}
+
+fn reproducible(epoch: u64) -> CompressOptions {
+    CompressOptions {
+        reproducible: true,
+        fixed_mtime: Some(UNIX_EPOCH + Duration::from_secs(epoch)),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_zip_reproducible() {
+    let dir = TempDir::new().unwrap();
+    let one = create_tree(&dir.path().join("one"), false);
+    let two = create_tree(&dir.path().join("two"), true);
+
+    let first = dir.path().join("1.zip");
+    let second = dir.path().join("2.zip");
+    let options = CompressOptions { method: ZipMethod::Deflate, ..reproducible(1_700_000_000) };
+    compress_to_zip(&[&one], &first, &options).unwrap();
+    compress_to_zip(&[&two], &second, &CompressOptions { threads: 1, ..options.clone() }).unwrap();
+    assert_eq!(fs::read(&first).unwrap(), fs::read(&second).unwrap());
+
+    let mut zip = zip::ZipArchive::new(fs::File::open(&first).unwrap()).unwrap();
+    let names: Vec<String> = zip.file_names().map(String::from).collect();
+    assert_eq!(names, ["A.txt", "a/", "a/c/", "a/c/d.txt", "a/z.txt", "b.txt", "empty/", "tool.sh"]);
+    for i in 0..zip.len() {
+        let entry = zip.by_index(i).unwrap();
+        let t = entry.last_modified().unwrap();
+        assert_eq!((t.year(), t.month(), t.day(), t.hour(), t.minute()), (2023, 11, 14, 22, 13));
+        let expected = if entry.is_dir() || entry.name() == "tool.sh" && cfg!(unix) { 0o755 } else { 0o644 };
+        assert_eq!(entry.unix_mode().unwrap() & 0o777, expected, "{}", entry.name());
+    }
+}
+
+#[test]
+fn test_tar_reproducible() {
+    let dir = TempDir::new().unwrap();
+    let one = create_tree(&dir.path().join("one"), false);
+    let two = create_tree(&dir.path().join("two"), true);
+
+    for codec in [None, Some(Codec::Gzip), Some(Codec::Zstd)] {
+        let first = dir.path().join(format!("1-{codec:?}.tar"));
+        let second = dir.path().join(format!("2-{codec:?}.tar"));
+        compress_to_tar(&[&one], &first, codec, &reproducible(1_234_567_890)).unwrap();
+        compress_to_tar(&[&two], &second, codec, &reproducible(1_234_567_890)).unwrap();
+        assert_eq!(fs::read(&first).unwrap(), fs::read(&second).unwrap(), "{codec:?}");
+    }
+
+    let mut archive = tar::Archive::new(fs::File::open(dir.path().join("1-None.tar")).unwrap());
+    let mut names = Vec::new();
+    for entry in archive.entries().unwrap() {
+        let entry = entry.unwrap();
+        let header = entry.header();
+        assert_eq!(header.mtime().unwrap(), 1_234_567_890);
+        assert_eq!((header.uid().unwrap(), header.gid().unwrap()), (0, 0));
+        assert_eq!(header.username().unwrap(), Some(""));
+        let mode = header.mode().unwrap();
+        assert!(mode == 0o644 || mode == 0o755, "{mode:o}");
+        names.push(entry.path().unwrap().to_string_lossy().trim_end_matches('/').to_string());
+    }
+    assert_eq!(names, ["A.txt", "a", "a/c", "a/c/d.txt", "a/z.txt", "b.txt", "empty", "tool.sh"]);
+}
+
+#[test]
+fn test_reproducible_rejects_password() {
+    let dir = TempDir::new().unwrap();
+    let src = create_tree(&dir.path().join("src"), false);
+    let archive = dir.path().join("enc.zip");
+    let options = CompressOptions { password: Some("pw".into()), ..reproducible(0) };
+    assert!(compress_to_zip(&[&src], &archive, &options).is_err());
+    assert!(!archive.exists());
+}
+
+#[test]
+fn test_cli_source_date_epoch() {
+    let dir = TempDir::new().unwrap();
+    let src = create_tree(&dir.path().join("src"), false);
+    let run = |name: &str, extra: &[&str]| -> u64 {
+        let archive = dir.path().join(name);
+        let status = Command::new(env!("CARGO_BIN_EXE_fastzip"))
+            .args(["c", "-q", "--reproducible"])
+            .args(extra)
+            .arg("-o")
+            .arg(&archive)
+            .arg(&src)
+            .env("SOURCE_DATE_EPOCH", "1600000000")
+            .status()
+            .unwrap();
+        assert!(status.success());
+        let mut tar = tar::Archive::new(fs::File::open(&archive).unwrap());
+        let mtime = tar.entries().unwrap().next().unwrap().unwrap().header().mtime().unwrap();
+        mtime
+    };
+    assert_eq!(run("env.tar", &[]), 1_600_000_000);
+    assert_eq!(run("flag.tar", &["--mtime", "@1500000000"]), 1_500_000_000);
+
+    // --mtime 需配合 --reproducible
+    let status = Command::new(env!("CARGO_BIN_EXE_fastzip"))
+        .args(["c", "-q", "--mtime", "@1", "-o"])
+        .arg(dir.path().join("bad.zip"))
+        .arg(&src)
+        .status()
+        .unwrap();
+    assert!(!status.success());
+}
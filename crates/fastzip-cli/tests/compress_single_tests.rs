@@ -0,0 +1,130 @@
+//! 单文件压缩测试：.gz/.xz/.bz2/.zst 往返、级别、删除原文件、批量原地压缩
+
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use fastzip_core::{compress_single, compress_single_many, Codec, CompressOptions};
+use tempfile::TempDir;
+
+mod common;
+use common::extract;
+
+fn sample() -> Vec<u8> {
+    (0..200_000u32).flat_map(|i| format!("line {} {}\n", i, i % 7).into_bytes()).collect()
+}
+
+fn decompress(path: &Path, dest: &Path) -> Vec<u8> {
+    let dir = extract(path, dest, None).unwrap();
+    let name = path.file_stem().unwrap();
+    fs::read(dir.join(name)).unwrap()
+}
+
+#[test]
+fn test_compress_single_all_codecs_roundtrip() {
+    let dir = TempDir::new().unwrap();
+    let src = dir.path().join("app.log");
+    fs::write(&src, sample()).unwrap();
+
+    for codec in [Codec::Gzip, Codec::Xz, Codec::Bzip2, Codec::Zstd] {
+        let dest = dir.path().join(format!("app.log.{}", codec.extension()));
+        compress_single(&src, &dest, codec, &CompressOptions::default()).unwrap();
+        assert!(fs::metadata(&dest).unwrap().len() < sample().len() as u64);
+
+        let out = dir.path().join(format!("out-{}", codec.extension()));
+        assert_eq!(decompress(&dest, &out), sample(), "{:?}", codec);
+    }
+    assert!(src.exists(), "默认保留原文件");
+}
+
+#[test]
+fn test_compress_single_level_and_delete_original() {
+    let dir = TempDir::new().unwrap();
+    let src = dir.path().join("data.txt");
+    fs::write(&src, sample()).unwrap();
+
+    let fast = dir.path().join("fast.zst");
+    let best = dir.path().join("best.zst");
+    let level = |level| CompressOptions { level: Some(level), ..Default::default() };
+    compress_single(&src, &fast, Codec::Zstd, &level(1)).unwrap();
+    compress_single(&src, &best, Codec::Zstd, &level(12)).unwrap();
+    assert!(fs::metadata(&best).unwrap().len() <= fs::metadata(&fast).unwrap().len());
+
+    let dest = dir.path().join("data.txt.gz");
+    let options = CompressOptions { keep_original: false, ..Default::default() };
+    compress_single(&src, &dest, Codec::Gzip, &options).unwrap();
+    assert!(!src.exists());
+    assert!(dest.exists());
+
+    // 输出已存在时报错且不改动已有文件，显式覆盖时才写入
+    fs::write(&src, sample()).unwrap();
+    let error = compress_single(&src, &dest, Codec::Gzip, &CompressOptions::default()).unwrap_err();
+    assert!(error.to_string().contains("已存在"), "{error}");
+    assert!(src.exists());
+    assert_eq!(decompress(&dest, &dir.path().join("existing")), sample());
+    fs::write(&dest, b"stale").unwrap();
+    let overwrite = CompressOptions { overwrite: true, ..Default::default() };
+    compress_single(&src, &dest, Codec::Gzip, &overwrite).unwrap();
+    assert_eq!(decompress(&dest, &dir.path().join("overwritten")), sample());
+
+    // 源文件不存在时报错且不留下输出
+    let missing = dir.path().join("missing.txt");
+    assert!(compress_single(&missing, &dir.path().join("missing.txt.gz"), Codec::Gzip, &options).is_err());
+    assert!(!dir.path().join("missing.txt.gz").exists());
+}
+
+#[test]
+fn test_compress_single_many_in_place() {
+    let dir = TempDir::new().unwrap();
+    let files: Vec<_> = (0..4)
+        .map(|i| {
+            let p = dir.path().join(format!("f{i}.log"));
+            fs::write(&p, format!("content {i}").repeat(1000)).unwrap();
+            p
+        })
+        .collect();
+
+    let options = CompressOptions { threads: 2, ..Default::default() };
+    let results = compress_single_many(&files, Codec::Xz, &options).unwrap();
+    assert_eq!(results.len(), files.len());
+    for ((src, result), expected) in results.into_iter().zip(&files) {
+        assert_eq!(&src, expected, "结果保持输入顺序");
+        let dest = result.unwrap();
+        assert_eq!(dest, dir.path().join(format!("{}.xz", src.file_name().unwrap().to_string_lossy())));
+        assert!(dest.exists());
+    }
+
+    // 再次压缩：已存在的输出逐个报错
+    let results = compress_single_many(&files, Codec::Xz, &options).unwrap();
+    assert!(results.iter().all(|(_, result)| result.is_err()));
+}
+
+#[test]
+fn test_cli_compress_single_and_batch() {
+    let dir = TempDir::new().unwrap();
+    let log = dir.path().join("file.log");
+    fs::write(&log, sample()).unwrap();
+
+    let status = Command::new(env!("CARGO_BIN_EXE_fastzip"))
+        .args(["compress", "-q"])
+        .arg(&log)
+        .arg("-o")
+        .arg(dir.path().join("file.log.zst"))
+        .status()
+        .unwrap();
+    assert!(status.success());
+    assert!(dir.path().join("file.log.zst").exists());
+
+    let other = dir.path().join("other.log");
+    fs::write(&other, b"other").unwrap();
+    let status = Command::new(env!("CARGO_BIN_EXE_fastzip"))
+        .args(["c", "-q", "--format", "gz", "--delete-original", "-l", "9"])
+        .arg(&log)
+        .arg(&other)
+        .status()
+        .unwrap();
+    assert!(status.success());
+    assert!(dir.path().join("file.log.gz").exists());
+    assert!(dir.path().join("other.log.gz").exists());
+    assert!(!log.exists() && !other.exists());
+}
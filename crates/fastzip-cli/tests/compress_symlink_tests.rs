@@ -0,0 +1,196 @@
+//! 压缩时的符号链接处理：存储、跟随（循环检测）、跳过
+#![cfg(unix)]
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Read;
+use std::os::unix::fs::symlink;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use fastzip_core::{compress_to_7z, compress_to_tar, compress_to_zip, CompressOptions, SymlinkPolicy, ZipEncryption};
+use tempfile::TempDir;
+
+mod common;
+use common::{create_tree, extract};
+
+/// 含文件链接、目录链接、指向根目录的循环链接与失效链接的源树
+fn create_source(dir: &Path) -> PathBuf {
+    let src = create_tree(&dir.join("src"), &[], [("real/data.txt", "payload")]);
+    symlink("real/data.txt", src.join("link_file")).unwrap();
+    symlink("real", src.join("link_dir")).unwrap();
+    symlink(".", src.join("loop")).unwrap();
+    symlink("nowhere", src.join("broken")).unwrap();
+    src
+}
+
+/// ZIP 条目：名称 -> 符号链接目标（非链接为 None）
+fn zip_entries(path: &Path) -> BTreeMap<String, Option<String>> {
+    let mut zip = zip::ZipArchive::new(fs::File::open(path).unwrap()).unwrap();
+    (0..zip.len())
+        .map(|i| {
+            let mut entry = zip.by_index(i).unwrap();
+            let name = entry.name().trim_end_matches('/').to_string();
+            let target = entry.is_symlink().then(|| {
+                let mut target = String::new();
+                entry.read_to_string(&mut target).unwrap();
+                target
+            });
+            (name, target)
+        })
+        .collect()
+}
+
+/// tar 条目：名称 -> 符号链接目标（非链接为 None）
+fn tar_entries(path: &Path) -> BTreeMap<String, Option<String>> {
+    let mut archive = tar::Archive::new(fs::File::open(path).unwrap());
+    archive
+        .entries()
+        .unwrap()
+        .map(|e| {
+            let e = e.unwrap();
+            let name = e.path().unwrap().to_string_lossy().trim_end_matches('/').to_string();
+            let target = e.header().entry_type().is_symlink().then(|| {
+                e.link_name().unwrap().unwrap().to_string_lossy().into_owned()
+            });
+            (name, target)
+        })
+        .collect()
+}
+
+fn link(target: &str) -> Option<String> {
+    Some(target.to_string())
+}
+
+#[test]
+fn test_store_symlinks() {
+    let dir = TempDir::new().unwrap();
+    let src = create_source(dir.path());
+    let expected: BTreeMap<String, Option<String>> = [
+        ("real", None),
+        ("real/data.txt", None),
+        ("link_file", link("real/data.txt")),
+        ("link_dir", link("real")),
+        ("loop", link(".")),
+        ("broken", link("nowhere")),
+    ]
+    .into_iter()
+    .map(|(k, v)| (k.to_string(), v))
+    .collect();
+
+    let zip = dir.path().join("out.zip");
+    compress_to_zip(&[&src], &zip, &CompressOptions::default()).unwrap();
+    assert_eq!(zip_entries(&zip), expected);
+
+    let tar = dir.path().join("out.tar");
+    compress_to_tar(&[&src], &tar, None, &CompressOptions::default()).unwrap();
+    assert_eq!(tar_entries(&tar), expected);
+    let reproducible = CompressOptions { reproducible: true, ..Default::default() };
+    compress_to_tar(&[&src], &tar, None, &reproducible).unwrap();
+    assert_eq!(tar_entries(&tar), expected);
+
+    // 7z：p7zip 约定的链接属性，链接目标为内容
+    let sz = dir.path().join("out.7z");
+    compress_to_7z(&[&src], &sz, &CompressOptions::default()).unwrap();
+    let mut file = fs::File::open(&sz).unwrap();
+    let len = file.metadata().unwrap().len();
+    let archive = sevenz_rust::Archive::read(&mut file, len, &[]).unwrap();
+    let entry = archive.files.iter().find(|f| f.name() == "link_dir").unwrap();
+    assert_eq!(entry.windows_attributes() >> 16 & 0o170000, 0o120000);
+    assert_eq!(entry.size(), 4);
+
+    // 解压时重建链接
+    let out = extract(&zip, &dir.path().join("x"), None).unwrap();
+    assert_eq!(fs::read_link(out.join("link_dir")).unwrap(), Path::new("real"));
+    assert_eq!(fs::read_to_string(out.join("link_file")).unwrap(), "payload");
+    assert!(fs::symlink_metadata(out.join("broken")).unwrap().file_type().is_symlink());
+}
+
+#[test]
+fn test_follow_symlinks_with_cycle() {
+    let dir = TempDir::new().unwrap();
+    let src = create_source(dir.path());
+    fs::remove_file(src.join("broken")).unwrap();
+    let options = CompressOptions { symlinks: SymlinkPolicy::Follow, ..Default::default() };
+    let expected: BTreeMap<String, Option<String>> =
+        ["real", "real/data.txt", "link_file", "link_dir", "link_dir/data.txt"]
+            .into_iter()
+            .map(|k| (k.to_string(), None))
+            .collect();
+
+    let zip = dir.path().join("out.zip");
+    compress_to_zip(&[&src], &zip, &options).unwrap();
+    assert_eq!(zip_entries(&zip), expected);
+    let out = extract(&zip, &dir.path().join("x"), None).unwrap();
+    assert_eq!(fs::read_to_string(out.join("link_dir/data.txt")).unwrap(), "payload");
+    assert!(!fs::symlink_metadata(out.join("link_file")).unwrap().file_type().is_symlink());
+
+    let tar = dir.path().join("out.tar");
+    compress_to_tar(&[&src], &tar, None, &options).unwrap();
+    assert_eq!(tar_entries(&tar), expected);
+
+    // 顶层源本身为链接时打包其指向的目录
+    let top = dir.path().join("top");
+    symlink(&src, &top).unwrap();
+    compress_to_zip(&[&top], &zip, &options).unwrap();
+    assert_eq!(zip_entries(&zip), expected);
+}
+
+#[test]
+fn test_skip_symlinks() {
+    let dir = TempDir::new().unwrap();
+    let src = create_source(dir.path());
+    let options = CompressOptions { symlinks: SymlinkPolicy::Skip, ..Default::default() };
+    let expected: BTreeMap<String, Option<String>> =
+        ["real", "real/data.txt"].into_iter().map(|k| (k.to_string(), None)).collect();
+
+    let zip = dir.path().join("out.zip");
+    compress_to_zip(&[&src, &src.join("link_file")], &zip, &options).unwrap();
+    assert_eq!(zip_entries(&zip), expected);
+    let tar = dir.path().join("out.tar");
+    compress_to_tar(&[&src], &tar, None, &options).unwrap();
+    assert_eq!(tar_entries(&tar), expected);
+    let sz = dir.path().join("out.7z");
+    compress_to_7z(&[&src], &sz, &options).unwrap();
+    let mut file = fs::File::open(&sz).unwrap();
+    let len = file.metadata().unwrap().len();
+    let archive = sevenz_rust::Archive::read(&mut file, len, &[]).unwrap();
+    assert_eq!(archive.files.len(), 2);
+}
+
+#[test]
+fn test_encrypted_symlinks_roundtrip() {
+    let dir = TempDir::new().unwrap();
+    let src = create_source(dir.path());
+    for encryption in [ZipEncryption::Aes256, ZipEncryption::ZipCrypto] {
+        let zip = dir.path().join(format!("{encryption:?}.zip"));
+        let options = CompressOptions { password: Some("pw".into()), encryption, ..Default::default() };
+        compress_to_zip(&[&src], &zip, &options).unwrap();
+        let out = extract(&zip, &dir.path().join(format!("{encryption:?}")), Some("pw")).unwrap();
+        assert_eq!(fs::read_link(out.join("link_dir")).unwrap(), Path::new("real"), "{encryption:?}");
+        assert_eq!(fs::read_to_string(out.join("link_file")).unwrap(), "payload");
+    }
+}
+
+#[test]
+fn test_cli_symlinks() {
+    let dir = TempDir::new().unwrap();
+    let src = create_source(dir.path());
+    let archive = dir.path().join("cli.zip");
+    let status = Command::new(env!("CARGO_BIN_EXE_fastzip"))
+        .args(["c", "-q", "--symlinks", "skip", "-o"])
+        .arg(&archive)
+        .arg(&src)
+        .status()
+        .unwrap();
+    assert!(status.success());
+    assert_eq!(zip_entries(&archive).len(), 2);
+
+    let status = Command::new(env!("CARGO_BIN_EXE_fastzip"))
+        .args(["c", "-q", "--symlinks", "sometimes", "-o"])
+        .arg(dir.path().join("bad.zip"))
+        .arg(&src)
+        .status()
+        .unwrap();
+    assert!(!status.success());
+}
@@ -0,0 +1,60 @@
+//! tar.* 压缩测试：各编码往返、权限与符号链接
+
+use std::fs;
+use std::path::Path;
+
+use fastzip_core::{compress_to_tar, Codec, CompressOptions};
+use tempfile::TempDir;
+
+mod common;
+use common::{create_tree, extract};
+
+fn create_source(dir: &Path) -> std::path::PathBuf {
+    let src = create_tree(&dir.join("proj"), &[], [("README", "hello tar"), ("bin/run.sh", "#!/bin/sh\necho hi\n")]);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(src.join("bin/run.sh"), fs::Permissions::from_mode(0o755)).unwrap();
+        std::os::unix::fs::symlink("bin/run.sh", src.join("run")).unwrap();
+    }
+    src
+}
+
+#[test]
+fn test_compress_tar_all_codecs_roundtrip() {
+    let dir = TempDir::new().unwrap();
+    let src = create_source(dir.path());
+
+    for (name, codec) in [
+        ("out.tar", None),
+        ("out.tar.gz", Some(Codec::Gzip)),
+        ("out.tar.xz", Some(Codec::Xz)),
+        ("out.tar.bz2", Some(Codec::Bzip2)),
+        ("out.tar.zst", Some(Codec::Zstd)),
+    ] {
+        let archive = dir.path().join(name);
+        compress_to_tar(&[&src], &archive, codec, &CompressOptions::default()).unwrap();
+
+        let dest = extract(&archive, &dir.path().join(format!("x-{name}")), None).unwrap();
+        assert_eq!(fs::read(dest.join("README")).unwrap(), b"hello tar", "{name}");
+        assert_eq!(fs::read(dest.join("bin/run.sh")).unwrap(), b"#!/bin/sh\necho hi\n", "{name}");
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn test_compress_tar_keeps_permissions_and_symlinks() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = TempDir::new().unwrap();
+    let src = create_source(dir.path());
+    let archive = dir.path().join("out.tgz");
+    compress_to_tar(&[&src], &archive, Some(Codec::Gzip), &CompressOptions::default()).unwrap();
+
+    let dest = extract(&archive, &dir.path().join("x"), None).unwrap();
+    let mode = fs::metadata(dest.join("bin/run.sh")).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o755);
+    let link = dest.join("run");
+    assert!(fs::symlink_metadata(&link).unwrap().file_type().is_symlink());
+    assert_eq!(fs::read_link(&link).unwrap(), Path::new("bin/run.sh"));
+}
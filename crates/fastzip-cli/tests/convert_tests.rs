@@ -0,0 +1,173 @@
+//! 归档格式转换：ZIP、7z、tar.* 之间逐条目流式转换，保留路径、时间与权限
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{Duration, UNIX_EPOCH};
+
+use fastzip_core::{
+    compress_to_7z, compress_to_tar, compress_to_zip, convert_archive, Codec, CompressOptions, ZipEncryption,
+};
+use tempfile::TempDir;
+
+mod common;
+use common::{create_tree, extract};
+
+const MTIME: u64 = 1_600_000_000;
+
+/// 源树：bin/run.sh（0755）、data/a.txt、data/empty.txt、空目录 logs，以及指向 data 的链接
+fn create_source(dir: &Path) -> PathBuf {
+    let text = "alpha\n".repeat(1000);
+    let files = [("bin/run.sh", "#!/bin/sh\n"), ("data/a.txt", &text), ("data/empty.txt", "")];
+    let src = create_tree(&dir.join("src"), &["logs"], files);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(src.join("bin/run.sh"), fs::Permissions::from_mode(0o755)).unwrap();
+        std::os::unix::fs::symlink("data", src.join("link")).unwrap();
+    }
+    for name in ["bin/run.sh", "data/a.txt", "data/empty.txt"] {
+        let time = filetime::FileTime::from_system_time(UNIX_EPOCH + Duration::from_secs(MTIME));
+        filetime::set_file_mtime(src.join(name), time).unwrap();
+    }
+    src
+}
+
+/// 解压后的文件树：相对路径 -> 内容（目录为 "<dir>"，链接为 "-> 目标"）
+fn tree(root: &Path) -> BTreeMap<String, String> {
+    let mut out = BTreeMap::new();
+    let mut stack = vec![root.to_path_buf()];
+    while let Some(dir) = stack.pop() {
+        for entry in fs::read_dir(&dir).unwrap() {
+            let path = entry.unwrap().path();
+            let rel = path.strip_prefix(root).unwrap().to_string_lossy().replace('\\', "/");
+            let meta = fs::symlink_metadata(&path).unwrap();
+            let value = if meta.file_type().is_symlink() {
+                format!("-> {}", fs::read_link(&path).unwrap().display())
+            } else if meta.is_dir() {
+                stack.push(path);
+                "<dir>".to_string()
+            } else {
+                fs::read_to_string(&path).unwrap()
+            };
+            out.insert(rel, value);
+        }
+    }
+    out
+}
+
+fn mtime(path: &Path) -> u64 {
+    fs::metadata(path).unwrap().modified().unwrap().duration_since(UNIX_EPOCH).unwrap().as_secs()
+}
+
+#[test]
+fn test_convert_chain_keeps_content_and_metadata() {
+    let dir = TempDir::new().unwrap();
+    let src = create_source(dir.path());
+    let expected = tree(&src);
+    let zip = dir.path().join("src.zip");
+    compress_to_zip(&[&src], &zip, &CompressOptions::default()).unwrap();
+
+    // zip -> tar.gz -> 7z -> tar.xz -> zip
+    let chain = ["a.tar.gz", "b.7z", "c.tar.xz", "d.zip"];
+    let mut previous = zip;
+    for name in chain {
+        let next = dir.path().join(name);
+        let count = convert_archive(&previous, &next, None, &CompressOptions::default()).unwrap();
+        assert_eq!(count as usize, expected.len(), "{name}");
+
+        let out = extract(&next, &dir.path().join(format!("x-{name}")), None).unwrap();
+        let mut actual = tree(&out);
+        if name.ends_with(".7z") {
+            // 7z 解压不还原链接，链接仍由下一步转换读出
+            actual.remove("link");
+            let mut expected = expected.clone();
+            expected.remove("link");
+            assert_eq!(actual, expected, "{name}");
+        } else {
+            assert_eq!(actual, expected, "{name}");
+        }
+        assert_eq!(mtime(&out.join("data/a.txt")), MTIME, "{name}");
+        #[cfg(unix)]
+        if !name.ends_with(".7z") {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(out.join("bin/run.sh")).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o755, "{name}");
+        }
+        previous = next;
+    }
+}
+
+#[test]
+fn test_convert_tar_to_zip_records_metadata() {
+    let dir = TempDir::new().unwrap();
+    let src = create_source(dir.path());
+    let tar = dir.path().join("src.tar.zst");
+    compress_to_tar(&[&src], &tar, Some(Codec::Zstd), &CompressOptions::default()).unwrap();
+    let zip = dir.path().join("out.zip");
+    convert_archive(&tar, &zip, None, &CompressOptions::default()).unwrap();
+
+    let mut archive = zip::ZipArchive::new(fs::File::open(&zip).unwrap()).unwrap();
+    let entry = archive.by_name("bin/run.sh").unwrap();
+    let extended = entry.extra_data_fields().find_map(|field| match field {
+        zip::extra_fields::ExtraField::ExtendedTimestamp(ts) => ts.mod_time(),
+        _ => None,
+    });
+    assert_eq!(extended, Some(MTIME as u32));
+    #[cfg(unix)]
+    assert_eq!(entry.unix_mode().unwrap() & 0o777, 0o755);
+    drop(entry);
+    assert!(archive.by_name("logs/").unwrap().is_dir());
+    #[cfg(unix)]
+    assert!(archive.by_name("link").unwrap().is_symlink());
+}
+
+#[test]
+fn test_convert_with_passwords_and_filter() {
+    let dir = TempDir::new().unwrap();
+    let src = create_source(dir.path());
+    let sz = dir.path().join("secret.7z");
+    let options = CompressOptions { password: Some("old".into()), ..Default::default() };
+    compress_to_7z(&[&src], &sz, &options).unwrap();
+
+    // 缺少源密码时失败且不留下输出
+    let out = dir.path().join("out.zip");
+    assert!(convert_archive(&sz, &out, None, &CompressOptions::default()).is_err());
+    assert!(!out.exists());
+
+    for encryption in [ZipEncryption::Aes256, ZipEncryption::ZipCrypto] {
+        let out = dir.path().join(format!("{encryption:?}.zip"));
+        let options = CompressOptions {
+            password: Some("new".into()),
+            encryption,
+            exclude: vec!["logs".into(), "*.sh".into()],
+            ..Default::default()
+        };
+        convert_archive(&sz, &out, Some("old"), &options).unwrap();
+        let tree = tree(&extract(&out, &dir.path().join(format!("x-{encryption:?}")), Some("new")).unwrap());
+        assert_eq!(tree["data/a.txt"], "alpha\n".repeat(1000));
+        assert!(!tree.contains_key("logs") && !tree.contains_key("bin/run.sh"), "{encryption:?}");
+    }
+}
+
+#[test]
+fn test_cli_convert() {
+    let dir = TempDir::new().unwrap();
+    let src = create_source(dir.path());
+    let zip = dir.path().join("src.zip");
+    compress_to_zip(&[&src], &zip, &CompressOptions::default()).unwrap();
+    let fastzip = || Command::new(env!("CARGO_BIN_EXE_fastzip"));
+
+    let out = dir.path().join("out.tar.gz");
+    let status = fastzip().args(["convert", "-q"]).arg(&zip).arg("-o").arg(&out).status().unwrap();
+    assert!(status.success());
+    assert_eq!(tree(&extract(&out, &dir.path().join("x"), None).unwrap()), tree(&src));
+
+    // 不支持的输出格式、与源相同的路径
+    let status = fastzip().args(["convert", "-q"]).arg(&zip).arg("-o").arg(dir.path().join("out.gz")).status();
+    assert!(!status.unwrap().success());
+    let status = fastzip().args(["convert", "-q"]).arg(&zip).arg("-o").arg(&zip).status().unwrap();
+    assert!(!status.success());
+    assert!(zip.exists());
+}
@@ -0,0 +1,149 @@
+//! 归档比较：两个归档或归档与目录之间的新增、删除、内容修改与仅元数据变化
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{Duration, UNIX_EPOCH};
+
+use fastzip_core::{
+    compress_to_7z, compress_to_tar, compress_to_zip, diff_archives, ArchiveDiff, ChangeKind, Codec,
+    CompressOptions,
+};
+use tempfile::TempDir;
+
+const MTIME: u64 = 1_600_000_000;
+
+fn write(root: &Path, name: &str, content: &str) {
+    let path = root.join(name);
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(&path, content).unwrap();
+    let time = filetime::FileTime::from_system_time(UNIX_EPOCH + Duration::from_secs(MTIME));
+    filetime::set_file_mtime(&path, time).unwrap();
+}
+
+/// v1：README、src/lib.rs、src/main.rs、docs/old.txt、bin/run.sh
+fn create_v1(dir: &Path) -> PathBuf {
+    let root = dir.join("v1");
+    write(&root, "README", "readme v1");
+    write(&root, "src/lib.rs", "pub fn a() {}");
+    write(&root, "src/main.rs", "fn main() {}");
+    write(&root, "docs/old.txt", "old");
+    write(&root, "bin/run.sh", "#!/bin/sh");
+    root
+}
+
+/// v2：README 同长度改内容、src/lib.rs 变长、删除 docs/old.txt、新增 docs/new.txt，
+/// bin/run.sh 只改修改时间（Unix 上另改权限）
+fn create_v2(dir: &Path) -> PathBuf {
+    let root = dir.join("v2");
+    write(&root, "README", "readme v2");
+    write(&root, "src/lib.rs", "pub fn a() {}\npub fn b() {}");
+    write(&root, "src/main.rs", "fn main() {}");
+    write(&root, "docs/new.txt", "new");
+    write(&root, "bin/run.sh", "#!/bin/sh");
+    let time = filetime::FileTime::from_system_time(UNIX_EPOCH + Duration::from_secs(MTIME + 3600));
+    filetime::set_file_mtime(root.join("bin/run.sh"), time).unwrap();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(root.join("bin/run.sh"), fs::Permissions::from_mode(0o755)).unwrap();
+    }
+    root
+}
+
+fn changes(diff: &ArchiveDiff) -> Vec<(&str, ChangeKind)> {
+    diff.changes.iter().map(|c| (c.path.as_str(), c.change)).collect()
+}
+
+fn expected() -> Vec<(&'static str, ChangeKind)> {
+    vec![
+        ("README", ChangeKind::Modified),
+        ("bin/run.sh", ChangeKind::Metadata),
+        ("docs/new.txt", ChangeKind::Added),
+        ("docs/old.txt", ChangeKind::Removed),
+        ("src/lib.rs", ChangeKind::Modified),
+    ]
+}
+
+#[test]
+fn test_diff_archive_formats_and_directories() {
+    let dir = TempDir::new().unwrap();
+    let (v1, v2) = (create_v1(dir.path()), create_v2(dir.path()));
+    let zip = dir.path().join("v1.zip");
+    compress_to_zip(&[&v1], &zip, &CompressOptions::default()).unwrap();
+    let tgz = dir.path().join("v2.tar.gz");
+    compress_to_tar(&[&v2], &tgz, Some(Codec::Gzip), &CompressOptions::default()).unwrap();
+    let sz = dir.path().join("v2.7z");
+    compress_to_7z(&[&v2], &sz, &CompressOptions::default()).unwrap();
+
+    for (old, new) in [(&zip, &tgz), (&zip, &v2), (&v1, &tgz)] {
+        let diff = diff_archives(old, new, None).unwrap();
+        assert_eq!(changes(&diff), expected(), "{} {}", old.display(), new.display());
+        // src/、src/main.rs、bin/、docs/
+        assert_eq!(diff.unchanged, 4);
+    }
+
+    let diff = diff_archives(&zip, &tgz, None).unwrap();
+    let lib = diff.changes.into_iter().find(|c| c.path == "src/lib.rs").unwrap();
+    assert_eq!((lib.old.unwrap().size, lib.new.unwrap().size), (13, 27));
+
+    // 7z 与同一目录、归档与其源目录均无差异
+    assert!(diff_archives(&sz, &v2, None).unwrap().is_empty());
+    let same = diff_archives(&zip, &v1, None).unwrap();
+    assert!(same.is_empty());
+    assert_eq!(same.unchanged, 8);
+}
+
+#[test]
+fn test_diff_encrypted_and_errors() {
+    let dir = TempDir::new().unwrap();
+    let v1 = create_v1(dir.path());
+    let zip = dir.path().join("secret.zip");
+    let options = CompressOptions { password: Some("pw".into()), ..Default::default() };
+    compress_to_zip(&[&v1], &zip, &options).unwrap();
+
+    assert!(diff_archives(&zip, &v1, None).is_err());
+    assert!(diff_archives(&zip, &v1, Some("pw")).unwrap().is_empty());
+    assert!(diff_archives(&zip, &dir.path().join("missing.zip"), None).is_err());
+}
+
+#[test]
+fn test_cli_diff() {
+    let dir = TempDir::new().unwrap();
+    let (v1, v2) = (create_v1(dir.path()), create_v2(dir.path()));
+    let zip = dir.path().join("v1.zip");
+    compress_to_zip(&[&v1], &zip, &CompressOptions::default()).unwrap();
+    let fastzip = || Command::new(env!("CARGO_BIN_EXE_fastzip"));
+
+    let output = fastzip().arg("diff").arg(&zip).arg(&v2).output().unwrap();
+    assert!(output.status.success());
+    let text = String::from_utf8(output.stdout).unwrap();
+    let lines: Vec<&str> = text.lines().collect();
+    assert!(lines[0].starts_with("M README  (CRC32 "), "{}", lines[0]);
+    assert!(lines.contains(&"+ docs/new.txt"));
+    assert!(lines.contains(&"- docs/old.txt"));
+    assert!(lines.contains(&"M src/lib.rs  (13 -> 27 字节)"));
+    assert!(lines.iter().any(|l| l.starts_with("m bin/run.sh  (")));
+    assert_eq!(lines.last().unwrap(), &"新增 1，删除 1，修改 2，仅元数据 1，相同 4");
+
+    let output = fastzip().args(["diff", "--json"]).arg(&zip).arg(&v2).output().unwrap();
+    assert!(output.status.success());
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["unchanged"], 4);
+    let changes = report["changes"].as_array().unwrap();
+    let kinds: Vec<(&str, &str)> =
+        changes.iter().map(|c| (c["path"].as_str().unwrap(), c["change"].as_str().unwrap())).collect();
+    assert_eq!(
+        kinds,
+        [
+            ("README", "modified"),
+            ("bin/run.sh", "metadata"),
+            ("docs/new.txt", "added"),
+            ("docs/old.txt", "removed"),
+            ("src/lib.rs", "modified"),
+        ]
+    );
+    assert!(changes[2]["old"].is_null());
+    assert_eq!(changes[2]["new"]["type"], "file");
+    assert_eq!(changes[2]["new"]["size"], 3);
+}
@@ -0,0 +1,327 @@
+//! 删除、重命名归档内的条目：ZIP 原样复制其余条目，tar.* 流式重写
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use fastzip_core::{
+    compress_to_tar, compress_to_zip, delete_entries, rename_entry, Codec, CompressOptions, ZipEncryption, ZipMethod,
+};
+use tempfile::TempDir;
+
+mod common;
+use common::create_tree;
+
+/// 源树：README.md、config/app.toml、config/secret.pem、keys/id.pem、docs/guide.txt
+fn create_source(dir: &Path) -> PathBuf {
+    create_tree(
+        &dir.join("src"),
+        &[],
+        [
+            ("README.md", "readme"),
+            ("config/app.toml", "name = 'app'"),
+            ("config/secret.pem", "-----BEGIN KEY-----"),
+            ("keys/id.pem", "id"),
+            ("docs/guide.txt", "guide"),
+        ],
+    )
+}
+
+fn strings(items: &[&str]) -> Vec<String> {
+    items.iter().map(|s| s.to_string()).collect()
+}
+
+/// 条目名 -> 原始压缩数据
+fn zip_raw(path: &Path) -> BTreeMap<String, Vec<u8>> {
+    let bytes = fs::read(path).unwrap();
+    let mut zip = zip::ZipArchive::new(fs::File::open(path).unwrap()).unwrap();
+    (0..zip.len())
+        .map(|i| {
+            let entry = zip.by_index_raw(i).unwrap();
+            let start = entry.data_start() as usize;
+            (entry.name().to_string(), bytes[start..start + entry.compressed_size() as usize].to_vec())
+        })
+        .collect()
+}
+
+/// 文件条目名 -> 内容
+fn tar_files(path: &Path, codec: Option<Codec>) -> BTreeMap<String, String> {
+    let file = fs::File::open(path).unwrap();
+    let reader: Box<dyn Read> = match codec {
+        Some(Codec::Gzip) => Box::new(flate2::read::MultiGzDecoder::new(file)),
+        Some(Codec::Xz) => Box::new(xz2::read::XzDecoder::new_multi_decoder(file)),
+        _ => Box::new(file),
+    };
+    let mut archive = tar::Archive::new(reader);
+    let mut files = BTreeMap::new();
+    for entry in archive.entries().unwrap() {
+        let mut entry = entry.unwrap();
+        if entry.header().entry_type().is_file() {
+            let name = entry.path().unwrap().to_string_lossy().into_owned();
+            let mut content = String::new();
+            entry.read_to_string(&mut content).unwrap();
+            files.insert(name, content);
+        }
+    }
+    files
+}
+
+fn keys<V>(map: &BTreeMap<String, V>) -> Vec<&str> {
+    map.keys().map(String::as_str).collect()
+}
+
+#[test]
+fn test_zip_delete_with_globs() {
+    let dir = TempDir::new().unwrap();
+    let src = create_source(dir.path());
+    let archive = dir.path().join("out.zip");
+    let options = CompressOptions { method: ZipMethod::Deflate, ..Default::default() };
+    compress_to_zip(&[&src], &archive, &options).unwrap();
+    let before = zip_raw(&archive);
+
+    let mut deleted = delete_entries(&archive, &strings(&["*.pem", "docs"])).unwrap();
+    deleted.sort();
+    assert_eq!(deleted, ["config/secret.pem", "docs/", "docs/guide.txt", "keys/id.pem"]);
+
+    let after = zip_raw(&archive);
+    assert_eq!(keys(&after), ["README.md", "config/", "config/app.toml", "keys/"]);
+    for (name, data) in &after {
+        assert_eq!(data, &before[name], "{name}");
+    }
+
+    // 没有匹配时报错，归档不变
+    let bytes = fs::read(&archive).unwrap();
+    assert!(delete_entries(&archive, &strings(&["nothing*"])).is_err());
+    assert_eq!(fs::read(&archive).unwrap(), bytes);
+}
+
+#[test]
+fn test_zip_rename() {
+    let dir = TempDir::new().unwrap();
+    let src = create_source(dir.path());
+    let archive = dir.path().join("out.zip");
+    let options = CompressOptions { password: Some("pw".into()), ..Default::default() };
+    compress_to_zip(&[&src], &archive, &options).unwrap();
+    let before = zip_raw(&archive);
+
+    let mut renamed = rename_entry(&archive, "config", "settings/prod").unwrap();
+    renamed.sort();
+    assert_eq!(
+        renamed,
+        [
+            ("config/".to_string(), "settings/prod/".to_string()),
+            ("config/app.toml".to_string(), "settings/prod/app.toml".to_string()),
+            ("config/secret.pem".to_string(), "settings/prod/secret.pem".to_string()),
+        ]
+    );
+    let after = zip_raw(&archive);
+    // 加密数据原样保留，改名后仍可解密
+    assert_eq!(after["settings/prod/app.toml"], before["config/app.toml"]);
+    let mut zip = zip::ZipArchive::new(fs::File::open(&archive).unwrap()).unwrap();
+    let mut content = String::new();
+    zip.by_name_decrypt("settings/prod/app.toml", b"pw").unwrap().read_to_string(&mut content).unwrap();
+    assert_eq!(content, "name = 'app'");
+    drop(zip);
+
+    // 与已有条目重名、非法路径均拒绝
+    assert!(rename_entry(&archive, "README.md", "keys/id.pem").is_err());
+    assert!(rename_entry(&archive, "README.md", "../x").is_err());
+    assert!(rename_entry(&archive, "missing", "x").is_err());
+}
+
+#[test]
+fn test_zipcrypto_delete() {
+    let dir = TempDir::new().unwrap();
+    let src = create_source(dir.path());
+    let archive = dir.path().join("zc.zip");
+    let options = CompressOptions {
+        password: Some("pw".into()),
+        encryption: ZipEncryption::ZipCrypto,
+        ..Default::default()
+    };
+    compress_to_zip(&[&src], &archive, &options).unwrap();
+    delete_entries(&archive, &strings(&["config/secret.pem"])).unwrap();
+    let mut zip = zip::ZipArchive::new(fs::File::open(&archive).unwrap()).unwrap();
+    assert!(zip.by_name("config/secret.pem").is_err());
+    let mut content = String::new();
+    zip.by_name_decrypt("keys/id.pem", b"pw").unwrap().read_to_string(&mut content).unwrap();
+    assert_eq!(content, "id");
+}
+
+#[test]
+fn test_tar_delete_and_rename() {
+    let dir = TempDir::new().unwrap();
+    let src = create_source(dir.path());
+    // 超过 100 字节的路径需 GNU 长名扩展
+    let long = format!("{}/deep.txt", "d".repeat(120));
+    fs::create_dir_all(src.join(&long).parent().unwrap()).unwrap();
+    fs::write(src.join(&long), "deep").unwrap();
+
+    for (codec, ext) in [(None, "tar"), (Some(Codec::Gzip), "tar.gz"), (Some(Codec::Xz), "tar.xz")] {
+        let archive = dir.path().join(format!("out.{ext}"));
+        compress_to_tar(&[&src], &archive, codec, &CompressOptions::default()).unwrap();
+
+        let deleted = delete_entries(&archive, &strings(&["*.pem"])).unwrap();
+        assert_eq!(deleted.len(), 2, "{ext}");
+        let renamed = rename_entry(&archive, "README.md", "docs/README.md").unwrap();
+        assert_eq!(renamed.len(), 1);
+        rename_entry(&archive, &long, "short.txt").unwrap();
+
+        let files = tar_files(&archive, codec);
+        assert_eq!(keys(&files), ["config/app.toml", "docs/README.md", "docs/guide.txt", "short.txt"], "{ext}");
+        assert_eq!(files["docs/README.md"], "readme");
+        assert_eq!(files["short.txt"], "deep");
+    }
+
+    let sz = dir.path().join("out.7z");
+    fastzip_core::compress_to_7z(&[&src], &sz, &CompressOptions::default()).unwrap();
+    assert!(delete_entries(&sz, &strings(&["*.pem"])).is_err());
+}
+
+/// 按 BGZF 分块压缩（每块一个带 `BC` 扩展字段的 gzip 成员）
+fn bgzf(data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::new();
+    for chunk in data.chunks(60_000) {
+        let mut enc = flate2::GzBuilder::new()
+            .extra(vec![b'B', b'C', 2, 0, 0, 0])
+            .write(Vec::new(), flate2::Compression::fast());
+        enc.write_all(chunk).unwrap();
+        let mut member = enc.finish().unwrap();
+        let bsize = (member.len() - 1) as u16;
+        member[16..18].copy_from_slice(&bsize.to_le_bytes());
+        out.extend(member);
+    }
+    out
+}
+
+/// a.txt、b.txt 带 PAX 记录（纳秒时间、xattr），link 为指向 a.txt 的硬链接，另有一个稀疏条目
+fn pax_tar() -> Vec<u8> {
+    let mut builder = tar::Builder::new(Vec::new());
+    for (name, data) in [("a.txt", &b"alpha"[..]), ("b.txt", &b"beta"[..])] {
+        builder
+            .append_pax_extensions([
+                ("mtime", &b"1600000000.123456789"[..]),
+                ("SCHILY.xattr.user.tag", &b"v"[..]),
+            ])
+            .unwrap();
+        let mut header = tar::Header::new_ustar();
+        header.set_size(data.len() as u64);
+        header.set_mode(0o644);
+        builder.append_data(&mut header, name, data).unwrap();
+    }
+    let mut header = tar::Header::new_ustar();
+    header.set_entry_type(tar::EntryType::Link);
+    header.set_size(0);
+    builder.append_link(&mut header, "link", "a.txt").unwrap();
+    let mut header = tar::Header::new_gnu();
+    header.set_entry_type(tar::EntryType::GNUSparse);
+    header.set_size(0);
+    header.as_gnu_mut().unwrap().set_real_size(0);
+    builder.append_data(&mut header, "holes.bin", &[][..]).unwrap();
+    builder.into_inner().unwrap()
+}
+
+#[test]
+fn test_tar_keeps_extension_records() {
+    let dir = TempDir::new().unwrap();
+    let tar = pax_tar();
+    let archive = dir.path().join("pax.tar.gz");
+    fs::write(&archive, bgzf(&tar)).unwrap();
+
+    // 删除硬链接的目标或稀疏条目均报错，归档不变
+    let bytes = fs::read(&archive).unwrap();
+    assert!(delete_entries(&archive, &strings(&["a.txt"])).is_err());
+    assert!(delete_entries(&archive, &strings(&["holes.bin"])).is_err());
+    assert!(rename_entry(&archive, "holes.bin", "h.bin").is_err());
+    assert_eq!(fs::read(&archive).unwrap(), bytes);
+
+    rename_entry(&archive, "b.txt", "renamed/b.txt").unwrap();
+    rename_entry(&archive, "a.txt", "first.txt").unwrap();
+    // 仍为 BGZF：首个成员带 `BC` 扩展字段，以空的 EOF 块结尾
+    let bytes = fs::read(&archive).unwrap();
+    assert_eq!(&bytes[12..14], b"BC");
+    assert_eq!(&bytes[bytes.len() - 8..], &[0; 8]);
+
+    let mut out = Vec::new();
+    flate2::read::MultiGzDecoder::new(&bytes[..]).read_to_end(&mut out).unwrap();
+    let mut archive = tar::Archive::new(&out[..]);
+    let mut seen = Vec::new();
+    for entry in archive.entries().unwrap() {
+        let mut entry = entry.unwrap();
+        let name = entry.path().unwrap().to_string_lossy().into_owned();
+        if name.ends_with(".txt") {
+            // 改名后其余 PAX 记录保留
+            let pax: Vec<(String, Vec<u8>)> = entry
+                .pax_extensions()
+                .unwrap()
+                .unwrap()
+                .map(|e| e.unwrap())
+                .map(|e| (e.key().unwrap().to_string(), e.value_bytes().to_vec()))
+                .filter(|(key, _)| key != "path")
+                .collect();
+            assert_eq!(pax.len(), 2, "{name}");
+            assert_eq!(pax[0].1, b"1600000000.123456789");
+        }
+        let link = entry.link_name().unwrap().map(|l| l.to_string_lossy().into_owned());
+        seen.push((name, link));
+    }
+    assert_eq!(
+        seen,
+        [
+            ("first.txt".to_string(), None),
+            ("renamed/b.txt".to_string(), None),
+            ("link".to_string(), Some("first.txt".to_string())),
+            ("holes.bin".to_string(), None),
+        ]
+    );
+    // 未改动的稀疏条目按原始字节复制
+    assert_eq!(&out[out.len() - 1536..out.len() - 1024], &tar[tar.len() - 1536..tar.len() - 1024]);
+}
+
+#[test]
+fn test_tar_zstd_stays_seekable() {
+    let dir = TempDir::new().unwrap();
+    let src = create_source(dir.path());
+    let archive = dir.path().join("out.tar.zst");
+    compress_to_tar(&[&src], &archive, Some(Codec::Zstd), &CompressOptions::default()).unwrap();
+    delete_entries(&archive, &strings(&["*.pem"])).unwrap();
+    // seek table 尾部魔数
+    let bytes = fs::read(&archive).unwrap();
+    assert_eq!(&bytes[bytes.len() - 4..], &0x8F92_EAB1u32.to_le_bytes());
+}
+
+#[cfg(unix)]
+#[test]
+fn test_rewrite_keeps_permissions() {
+    use std::os::unix::fs::PermissionsExt;
+    let dir = TempDir::new().unwrap();
+    let src = create_source(dir.path());
+    let archive = dir.path().join("out.zip");
+    compress_to_zip(&[&src], &archive, &CompressOptions::default()).unwrap();
+    fs::set_permissions(&archive, fs::Permissions::from_mode(0o640)).unwrap();
+    delete_entries(&archive, &strings(&["docs"])).unwrap();
+    assert_eq!(fs::metadata(&archive).unwrap().permissions().mode() & 0o777, 0o640);
+}
+
+#[test]
+fn test_cli_delete_and_rename() {
+    let dir = TempDir::new().unwrap();
+    let src = create_source(dir.path());
+    let archive = dir.path().join("cli.zip");
+    compress_to_zip(&[&src], &archive, &CompressOptions::default()).unwrap();
+    let fastzip = || Command::new(env!("CARGO_BIN_EXE_fastzip"));
+
+    let status = fastzip().args(["delete", "-q"]).arg(&archive).arg("*.pem").status().unwrap();
+    assert!(status.success());
+    let status = fastzip().args(["rename", "-q"]).arg(&archive).args(["docs", "manual"]).status().unwrap();
+    assert!(status.success());
+    assert_eq!(
+        keys(&zip_raw(&archive)),
+        ["README.md", "config/", "config/app.toml", "keys/", "manual/", "manual/guide.txt"]
+    );
+
+    let status = fastzip().args(["delete", "-q"]).arg(&archive).arg("*.pem").status().unwrap();
+    assert!(!status.success());
+}
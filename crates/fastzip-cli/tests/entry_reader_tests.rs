@@ -0,0 +1,97 @@
+//! 单条目随机读取：各格式的 `open_entry` 返回 `Read + Seek`，支持 `归档!/条目` 路径
+
+use std::fs;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+use fastzip_core::formats::{SevenZExtractor, ZipExtractor};
+use fastzip_core::{
+    compress_to_7z, compress_to_tar, compress_to_zip, open_entry, open_entry_path, ArchiveFormat, CompressOptions,
+    FastZipError, ZipMethod,
+};
+use tempfile::TempDir;
+
+mod common;
+use common::create_tree;
+
+fn create_source(dir: &Path) -> (PathBuf, Vec<u8>) {
+    let big: Vec<u8> = (0..300_000u32).flat_map(|i| (i / 7).to_le_bytes()).collect();
+    let src = create_tree(&dir.join("src"), &[], [("db/app.sqlite", &big[..]), ("readme.txt", b"hello")]);
+    (src, big)
+}
+
+fn read_at(reader: &mut (impl Read + Seek), pos: SeekFrom, len: usize) -> Vec<u8> {
+    reader.seek(pos).unwrap();
+    let mut buf = Vec::new();
+    reader.take(len as u64).read_to_end(&mut buf).unwrap();
+    buf
+}
+
+#[test]
+fn test_open_entry_formats() {
+    let dir = TempDir::new().unwrap();
+    let (src, big) = create_source(dir.path());
+    let deflate = CompressOptions { method: ZipMethod::Deflate, ..Default::default() };
+    let archives = [
+        ("stored.zip", CompressOptions::default(), None),
+        ("deflate.zip", deflate.clone(), None),
+        ("bzip2.zip", CompressOptions { method: ZipMethod::Bzip2, ..Default::default() }, None),
+        ("secret.zip", CompressOptions { password: Some("pw".into()), ..deflate }, Some("pw")),
+        ("plain.tar", CompressOptions::default(), None),
+        ("packed.tar.gz", CompressOptions::default(), None),
+        ("packed.tar.zst", CompressOptions::default(), None),
+        ("packed.7z", CompressOptions::default(), None),
+    ];
+    for (name, options, password) in archives {
+        let archive = dir.path().join(name);
+        match name {
+            n if n.ends_with(".zip") => compress_to_zip(&[&src], &archive, &options).unwrap(),
+            n if n.ends_with(".7z") => compress_to_7z(&[&src], &archive, &options).unwrap(),
+            _ => {
+                let codec = ArchiveFormat::from_extension(&archive).and_then(|f| f.codec());
+                compress_to_tar(&[&src], &archive, codec, &options).unwrap()
+            }
+        }
+        let mut reader = open_entry(&archive, "db/app.sqlite", password).unwrap();
+        assert_eq!(reader.len(), big.len() as u64, "{name}");
+        // 向前跳、向回定位、相对末尾定位、越过末尾
+        assert_eq!(read_at(&mut reader, SeekFrom::Start(1_000_000), 64), big[1_000_000..1_000_064], "{name}");
+        assert_eq!(read_at(&mut reader, SeekFrom::Start(100), 16), big[100..116], "{name}");
+        assert_eq!(read_at(&mut reader, SeekFrom::Current(84), 16), big[200..216], "{name}");
+        assert_eq!(read_at(&mut reader, SeekFrom::End(-10), 64), big[big.len() - 10..], "{name}");
+        assert!(read_at(&mut reader, SeekFrom::Start(5_000_000), 16).is_empty(), "{name}");
+
+        let mut text = String::new();
+        open_entry(&archive, "/readme.txt", password).unwrap().read_to_string(&mut text).unwrap();
+        assert_eq!(text, "hello", "{name}");
+        assert!(matches!(open_entry(&archive, "missing", password), Err(FastZipError::FileNotFound(_))), "{name}");
+        assert!(open_entry(&archive, "db", password).is_err(), "{name}");
+    }
+
+    let secret = dir.path().join("secret.zip");
+    assert!(matches!(open_entry(&secret, "readme.txt", None), Err(FastZipError::PasswordRequired)));
+    assert!(matches!(
+        ZipExtractor::open_entry(&secret, "readme.txt", Some("wrong")),
+        Err(FastZipError::PasswordRequired)
+    ));
+    let mut reader = SevenZExtractor::open_entry(&dir.path().join("packed.7z"), "readme.txt", None).unwrap();
+    assert_eq!(read_at(&mut reader, SeekFrom::Start(1), 10), b"ello");
+}
+
+#[test]
+fn test_open_entry_path() {
+    let dir = TempDir::new().unwrap();
+    let (src, big) = create_source(dir.path());
+    // 目录名本身含 `!/` 时以第一个左侧为文件的分隔为准
+    let nested = dir.path().join("a!");
+    fs::create_dir_all(&nested).unwrap();
+    let archive = nested.join("data.zip");
+    let options = CompressOptions { method: ZipMethod::Deflate, ..Default::default() };
+    compress_to_zip(&[&src], &archive, &options).unwrap();
+
+    let spec = format!("{}!/db/app.sqlite", archive.display());
+    let mut reader = open_entry_path(&spec, None).unwrap();
+    assert_eq!(read_at(&mut reader, SeekFrom::Start(1_100_000), 8), big[1_100_000..1_100_008]);
+    assert!(open_entry_path(&archive.display().to_string(), None).is_err());
+    assert!(open_entry_path(&format!("{}!/readme.txt", dir.path().join("none.zip").display()), None).is_err());
+}
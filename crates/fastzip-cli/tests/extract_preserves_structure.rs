@@ -40,6 +40,7 @@ fn test_nested_directories_preserved() {
         smart: true,
         overwrite: false,
         password: None,
+        ..Default::default()
     };
 
     let result = extract_one(&zip_path, &options).unwrap();
@@ -39,6 +39,7 @@ fn test_tar_gz_extract() {
         smart: true,
         overwrite: false,
         password: None,
+        ..Default::default()
     };
 
     let result = extract_one(&path, &options).unwrap();
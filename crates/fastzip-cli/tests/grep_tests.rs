@@ -0,0 +1,146 @@
+//! 归档内容搜索：逐条目按行匹配，报告条目与行号，跳过二进制条目，嵌套的单文件压缩边读边解压
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use fastzip_core::{compress_single, compress_to_tar, compress_to_zip, grep_archives, Codec, CompressOptions, GrepOptions};
+use tempfile::TempDir;
+
+mod common;
+use common::create_tree;
+
+/// logs/ 下两个文本日志、一个 gzip 压缩的日志和一个二进制文件
+fn create_logs(dir: &Path) -> PathBuf {
+    let mut gz = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    gz.write_all(b"boot\nboot\nERROR timeout\n").unwrap();
+    let files = [
+        ("app.log", &b"start\nERROR disk full\nok\n"[..]),
+        ("web.log", b"GET /\nGET /health\n"),
+        ("old/app.log.gz", &gz.finish().unwrap()),
+        ("core.bin", b"ERROR\0\x01\x02"),
+    ];
+    create_tree(&dir.join("logs"), &[], files)
+}
+
+/// (归档文件名, 条目, 行号, 内容)
+fn search(archives: &[&Path], pattern: &str, options: &GrepOptions) -> Vec<(String, String, u64, String)> {
+    let mut found = Vec::new();
+    let results = grep_archives(archives, pattern, options, |archive, m| {
+        let name = archive.file_name().unwrap().to_string_lossy().into_owned();
+        found.push((name, m.entry.clone(), m.line_number, m.line.clone()));
+        Ok(())
+    })
+    .unwrap();
+    assert!(results.iter().all(|r| r.is_ok()));
+    found
+}
+
+fn row(archive: &str, entry: &str, line: u64, text: &str) -> (String, String, u64, String) {
+    (archive.into(), entry.into(), line, text.into())
+}
+
+#[test]
+fn test_grep_archives() {
+    let dir = TempDir::new().unwrap();
+    let logs = create_logs(dir.path());
+    let zip = dir.path().join("logs.zip");
+    compress_to_zip(&[&logs], &zip, &CompressOptions::default()).unwrap();
+    let tgz = dir.path().join("logs.tar.gz");
+    compress_to_tar(&[&logs], &tgz, Some(Codec::Gzip), &CompressOptions::default()).unwrap();
+
+    let mut found = search(&[&zip, &tgz], "ERROR", &GrepOptions::default());
+    found.sort();
+    assert_eq!(
+        found,
+        [
+            row("logs.tar.gz", "app.log", 2, "ERROR disk full"),
+            row("logs.tar.gz", "old/app.log.gz", 3, "ERROR timeout"),
+            row("logs.zip", "app.log", 2, "ERROR disk full"),
+            row("logs.zip", "old/app.log.gz", 3, "ERROR timeout"),
+        ]
+    );
+
+    let options = GrepOptions { ignore_case: true, ..Default::default() };
+    let summary = grep_archives(&[&zip], "error", &options, |_, _| Ok(())).unwrap().remove(0).unwrap();
+    assert_eq!((summary.entries, summary.matched_entries, summary.matches), (4, 2, 2));
+    assert_eq!(summary.binary, ["core.bin"]);
+
+    // 找到第一处匹配即停止，后面的归档不再搜索
+    let options = GrepOptions { stop_on_match: true, ..Default::default() };
+    let mut matches = 0;
+    let results = grep_archives(&[&zip, &tgz], "ERROR", &options, |_, _| {
+        matches += 1;
+        Ok(())
+    })
+    .unwrap();
+    assert_eq!(matches, 1);
+    assert_eq!(results[0].as_ref().unwrap().matches, 1);
+    assert_eq!(results[1].as_ref().unwrap().entries, 0);
+
+    // 二进制按文本搜索、条目筛选
+    let options = GrepOptions { binary: true, include: vec!["*.bin".into()], ..Default::default() };
+    assert_eq!(search(&[&zip], "ERROR", &options), [row("logs.zip", "core.bin", 1, "ERROR\0\u{1}\u{2}")]);
+    let options = GrepOptions { exclude: vec!["old/*".into()], ..Default::default() };
+    assert_eq!(search(&[&tgz], "ERROR", &options).len(), 1);
+
+    // 无效模式
+    assert!(grep_archives(&[&zip], "(", &GrepOptions::default(), |_, _| Ok(())).is_err());
+}
+
+#[test]
+fn test_grep_single_files_and_errors() {
+    let dir = TempDir::new().unwrap();
+    let logs = create_logs(dir.path());
+    let xz = dir.path().join("app.log.xz");
+    compress_single(&logs.join("app.log"), &xz, Codec::Xz, &CompressOptions::default()).unwrap();
+    let plain = logs.join("web.log");
+    assert_eq!(
+        search(&[&xz, &plain], "ERROR|health", &GrepOptions::default()),
+        [row("app.log.xz", "app.log", 2, "ERROR disk full"), row("web.log", "web.log", 2, "GET /health")]
+    );
+
+    let secret = dir.path().join("secret.zip");
+    let options = CompressOptions { password: Some("pw".into()), ..Default::default() };
+    compress_to_zip(&[&logs], &secret, &options).unwrap();
+    let missing = dir.path().join("missing.zip");
+    let results = grep_archives(&[&secret, &missing], "ERROR", &GrepOptions::default(), |_, _| Ok(())).unwrap();
+    assert!(results[0].is_err() && results[1].is_err());
+    let options = GrepOptions { password: Some("pw".into()), ..Default::default() };
+    assert_eq!(search(&[&secret], "ERROR", &options).len(), 2);
+}
+
+#[test]
+fn test_cli_grep() {
+    let dir = TempDir::new().unwrap();
+    let logs = create_logs(dir.path());
+    let zip = dir.path().join("logs.zip");
+    compress_to_zip(&[&logs], &zip, &CompressOptions::default()).unwrap();
+    let fastzip = || Command::new(env!("CARGO_BIN_EXE_fastzip"));
+    let stdout = |output: std::process::Output| String::from_utf8(output.stdout).unwrap();
+
+    let output = fastzip().args(["grep", "-i", "error"]).arg(&zip).output().unwrap();
+    assert!(output.status.success());
+    assert_eq!(stdout(output), "app.log:2:ERROR disk full\nold/app.log.gz:3:ERROR timeout\n");
+
+    // -c 也列出没有匹配的条目
+    let output = fastzip().args(["grep", "-c", "boot|ERROR"]).arg(&zip).arg(&zip).output().unwrap();
+    let zip_name = zip.display().to_string();
+    let mut counts: Vec<String> = stdout(output).lines().map(String::from).collect();
+    counts.sort();
+    let expected = ["app.log:1", "core.bin:0", "old/app.log.gz:3", "web.log:0"].map(|c| format!("{zip_name}:{c}"));
+    assert_eq!(counts, expected.iter().flat_map(|c| [c.clone(), c.clone()]).collect::<Vec<_>>());
+
+    let output = fastzip().args(["grep", "-l", "-H", "GET"]).arg(&zip).output().unwrap();
+    assert_eq!(stdout(output), format!("{zip_name}:web.log\n"));
+
+    // 没有匹配时退出状态为 1，出错为 2；-q 找到匹配即停止，不再打开后面的归档
+    let output = fastzip().args(["grep", "-q", "nothing"]).arg(&zip).output().unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    let output = fastzip().args(["grep", "-q", "-c", "ERROR"]).arg(&zip).arg(dir.path().join("missing.zip")).output();
+    let output = output.unwrap();
+    assert_eq!(output.status.code(), Some(0));
+    assert!(output.stdout.is_empty() && output.stderr.is_empty());
+    let output = fastzip().args(["grep", "ERROR"]).arg(dir.path().join("missing.zip")).output().unwrap();
+    assert_eq!(output.status.code(), Some(2));
+}
@@ -0,0 +1,165 @@
+//! 条目哈希与校验清单：归档与目录逐文件 SHA-256 / BLAKE3，sha256sum 兼容清单的写出与校验
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use fastzip_core::{
+    compress_single, compress_to_7z, compress_to_tar, compress_to_zip, extract_with_hashes, hash_entries,
+    verify_manifest, write_manifest, Codec, CompressOptions, ExtractOptions, HashAlgorithm,
+};
+use tempfile::TempDir;
+
+mod common;
+use common::create_tree;
+
+const ABC_SHA256: &str = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+const ABC_BLAKE3: &str = "6437b3ac38465133ffb63b75273a8db548c558465d79db03fd359c6cd5bd9d85";
+
+fn create_source(dir: &Path) -> PathBuf {
+    let big = vec![7u8; 300_000];
+    create_tree(&dir.join("src"), &["docs/empty"], [("abc.txt", &b"abc"[..]), ("docs/big.bin", &big)])
+}
+
+fn write_manifest_file(path: &Path, target: &Path, algorithm: HashAlgorithm) {
+    let hashes = hash_entries(target, algorithm, None).unwrap();
+    let mut out = Vec::new();
+    write_manifest(&mut out, &hashes).unwrap();
+    fs::write(path, out).unwrap();
+}
+
+#[test]
+fn test_hash_archives_match_directory() {
+    let dir = TempDir::new().unwrap();
+    let src = create_source(dir.path());
+    let zip = dir.path().join("out.zip");
+    compress_to_zip(&[&src], &zip, &CompressOptions::default()).unwrap();
+    let tzst = dir.path().join("out.tar.zst");
+    compress_to_tar(&[&src], &tzst, Some(Codec::Zstd), &CompressOptions::default()).unwrap();
+    let sz = dir.path().join("out.7z");
+    compress_to_7z(&[&src], &sz, &CompressOptions::default()).unwrap();
+
+    for algorithm in [HashAlgorithm::Sha256, HashAlgorithm::Blake3] {
+        let expected = hash_entries(&src, algorithm, None).unwrap();
+        // 只含文件，按路径排序
+        let paths: Vec<&str> = expected.iter().map(|h| h.path.as_str()).collect();
+        assert_eq!(paths, ["abc.txt", "docs/big.bin"]);
+        let abc = if algorithm == HashAlgorithm::Sha256 { ABC_SHA256 } else { ABC_BLAKE3 };
+        assert_eq!(expected[0].digest, abc);
+        for archive in [&zip, &tzst, &sz] {
+            assert_eq!(hash_entries(archive, algorithm, None).unwrap(), expected, "{}", archive.display());
+        }
+    }
+
+    // 加密归档需要密码
+    let secret = dir.path().join("secret.zip");
+    let options = CompressOptions { password: Some("pw".into()), ..Default::default() };
+    compress_to_zip(&[&src], &secret, &options).unwrap();
+    assert!(hash_entries(&secret, HashAlgorithm::Sha256, None).is_err());
+    assert_eq!(hash_entries(&secret, HashAlgorithm::Sha256, Some("pw")).unwrap()[0].digest, ABC_SHA256);
+}
+
+#[test]
+fn test_extract_with_hashes_matches_archive() {
+    let dir = TempDir::new().unwrap();
+    let src = create_source(dir.path());
+    let zip = dir.path().join("out.zip");
+    compress_to_zip(&[&src], &zip, &CompressOptions::default()).unwrap();
+    let secret = dir.path().join("secret.zip");
+    let options = CompressOptions { password: Some("pw".into()), ..Default::default() };
+    compress_to_zip(&[&src], &secret, &options).unwrap();
+    let sz = dir.path().join("out.7z");
+    compress_to_7z(&[&src], &sz, &CompressOptions::default()).unwrap();
+    let tzst = dir.path().join("out.tar.zst");
+    compress_to_tar(&[&src], &tzst, Some(Codec::Zstd), &CompressOptions::default()).unwrap();
+
+    let expected = hash_entries(&src, HashAlgorithm::Blake3, None).unwrap();
+    for (i, archive) in [&zip, &secret, &sz, &tzst].into_iter().enumerate() {
+        for smart in [true, false] {
+            let options = ExtractOptions {
+                dest: Some(dir.path().join(format!("dest-{i}-{smart}"))),
+                smart,
+                password: Some("pw".into()),
+                ..Default::default()
+            };
+            let (dest, hashes) = extract_with_hashes(archive, &options, HashAlgorithm::Blake3).unwrap();
+            assert_eq!(hashes, expected, "{}", archive.display());
+            assert_eq!(fs::read(dest.join("abc.txt")).unwrap(), b"abc");
+        }
+    }
+
+    // 单文件压缩没有条目
+    let gz = dir.path().join("abc.txt.gz");
+    compress_single(&src.join("abc.txt"), &gz, Codec::Gzip, &CompressOptions::default()).unwrap();
+    assert!(extract_with_hashes(&gz, &ExtractOptions::default(), HashAlgorithm::Sha256).is_err());
+}
+
+#[test]
+fn test_verify_manifest() {
+    let dir = TempDir::new().unwrap();
+    let src = create_source(dir.path());
+    let zip = dir.path().join("out.zip");
+    compress_to_zip(&[&src], &zip, &CompressOptions::default()).unwrap();
+    let manifest = dir.path().join("SHA256SUMS");
+    write_manifest_file(&manifest, &zip, HashAlgorithm::Sha256);
+    assert_eq!(
+        fs::read_to_string(&manifest).unwrap().lines().next().unwrap(),
+        format!("{ABC_SHA256}  abc.txt")
+    );
+
+    let report = verify_manifest(&src, &manifest, HashAlgorithm::Sha256, None).unwrap();
+    assert!(report.is_ok());
+    assert_eq!(report.ok, 2);
+
+    // 改内容、删文件、多出文件
+    fs::write(src.join("abc.txt"), "abd").unwrap();
+    fs::remove_file(src.join("docs/big.bin")).unwrap();
+    fs::write(src.join("new.txt"), "new").unwrap();
+    let report = verify_manifest(&src, &manifest, HashAlgorithm::Sha256, None).unwrap();
+    assert!(!report.is_ok());
+    assert_eq!(report.ok, 0);
+    assert_eq!(report.mismatched, ["abc.txt"]);
+    assert_eq!(report.missing, ["docs/big.bin"]);
+    assert_eq!(report.extra, ["new.txt"]);
+
+    // 算法不一致时全部不匹配
+    let report = verify_manifest(&zip, &manifest, HashAlgorithm::Blake3, None).unwrap();
+    assert_eq!(report.mismatched.len(), 2);
+    assert!(verify_manifest(&zip, &dir.path().join("missing"), HashAlgorithm::Sha256, None).is_err());
+}
+
+#[test]
+fn test_cli_hash_extract_and_verify() {
+    let dir = TempDir::new().unwrap();
+    let src = create_source(dir.path());
+    let archive = dir.path().join("cli.tar.gz");
+    compress_to_tar(&[&src], &archive, Some(Codec::Gzip), &CompressOptions::default()).unwrap();
+    let fastzip = || Command::new(env!("CARGO_BIN_EXE_fastzip"));
+
+    let output = fastzip().arg("hash").arg(&archive).output().unwrap();
+    assert!(output.status.success());
+    let text = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(text.lines().next().unwrap(), format!("{ABC_SHA256}  abc.txt"));
+    let output = fastzip().args(["hash", "--algo", "blake3"]).arg(&archive).output().unwrap();
+    assert!(String::from_utf8(output.stdout).unwrap().starts_with(ABC_BLAKE3));
+    assert!(!fastzip().args(["hash", "--algo", "md5"]).arg(&archive).status().unwrap().success());
+
+    // 解压时写出清单，再用清单校验解压出的目录与归档本身
+    let dest = dir.path().join("dest");
+    let manifest = dir.path().join("SHA256SUMS");
+    let mut extract = fastzip();
+    extract.args(["extract", "-q", "--flat", "-d"]).arg(&dest).arg("--manifest").arg(&manifest).arg(&archive);
+    assert!(extract.status().unwrap().success());
+    assert_eq!(fs::read_to_string(&manifest).unwrap(), text);
+
+    let tree = dest.join("cli");
+    let output = fastzip().arg("verify").arg(&tree).arg("--manifest").arg(&manifest).output().unwrap();
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8(output.stdout).unwrap().lines().last().unwrap(), "一致 2，不一致 0，缺失 0，清单外 0");
+    assert!(fastzip().arg("verify").arg(&archive).arg("-m").arg(&manifest).status().unwrap().success());
+
+    fs::write(tree.join("abc.txt"), "tampered").unwrap();
+    let output = fastzip().arg("verify").arg(&tree).arg("--manifest").arg(&manifest).output().unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8(output.stdout).unwrap().contains("abc.txt: 失败"));
+}
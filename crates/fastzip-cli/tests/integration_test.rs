@@ -36,6 +36,7 @@ fn test_extract_zip_smart() {
         smart: true,
         overwrite: false,
         password: None,
+        ..Default::default()
     };
 
     let result = extract_one(&zip_path, &options).unwrap();
@@ -55,6 +56,7 @@ fn test_extract_zip_single_file_smart_dest() {
         smart: true,
         overwrite: false,
         password: None,
+        ..Default::default()
     };
 
     let result = extract_one(&zip_path, &options).unwrap();
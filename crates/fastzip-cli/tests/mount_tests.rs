@@ -0,0 +1,108 @@
+//! 只读挂载：需要 Linux FUSE 且有挂载权限，环境不满足时跳过
+
+#![cfg(target_os = "linux")]
+
+use std::fs;
+use std::path::Path;
+use std::process::{Child, Command, Stdio};
+use std::time::{Duration, Instant};
+
+use fastzip_core::{compress_to_tar, compress_to_zip, unmount, Codec, CompressOptions, ZipMethod};
+use tempfile::TempDir;
+
+mod common;
+use common::create_tree;
+
+fn create_source(dir: &Path) -> std::path::PathBuf {
+    let text = "line\n".repeat(50_000);
+    let src = create_tree(&dir.join("src"), &[], [("data.txt", text.as_str()), ("docs/readme.md", "# readme")]);
+    #[cfg(unix)]
+    std::os::unix::fs::symlink("docs/readme.md", src.join("link")).unwrap();
+    src
+}
+
+/// 挂载并等待挂载点出现内容；无法挂载时返回 None
+fn mount(archive: &Path, mountpoint: &Path) -> Option<Child> {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_fastzip"))
+        .args(["mount", "-q"])
+        .arg(archive)
+        .arg(mountpoint)
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let start = Instant::now();
+    while start.elapsed() < Duration::from_secs(10) {
+        if let Some(status) = child.try_wait().unwrap() {
+            let mut stderr = String::new();
+            std::io::Read::read_to_string(&mut child.stderr.take().unwrap(), &mut stderr).unwrap();
+            eprintln!("跳过挂载测试（{}）: {}", status, stderr.trim());
+            return None;
+        }
+        if fs::read_dir(mountpoint).map(|mut d| d.next().is_some()).unwrap_or(false) {
+            return Some(child);
+        }
+        std::thread::sleep(Duration::from_millis(50));
+    }
+    let _ = unmount(mountpoint);
+    child.kill().unwrap();
+    child.wait().unwrap();
+    panic!("挂载超时");
+}
+
+fn check_and_unmount(mut child: Child, src: &Path, mountpoint: &Path) {
+    let result = std::panic::catch_unwind(|| {
+        assert_eq!(fs::read(mountpoint.join("data.txt")).unwrap(), fs::read(src.join("data.txt")).unwrap());
+        assert_eq!(fs::read_to_string(mountpoint.join("docs/readme.md")).unwrap(), "# readme");
+        assert_eq!(fs::read_link(mountpoint.join("link")).unwrap(), Path::new("docs/readme.md"));
+        assert_eq!(fs::read_to_string(mountpoint.join("link")).unwrap(), "# readme");
+        let mut names: Vec<String> =
+            fs::read_dir(mountpoint).unwrap().map(|e| e.unwrap().file_name().to_string_lossy().into_owned()).collect();
+        names.sort();
+        assert_eq!(names, ["data.txt", "docs", "link"]);
+        assert!(fs::metadata(mountpoint.join("docs")).unwrap().is_dir());
+        // 只读
+        assert!(fs::write(mountpoint.join("new.txt"), "x").is_err());
+        assert!(fs::remove_file(mountpoint.join("data.txt")).is_err());
+    });
+    unmount(mountpoint).unwrap();
+    assert!(child.wait().unwrap().success());
+    assert!(fs::read_dir(mountpoint).unwrap().next().is_none());
+    if let Err(e) = result {
+        std::panic::resume_unwind(e);
+    }
+}
+
+#[test]
+fn test_mount_zip_and_tar() {
+    let dir = TempDir::new().unwrap();
+    let src = create_source(dir.path());
+    let zip = dir.path().join("src.zip");
+    let options = CompressOptions { method: ZipMethod::Deflate, ..Default::default() };
+    compress_to_zip(&[&src], &zip, &options).unwrap();
+    let tzst = dir.path().join("src.tar.zst");
+    compress_to_tar(&[&src], &tzst, Some(Codec::Zstd), &CompressOptions::default()).unwrap();
+
+    for archive in [&zip, &tzst] {
+        let mountpoint = dir.path().join("mnt");
+        fs::create_dir_all(&mountpoint).unwrap();
+        let Some(child) = mount(archive, &mountpoint) else {
+            return;
+        };
+        check_and_unmount(child, &src, &mountpoint);
+    }
+}
+
+#[test]
+fn test_mount_errors() {
+    let dir = TempDir::new().unwrap();
+    let src = create_source(dir.path());
+    let zip = dir.path().join("src.zip");
+    compress_to_zip(&[&src], &zip, &CompressOptions::default()).unwrap();
+    let fastzip = || Command::new(env!("CARGO_BIN_EXE_fastzip"));
+
+    // 挂载点不存在、不支持的格式
+    let status = fastzip().args(["mount", "-q"]).arg(&zip).arg(dir.path().join("missing")).status().unwrap();
+    assert!(!status.success());
+    let status = fastzip().args(["mount", "-q"]).arg(src.join("data.txt")).arg(dir.path()).status().unwrap();
+    assert!(!status.success());
+}
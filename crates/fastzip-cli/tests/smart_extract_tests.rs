@@ -22,6 +22,38 @@ fn create_multi_top_level_zip() -> (TempDir, std::path::PathBuf) {
     (dir, zip_path)
 }
 
+fn create_tar_gz(name: &str, entries: &[(&str, &[u8])]) -> (TempDir, std::path::PathBuf) {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join(name);
+
+    let enc = flate2::write::GzEncoder::new(
+        std::fs::File::create(&path).unwrap(),
+        flate2::Compression::default(),
+    );
+    let mut tar = tar::Builder::new(enc);
+    for (entry, data) in entries {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(data.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        tar.append_data(&mut header, entry, *data).unwrap();
+    }
+    tar.into_inner().unwrap().finish().unwrap();
+
+    (dir, path)
+}
+
+fn assert_no_staging_left(dir: &std::path::Path) {
+    for entry in fs::read_dir(dir).unwrap() {
+        let name = entry.unwrap().file_name();
+        assert!(
+            !name.to_string_lossy().starts_with(".fastzip-staging"),
+            "临时目录未清理: {:?}",
+            name
+        );
+    }
+}
+
 fn create_single_root_zip() -> (TempDir, std::path::PathBuf) {
     // 单根目录：foo/a, foo/b -> 应解压到 base_dir，创建 foo/
     let dir = TempDir::new().unwrap();
@@ -49,6 +81,7 @@ fn test_smart_multi_top_level_creates_subfolder() {
         smart: true,
         overwrite: false,
         password: None,
+        ..Default::default()
     };
 
     let result = extract_one(&zip_path, &options).unwrap();
@@ -70,6 +103,7 @@ fn test_smart_single_root_extracts_to_base() {
         smart: true,
         overwrite: false,
         password: None,
+        ..Default::default()
     };
 
     let result = extract_one(&zip_path, &options).unwrap();
@@ -91,6 +125,7 @@ fn test_smart_duplicate_folder_uses_2_and_3() {
         smart: true,
         overwrite: false,
         password: None,
+        ..Default::default()
     };
 
     let r1 = extract_one(&zip_path, &options).unwrap();
@@ -106,3 +141,66 @@ fn test_smart_duplicate_folder_uses_2_and_3() {
     assert!(r2.join("a/file1.txt").exists());
     assert!(r3.join("a/file1.txt").exists());
 }
+
+#[test]
+fn test_smart_tar_gz_single_root_single_pass() {
+    // tar.* 单遍智能解压：单根目录 -> 解压到 base_dir
+    let (dir, path) = create_tar_gz("logs.tar.gz", &[("logs/a.log", b"a"), ("logs/b.log", b"b")]);
+    let dest_dir = dir.path().join("out");
+
+    let options = ExtractOptions {
+        dest: Some(dest_dir.clone()),
+        ..Default::default()
+    };
+
+    let result = extract_one(&path, &options).unwrap();
+
+    assert_eq!(result, dest_dir);
+    assert_eq!(fs::read_to_string(dest_dir.join("logs/a.log")).unwrap(), "a");
+    assert_eq!(fs::read_to_string(dest_dir.join("logs/b.log")).unwrap(), "b");
+    assert_no_staging_left(&dest_dir);
+}
+
+#[test]
+fn test_smart_tar_gz_multi_top_level_single_pass() {
+    // tar.* 单遍智能解压：多顶层 -> archive_stem 子文件夹，重名 (2)
+    let (dir, path) = create_tar_gz("multi.tar.gz", &[("a/file1.txt", b"a"), ("b/file2.txt", b"b")]);
+    let dest_dir = dir.path().join("out");
+
+    let options = ExtractOptions {
+        dest: Some(dest_dir.clone()),
+        ..Default::default()
+    };
+
+    let r1 = extract_one(&path, &options).unwrap();
+    assert_eq!(r1, dest_dir.join("multi"));
+    assert!(r1.join("a/file1.txt").exists());
+    assert!(r1.join("b/file2.txt").exists());
+
+    let r2 = extract_one(&path, &options).unwrap();
+    assert_eq!(r2, dest_dir.join("multi (2)"));
+    assert_no_staging_left(&dest_dir);
+}
+
+#[test]
+fn test_smart_tar_gz_merges_into_existing_root() {
+    // 单根目录已存在：合并写入，已有文件被覆盖，其他文件保留
+    let (dir, path) = create_tar_gz("logs.tar.gz", &[("logs/a.log", b"new"), ("logs/sub/c.log", b"c")]);
+    let dest_dir = dir.path().join("out");
+    fs::create_dir_all(dest_dir.join("logs")).unwrap();
+    fs::write(dest_dir.join("logs/a.log"), "old").unwrap();
+    fs::write(dest_dir.join("logs/keep.log"), "keep").unwrap();
+
+    let options = ExtractOptions {
+        dest: Some(dest_dir.clone()),
+        ..Default::default()
+    };
+
+    let result = extract_one(&path, &options).unwrap();
+
+    assert_eq!(result, dest_dir);
+    assert_eq!(fs::read_to_string(dest_dir.join("logs/a.log")).unwrap(), "new");
+    assert_eq!(fs::read_to_string(dest_dir.join("logs/keep.log")).unwrap(), "keep");
+    assert_eq!(fs::read_to_string(dest_dir.join("logs/sub/c.log")).unwrap(), "c");
+    assert_no_staging_left(&dest_dir);
+}
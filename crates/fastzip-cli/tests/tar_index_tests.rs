@@ -0,0 +1,172 @@
+//! tar.* 随机访问索引测试
+
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+
+use fastzip_core::formats::{Checkpoint, TarExtractor, TarIndex};
+use fastzip_core::{compress_to_tar, ArchiveFormat, Codec, CompressOptions};
+use tempfile::TempDir;
+
+mod common;
+use common::extract;
+
+/// 可压缩但不过分重复的伪随机文本
+fn sample_data(seed: u64, len: usize) -> Vec<u8> {
+    let words: [&[u8]; 8] = [b"alpha ", b"beta ", b"gamma ", b"delta\n", b"omega ", b"zeta ", b"kappa ", b"sigma\n"];
+    let mut state = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+    let mut out = Vec::with_capacity(len + 8);
+    while out.len() < len {
+        state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        out.extend_from_slice(words[(state >> 61) as usize]);
+        out.extend_from_slice(format!("{}", (state >> 40) & 0xFFF).as_bytes());
+    }
+    out.truncate(len);
+    out
+}
+
+/// 10 个 1 MiB 的文件，位于同一根目录下
+fn sample_entries() -> Vec<(String, Vec<u8>)> {
+    (0..10)
+        .map(|i| (format!("data/file{i}.txt"), sample_data(i, 1024 * 1024)))
+        .collect()
+}
+
+fn write_tar<W: Write>(writer: W, entries: &[(String, Vec<u8>)]) -> W {
+    let mut tar = tar::Builder::new(writer);
+    for (name, data) in entries {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(data.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        tar.append_data(&mut header, name, &data[..]).unwrap();
+    }
+    tar.into_inner().unwrap()
+}
+
+fn read_entry(archive: &Path, format: ArchiveFormat, name: &str) -> Vec<u8> {
+    let mut out = Vec::new();
+    TarExtractor::extract_entry(archive, format, name, &mut out).unwrap();
+    out
+}
+
+#[test]
+fn test_index_tar_gz_checkpoints() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("big.tar.gz");
+    let entries = sample_entries();
+    let enc = flate2::write::GzEncoder::new(fs::File::create(&path).unwrap(), flate2::Compression::fast());
+    write_tar(enc, &entries).finish().unwrap();
+
+    let index = TarExtractor::index(&path, ArchiveFormat::TarGz).unwrap();
+    assert!(TarIndex::sidecar_path(&path).exists());
+    assert_eq!(index.entries.len(), entries.len());
+    assert!(index.checkpoints.len() >= 2, "应记录 gzip 断点");
+    assert!(index.checkpoints.iter().all(|c| matches!(c, Checkpoint::Deflate(_))));
+
+    // 最后一个条目从断点恢复解码
+    for (name, data) in [&entries[9], &entries[5], &entries[0]] {
+        assert_eq!(&read_entry(&path, ArchiveFormat::TarGz, name), data);
+    }
+
+    let top = TarExtractor::list_top_level(&path, ArchiveFormat::TarGz).unwrap();
+    assert_eq!(top.single_root_dir.as_deref(), Some("data"));
+}
+
+#[test]
+fn test_index_stale_sidecar_ignored() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("a.tar.gz");
+    let enc = flate2::write::GzEncoder::new(fs::File::create(&path).unwrap(), flate2::Compression::default());
+    write_tar(enc, &[("a.txt".to_string(), b"old".to_vec())]).finish().unwrap();
+    TarExtractor::index(&path, ArchiveFormat::TarGz).unwrap();
+
+    let enc = flate2::write::GzEncoder::new(fs::File::create(&path).unwrap(), flate2::Compression::default());
+    write_tar(enc, &[("b.txt".to_string(), b"newer".to_vec()), ("c.txt".to_string(), b"c".to_vec())])
+        .finish()
+        .unwrap();
+
+    assert!(TarIndex::load(&path, ArchiveFormat::TarGz).unwrap().is_none());
+    assert_eq!(read_entry(&path, ArchiveFormat::TarGz, "b.txt"), b"newer");
+}
+
+#[test]
+fn test_seekable_tar_zst_roundtrip() {
+    let dir = TempDir::new().unwrap();
+    let src = dir.path().join("src");
+    let entries = sample_entries();
+    for (name, data) in &entries {
+        let p = src.join(name);
+        fs::create_dir_all(p.parent().unwrap()).unwrap();
+        fs::write(p, data).unwrap();
+    }
+
+    let archive = dir.path().join("out.tar.zst");
+    compress_to_tar(&[src.join("data")], &archive, Some(Codec::Zstd), &CompressOptions::default()).unwrap();
+
+    let index = TarExtractor::index(&archive, ArchiveFormat::TarZst).unwrap();
+    assert!(index.checkpoints.len() >= 2, "应记录 zstd 帧起点");
+    assert!(index.checkpoints.iter().all(|c| matches!(c, Checkpoint::Frame { .. })));
+    assert_eq!(read_entry(&archive, ArchiveFormat::TarZst, "file7.txt"), entries[7].1);
+
+    // 普通解压仍然可用
+    let out = extract(&archive, &dir.path().join("x"), None).unwrap();
+    assert_eq!(fs::read(out.join("file3.txt")).unwrap(), entries[3].1);
+}
+
+#[test]
+fn test_index_tar_xz_blocks() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("big.tar.xz");
+    let entries = sample_entries();
+    let stream = xz2::stream::MtStreamBuilder::new()
+        .preset(1)
+        .threads(2)
+        .block_size(1024 * 1024)
+        .encoder()
+        .unwrap();
+    let enc = xz2::write::XzEncoder::new_stream(fs::File::create(&path).unwrap(), stream);
+    write_tar(enc, &entries).finish().unwrap();
+
+    let index = TarExtractor::index(&path, ArchiveFormat::TarXz).unwrap();
+    assert!(index.checkpoints.len() > 5);
+    assert!(index.checkpoints.iter().all(|c| matches!(c, Checkpoint::XzBlock { .. })));
+    assert_eq!(read_entry(&path, ArchiveFormat::TarXz, "data/file8.txt"), entries[8].1);
+
+    // 从 sidecar 重新加载
+    let loaded = TarIndex::load(&path, ArchiveFormat::TarXz).unwrap().unwrap();
+    assert_eq!(loaded.checkpoints, index.checkpoints);
+    assert_eq!(loaded.entries, index.entries);
+}
+
+#[test]
+fn test_index_tar_xz_corrupt_footer() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("bad.tar.xz");
+    let mut enc = xz2::write::XzEncoder::new(Vec::new(), 1);
+    enc.write_all(&write_tar(Vec::new(), &sample_entries()[..2])).unwrap();
+    let mut data = enc.finish().unwrap();
+    // 流尾中的索引大小远超文件长度
+    let len = data.len();
+    data[len - 8..len - 4].copy_from_slice(&u32::MAX.to_le_bytes());
+    fs::write(&path, &data).unwrap();
+    assert!(TarExtractor::index(&path, ArchiveFormat::TarXz).is_err());
+
+    // 截断到不足一个流尾
+    fs::write(&path, &data[..6]).unwrap();
+    assert!(TarExtractor::index(&path, ArchiveFormat::TarXz).is_err());
+}
+
+#[test]
+fn test_index_plain_tar() {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("plain.tar");
+    write_tar(fs::File::create(&path).unwrap(), &[
+        ("a/one.txt".to_string(), b"one".to_vec()),
+        ("a/two.txt".to_string(), b"two".to_vec()),
+    ]);
+
+    assert_eq!(read_entry(&path, ArchiveFormat::Tar, "./a/two.txt"), b"two");
+    let mut out = Vec::new();
+    assert!(TarExtractor::extract_entry(&path, ArchiveFormat::Tar, "missing", &mut out).is_err());
+}
@@ -0,0 +1,179 @@
+//! 分卷输出：ZIP 写成 .z01…/.zip，7z 与 tar.* 按字节切分为 .001、.002…，均可由 FastZip 直接解压
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use fastzip_core::formats::{byte_volume_path, zip_volume_path};
+use fastzip_core::{
+    compress_to_7z, compress_to_tar, compress_to_zip, convert_archive, Codec, CompressOptions, ZipMethod,
+};
+use tempfile::TempDir;
+
+mod common;
+use common::{create_tree, extract};
+
+const VOLUME: u64 = 64 * 1024;
+
+/// 源树：约 300 KiB 不可压缩数据、若干小文件与空目录
+fn create_source(dir: &Path) -> PathBuf {
+    let mut state = 0x2545_f491_u32;
+    let noise: Vec<u8> = (0..300 * 1024)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state as u8
+        })
+        .collect();
+    let small = (0..20).map(|i| (format!("data/small-{i}.txt"), format!("file {i}\n").into_bytes()));
+    create_tree(&dir.join("src"), &["empty"], small.chain([("data/noise.bin".into(), noise)]))
+}
+
+/// 解压后的文件树：相对路径 -> 内容（目录为空）
+fn tree(root: &Path) -> BTreeMap<String, Vec<u8>> {
+    let mut out = BTreeMap::new();
+    let mut stack = vec![root.to_path_buf()];
+    while let Some(dir) = stack.pop() {
+        for entry in fs::read_dir(&dir).unwrap() {
+            let path = entry.unwrap().path();
+            let rel = path.strip_prefix(root).unwrap().to_string_lossy().replace('\\', "/");
+            if path.is_dir() {
+                stack.push(path);
+                out.insert(rel, Vec::new());
+            } else {
+                out.insert(rel, fs::read(&path).unwrap());
+            }
+        }
+    }
+    out
+}
+
+fn split_options() -> CompressOptions {
+    CompressOptions { volume_size: Some(VOLUME), ..Default::default() }
+}
+
+/// 依次存在的分卷
+fn volumes(path: impl Fn(u32) -> PathBuf) -> Vec<PathBuf> {
+    (1..).map(path).take_while(|p| p.exists()).collect()
+}
+
+#[test]
+fn test_zip_volumes() {
+    let dir = TempDir::new().unwrap();
+    let src = create_source(dir.path());
+    let expected = tree(&src);
+    let zip = dir.path().join("out.zip");
+    let options = CompressOptions { method: ZipMethod::Stored, ..split_options() };
+    compress_to_zip(&[&src], &zip, &options).unwrap();
+
+    let parts = volumes(|n| zip_volume_path(&zip, n));
+    assert_eq!(parts.len(), 4);
+    for part in &parts {
+        assert!(fs::metadata(part).unwrap().len() <= VOLUME);
+    }
+    assert!(fs::metadata(&zip).unwrap().len() <= VOLUME);
+    // 第一卷以分卷签名开头
+    assert_eq!(fs::read(&parts[0]).unwrap()[..4], [0x50, 0x4b, 0x07, 0x08]);
+
+    // 由末卷或任意一卷打开
+    for (i, path) in [&zip, &parts[0], &parts[2]].into_iter().enumerate() {
+        let out = extract(path, &dir.path().join(format!("x{i}")), None).unwrap();
+        assert_eq!(tree(&out), expected);
+    }
+
+    // 重新压缩为更少的卷时清理多余的旧分卷
+    fs::remove_file(src.join("data/noise.bin")).unwrap();
+    compress_to_zip(&[&src], &zip, &options).unwrap();
+    assert!(volumes(|n| zip_volume_path(&zip, n)).is_empty());
+    zip::ZipArchive::new(fs::File::open(&zip).unwrap()).unwrap();
+}
+
+#[test]
+fn test_zip_missing_volume() {
+    let dir = TempDir::new().unwrap();
+    let src = create_source(dir.path());
+    let zip = dir.path().join("out.zip");
+    compress_to_zip(&[&src], &zip, &split_options()).unwrap();
+    fs::remove_file(zip_volume_path(&zip, 2)).unwrap();
+    assert!(extract(&zip, &dir.path().join("x"), None).is_err());
+}
+
+#[test]
+fn test_7z_and_tar_volumes() {
+    let dir = TempDir::new().unwrap();
+    let src = create_source(dir.path());
+    let expected = tree(&src);
+
+    let sz = dir.path().join("out.7z");
+    compress_to_7z(&[&src], &sz, &split_options()).unwrap();
+    let tgz = dir.path().join("out.tar.gz");
+    compress_to_tar(&[&src], &tgz, Some(Codec::Gzip), &split_options()).unwrap();
+    let tar = dir.path().join("out.tar");
+    compress_to_tar(&[&src], &tar, None, &split_options()).unwrap();
+
+    for base in [&sz, &tgz, &tar] {
+        assert!(!base.exists());
+        let parts = volumes(|n| byte_volume_path(base, n));
+        assert!(parts.len() >= 5, "{}", base.display());
+        let (last, full) = parts.split_last().unwrap();
+        for part in full {
+            assert_eq!(fs::metadata(part).unwrap().len(), VOLUME);
+        }
+        assert!(fs::metadata(last).unwrap().len() <= VOLUME);
+
+        // 由逻辑名或第一卷打开
+        for (i, path) in [base, &parts[0]].into_iter().enumerate() {
+            let name = base.file_name().unwrap().to_string_lossy();
+            let out = extract(path, &dir.path().join(format!("x-{name}-{i}")), None).unwrap();
+            assert_eq!(tree(&out), expected, "{name}");
+        }
+    }
+}
+
+#[test]
+fn test_convert_to_volumes() {
+    let dir = TempDir::new().unwrap();
+    let src = create_source(dir.path());
+    let zip = dir.path().join("src.zip");
+    compress_to_zip(&[&src], &zip, &split_options()).unwrap();
+
+    // 分卷 ZIP 转为分卷 tar
+    let tar = dir.path().join("out.tar");
+    convert_archive(&zip, &tar, None, &split_options()).unwrap();
+    assert!(byte_volume_path(&tar, 2).exists());
+    assert_eq!(tree(&extract(&byte_volume_path(&tar, 1), &dir.path().join("x"), None).unwrap()), tree(&src));
+}
+
+#[test]
+fn test_volume_size_too_small() {
+    let dir = TempDir::new().unwrap();
+    let src = create_source(dir.path());
+    let options = CompressOptions { volume_size: Some(1024), ..Default::default() };
+    assert!(compress_to_zip(&[&src], &dir.path().join("a.zip"), &options).is_err());
+    assert!(compress_to_7z(&[&src], &dir.path().join("a.7z"), &options).is_err());
+}
+
+#[test]
+fn test_cli_volume_size() {
+    let dir = TempDir::new().unwrap();
+    let src = create_source(dir.path());
+    let fastzip = || Command::new(env!("CARGO_BIN_EXE_fastzip"));
+
+    let zip = dir.path().join("cli.zip");
+    let status =
+        fastzip().args(["compress", "-q", "--volume-size", "64k"]).arg(&src).arg("-o").arg(&zip).status().unwrap();
+    assert!(status.success());
+    assert!(zip_volume_path(&zip, 1).exists());
+
+    let dest = dir.path().join("x");
+    let status = fastzip().args(["extract", "-q", "--flat", "-d"]).arg(&dest).arg(zip_volume_path(&zip, 1)).status();
+    assert!(status.unwrap().success());
+    // 子目录名取自逻辑名 cli.zip
+    assert_eq!(tree(&dest.join("cli")), tree(&src));
+
+    // 分卷归档不支持原地修改
+    let status = fastzip().args(["delete", "-q"]).arg(&zip).arg("*.txt").status().unwrap();
+    assert!(!status.success());
+}
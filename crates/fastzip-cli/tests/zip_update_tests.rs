@@ -0,0 +1,184 @@
+//! 更新已有 ZIP：加入新文件、替换有变化的文件，未变化的条目原样保留
+
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{Duration, UNIX_EPOCH};
+
+use fastzip_core::{compress_to_zip, update_zip, CompressOptions, UpdateMode, ZipEncryption, ZipMethod};
+use tempfile::TempDir;
+
+mod common;
+use common::create_tree;
+
+const MTIME: u64 = 1_600_000_000;
+
+fn set_mtime(path: &Path, secs: u64) {
+    filetime::set_file_mtime(path, filetime::FileTime::from_system_time(UNIX_EPOCH + Duration::from_secs(secs)))
+        .unwrap();
+}
+
+/// 源树：a.txt、sub/b.txt，修改时间固定
+fn create_source(dir: &Path) -> PathBuf {
+    let src = create_tree(&dir.join("src"), &[], [("a.txt", "alpha ".repeat(100)), ("sub/b.txt", "beta ".repeat(100))]);
+    set_mtime(&src.join("a.txt"), MTIME);
+    set_mtime(&src.join("sub/b.txt"), MTIME);
+    src
+}
+
+/// 条目名 -> 原始字节（压缩后的数据，含加密头），用于判断是否原样复制
+fn raw_entries(path: &Path) -> Vec<(String, Vec<u8>)> {
+    let bytes = fs::read(path).unwrap();
+    let mut zip = zip::ZipArchive::new(fs::File::open(path).unwrap()).unwrap();
+    (0..zip.len())
+        .map(|i| {
+            let entry = zip.by_index_raw(i).unwrap();
+            let start = entry.data_start() as usize;
+            let data = bytes[start..start + entry.compressed_size() as usize].to_vec();
+            (entry.name().to_string(), data)
+        })
+        .collect()
+}
+
+fn read_entry(path: &Path, name: &str, password: Option<&str>) -> String {
+    let mut zip = zip::ZipArchive::new(fs::File::open(path).unwrap()).unwrap();
+    let mut entry = match password {
+        Some(pw) => zip.by_name_decrypt(name, pw.as_bytes()).unwrap(),
+        None => zip.by_name(name).unwrap(),
+    };
+    let mut content = String::new();
+    entry.read_to_string(&mut content).unwrap();
+    content
+}
+
+/// 排序后的条目名（遍历顺序取决于文件系统）
+fn names(entries: &[(String, Vec<u8>)]) -> Vec<&str> {
+    let mut names: Vec<&str> = entries.iter().map(|(n, _)| n.as_str()).collect();
+    names.sort_unstable();
+    names
+}
+
+#[test]
+fn test_update_adds_and_replaces() {
+    let dir = TempDir::new().unwrap();
+    let src = create_source(dir.path());
+    let archive = dir.path().join("out.zip");
+    let options = CompressOptions { method: ZipMethod::Deflate, ..Default::default() };
+    compress_to_zip(&[&src], &archive, &options).unwrap();
+    let before = raw_entries(&archive);
+
+    fs::write(src.join("a.txt"), "changed").unwrap();
+    set_mtime(&src.join("a.txt"), MTIME + 100);
+    fs::write(src.join("new.txt"), "new").unwrap();
+    let summary = update_zip(&archive, &[&src], UpdateMode::Update, &options).unwrap();
+    assert_eq!(summary.added, ["new.txt"]);
+    assert_eq!(summary.replaced, ["a.txt"]);
+    assert_eq!(summary.unchanged, 2);
+
+    let after = raw_entries(&archive);
+    assert_eq!(names(&after), ["a.txt", "new.txt", "sub/", "sub/b.txt"]);
+    // 未变化的条目保持原顺序与原始字节，写入的条目在末尾
+    let kept: Vec<_> = before.iter().filter(|(n, _)| n != "a.txt").collect();
+    assert_eq!(after[..2].iter().collect::<Vec<_>>(), kept);
+    assert_eq!(read_entry(&archive, "a.txt", None), "changed");
+    assert_eq!(read_entry(&archive, "new.txt", None), "new");
+
+    // 无变化时不改写归档
+    let bytes = fs::read(&archive).unwrap();
+    let summary = update_zip(&archive, &[&src], UpdateMode::Update, &options).unwrap();
+    assert!(summary.added.is_empty() && summary.replaced.is_empty());
+    assert_eq!(fs::read(&archive).unwrap(), bytes);
+}
+
+#[test]
+fn test_freshen_only_replaces_existing() {
+    let dir = TempDir::new().unwrap();
+    let src = create_source(dir.path());
+    let archive = dir.path().join("out.zip");
+    compress_to_zip(&[&src], &archive, &CompressOptions::default()).unwrap();
+
+    fs::write(src.join("sub/b.txt"), "beta v2").unwrap();
+    fs::write(src.join("new.txt"), "new").unwrap();
+    let summary = update_zip(&archive, &[&src], UpdateMode::Freshen, &CompressOptions::default()).unwrap();
+    assert!(summary.added.is_empty());
+    assert_eq!(summary.replaced, ["sub/b.txt"]);
+    assert_eq!(names(&raw_entries(&archive)), ["a.txt", "sub/", "sub/b.txt"]);
+    assert_eq!(read_entry(&archive, "sub/b.txt", None), "beta v2");
+
+    // freshen 不创建归档
+    let missing = dir.path().join("missing.zip");
+    assert!(update_zip(&missing, &[&src], UpdateMode::Freshen, &CompressOptions::default()).is_err());
+    assert!(!missing.exists());
+    // update 新建归档
+    update_zip(&missing, &[&src], UpdateMode::Update, &CompressOptions::default()).unwrap();
+    assert_eq!(raw_entries(&missing).len(), 4);
+}
+
+#[test]
+fn test_update_keeps_encrypted_entries() {
+    let dir = TempDir::new().unwrap();
+    let src = create_source(dir.path());
+    for encryption in [ZipEncryption::Aes256, ZipEncryption::ZipCrypto] {
+        let archive = dir.path().join(format!("{encryption:?}.zip"));
+        let options = CompressOptions { password: Some("pw".into()), encryption, ..Default::default() };
+        compress_to_zip(&[&src], &archive, &options).unwrap();
+        let before = raw_entries(&archive);
+
+        let extra = dir.path().join(format!("{encryption:?}.txt"));
+        fs::write(&extra, "extra").unwrap();
+        update_zip(&archive, &[&src, &extra], UpdateMode::Update, &options).unwrap();
+        let after = raw_entries(&archive);
+        assert_eq!(after[..before.len()], before[..], "{encryption:?}");
+        assert_eq!(read_entry(&archive, "a.txt", Some("pw")), "alpha ".repeat(100));
+        assert_eq!(read_entry(&archive, &format!("{encryption:?}.txt"), Some("pw")), "extra");
+    }
+}
+
+#[test]
+fn test_update_preserves_comment() {
+    let dir = TempDir::new().unwrap();
+    let src = create_source(dir.path());
+    let archive = dir.path().join("c.zip");
+    let mut zip = zip::ZipWriter::new(fs::File::create(&archive).unwrap());
+    zip.set_comment("keep me");
+    zip.start_file("old.txt", zip::write::SimpleFileOptions::default()).unwrap();
+    zip.finish().unwrap();
+
+    update_zip(&archive, &[&src], UpdateMode::Update, &CompressOptions::default()).unwrap();
+    let zip = zip::ZipArchive::new(fs::File::open(&archive).unwrap()).unwrap();
+    assert_eq!(zip.comment(), b"keep me");
+    assert_eq!(zip.len(), 4);
+}
+
+#[test]
+fn test_cli_add_and_update() {
+    let dir = TempDir::new().unwrap();
+    let src = create_source(dir.path());
+    let archive = dir.path().join("cli.zip");
+    let run = |cmd: &str, extra: &[&Path]| {
+        Command::new(env!("CARGO_BIN_EXE_fastzip"))
+            .args([cmd, "-q"])
+            .arg(&archive)
+            .args(extra)
+            .status()
+            .unwrap()
+    };
+    assert!(run("add", &[&src.join("a.txt")]).success());
+    assert_eq!(names(&raw_entries(&archive)), ["a.txt"]);
+
+    // update 不加入新文件
+    assert!(run("update", &[&src]).success());
+    assert_eq!(names(&raw_entries(&archive)), ["a.txt"]);
+    assert!(run("add", &[&src]).success());
+    assert_eq!(raw_entries(&archive).len(), 3);
+
+    let status = Command::new(env!("CARGO_BIN_EXE_fastzip"))
+        .args(["add", "-q", "-o"])
+        .arg(dir.path().join("x.zip"))
+        .arg(&archive)
+        .arg(&src)
+        .status()
+        .unwrap();
+    assert!(!status.success());
+}
@@ -0,0 +1,323 @@
+//! 异步接口（`async` feature）：在 tokio 运行时上解压 `AsyncRead`、压缩到 `AsyncWrite`
+//!
+//! 解压缩本身在阻塞线程池中运行，与异步端经有界通道交换数据块，读写两端因此有背压；
+//! 进度以累计快照报告，只保留最新值；丢弃 [`AsyncTask`] 即取消。
+
+use std::fs::File;
+use std::future::Future;
+use std::io::{self, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::sync::{mpsc, watch};
+use tokio::task::JoinHandle;
+
+use crate::compress::{compress_single, compress_to_7z_hooked, compress_to_zip_hooked, write_tar, CompressOptions};
+use crate::error::{FastZipError, Result};
+use crate::extractor::{extract_hooked, extract_tar_stream, ExtractOptions};
+use crate::formats::{open_decoder, ArchiveFormat};
+use crate::hook::{cancelled, HookEvent, TaskHook};
+
+/// 通道中每个数据块的大小
+const CHUNK_SIZE: usize = 256 * 1024;
+
+/// 通道中最多缓冲的数据块数
+const CHANNEL_DEPTH: usize = 8;
+
+/// 进度快照，各项均为累计值
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Progress {
+    /// 已读取的字节数：解压时为输入的归档，压缩时为源文件
+    pub read: u64,
+    /// 已写出的字节数：解压时为解出的数据，压缩时为输出的归档
+    pub written: u64,
+    /// 已开始处理的条目数
+    pub entries: u64,
+    /// 最近开始处理的条目
+    pub entry: Option<String>,
+}
+
+/// 进行中的异步任务：`.await` 得到结果，[`AsyncTask::progress`] 取最新进度
+///
+/// 丢弃即取消：输入输出立即停止，后台的阻塞任务在下一个条目或数据块处以错误结束。
+pub struct AsyncTask<T> {
+    handle: JoinHandle<Result<T>>,
+    progress: watch::Receiver<Progress>,
+    cancel: Arc<AtomicBool>,
+}
+
+impl<T> AsyncTask<T> {
+    /// 等到进度更新后返回最新快照，期间的中间值被跳过；任务结束且最新值已取过后返回 None
+    pub async fn progress(&mut self) -> Option<Progress> {
+        self.progress.changed().await.ok()?;
+        Some(self.progress.borrow_and_update().clone())
+    }
+}
+
+impl<T> Future for AsyncTask<T> {
+    type Output = Result<T>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<T>> {
+        Pin::new(&mut self.handle).poll(cx).map(|joined| joined.unwrap_or_else(|e| Err(join_err(e))))
+    }
+}
+
+impl<T> Drop for AsyncTask<T> {
+    fn drop(&mut self) {
+        // 先置取消标志，阻塞端看到通道关闭时才能区分取消与正常结束
+        self.cancel.store(true, Ordering::Relaxed);
+        self.handle.abort();
+    }
+}
+
+/// 异步解压：从 `input` 读取名为 `name` 的归档，按 `options` 解压到 `options.dest`（须指定）
+///
+/// `name` 为归档文件名，用于识别格式与智能解压时的目录名。tar.* 边读边解压；
+/// 其余格式（ZIP、7z 等需随机访问）先读到临时文件再解压，其中 ZIP、7z 逐条目报告进度并响应取消，
+/// 其他格式只在读取输入期间响应。须在 tokio 运行时内调用。
+pub fn extract_async<R>(input: R, name: &str, options: ExtractOptions) -> AsyncTask<PathBuf>
+where
+    R: AsyncRead + Unpin + Send + 'static,
+{
+    let (progress_tx, progress) = watch::channel(Progress::default());
+    let progress_tx = Arc::new(progress_tx);
+    let cancel = Arc::new(AtomicBool::new(false));
+    let (chunk_tx, chunk_rx) = mpsc::channel(CHANNEL_DEPTH);
+    let reader = ChannelReader {
+        rx: chunk_rx,
+        chunk: Vec::new(),
+        pos: 0,
+        read: 0,
+        cancel: cancel.clone(),
+        progress: progress_tx.clone(),
+    };
+    let hook = progress_hook(&cancel, progress_tx, |progress, len| progress.written += len);
+    let name = PathBuf::from(name);
+    let handle = tokio::spawn(async move {
+        let _pump = AbortOnDrop(tokio::spawn(pump(input, chunk_tx)));
+        let worker = tokio::task::spawn_blocking(move || extract_blocking(reader, &name, &options, &hook));
+        worker.await.map_err(join_err)?
+    });
+    AsyncTask { handle, progress, cancel }
+}
+
+/// 异步压缩：把 `sources` 按 `format` 压缩后写入 `output`，完成后关闭 `output`，返回写出的字节数
+///
+/// tar.* 边压缩边输出；ZIP、7z 与单文件压缩（只能有一个输入）先写到临时文件再输出，
+/// 其中 ZIP、7z 压缩期间逐条目报告进度并响应取消，单文件压缩只在输出期间响应。
+/// 不支持分卷。须在 tokio 运行时内调用。
+pub fn compress_async<W>(
+    sources: Vec<PathBuf>,
+    format: ArchiveFormat,
+    mut output: W,
+    options: CompressOptions,
+) -> AsyncTask<u64>
+where
+    W: AsyncWrite + Unpin + Send + 'static,
+{
+    let (progress_tx, progress) = watch::channel(Progress::default());
+    let progress_tx = Arc::new(progress_tx);
+    let cancel = Arc::new(AtomicBool::new(false));
+    let (chunk_tx, mut chunk_rx) = mpsc::channel::<Vec<u8>>(CHANNEL_DEPTH);
+    let writer = ChannelWriter { tx: chunk_tx, buf: Vec::with_capacity(CHUNK_SIZE), cancel: cancel.clone() };
+    let hook = progress_hook(&cancel, progress_tx.clone(), |progress, len| progress.read += len);
+    let handle = tokio::spawn(async move {
+        let worker = tokio::task::spawn_blocking(move || compress_blocking(&sources, format, writer, &options, &hook));
+        let mut written = 0;
+        while let Some(chunk) = chunk_rx.recv().await {
+            output.write_all(&chunk).await?;
+            written += chunk.len() as u64;
+            progress_tx.send_modify(|progress| progress.written = written);
+        }
+        worker.await.map_err(join_err)??;
+        output.shutdown().await?;
+        Ok(written)
+    });
+    AsyncTask { handle, progress, cancel }
+}
+
+/// 把阻塞端的条目与数据事件记入进度；`on_data` 决定数据计入哪一项
+fn progress_hook(
+    cancel: &Arc<AtomicBool>,
+    progress: Arc<watch::Sender<Progress>>,
+    on_data: fn(&mut Progress, u64),
+) -> TaskHook {
+    TaskHook::new(cancel.clone(), move |event| {
+        progress.send_modify(|progress| match event {
+            HookEvent::Entry(name) => {
+                progress.entries += 1;
+                progress.entry = Some(name.to_string());
+            }
+            HookEvent::Data(len) => on_data(progress, len as u64),
+        })
+    })
+}
+
+fn extract_blocking(
+    mut reader: ChannelReader,
+    name: &Path,
+    options: &ExtractOptions,
+    hook: &TaskHook,
+) -> Result<PathBuf> {
+    if options.dest.is_none() {
+        return Err(FastZipError::Other("异步解压须指定目标目录".into()));
+    }
+    match ArchiveFormat::from_extension(name) {
+        Some(format) if format.is_tar() => {
+            let decoded: Box<dyn Read + Send> = match format.codec() {
+                Some(codec) => open_decoder(reader, codec, options.threads)?,
+                None => Box::new(reader),
+            };
+            extract_tar_stream(hook.reader(decoded), name, options, &mut |entry| Ok(hook.entry(entry)?))
+        }
+        _ => {
+            let spool = tempfile::tempdir()?;
+            let path = spool.path().join(name.file_name().unwrap_or("archive".as_ref()));
+            let mut file = BufWriter::new(File::create(&path)?);
+            io::copy(&mut reader, &mut file)?;
+            file.flush()?;
+            drop(file);
+            extract_hooked(&path, options, hook)
+        }
+    }
+}
+
+fn compress_blocking(
+    sources: &[PathBuf],
+    format: ArchiveFormat,
+    mut out: ChannelWriter,
+    options: &CompressOptions,
+    hook: &TaskHook,
+) -> Result<()> {
+    if options.volume_size.is_some() {
+        return Err(FastZipError::Other("异步压缩不支持分卷".into()));
+    }
+    if format.is_tar() {
+        write_tar(&mut out, sources, format.codec(), options)?;
+    } else {
+        let spool = tempfile::tempdir()?;
+        let dest = spool.path().join("archive");
+        match format {
+            ArchiveFormat::Zip => compress_to_zip_hooked(sources, &dest, options, hook)?,
+            ArchiveFormat::SevenZ => compress_to_7z_hooked(sources, &dest, options, hook)?,
+            format if format.is_single_compressed() => {
+                let [source] = sources else {
+                    return Err(FastZipError::Other("单文件压缩只能有一个输入".into()));
+                };
+                let codec = format.codec().expect("单文件压缩格式有编码");
+                // 临时输出不应删除调用方的源文件
+                compress_single(source, &dest, codec, &CompressOptions { keep_original: true, ..options.clone() })?;
+            }
+            format => return Err(FastZipError::UnsupportedFormat(format!("{:?} 不支持压缩", format))),
+        }
+        io::copy(&mut File::open(&dest)?, &mut out)?;
+    }
+    out.flush()?;
+    Ok(())
+}
+
+/// 把 `input` 按块送入通道；输入出错时把错误也送过去，接收端关闭时停止
+async fn pump<R: AsyncRead + Unpin>(mut input: R, tx: mpsc::Sender<io::Result<Vec<u8>>>) {
+    loop {
+        let mut buf = vec![0u8; CHUNK_SIZE];
+        let chunk = match input.read(&mut buf).await {
+            Ok(0) => return,
+            Ok(n) => {
+                buf.truncate(n);
+                Ok(buf)
+            }
+            Err(e) => Err(e),
+        };
+        let failed = chunk.is_err();
+        if tx.send(chunk).await.is_err() || failed {
+            return;
+        }
+    }
+}
+
+/// 阻塞端的输入：从通道取数据块
+struct ChannelReader {
+    rx: mpsc::Receiver<io::Result<Vec<u8>>>,
+    chunk: Vec<u8>,
+    pos: usize,
+    read: u64,
+    cancel: Arc<AtomicBool>,
+    progress: Arc<watch::Sender<Progress>>,
+}
+
+impl Read for ChannelReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.pos == self.chunk.len() {
+            if self.cancel.load(Ordering::Relaxed) {
+                return Err(cancelled());
+            }
+            match self.rx.blocking_recv() {
+                Some(Ok(chunk)) => {
+                    self.read += chunk.len() as u64;
+                    self.progress.send_modify(|progress| progress.read = self.read);
+                    self.chunk = chunk;
+                    self.pos = 0;
+                }
+                Some(Err(e)) => return Err(e),
+                None if self.cancel.load(Ordering::Relaxed) => return Err(cancelled()),
+                None => return Ok(0),
+            }
+        }
+        let n = buf.len().min(self.chunk.len() - self.pos);
+        buf[..n].copy_from_slice(&self.chunk[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+/// 阻塞端的输出：攒满一块后送入通道，通道满时阻塞
+struct ChannelWriter {
+    tx: mpsc::Sender<Vec<u8>>,
+    buf: Vec<u8>,
+    cancel: Arc<AtomicBool>,
+}
+
+impl ChannelWriter {
+    fn send(&mut self) -> io::Result<()> {
+        if self.cancel.load(Ordering::Relaxed) {
+            return Err(cancelled());
+        }
+        let chunk = std::mem::replace(&mut self.buf, Vec::with_capacity(CHUNK_SIZE));
+        self.tx.blocking_send(chunk).map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "输出已关闭"))
+    }
+}
+
+impl Write for ChannelWriter {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        let n = data.len().min(CHUNK_SIZE - self.buf.len());
+        self.buf.extend_from_slice(&data[..n]);
+        if self.buf.len() == CHUNK_SIZE {
+            self.send()?;
+        }
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if self.buf.is_empty() {
+            return Ok(());
+        }
+        self.send()
+    }
+}
+
+/// 随外层任务一起取消的子任务
+struct AbortOnDrop<T>(JoinHandle<T>);
+
+impl<T> Drop for AbortOnDrop<T> {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+fn join_err(e: tokio::task::JoinError) -> FastZipError {
+    FastZipError::Other(format!("后台任务失败: {}", e))
+}
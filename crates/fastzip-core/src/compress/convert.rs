@@ -0,0 +1,497 @@
+//! 归档格式转换：逐条目从源归档流式读出，直接写入目标格式，不解压到磁盘
+//!
+//! ZIP、7z、tar.* 源按顺序解码；只有 RAR（unrar 只能解到文件）与 ZipCrypto 输出
+//! （加密头需先算出 CRC）会把单个条目暂存到临时文件。
+
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Seek, Write};
+use std::path::Path;
+use std::time::SystemTime;
+
+use sevenz_rust::nt_time::FileTime;
+use sevenz_rust::{Password, SevenZArchiveEntry, SevenZReader, SevenZWriter};
+use zip::write::FullFileOptions;
+use zip::{ZipArchive, ZipWriter};
+
+use super::encryption::{force_ae2, ZipCryptoWriter};
+use super::filter::EntryFilter;
+use super::volume::{remove_output, write_zip, Output};
+use super::{
+    check_sevenz_options, reject_aes_lzma, reject_comment, reject_password, seven_err, sevenz_writer, stamp_options,
+    unix_secs, write_lzma_entry, zip_file_options, CompressOptions, ZipEncryption, ZipMethod,
+};
+use crate::error::{FastZipError, Result};
+use crate::formats::{
+    detect_format, entry_mtime, mark_lzma, open_decoder, open_zip_entry, unix_mode, ArchiveFormat,
+    ArchiveInput, Codec, Encoder,
+};
+
+/// 转换中的一个条目
+pub(super) struct Entry {
+    /// 以 `/` 分隔，不含末尾的 `/`
+    pub name: String,
+    pub kind: Kind,
+    pub size: u64,
+    /// 修改时间（Unix 秒）
+    pub mtime: Option<i64>,
+    /// 权限位（0o7777）
+    pub mode: Option<u32>,
+}
+
+pub(super) enum Kind {
+    File,
+    Dir,
+    Symlink(String),
+}
+
+/// 把 `source` 转换为 `dest`，目标格式按扩展名选择（ZIP、7z、tar.*），返回写入的条目数
+///
+/// 条目路径、修改时间与 Unix 权限在两侧均支持时保留；符号链接按各格式的约定转换，
+/// tar 硬链接等无法表示的条目跳过。`password` 用于读取加密的源归档；
+/// `options` 决定输出的压缩方法、级别与加密，其中的 include/exclude 用于筛选条目。
+/// 7z 输出不使用固实压缩（固实块需要同时持有全部条目）；`volume_size` 的分卷规则同压缩。
+pub fn convert_archive(
+    source: &Path,
+    dest: &Path,
+    password: Option<&str>,
+    options: &CompressOptions,
+) -> Result<u64> {
+    let format = detect_format(source)?;
+    let target = ArchiveFormat::from_extension(dest)
+        .filter(|f| matches!(f, ArchiveFormat::Zip | ArchiveFormat::SevenZ) || f.is_tar())
+        .ok_or_else(|| {
+            FastZipError::UnsupportedFormat(format!("无法写入 {}（支持 ZIP、7z、tar.*）", dest.display()))
+        })?;
+    if fs::canonicalize(dest).is_ok_and(|d| fs::canonicalize(source).is_ok_and(|s| s == d)) {
+        return Err(FastZipError::Other("输出路径不能与源归档相同".into()));
+    }
+    let filter = EntryFilter::new(options)?;
+
+    let mut count = 0;
+    let mut convert = |dest: &Path| -> Result<()> {
+        let mut sink = open_sink(dest, target, options)?;
+        read_entries(source, format, password, &mut |entry, data| {
+            if !filter.accept(&entry.name, matches!(entry.kind, Kind::Dir), None) {
+                io::copy(data, &mut io::sink())?;
+                return Ok(());
+            }
+            sink.add(&entry, data)?;
+            count += 1;
+            Ok(())
+        })?;
+        sink.finish()
+    };
+    let result = match target {
+        ArchiveFormat::Zip => write_zip(dest, options.volume_size, convert),
+        _ => convert(dest),
+    };
+    if result.is_err() {
+        remove_output(dest);
+    }
+    result.map(|_| count)
+}
+
+pub(super) type Visit<'a> = dyn FnMut(Entry, &mut dyn Read) -> Result<()> + 'a;
+
+/// 按顺序读出 `source` 的条目，`visit` 收到条目信息与其内容
+pub(super) fn read_entries(source: &Path, format: ArchiveFormat, password: Option<&str>, visit: &mut Visit) -> Result<()> {
+    match format {
+        ArchiveFormat::Zip => read_zip(source, password, visit),
+        ArchiveFormat::SevenZ => read_7z(source, password, visit),
+        format if format.is_tar() => read_tar(source, format.codec(), visit),
+        #[cfg(feature = "unrar")]
+        ArchiveFormat::Rar => read_rar(source, password, visit),
+        format => Err(FastZipError::UnsupportedFormat(format!("{:?} 不支持逐条目读取", format))),
+    }
+}
+
+fn read_zip(source: &Path, password: Option<&str>, visit: &mut Visit) -> Result<()> {
+    let input = ArchiveInput::open(source)?;
+    let mut zip = ZipArchive::new(input.reader()?)?;
+    for i in 0..zip.len() {
+        let mut file = open_zip_entry(&mut zip, i, password)?;
+        let name = clean_name(file.name());
+        if name.is_empty() {
+            continue;
+        }
+        let kind = if file.is_dir() {
+            Kind::Dir
+        } else if file.is_symlink() {
+            let mut target = String::new();
+            file.read_to_string(&mut target)?;
+            Kind::Symlink(target)
+        } else {
+            Kind::File
+        };
+        let entry = Entry {
+            name,
+            kind,
+            size: file.size(),
+            mtime: entry_mtime(&file),
+            mode: file.unix_mode().map(|m| m & 0o7777),
+        };
+        visit(entry, &mut file)?;
+    }
+    Ok(())
+}
+
+fn read_tar(source: &Path, codec: Option<Codec>, visit: &mut Visit) -> Result<()> {
+    let tar_err = |e: io::Error| FastZipError::Tar(e.to_string());
+    let file = File::open(source)?;
+    let reader: Box<dyn Read + Send> = match codec {
+        Some(codec) => open_decoder(file, codec, 0)?,
+        None => Box::new(BufReader::with_capacity(1024 * 1024, file)),
+    };
+    let mut archive = tar::Archive::new(reader);
+    for entry in archive.entries().map_err(tar_err)? {
+        let mut entry = entry.map_err(tar_err)?;
+        let name = clean_name(&entry.path().map_err(tar_err)?.to_string_lossy());
+        let header = entry.header();
+        let entry_type = header.entry_type();
+        let kind = if entry_type.is_dir() {
+            Kind::Dir
+        } else if entry_type.is_file() || entry_type.is_contiguous() {
+            Kind::File
+        } else if entry_type.is_symlink() {
+            let target = entry.link_name().map_err(tar_err)?.unwrap_or_default();
+            Kind::Symlink(target.to_string_lossy().into_owned())
+        } else {
+            if !entry_type.is_pax_global_extensions() {
+                tracing::warn!(name = %name, "目标格式无法表示该 tar 条目类型，已跳过");
+            }
+            continue;
+        };
+        if name.is_empty() {
+            continue;
+        }
+        let converted = Entry {
+            name,
+            kind,
+            size: entry.size(),
+            mtime: header.mtime().ok().map(|t| t as i64),
+            mode: header.mode().ok().map(|m| m & 0o7777),
+        };
+        visit(converted, &mut entry)?;
+    }
+    Ok(())
+}
+
+fn read_7z(source: &Path, password: Option<&str>, visit: &mut Visit) -> Result<()> {
+    let input = ArchiveInput::open(source)?;
+    let password = password.map_or_else(Password::empty, Password::from);
+    let mut reader = SevenZReader::new(input.reader()?, input.size()?, password).map_err(seven_err)?;
+    // 回调只能返回 sevenz 的错误，自身错误暂存后中止遍历
+    let mut failure = None;
+    reader
+        .for_each_entries(|entry, data| {
+            // 不看 anti 标记：sevenz-rust 写出的空条目都带有该标记，解压时同样忽略
+            let result = (|| -> Result<()> {
+                let unix = unix_mode(entry);
+                let kind = if entry.is_directory {
+                    Kind::Dir
+                } else if unix.is_some_and(|m| m & 0o170000 == 0o120000) {
+                    let mut target = String::new();
+                    data.read_to_string(&mut target)?;
+                    Kind::Symlink(target)
+                } else {
+                    Kind::File
+                };
+                let converted = Entry {
+                    name: clean_name(entry.name()),
+                    kind,
+                    size: entry.size(),
+                    mtime: entry.has_last_modified_date.then(|| entry.last_modified_date.to_unix_time()),
+                    mode: unix.map(|m| m & 0o7777),
+                };
+                match converted.name.is_empty() {
+                    true => Ok(()),
+                    false => visit(converted, data),
+                }
+            })();
+            match result {
+                Ok(()) => Ok(true),
+                Err(e) => {
+                    failure = Some(e);
+                    Ok(false)
+                }
+            }
+        })
+        .map_err(seven_err)?;
+    failure.map_or(Ok(()), Err)
+}
+
+/// unrar 只能把条目解到文件：逐个解到临时目录再读出
+#[cfg(feature = "unrar")]
+fn read_rar(source: &Path, password: Option<&str>, visit: &mut Visit) -> Result<()> {
+    let rar_err = |e: unrar::error::UnrarError| FastZipError::Other(format!("RAR 读取: {}", e));
+    let archive = match password {
+        Some(pw) => unrar::Archive::with_password(source, pw.as_bytes()),
+        None => unrar::Archive::new(source),
+    };
+    let mut open = archive.as_first_part().open_for_processing().map_err(rar_err)?;
+    let spill = tempfile::tempdir()?;
+    let spilled = spill.path().join("entry");
+    while let Some(header) = open.read_header().map_err(rar_err)? {
+        let info = header.entry();
+        let time = zip::DateTime::try_from_msdos((info.file_time >> 16) as u16, info.file_time as u16).ok();
+        let mut entry = Entry {
+            name: clean_name(&info.filename.to_string_lossy()),
+            kind: Kind::File,
+            size: info.unpacked_size,
+            mtime: time.map(crate::formats::dos_unix_secs),
+            mode: None,
+        };
+        if info.is_directory() {
+            entry.kind = Kind::Dir;
+            open = header.skip().map_err(rar_err)?;
+            visit(entry, &mut io::empty())?;
+        } else {
+            open = header.extract_to(&spilled).map_err(rar_err)?;
+            let mut file = File::open(&spilled)?;
+            entry.size = file.metadata()?.len();
+            visit(entry, &mut file)?;
+            fs::remove_file(&spilled)?;
+        }
+    }
+    Ok(())
+}
+
+/// 统一为 `/` 分隔，去掉开头的 `./` 与末尾的 `/`
+pub(super) fn clean_name(name: &str) -> String {
+    let name = name.replace('\\', "/");
+    name.trim_start_matches("./").trim_end_matches('/').to_string()
+}
+
+/// 缺少修改时间时使用当前时间
+fn mtime_or_now(entry: &Entry) -> i64 {
+    entry.mtime.or_else(|| unix_secs(SystemTime::now())).unwrap_or(0)
+}
+
+trait Sink {
+    fn add(&mut self, entry: &Entry, data: &mut dyn Read) -> Result<()>;
+    fn finish(self: Box<Self>) -> Result<()>;
+}
+
+fn open_sink<'a>(dest: &Path, format: ArchiveFormat, options: &'a CompressOptions) -> Result<Box<dyn Sink + 'a>> {
+    match format {
+        ArchiveFormat::SevenZ => {
+            check_sevenz_options(options)?;
+            Ok(Box::new(SevenZSink { writer: sevenz_writer(dest, options)? }))
+        }
+        ArchiveFormat::Zip => {
+            reject_aes_lzma(options)?;
+            let comment = options.zip_comment()?.unwrap_or_default();
+            let out = BufWriter::with_capacity(1024 * 1024, File::create(dest)?);
+            match (&options.password, options.encryption) {
+                (Some(password), ZipEncryption::ZipCrypto) => {
+                    let mut zip = ZipCryptoWriter::new(out, password);
+                    zip.set_comment(comment);
+                    zip.set_extended_timestamp(options.extended_timestamp);
+                    Ok(Box::new(ZipCryptoSink { zip, method: options.method, level: options.zip_level() }))
+                }
+                (password, _) => {
+                    let dir_opts = zip_file_options(options);
+                    let file_opts = match password {
+                        Some(password) => dir_opts.clone().with_aes_encryption(zip::AesMode::Aes256, password),
+                        None => dir_opts.clone(),
+                    };
+                    let mut zip = ZipWriter::new(out);
+                    zip.set_raw_comment(comment.into());
+                    Ok(Box::new(ZipSink {
+                        zip,
+                        dir_opts,
+                        file_opts,
+                        options,
+                        dest: dest.to_path_buf(),
+                    }))
+                }
+            }
+        }
+        format => {
+            reject_password(options, "tar")?;
+            reject_comment(options, "tar")?;
+            let out = BufWriter::with_capacity(1024 * 1024, Output::create(dest, options.volume_size)?);
+            match format.codec() {
+                None => Ok(Box::new(TarSink::new(out, |mut out: BufWriter<Output>| Ok(out.flush()?)))),
+                Some(codec) => {
+                    let encoder = Encoder::new(out, codec, Some(options.codec_level(codec)))?;
+                    Ok(Box::new(TarSink::new(encoder, |encoder: Encoder<BufWriter<Output>>| {
+                        Ok(encoder.finish()?.flush()?)
+                    })))
+                }
+            }
+        }
+    }
+}
+
+struct ZipSink<'a> {
+    zip: ZipWriter<BufWriter<File>>,
+    dir_opts: FullFileOptions<'static>,
+    file_opts: FullFileOptions<'a>,
+    options: &'a CompressOptions,
+    dest: std::path::PathBuf,
+}
+
+impl Sink for ZipSink<'_> {
+    fn add(&mut self, entry: &Entry, data: &mut dyn Read) -> Result<()> {
+        let base = match entry.kind {
+            Kind::Dir => &self.dir_opts,
+            _ => &self.file_opts,
+        };
+        let opts = stamp_options(base.clone(), entry.mtime, entry.mode, self.options)?;
+        match &entry.kind {
+            Kind::Dir => self.zip.add_directory(format!("{}/", entry.name), opts)?,
+            Kind::Symlink(target) => self.zip.add_symlink(&entry.name, target, opts)?,
+            Kind::File => {
+                self.zip.start_file(&entry.name, opts)?;
+                match self.options.method {
+                    ZipMethod::Lzma => {
+                        write_lzma_entry(&mut self.zip, data, self.options.zip_level().unwrap_or_default())?
+                    }
+                    _ => {
+                        io::copy(data, &mut self.zip)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn finish(self: Box<Self>) -> Result<()> {
+        self.zip.finish()?.flush()?;
+        if self.options.method == ZipMethod::Lzma {
+            let mut file = File::options().read(true).write(true).open(&self.dest)?;
+            mark_lzma(&mut file, |f| f.is_file() && !f.is_symlink())?;
+        }
+        match &self.options.password {
+            Some(_) => force_ae2(&self.dest),
+            None => Ok(()),
+        }
+    }
+}
+
+struct ZipCryptoSink {
+    zip: ZipCryptoWriter<BufWriter<File>>,
+    method: ZipMethod,
+    level: Option<u32>,
+}
+
+impl Sink for ZipCryptoSink {
+    fn add(&mut self, entry: &Entry, data: &mut dyn Read) -> Result<()> {
+        let time = mtime_or_now(entry);
+        match &entry.kind {
+            Kind::Dir => {
+                let mode = 0o40000 | entry.mode.unwrap_or(0o755);
+                self.zip.add_dir_entry(&format!("{}/", entry.name), mode, time)
+            }
+            Kind::Symlink(target) => {
+                let target = target.as_bytes();
+                let len = target.len() as u64;
+                self.zip.add_entry(&entry.name, len, 0o120777 << 16, time, ZipMethod::Stored, None, || Ok(target))
+            }
+            Kind::File => {
+                // 加密头需要明文 CRC：先暂存到临时文件，再读第二遍压缩加密
+                let mut spill = tempfile::tempfile()?;
+                let len = io::copy(data, &mut spill)?;
+                let attr = (0o100000 | entry.mode.unwrap_or(0o644)) << 16;
+                self.zip.add_entry(&entry.name, len, attr, time, self.method, self.level, || {
+                    let mut file = spill.try_clone()?;
+                    file.rewind()?;
+                    Ok(file)
+                })
+            }
+        }
+    }
+
+    fn finish(self: Box<Self>) -> Result<()> {
+        self.zip.finish()?.flush()?;
+        Ok(())
+    }
+}
+
+struct TarSink<W: Write> {
+    tar: tar::Builder<W>,
+    done: fn(W) -> Result<()>,
+}
+
+impl<W: Write> TarSink<W> {
+    fn new(out: W, done: fn(W) -> Result<()>) -> Self {
+        Self { tar: tar::Builder::new(out), done }
+    }
+}
+
+impl<W: Write> Sink for TarSink<W> {
+    fn add(&mut self, entry: &Entry, data: &mut dyn Read) -> Result<()> {
+        let tar_err = |e: io::Error| FastZipError::Tar(e.to_string());
+        let mut header = tar::Header::new_gnu();
+        header.set_mtime(mtime_or_now(entry).max(0) as u64);
+        match &entry.kind {
+            Kind::Dir => {
+                header.set_entry_type(tar::EntryType::Directory);
+                header.set_mode(entry.mode.unwrap_or(0o755));
+                header.set_size(0);
+                let name = format!("{}/", entry.name);
+                self.tar.append_data(&mut header, name, io::empty()).map_err(tar_err)
+            }
+            Kind::Symlink(target) => {
+                header.set_entry_type(tar::EntryType::Symlink);
+                header.set_mode(0o777);
+                header.set_size(0);
+                self.tar.append_link(&mut header, &entry.name, target).map_err(tar_err)
+            }
+            Kind::File => {
+                header.set_entry_type(tar::EntryType::Regular);
+                header.set_mode(entry.mode.unwrap_or(0o644));
+                header.set_size(entry.size);
+                self.tar.append_data(&mut header, &entry.name, data.take(entry.size)).map_err(tar_err)
+            }
+        }
+    }
+
+    fn finish(self: Box<Self>) -> Result<()> {
+        let out = self.tar.into_inner().map_err(|e| FastZipError::Tar(e.to_string()))?;
+        (self.done)(out)
+    }
+}
+
+struct SevenZSink {
+    writer: SevenZWriter<Output>,
+}
+
+impl Sink for SevenZSink {
+    fn add(&mut self, entry: &Entry, data: &mut dyn Read) -> Result<()> {
+        let mut converted = SevenZArchiveEntry::new();
+        converted.name = entry.name.clone();
+        if let Some(time) = entry.mtime.and_then(|secs| FileTime::from_unix_time(secs).ok()) {
+            converted.last_modified_date = time;
+            converted.has_last_modified_date = true;
+        }
+        let file_type = match entry.kind {
+            Kind::Dir => 0o40000,
+            Kind::Symlink(_) => 0o120000,
+            Kind::File => 0o100000,
+        };
+        if let Some(mode) = entry.mode.or(matches!(entry.kind, Kind::Symlink(_)).then_some(0o777)) {
+            // p7zip 约定：高 16 位为 Unix 模式；目录另设 FILE_ATTRIBUTE_DIRECTORY
+            let dir_attr = if matches!(entry.kind, Kind::Dir) { 0x10 } else { 0 };
+            converted.has_windows_attributes = true;
+            converted.windows_attributes = 0x8000 | dir_attr | ((file_type | mode) << 16);
+        }
+        match &entry.kind {
+            Kind::Dir => {
+                converted.is_directory = true;
+                self.writer.push_archive_entry::<&[u8]>(converted, None)
+            }
+            Kind::Symlink(target) => self.writer.push_archive_entry(converted, Some(target.as_bytes())),
+            Kind::File if entry.size == 0 => self.writer.push_archive_entry::<&[u8]>(converted, None),
+            Kind::File => self.writer.push_archive_entry(converted, Some(data)),
+        }
+        .map_err(seven_err)?;
+        Ok(())
+    }
+
+    fn finish(self: Box<Self>) -> Result<()> {
+        self.writer.finish()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,224 @@
+//! 比较两个归档，或归档与目录：逐条目流式读出内容计算 CRC32，不解压到磁盘
+
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::Path;
+
+use super::convert::{read_entries, Kind};
+use super::{link_target, unix_secs, walk_sources, CompressOptions, EntryKind};
+use crate::error::Result;
+use crate::formats::detect_format;
+
+/// 条目类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiffEntryKind {
+    File,
+    Dir,
+    Symlink,
+}
+
+/// 一侧条目的信息
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiffEntryInfo {
+    pub kind: DiffEntryKind,
+    /// 内容字节数（目录为 0，链接为目标的长度）
+    pub size: u64,
+    /// 内容的 CRC32（链接为目标的 CRC32，目录为 0）
+    pub crc32: u32,
+    /// 链接目标
+    pub target: Option<String>,
+    /// 修改时间（Unix 秒）
+    pub mtime: Option<i64>,
+    /// 权限位（0o7777）
+    pub mode: Option<u32>,
+}
+
+/// 变化类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeKind {
+    /// 只在新的一侧
+    Added,
+    /// 只在旧的一侧
+    Removed,
+    /// 类型、大小、CRC32 或链接目标不同
+    Modified,
+    /// 内容相同，只有修改时间或权限不同
+    Metadata,
+}
+
+impl ChangeKind {
+    /// 英文名（added、removed、modified、metadata），用于 JSON 等输出
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Added => "added",
+            Self::Removed => "removed",
+            Self::Modified => "modified",
+            Self::Metadata => "metadata",
+        }
+    }
+}
+
+/// 一个有变化的条目
+#[derive(Debug, Clone)]
+pub struct DiffEntry {
+    /// 以 `/` 分隔的条目路径
+    pub path: String,
+    pub change: ChangeKind,
+    /// 旧的一侧（新增时为 None）
+    pub old: Option<DiffEntryInfo>,
+    /// 新的一侧（删除时为 None）
+    pub new: Option<DiffEntryInfo>,
+}
+
+/// 比较结果
+#[derive(Debug, Clone, Default)]
+pub struct ArchiveDiff {
+    /// 按路径排序的变化
+    pub changes: Vec<DiffEntry>,
+    /// 完全相同的条目数
+    pub unchanged: usize,
+}
+
+impl ArchiveDiff {
+    /// 两侧没有差异
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// 某一类变化的条目数
+    pub fn count(&self, change: ChangeKind) -> usize {
+        self.changes.iter().filter(|c| c.change == change).count()
+    }
+}
+
+/// 条目路径 -> 信息
+type Snapshot = BTreeMap<String, DiffEntryInfo>;
+
+/// 比较 `old` 与 `new`，两侧均可为任意可逐条目读取的归档（ZIP、7z、tar.*，启用 unrar 时含 RAR）或目录
+///
+/// 目录以其内容为根，与压缩该目录得到的条目名一致。文件按大小与 CRC32 判断内容变化；
+/// 修改时间容许 2 秒误差（ZIP 的 DOS 时间精度），目录不比较修改时间，
+/// 一侧缺少时间或权限时不比较该项。`password` 用于两侧的加密归档。
+pub fn diff_archives(old: &Path, new: &Path, password: Option<&str>) -> Result<ArchiveDiff> {
+    let old = snapshot(old, password)?;
+    let mut new = snapshot(new, password)?;
+    let mut diff = ArchiveDiff::default();
+    for (path, before) in old {
+        let Some(after) = new.remove(&path) else {
+            diff.changes.push(DiffEntry { path, change: ChangeKind::Removed, old: Some(before), new: None });
+            continue;
+        };
+        match compare(&before, &after) {
+            Some(change) => diff.changes.push(DiffEntry { path, change, old: Some(before), new: Some(after) }),
+            None => diff.unchanged += 1,
+        }
+    }
+    for (path, after) in new {
+        diff.changes.push(DiffEntry { path, change: ChangeKind::Added, old: None, new: Some(after) });
+    }
+    diff.changes.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(diff)
+}
+
+fn compare(old: &DiffEntryInfo, new: &DiffEntryInfo) -> Option<ChangeKind> {
+    if old.kind != new.kind || old.size != new.size || old.crc32 != new.crc32 || old.target != new.target {
+        return Some(ChangeKind::Modified);
+    }
+    let mtime = old.kind != DiffEntryKind::Dir
+        && matches!((old.mtime, new.mtime), (Some(a), Some(b)) if (a - b).abs() >= 2);
+    let mode = matches!((old.mode, new.mode), (Some(a), Some(b)) if a != b);
+    (mtime || mode).then_some(ChangeKind::Metadata)
+}
+
+fn snapshot(path: &Path, password: Option<&str>) -> Result<Snapshot> {
+    if path.is_dir() {
+        return scan_dir(path);
+    }
+    let format = detect_format(path)?;
+    let mut entries = Snapshot::new();
+    read_entries(path, format, password, &mut |entry, data| {
+        let (kind, target) = match entry.kind {
+            Kind::File => (DiffEntryKind::File, None),
+            Kind::Dir => (DiffEntryKind::Dir, None),
+            Kind::Symlink(target) => (DiffEntryKind::Symlink, Some(target)),
+        };
+        let (size, crc32) = match &target {
+            Some(target) => (target.len() as u64, crc32fast::hash(target.as_bytes())),
+            None => checksum(data)?,
+        };
+        let info = DiffEntryInfo { kind, size, crc32, target, mtime: entry.mtime, mode: entry.mode };
+        entries.insert(entry.name, info);
+        Ok(())
+    })?;
+    Ok(entries)
+}
+
+/// 目录内容，规则同压缩该目录（存储链接本身，跳过特殊文件）
+fn scan_dir(dir: &Path) -> Result<Snapshot> {
+    let mut entries = Snapshot::new();
+    walk_sources(&[dir], &CompressOptions::default(), |path, name, kind| {
+        let meta = fs::symlink_metadata(path)?;
+        let (kind, size, crc32, target) = match kind {
+            EntryKind::File => {
+                let (size, crc32) = checksum(&mut File::open(path)?)?;
+                (DiffEntryKind::File, size, crc32, None)
+            }
+            EntryKind::Dir => (DiffEntryKind::Dir, 0, 0, None),
+            EntryKind::Symlink => {
+                let target = link_target(path)?;
+                (DiffEntryKind::Symlink, target.len() as u64, crc32fast::hash(target.as_bytes()), Some(target))
+            }
+        };
+        #[cfg(unix)]
+        let mode = Some(std::os::unix::fs::PermissionsExt::mode(&meta.permissions()) & 0o7777);
+        #[cfg(not(unix))]
+        let mode = None;
+        let mtime = meta.modified().ok().and_then(unix_secs);
+        entries.insert(name.to_string(), DiffEntryInfo { kind, size, crc32, target, mtime, mode });
+        Ok(())
+    })?;
+    Ok(entries)
+}
+
+/// 读完 `data`，返回字节数与 CRC32
+fn checksum(data: &mut dyn Read) -> Result<(u64, u32)> {
+    struct Crc(crc32fast::Hasher);
+    impl Write for Crc {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.update(buf);
+            Ok(buf.len())
+        }
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+    let mut crc = Crc(crc32fast::Hasher::new());
+    let size = io::copy(data, &mut crc)?;
+    Ok((size, crc.0.finalize()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn info(kind: DiffEntryKind, crc32: u32, mtime: i64, mode: u32) -> DiffEntryInfo {
+        DiffEntryInfo { kind, size: 4, crc32, target: None, mtime: Some(mtime), mode: Some(mode) }
+    }
+
+    #[test]
+    fn test_compare() {
+        let file = info(DiffEntryKind::File, 1, 1000, 0o644);
+        assert_eq!(compare(&file, &file), None);
+        // DOS 时间误差内视为相同
+        assert_eq!(compare(&file, &info(DiffEntryKind::File, 1, 1001, 0o644)), None);
+        assert_eq!(compare(&file, &info(DiffEntryKind::File, 1, 1002, 0o644)), Some(ChangeKind::Metadata));
+        assert_eq!(compare(&file, &info(DiffEntryKind::File, 1, 1000, 0o755)), Some(ChangeKind::Metadata));
+        assert_eq!(compare(&file, &info(DiffEntryKind::File, 2, 1000, 0o644)), Some(ChangeKind::Modified));
+        assert_eq!(compare(&file, &DiffEntryInfo { mtime: None, mode: None, ..file.clone() }), None);
+
+        let dir = info(DiffEntryKind::Dir, 0, 1000, 0o755);
+        assert_eq!(compare(&dir, &info(DiffEntryKind::Dir, 0, 5000, 0o755)), None);
+        assert_eq!(compare(&dir, &info(DiffEntryKind::File, 0, 1000, 0o755)), Some(ChangeKind::Modified));
+    }
+}
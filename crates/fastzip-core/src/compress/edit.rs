@@ -0,0 +1,269 @@
+//! 修改已有归档：删除、重命名条目，修改 ZIP 注释
+//!
+//! ZIP 与 tar 未改动的条目均按原始字节复制（tar 含 PAX/GNU 扩展记录）；
+//! tar.* 解压后重写并按原有的分帧方式（BGZF、zstd 可寻址格式）重新编码。
+//! 结果先写入同目录的临时文件，成功后替换原归档。
+
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+use tempfile::NamedTempFile;
+use zip::ZipArchive;
+
+use super::check_zip_comment;
+use super::filter::GlobList;
+use super::volume::reject_volumes;
+use super::zip_rewrite::{raw_spans, RawZipWriter};
+use crate::error::{FastZipError, Result};
+use super::tar_rewrite::{write_end, write_renamed, RawTarReader};
+use crate::formats::{detect_format, open_decoder, ArchiveFormat, Codec, Encoder, Framing};
+
+/// 对单个条目的处理
+enum Edit {
+    Keep,
+    Delete,
+    Rename(String),
+}
+
+/// 一次修改的结果：（原条目名，新条目名；删除时为 None）
+type Changes = Vec<(String, Option<String>)>;
+
+/// 删除匹配 `patterns` 的条目，返回被删除的条目名
+///
+/// 模式规则同压缩时的 `exclude`：含 `/` 的模式匹配完整路径，否则匹配任意层级的名称；
+/// 匹配到目录时其下条目一并删除。没有条目匹配时报错且不改动归档。
+pub fn delete_entries(archive: &Path, patterns: &[String]) -> Result<Vec<String>> {
+    if patterns.is_empty() {
+        return Err(FastZipError::Other("未指定要删除的条目".into()));
+    }
+    let globs = GlobList::new(patterns)?;
+    let changes = edit_archive(archive, &|name| {
+        let rel = name.trim_end_matches('/');
+        let mut ancestors = rel.match_indices('/').map(|(i, _)| &rel[..i]).chain([rel]);
+        match ancestors.any(|path| globs.matches(path)) {
+            true => Edit::Delete,
+            false => Edit::Keep,
+        }
+    })?;
+    Ok(changes.into_iter().map(|(name, _)| name).collect())
+}
+
+/// 将条目 `from` 重命名为 `to`，返回（原名，新名）
+///
+/// `from` 为目录时其下条目一并移动。新名与已有条目重名时报错且不改动归档。
+pub fn rename_entry(archive: &Path, from: &str, to: &str) -> Result<Vec<(String, String)>> {
+    let from = from.trim_matches('/');
+    let to = to.trim_matches('/');
+    if to.is_empty() || to.split('/').any(|c| c.is_empty() || c == "." || c == "..") {
+        return Err(FastZipError::Other(format!("无效的条目名: {}", to)));
+    }
+    if from == to {
+        return Err(FastZipError::Other("新旧条目名相同".into()));
+    }
+    let changes = edit_archive(archive, &|name| {
+        let rel = name.trim_end_matches('/');
+        let slash = &name[rel.len()..];
+        if rel == from {
+            Edit::Rename(format!("{to}{slash}"))
+        } else if let Some(rest) = rel.strip_prefix(from).and_then(|r| r.strip_prefix('/')) {
+            Edit::Rename(format!("{to}/{rest}{slash}"))
+        } else {
+            Edit::Keep
+        }
+    })?;
+    Ok(changes.into_iter().filter_map(|(old, new)| Some((old, new?))).collect())
+}
+
+/// 把 ZIP 归档注释改为 `comment`（空字符串即清除），条目按原始字节复制，不重新压缩
+pub fn set_zip_comment(archive: &Path, comment: &str) -> Result<()> {
+    let comment = check_zip_comment(comment)?;
+    reject_volumes(archive)?;
+    if detect_format(archive)? != ArchiveFormat::Zip {
+        return Err(FastZipError::UnsupportedFormat("只有 ZIP 支持修改归档注释".into()));
+    }
+    let zip = ZipArchive::new(BufReader::new(File::open(archive)?))?;
+    if zip.comment() == comment {
+        return Ok(());
+    }
+    replace_file(archive, |out| {
+        let mut writer = RawZipWriter::new(out);
+        writer.copy_all(zip)?;
+        writer.finish(comment)?;
+        Ok(())
+    })
+}
+
+fn edit_archive(archive: &Path, edit: &dyn Fn(&str) -> Edit) -> Result<Changes> {
+    reject_volumes(archive)?;
+    let format = detect_format(archive)?;
+    let changes = match format {
+        ArchiveFormat::Zip => edit_zip(archive, edit)?,
+        format if format.is_tar() => edit_tar(archive, format.codec(), edit)?,
+        format => {
+            return Err(FastZipError::UnsupportedFormat(format!(
+                "{:?} 不支持删除或重命名条目（仅支持 ZIP 与 tar.*）",
+                format
+            )))
+        }
+    };
+    if changes.is_empty() {
+        return Err(FastZipError::Other("没有匹配的条目".into()));
+    }
+    Ok(changes)
+}
+
+fn edit_zip(archive: &Path, edit: &dyn Fn(&str) -> Edit) -> Result<Changes> {
+    let mut zip = ZipArchive::new(BufReader::new(File::open(archive)?))?;
+    let mut edits = Vec::with_capacity(zip.len());
+    let mut changes = Changes::new();
+    let mut names = NameSet::default();
+    for i in 0..zip.len() {
+        let name = zip.by_index_raw(i)?.name().to_string();
+        let e = edit(&name);
+        match &e {
+            Edit::Keep => names.insert(&name)?,
+            Edit::Delete => changes.push((name, None)),
+            Edit::Rename(new) => {
+                names.insert(new)?;
+                changes.push((name, Some(new.clone())));
+            }
+        }
+        edits.push(e);
+    }
+    if changes.is_empty() {
+        return Ok(changes);
+    }
+
+    let comment = zip.comment().to_vec();
+    let spans = raw_spans(&mut zip)?;
+    let mut src = zip.into_inner();
+    replace_file(archive, move |out| {
+        let mut writer = RawZipWriter::new(out);
+        for (span, e) in spans.iter().zip(&edits) {
+            match e {
+                Edit::Keep => writer.copy_entry(&mut src, span, None)?,
+                Edit::Delete => {}
+                Edit::Rename(new) => writer.copy_entry(&mut src, span, Some(new))?,
+            }
+        }
+        writer.finish(&comment)?;
+        Ok(())
+    })?;
+    Ok(changes)
+}
+
+fn edit_tar(archive: &Path, codec: Option<Codec>, edit: &dyn Fn(&str) -> Edit) -> Result<Changes> {
+    let mut file = File::open(archive)?;
+    // 沿用原有的分帧方式（BGZF、zstd 可寻址格式），修改后仍可并行解码与随机访问
+    let framing = match codec {
+        Some(codec) => Framing::detect(&mut file, codec)?,
+        None => Framing::Default,
+    };
+    let reader: Box<dyn Read + Send> = match codec {
+        Some(codec) => open_decoder(file, codec, 0)?,
+        None => Box::new(BufReader::with_capacity(1024 * 1024, file)),
+    };
+    let mut changes = Changes::new();
+    replace_file(archive, |out| {
+        match codec {
+            Some(codec) => {
+                let encoder = Encoder::with_framing(out, codec, None, framing)?;
+                rewrite_tar(reader, encoder, edit, &mut changes)?.finish()?;
+            }
+            None => {
+                rewrite_tar(reader, out, edit, &mut changes)?;
+            }
+        }
+        // 没有改动时放弃临时文件
+        match changes.is_empty() {
+            true => Err(FastZipError::Other("没有匹配的条目".into())),
+            false => Ok(()),
+        }
+    })?;
+    Ok(changes)
+}
+
+/// 逐条目复制 tar：保留的条目连同 PAX/GNU 扩展记录按原始字节写出，改名时只替换路径
+///
+/// 删除仍被硬链接引用的条目、删除或重命名稀疏文件时报错且不改动归档。
+fn rewrite_tar<R: Read, W: Write>(
+    reader: R,
+    mut out: W,
+    edit: &dyn Fn(&str) -> Edit,
+    changes: &mut Changes,
+) -> Result<W> {
+    let mut reader = RawTarReader::new(reader);
+    let mut names = NameSet::default();
+    let mut deleted = HashSet::new();
+    while let Some(entry) = reader.next_entry()? {
+        if entry.global {
+            out.write_all(&entry.blocks)?;
+            continue;
+        }
+        let name = entry.name.clone();
+        let action = edit(&name);
+        if entry.sparse && !matches!(action, Edit::Keep) {
+            return Err(FastZipError::Other(format!("不支持删除或重命名稀疏文件: {}", name)));
+        }
+        let new = match action {
+            Edit::Keep => None,
+            Edit::Delete => {
+                deleted.insert(name.trim_end_matches('/').to_string());
+                changes.push((name, None));
+                continue;
+            }
+            Edit::Rename(new) => {
+                changes.push((name.clone(), Some(new.clone())));
+                Some(new)
+            }
+        };
+        names.insert(new.as_deref().unwrap_or(&name))?;
+
+        // 硬链接目标是归档内的条目，随之改名；目标已删除时链接无法解压
+        let mut link = None;
+        if let (true, Some(target)) = (entry.header.entry_type().is_hard_link(), &entry.link) {
+            if deleted.contains(target.trim_end_matches('/')) {
+                return Err(FastZipError::Other(format!("{} 是硬链接 {} 的目标，不能删除", target, name)));
+            }
+            if let Edit::Rename(renamed) = edit(target) {
+                link = Some(renamed);
+            }
+        }
+        match (&new, &link) {
+            (None, None) => out.write_all(&entry.blocks)?,
+            _ => write_renamed(&mut out, &entry, new.as_deref().unwrap_or(&name), link.as_deref())?,
+        }
+        reader.copy_data(&mut out)?;
+    }
+    write_end(&mut out)?;
+    Ok(out)
+}
+
+/// 修改后的条目名集合，用于拒绝重名
+#[derive(Default)]
+struct NameSet(HashSet<String>);
+
+impl NameSet {
+    fn insert(&mut self, name: &str) -> Result<()> {
+        match self.0.insert(name.trim_end_matches('/').to_string()) {
+            true => Ok(()),
+            false => Err(FastZipError::Other(format!("条目重名: {}", name))),
+        }
+    }
+}
+
+/// 把新内容写入同目录的临时文件，成功后替换 `path` 并保留原权限；失败时原文件不变
+pub(super) fn replace_file(path: &Path, write: impl FnOnce(&mut BufWriter<File>) -> Result<()>) -> Result<()> {
+    let dir = path.parent().filter(|p| !p.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    let permissions = fs::metadata(path)?.permissions();
+    let tmp = NamedTempFile::new_in(dir)?;
+    let mut out = BufWriter::with_capacity(1024 * 1024, tmp.as_file().try_clone()?);
+    write(&mut out)?;
+    out.flush()?;
+    drop(out);
+    fs::set_permissions(tmp.path(), permissions)?;
+    tmp.persist(path).map_err(|e| e.error)?;
+    Ok(())
+}
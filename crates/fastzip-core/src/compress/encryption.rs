@@ -0,0 +1,496 @@
+//! ZIP 加密：AES-256（WinZip AE-2）与传统 ZipCrypto
+//!
+//! AES 由 zip 库写入，但其对 20 字节以上的条目使用 AE-1（保留明文 CRC），
+//! 写完后由 [`force_ae2`] 就地改为 AE-2。zip 库未公开 ZipCrypto 写入接口，
+//! 因此 [`ZipCryptoWriter`] 自行写出归档结构。
+
+use std::cell::Cell;
+use std::fs::{File, Metadata, OpenOptions};
+use std::io::{self, BufReader, Read, Seek, SeekFrom, Write};
+use std::num::Wrapping;
+use std::path::Path;
+use std::time::SystemTime;
+
+use flate2::write::DeflateEncoder;
+
+use super::{extended_timestamp_data, unix_secs, ZipMethod, EXTENDED_TIMESTAMP_ID};
+use crate::error::{FastZipError, Result};
+use crate::formats::zip_datetime;
+use crate::hook::TaskHook;
+
+/// ZIP 加密方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ZipEncryption {
+    /// AES-256（WinZip AE-2），默认
+    #[default]
+    Aes256,
+    /// 传统 ZipCrypto：强度很弱，仅用于兼容旧工具；只支持 Stored / Deflate，归档与单个条目须小于 4 GiB
+    ZipCrypto,
+}
+
+/// AES 扩展字段 ID（0x9901）
+const AES_EXTRA_ID: u16 = 0x9901;
+
+/// 将归档内所有 AES 条目改为 AE-2：CRC 置 0，扩展字段中的厂商版本改为 2
+///
+/// AE-2 不存储明文 CRC，避免泄露内容信息；完整性由 HMAC 保证。
+pub(crate) fn force_ae2(path: &Path) -> Result<()> {
+    let mut file = OpenOptions::new().read(true).write(true).open(path)?;
+    force_ae2_in(&mut file)
+}
+
+/// 同 [`force_ae2`]，作用于已打开的归档（如内存中的临时归档）
+pub(crate) fn force_ae2_in<F: Read + Write + Seek>(file: &mut F) -> Result<()> {
+    let mut headers = Vec::new();
+    {
+        let mut archive = zip::ZipArchive::new(&mut *file)?;
+        for i in 0..archive.len() {
+            let entry = archive.by_index_raw(i)?;
+            if entry.encrypted() {
+                headers.push((entry.header_start(), entry.central_header_start()));
+            }
+        }
+    }
+
+    for (local, central) in headers {
+        // 本地文件头：CRC 位于 +14，文件名长度 +26，扩展长度 +28，扩展字段从 +30 开始
+        patch_header(file, local, 14, 26, 28, 30)?;
+        // 中央目录：CRC 位于 +16，文件名长度 +28，扩展长度 +30，扩展字段从 +46 开始
+        patch_header(file, central, 16, 28, 30, 46)?;
+    }
+    file.flush()?;
+    Ok(())
+}
+
+fn patch_header<F: Read + Write + Seek>(file: &mut F, start: u64, crc_at: u64, name_len_at: usize, extra_len_at: usize, fixed: usize) -> Result<()> {
+    let mut head = vec![0u8; fixed];
+    file.seek(SeekFrom::Start(start))?;
+    file.read_exact(&mut head)?;
+    let name_len = u16::from_le_bytes([head[name_len_at], head[name_len_at + 1]]) as u64;
+    let extra_len = u16::from_le_bytes([head[extra_len_at], head[extra_len_at + 1]]) as usize;
+
+    let extra_start = start + fixed as u64 + name_len;
+    let mut extra = vec![0u8; extra_len];
+    file.seek(SeekFrom::Start(extra_start))?;
+    file.read_exact(&mut extra)?;
+
+    let mut pos = 0;
+    while pos + 4 <= extra.len() {
+        let id = u16::from_le_bytes([extra[pos], extra[pos + 1]]);
+        let len = u16::from_le_bytes([extra[pos + 2], extra[pos + 3]]) as usize;
+        if id == AES_EXTRA_ID && len >= 2 {
+            file.seek(SeekFrom::Start(extra_start + pos as u64 + 4))?;
+            file.write_all(&2u16.to_le_bytes())?;
+            file.seek(SeekFrom::Start(start + crc_at))?;
+            file.write_all(&0u32.to_le_bytes())?;
+            return Ok(());
+        }
+        pos += 4 + len;
+    }
+    Err(FastZipError::Other("AES 条目缺少扩展字段".into()))
+}
+
+struct CentralEntry {
+    name: Vec<u8>,
+    flags: u16,
+    method: u16,
+    crc: u32,
+    compressed: u32,
+    uncompressed: u32,
+    offset: u32,
+    external_attr: u32,
+    time: zip::DateTime,
+    extra: Vec<u8>,
+}
+
+/// ZipCrypto 加密的 ZIP 写入器（不支持 ZIP64：归档、条目达到 4 GiB 或条目达到 65535 个时报错）
+pub(crate) struct ZipCryptoWriter<W: Write + Seek> {
+    out: W,
+    password: Vec<u8>,
+    entries: Vec<CentralEntry>,
+    comment: Vec<u8>,
+    extended_timestamp: bool,
+}
+
+/// 通用标志：bit 0 加密，bit 11 文件名为 UTF-8
+const FLAGS: u16 = 1 | 1 << 11;
+/// 需要的解压版本 2.0
+const VERSION: u16 = 20;
+
+impl<W: Write + Seek> ZipCryptoWriter<W> {
+    pub fn new(out: W, password: &str) -> Self {
+        Self {
+            out,
+            password: password.as_bytes().to_vec(),
+            entries: Vec::new(),
+            comment: Vec::new(),
+            extended_timestamp: false,
+        }
+    }
+
+    /// 设置归档注释（长度由调用方检查）
+    pub fn set_comment(&mut self, comment: &[u8]) {
+        self.comment = comment.to_vec();
+    }
+
+    /// 是否为条目写入扩展时间戳
+    pub fn set_extended_timestamp(&mut self, enabled: bool) {
+        self.extended_timestamp = enabled;
+    }
+
+    /// 目录条目不含数据，不加密
+    pub fn add_directory(&mut self, name: &str, meta: &Metadata) -> Result<()> {
+        self.add_dir_entry(name, unix_mode(meta), file_mtime(meta))
+    }
+
+    /// 以给定的 Unix 模式与修改时间（Unix 秒）写入目录条目
+    pub fn add_dir_entry(&mut self, name: &str, mode: u32, mtime: i64) -> Result<()> {
+        self.check_count()?;
+        let offset = self.offset()?;
+        let name = name.as_bytes().to_vec();
+        let (time, extra) = (zip_datetime(mtime), self.extra(mtime));
+        self.write_local_header(&name, 1 << 11, 0, 0, 0, 0, time, &extra)?;
+        self.entries.push(CentralEntry {
+            name,
+            flags: 1 << 11,
+            method: 0,
+            crc: 0,
+            compressed: 0,
+            uncompressed: 0,
+            offset,
+            external_attr: (mode << 16) | 0x10,
+            time,
+            extra,
+        });
+        Ok(())
+    }
+
+    /// 写入文件条目；源文件读两遍，只有压缩的那一遍向 `hook` 报告进度
+    pub fn add_file(
+        &mut self,
+        path: &Path,
+        name: &str,
+        method: ZipMethod,
+        level: Option<u32>,
+        hook: &TaskHook,
+    ) -> Result<()> {
+        let meta = std::fs::metadata(path)?;
+        let attr = unix_mode(&meta) << 16;
+        let opened = Cell::new(false);
+        let open = || {
+            let file = File::open(path)?;
+            Ok(match opened.replace(true) {
+                true => hook.reader(file),
+                false => hook.guard(file),
+            })
+        };
+        self.add_entry(name, meta.len(), attr, file_mtime(&meta), method, level, open)
+    }
+
+    /// 符号链接以链接目标为内容，仅存储
+    pub fn add_symlink(&mut self, name: &str, target: &str, meta: &Metadata) -> Result<()> {
+        let target = target.as_bytes();
+        let (len, mtime) = (target.len() as u64, file_mtime(meta));
+        self.add_entry(name, len, 0o120777 << 16, mtime, ZipMethod::Stored, None, || Ok(target))
+    }
+
+    /// 写入一个加密条目；`mtime` 为 Unix 秒，`open` 需两次打开数据（先算 CRC，再压缩加密）
+    #[allow(clippy::too_many_arguments)]
+    pub fn add_entry<R: Read>(
+        &mut self,
+        name: &str,
+        len: u64,
+        external_attr: u32,
+        mtime: i64,
+        method: ZipMethod,
+        level: Option<u32>,
+        open: impl Fn() -> io::Result<R>,
+    ) -> Result<()> {
+        self.check_count()?;
+        let uncompressed = to_u32(len)?;
+        let method_id = match method {
+            ZipMethod::Stored => 0,
+            ZipMethod::Deflate => 8,
+            other => {
+                return Err(FastZipError::UnsupportedFormat(format!("ZipCrypto 不支持压缩方法 {other:?}")));
+            }
+        };
+
+        // 加密头的校验字节取明文 CRC 的最高字节，需先读一遍计算 CRC
+        let mut hasher = crc32fast::Hasher::new();
+        let mut reader = BufReader::with_capacity(1024 * 1024, open()?);
+        let mut buf = vec![0u8; 256 * 1024];
+        loop {
+            let n = reader.read(&mut buf)?;
+            if n == 0 {
+                break;
+            }
+            hasher.update(&buf[..n]);
+        }
+        let crc = hasher.finalize();
+
+        let offset = self.offset()?;
+        let name = name.as_bytes().to_vec();
+        let (time, extra) = (zip_datetime(mtime), self.extra(mtime));
+        self.write_local_header(&name, FLAGS, method_id, crc, 0, uncompressed, time, &extra)?;
+
+        let mut header = [0u8; 12];
+        getrandom::getrandom(&mut header[..11]).map_err(|e| FastZipError::Other(e.to_string()))?;
+        header[11] = (crc >> 24) as u8;
+        let mut enc = CryptoWriter::new(&mut self.out, &self.password);
+        enc.write_all(&header)?;
+
+        let mut reader = BufReader::with_capacity(1024 * 1024, open()?);
+        let enc = if method_id == 8 {
+            let level = flate2::Compression::new(level.unwrap_or(6).min(9));
+            let mut deflate = DeflateEncoder::new(enc, level);
+            io::copy(&mut reader, &mut deflate)?;
+            deflate.finish()?
+        } else {
+            io::copy(&mut reader, &mut enc)?;
+            enc
+        };
+        let compressed = to_u32(enc.written)?;
+
+        // 回填压缩后大小
+        let end = self.out.stream_position()?;
+        self.out.seek(SeekFrom::Start(offset as u64 + 18))?;
+        self.out.write_all(&compressed.to_le_bytes())?;
+        self.out.seek(SeekFrom::Start(end))?;
+
+        self.entries.push(CentralEntry {
+            name,
+            flags: FLAGS,
+            method: method_id,
+            crc,
+            compressed,
+            uncompressed,
+            offset,
+            external_attr,
+            time,
+            extra,
+        });
+        Ok(())
+    }
+
+    /// 写出中央目录与结束记录
+    pub fn finish(mut self) -> Result<W> {
+        let cd_start = self.offset()?;
+        for e in &self.entries {
+            let mut h = Vec::with_capacity(46 + e.name.len());
+            h.extend_from_slice(&0x0201_4b50u32.to_le_bytes());
+            // 创建系统 Unix（3），版本 2.0
+            h.extend_from_slice(&(3 << 8 | VERSION).to_le_bytes());
+            h.extend_from_slice(&VERSION.to_le_bytes());
+            h.extend_from_slice(&e.flags.to_le_bytes());
+            h.extend_from_slice(&e.method.to_le_bytes());
+            h.extend_from_slice(&e.time.timepart().to_le_bytes());
+            h.extend_from_slice(&e.time.datepart().to_le_bytes());
+            h.extend_from_slice(&e.crc.to_le_bytes());
+            h.extend_from_slice(&e.compressed.to_le_bytes());
+            h.extend_from_slice(&e.uncompressed.to_le_bytes());
+            h.extend_from_slice(&(e.name.len() as u16).to_le_bytes());
+            h.extend_from_slice(&(e.extra.len() as u16).to_le_bytes());
+            // 注释长度、起始磁盘号、内部属性
+            h.extend_from_slice(&[0; 6]);
+            h.extend_from_slice(&e.external_attr.to_le_bytes());
+            h.extend_from_slice(&e.offset.to_le_bytes());
+            h.extend_from_slice(&e.name);
+            h.extend_from_slice(&e.extra);
+            self.out.write_all(&h)?;
+        }
+        let cd_size = self.offset()? - cd_start;
+
+        let count = self.entries.len() as u16;
+        let mut end = Vec::with_capacity(22);
+        end.extend_from_slice(&0x0605_4b50u32.to_le_bytes());
+        end.extend_from_slice(&[0; 4]);
+        end.extend_from_slice(&count.to_le_bytes());
+        end.extend_from_slice(&count.to_le_bytes());
+        end.extend_from_slice(&cd_size.to_le_bytes());
+        end.extend_from_slice(&cd_start.to_le_bytes());
+        end.extend_from_slice(&(self.comment.len() as u16).to_le_bytes());
+        end.extend_from_slice(&self.comment);
+        self.out.write_all(&end)?;
+        self.out.flush()?;
+        Ok(self.out)
+    }
+
+    fn offset(&mut self) -> Result<u32> {
+        to_u32(self.out.stream_position()?)
+    }
+
+    /// 结束记录中的条目数为 16 位，0xFFFF 表示 ZIP64
+    fn check_count(&self) -> Result<()> {
+        match self.entries.len() >= u16::MAX as usize {
+            true => Err(FastZipError::Other("ZipCrypto 归档条目过多（不支持 ZIP64），请改用 AES".into())),
+            false => Ok(()),
+        }
+    }
+
+    /// 条目的扩展字段：按设置写入扩展时间戳
+    fn extra(&self, mtime: i64) -> Vec<u8> {
+        if !self.extended_timestamp {
+            return Vec::new();
+        }
+        let data = extended_timestamp_data(mtime);
+        let mut extra = EXTENDED_TIMESTAMP_ID.to_le_bytes().to_vec();
+        extra.extend_from_slice(&(data.len() as u16).to_le_bytes());
+        extra.extend_from_slice(&data);
+        extra
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn write_local_header(
+        &mut self,
+        name: &[u8],
+        flags: u16,
+        method: u16,
+        crc: u32,
+        compressed: u32,
+        uncompressed: u32,
+        time: zip::DateTime,
+        extra: &[u8],
+    ) -> Result<()> {
+        let mut h = Vec::with_capacity(30 + name.len());
+        h.extend_from_slice(&0x0403_4b50u32.to_le_bytes());
+        h.extend_from_slice(&VERSION.to_le_bytes());
+        h.extend_from_slice(&flags.to_le_bytes());
+        h.extend_from_slice(&method.to_le_bytes());
+        h.extend_from_slice(&time.timepart().to_le_bytes());
+        h.extend_from_slice(&time.datepart().to_le_bytes());
+        h.extend_from_slice(&crc.to_le_bytes());
+        h.extend_from_slice(&compressed.to_le_bytes());
+        h.extend_from_slice(&uncompressed.to_le_bytes());
+        h.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        h.extend_from_slice(&(extra.len() as u16).to_le_bytes());
+        h.extend_from_slice(name);
+        h.extend_from_slice(extra);
+        self.out.write_all(&h)?;
+        Ok(())
+    }
+}
+
+/// 大小与偏移为 32 位，0xFFFFFFFF 表示 ZIP64，也不能使用
+fn to_u32(n: u64) -> Result<u32> {
+    match u32::try_from(n) {
+        Ok(n) if n != u32::MAX => Ok(n),
+        _ => Err(FastZipError::Other("ZipCrypto 不支持达到 4 GiB 的归档或条目，请改用 AES".into())),
+    }
+}
+
+#[cfg(unix)]
+fn unix_mode(meta: &std::fs::Metadata) -> u32 {
+    use std::os::unix::fs::PermissionsExt;
+    meta.permissions().mode()
+}
+
+#[cfg(not(unix))]
+fn unix_mode(meta: &std::fs::Metadata) -> u32 {
+    if meta.is_dir() {
+        0o40755
+    } else {
+        0o100644
+    }
+}
+
+/// 条目的修改时间取源文件修改时间，无法获取时取当前时间
+fn file_mtime(meta: &Metadata) -> i64 {
+    meta.modified().ok().and_then(unix_secs).or_else(|| unix_secs(SystemTime::now())).unwrap_or(0)
+}
+
+/// ZipCrypto 密钥状态
+struct Keys([Wrapping<u32>; 3]);
+
+impl Keys {
+    fn new(password: &[u8]) -> Self {
+        let mut keys = Self([Wrapping(0x1234_5678), Wrapping(0x2345_6789), Wrapping(0x3456_7890)]);
+        for &b in password {
+            keys.update(b);
+        }
+        keys
+    }
+
+    fn update(&mut self, plain: u8) {
+        let k = &mut self.0;
+        k[0] = crc32_byte(k[0], plain);
+        k[1] = (k[1] + (k[0] & Wrapping(0xff))) * Wrapping(134_775_813) + Wrapping(1);
+        k[2] = crc32_byte(k[2], (k[1] >> 24).0 as u8);
+    }
+
+    fn encrypt(&mut self, plain: u8) -> u8 {
+        let t = (self.0[2].0 | 2) as u16;
+        let cipher = plain ^ (t.wrapping_mul(t ^ 1) >> 8) as u8;
+        self.update(plain);
+        cipher
+    }
+}
+
+const CRC_TABLE: [u32; 256] = {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut c = i as u32;
+        let mut k = 0;
+        while k < 8 {
+            c = if c & 1 != 0 { 0xEDB8_8320 ^ (c >> 1) } else { c >> 1 };
+            k += 1;
+        }
+        table[i] = c;
+        i += 1;
+    }
+    table
+};
+
+fn crc32_byte(crc: Wrapping<u32>, b: u8) -> Wrapping<u32> {
+    Wrapping(CRC_TABLE[((crc.0 ^ b as u32) & 0xff) as usize] ^ (crc.0 >> 8))
+}
+
+/// 加密并计数写入的字节
+struct CryptoWriter<'a, W: Write> {
+    inner: &'a mut W,
+    keys: Keys,
+    buf: Vec<u8>,
+    written: u64,
+}
+
+impl<'a, W: Write> CryptoWriter<'a, W> {
+    fn new(inner: &'a mut W, password: &[u8]) -> Self {
+        Self { inner, keys: Keys::new(password), buf: Vec::new(), written: 0 }
+    }
+}
+
+impl<W: Write> Write for CryptoWriter<'_, W> {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        self.buf.clear();
+        self.buf.extend(data.iter().map(|&b| self.keys.encrypt(b)));
+        self.inner.write_all(&self.buf)?;
+        self.written += data.len() as u64;
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rejects_zip64_sizes_and_counts() {
+        // 0xFFFFFFFF 与 0xFFFF 在 ZIP 中表示 ZIP64，不能作为实际值写出
+        assert_eq!(to_u32(u32::MAX as u64 - 1).unwrap(), u32::MAX - 1);
+        assert!(to_u32(u32::MAX as u64).is_err());
+        assert!(to_u32(1 << 32).is_err());
+
+        let mut zip = ZipCryptoWriter::new(io::Cursor::new(Vec::new()), "pw");
+        for i in 0..u16::MAX {
+            zip.add_dir_entry(&format!("{i}/"), 0o40755, 0).unwrap();
+        }
+        assert!(zip.add_dir_entry("last/", 0o40755, 0).is_err());
+        assert!(zip.finish().is_ok());
+    }
+}
@@ -0,0 +1,157 @@
+//! 压缩时的条目筛选：包含/排除 glob、大小与修改时间、.gitignore/.ignore
+
+use std::fs::Metadata;
+use std::path::Path;
+use std::time::SystemTime;
+
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+
+use super::CompressOptions;
+use crate::error::{FastZipError, Result};
+
+/// 一组 glob：含 `/` 的模式匹配完整条目路径，否则匹配任意层级的文件名
+#[derive(Debug)]
+pub(crate) struct GlobList {
+    names: GlobSet,
+    paths: GlobSet,
+    empty: bool,
+}
+
+impl GlobList {
+    pub fn new(patterns: &[String]) -> Result<Self> {
+        let mut names = GlobSetBuilder::new();
+        let mut paths = GlobSetBuilder::new();
+        for pattern in patterns {
+            let trimmed = pattern.trim_end_matches('/');
+            let anchored = trimmed.trim_start_matches("./").trim_start_matches('/');
+            let glob = GlobBuilder::new(anchored)
+                .literal_separator(true)
+                .build()
+                .map_err(|e| FastZipError::Other(format!("无效的 glob 模式 {}: {}", pattern, e)))?;
+            if anchored.contains('/') || anchored.len() != trimmed.len() {
+                paths.add(glob);
+            } else {
+                names.add(glob);
+            }
+        }
+        let build = |b: GlobSetBuilder| b.build().map_err(|e| FastZipError::Other(e.to_string()));
+        Ok(Self {
+            names: build(names)?,
+            paths: build(paths)?,
+            empty: patterns.is_empty(),
+        })
+    }
+
+    pub fn matches(&self, rel: &str) -> bool {
+        let name = rel.rsplit('/').next().unwrap_or(rel);
+        self.names.is_match(name) || self.paths.is_match(rel)
+    }
+}
+
+/// 由 [`CompressOptions`] 构建的条目筛选器
+#[derive(Debug)]
+pub(crate) struct EntryFilter {
+    include: GlobList,
+    exclude: GlobList,
+    min_size: Option<u64>,
+    max_size: Option<u64>,
+    newer_than: Option<SystemTime>,
+    older_than: Option<SystemTime>,
+}
+
+impl EntryFilter {
+    pub fn new(options: &CompressOptions) -> Result<Self> {
+        Ok(Self {
+            include: GlobList::new(&options.include)?,
+            exclude: GlobList::new(&options.exclude)?,
+            min_size: options.min_size,
+            max_size: options.max_size,
+            newer_than: options.newer_than,
+            older_than: options.older_than,
+        })
+    }
+
+    /// 目录匹配排除规则时整体跳过，不再进入
+    pub fn prune_dir(&self, rel: &str) -> bool {
+        self.exclude.matches(rel)
+    }
+
+    /// 条目是否写入归档；`rel` 为以 `/` 分隔的条目路径
+    ///
+    /// 设置了包含规则时，目录条目只有自身匹配才写入（其下匹配的文件照常写入）。
+    /// 大小与时间条件只作用于文件。
+    pub fn accept(&self, rel: &str, is_dir: bool, meta: Option<&Metadata>) -> bool {
+        if self.exclude.matches(rel) {
+            return false;
+        }
+        if !self.include.empty && !self.include.matches(rel) {
+            return false;
+        }
+        if is_dir {
+            return true;
+        }
+        let Some(meta) = meta else { return true };
+        let size = meta.len();
+        if self.min_size.is_some_and(|min| size < min) || self.max_size.is_some_and(|max| size > max) {
+            return false;
+        }
+        if self.newer_than.is_some() || self.older_than.is_some() {
+            let Ok(mtime) = meta.modified() else { return false };
+            if self.newer_than.is_some_and(|t| mtime < t) || self.older_than.is_some_and(|t| mtime > t) {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// 按文件名筛选单个文件（单文件批量压缩）
+    pub fn accept_file(&self, path: &Path) -> bool {
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        self.accept(&name, false, std::fs::metadata(path).ok().as_ref())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filter(include: &[&str], exclude: &[&str]) -> EntryFilter {
+        let options = CompressOptions {
+            include: include.iter().map(|s| s.to_string()).collect(),
+            exclude: exclude.iter().map(|s| s.to_string()).collect(),
+            ..Default::default()
+        };
+        EntryFilter::new(&options).unwrap()
+    }
+
+    #[test]
+    fn test_name_and_path_globs() {
+        let f = filter(&[], &["target", "*.log", "docs/*.tmp", "/build"]);
+        assert!(f.prune_dir("target"));
+        assert!(f.prune_dir("crates/core/target"));
+        assert!(!f.accept("a/b/run.log", false, None));
+        assert!(!f.accept("docs/x.tmp", false, None));
+        // `*` 不跨越目录
+        assert!(f.accept("docs/sub/x.tmp", false, None));
+        // 以 `/` 开头的模式只匹配根目录下的条目
+        assert!(f.prune_dir("build"));
+        assert!(!f.prune_dir("src/build"));
+        assert!(f.accept("src/main.rs", false, None));
+    }
+
+    #[test]
+    fn test_include_only_matching_files() {
+        let f = filter(&["*.rs", "src/**/*.toml"], &["generated_*"]);
+        assert!(f.accept("src/lib.rs", false, None));
+        assert!(f.accept("src/a/b/Cargo.toml", false, None));
+        assert!(!f.accept("README.md", false, None));
+        assert!(!f.accept("src/generated_api.rs", false, None));
+        assert!(!f.accept("src", true, None));
+    }
+
+    #[test]
+    fn test_invalid_glob() {
+        let options = CompressOptions { exclude: vec!["a[".into()], ..Default::default() };
+        assert!(EntryFilter::new(&options).is_err());
+    }
+}
@@ -0,0 +1,281 @@
+//! 归档内容搜索：逐条目流式解码并按行匹配正则，不解压到磁盘
+//!
+//! 条目本身是单文件压缩（`.gz`/`.xz`/`.bz2`/`.zst`，扩展名与魔数一致）时边读边解压；
+//! 开头 8 KiB 含 NUL 字节的条目视为二进制并跳过。
+
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read};
+use std::path::Path;
+
+use bzip2::read::MultiBzDecoder;
+use flate2::read::MultiGzDecoder;
+use regex::bytes::{Regex, RegexBuilder};
+use xz2::read::XzDecoder;
+
+use super::convert::{read_entries, Kind};
+use super::filter::GlobList;
+use crate::error::{FastZipError, Result};
+use crate::formats::{detect_format, ArchiveFormat, Codec};
+
+/// 判断二进制时检查的开头字节数
+const BINARY_PROBE: usize = 8 * 1024;
+
+/// 搜索选项
+#[derive(Debug, Clone, Default)]
+pub struct GrepOptions {
+    /// 忽略大小写
+    pub ignore_case: bool,
+    /// 把模式当作普通字符串而不是正则
+    pub fixed_strings: bool,
+    /// 只匹配完整单词
+    pub word: bool,
+    /// 只搜索匹配的条目（glob；含 `/` 的模式匹配条目路径，否则匹配文件名），为空则搜索全部
+    pub include: Vec<String>,
+    /// 跳过匹配的条目（规则同 `include`）
+    pub exclude: Vec<String>,
+    /// 二进制条目也按文本搜索
+    pub binary: bool,
+    /// 加密归档的密码
+    pub password: Option<String>,
+    /// 找到第一处匹配后停止，不再搜索其余条目与归档（只关心有无匹配时使用）
+    pub stop_on_match: bool,
+}
+
+/// 一处匹配
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GrepMatch {
+    /// 条目路径；单文件压缩或普通文件输入为去掉压缩扩展名的文件名
+    pub entry: String,
+    /// 行号（从 1 开始）
+    pub line_number: u64,
+    /// 去掉行尾换行的内容（非 UTF-8 字节按替换字符显示）
+    pub line: String,
+}
+
+/// 单个归档的搜索结果
+#[derive(Debug, Clone, Default)]
+pub struct GrepSummary {
+    /// 搜索过的文件条目数
+    pub entries: usize,
+    /// 有匹配的条目数
+    pub matched_entries: usize,
+    /// 匹配的行数
+    pub matches: u64,
+    /// 按二进制跳过的条目
+    pub binary: Vec<String>,
+    /// 搜索过的每个文件条目及其匹配行数（按搜索顺序，含没有匹配的条目）
+    pub entry_matches: Vec<(String, u64)>,
+}
+
+/// 在 `archives` 中搜索 `pattern`，每处匹配调用一次 `on_match(归档, 匹配)`
+///
+/// 输入可为可逐条目读取的归档（ZIP、7z、tar.*，启用 unrar 时含 RAR）、单文件压缩或普通文件，
+/// 按顺序逐个搜索；模式无效时直接返回错误，单个归档失败不影响其余归档。
+/// 设置 `stop_on_match` 时，第一处匹配之后的归档不再搜索，其结果为空的 [`GrepSummary`]。
+pub fn grep_archives<P: AsRef<Path>>(
+    archives: &[P],
+    pattern: &str,
+    options: &GrepOptions,
+    mut on_match: impl FnMut(&Path, &GrepMatch) -> Result<()>,
+) -> Result<Vec<Result<GrepSummary>>> {
+    let regex = build_regex(pattern, options)?;
+    let include = GlobList::new(&options.include)?;
+    let exclude = GlobList::new(&options.exclude)?;
+    let selected = |name: &str| (options.include.is_empty() || include.matches(name)) && !exclude.matches(name);
+    let mut stopped = false;
+    Ok(archives
+        .iter()
+        .map(|archive| {
+            let archive = archive.as_ref();
+            let mut summary = GrepSummary::default();
+            if stopped {
+                return Ok(summary);
+            }
+            let result = (|| -> Result<()> {
+                let mut search = |name: String, data: &mut dyn Read| -> Result<()> {
+                    if !selected(&name) {
+                        return Ok(());
+                    }
+                    let mut found = |m: &GrepMatch| on_match(archive, m);
+                    search_entry(&regex, name, data, options, &mut summary, &mut found)?;
+                    if options.stop_on_match && summary.matches > 0 {
+                        // 以错误中止遍历，下面按已停止处理
+                        stopped = true;
+                        return Err(FastZipError::Other("已找到匹配".into()));
+                    }
+                    Ok(())
+                };
+                match detect_format(archive) {
+                    Ok(format) if format.is_single_compressed() => {
+                        let name = file_name(archive, true);
+                        let file = File::open(archive)?;
+                        search(name, &mut decoder(format.codec().expect("单文件压缩格式有编码"), file)?)
+                    }
+                    Ok(format) => {
+                        read_entries(archive, format, options.password.as_deref(), &mut |entry, data| {
+                            match entry.kind {
+                                Kind::File => search(entry.name, data),
+                                _ => Ok(()),
+                            }
+                        })
+                    }
+                    Err(FastZipError::FormatDetectionFailed) => {
+                        search(file_name(archive, false), &mut File::open(archive)?)
+                    }
+                    Err(e) => Err(e),
+                }
+            })();
+            match result {
+                Err(_) if stopped => Ok(summary),
+                result => result.map(|()| summary),
+            }
+        })
+        .collect())
+}
+
+fn build_regex(pattern: &str, options: &GrepOptions) -> Result<Regex> {
+    let mut pattern = if options.fixed_strings { regex::escape(pattern) } else { pattern.to_string() };
+    if options.word {
+        pattern = format!(r"\b(?:{})\b", pattern);
+    }
+    RegexBuilder::new(&pattern)
+        .case_insensitive(options.ignore_case)
+        .build()
+        .map_err(|e| FastZipError::Other(format!("无效的搜索模式: {}", e)))
+}
+
+/// 按行搜索一个条目；条目为单文件压缩时先解压
+fn search_entry(
+    regex: &Regex,
+    name: String,
+    data: &mut dyn Read,
+    options: &GrepOptions,
+    summary: &mut GrepSummary,
+    on_match: &mut dyn FnMut(&GrepMatch) -> Result<()>,
+) -> Result<()> {
+    let mut raw = BufReader::with_capacity(64 * 1024, data);
+    let nested = ArchiveFormat::from_extension(Path::new(&name))
+        .filter(|format| format.is_single_compressed())
+        .filter(|format| ArchiveFormat::from_magic(raw.fill_buf().unwrap_or_default()) == Some(*format));
+    let mut reader: Box<dyn BufRead + '_> = match nested.and_then(|format| format.codec()) {
+        Some(codec) => Box::new(BufReader::with_capacity(64 * 1024, decoder(codec, raw)?)),
+        None => Box::new(raw),
+    };
+
+    summary.entries += 1;
+    if !options.binary && is_binary(&mut reader)? {
+        summary.entry_matches.push((name.clone(), 0));
+        summary.binary.push(name);
+        return Ok(());
+    }
+    let mut line = Vec::new();
+    let mut line_number = 0;
+    let mut matches = 0;
+    loop {
+        line.clear();
+        if reader.read_until(b'\n', &mut line)? == 0 {
+            break;
+        }
+        line_number += 1;
+        while matches!(line.last(), Some(b'\n' | b'\r')) {
+            line.pop();
+        }
+        if regex.is_match(&line) {
+            matches += 1;
+            let text = String::from_utf8_lossy(&line).into_owned();
+            on_match(&GrepMatch { entry: name.clone(), line_number, line: text })?;
+            if options.stop_on_match {
+                break;
+            }
+        }
+    }
+    summary.matches += matches;
+    if matches > 0 {
+        summary.matched_entries += 1;
+    }
+    summary.entry_matches.push((name, matches));
+    Ok(())
+}
+
+/// 开头 `BINARY_PROBE` 字节内有 NUL 即视为二进制（只预读，不消耗数据）
+fn is_binary(reader: &mut dyn BufRead) -> Result<bool> {
+    let buf = reader.fill_buf()?;
+    Ok(buf[..buf.len().min(BINARY_PROBE)].contains(&0))
+}
+
+/// 顺序解码器（支持拼接的多成员/多流）
+fn decoder<'a, R: Read + 'a>(codec: Codec, reader: R) -> Result<Box<dyn Read + 'a>> {
+    Ok(match codec {
+        Codec::Gzip => Box::new(MultiGzDecoder::new(reader)),
+        Codec::Xz => Box::new(XzDecoder::new_multi_decoder(reader)),
+        Codec::Bzip2 => Box::new(MultiBzDecoder::new(reader)),
+        Codec::Zstd => Box::new(zstd::Decoder::new(reader)?),
+    })
+}
+
+/// 文件名，`strip` 时去掉压缩扩展名
+fn file_name(path: &Path, strip: bool) -> String {
+    let name = if strip { path.file_stem() } else { path.file_name() };
+    name.unwrap_or_default().to_string_lossy().into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn search(text: &[u8], name: &str, options: &GrepOptions) -> (Vec<(u64, String)>, GrepSummary) {
+        let regex = build_regex("err(or)?", options).unwrap();
+        let mut summary = GrepSummary::default();
+        let mut found = Vec::new();
+        let mut on_match = |m: &GrepMatch| {
+            found.push((m.line_number, m.line.clone()));
+            Ok(())
+        };
+        search_entry(&regex, name.into(), &mut &text[..], options, &mut summary, &mut on_match).unwrap();
+        (found, summary)
+    }
+
+    #[test]
+    fn test_search_entry() {
+        let text = b"ok\r\nERROR: disk\nerr here\nfine\n";
+        let (found, summary) = search(text, "a.log", &GrepOptions::default());
+        assert_eq!(found, [(3, "err here".to_string())]);
+        assert_eq!((summary.entries, summary.matched_entries, summary.matches), (1, 1, 1));
+        assert_eq!(summary.entry_matches, [("a.log".to_string(), 1)]);
+        assert_eq!(search(b"fine\n", "b.log", &GrepOptions::default()).1.entry_matches, [("b.log".to_string(), 0)]);
+
+        let options = GrepOptions { ignore_case: true, ..Default::default() };
+        assert_eq!(search(text, "a.log", &options).0.len(), 2);
+        // 找到第一处匹配即停止
+        let options = GrepOptions { ignore_case: true, stop_on_match: true, ..Default::default() };
+        let (found, summary) = search(text, "a.log", &options);
+        assert_eq!(found, [(2, "ERROR: disk".to_string())]);
+        assert_eq!(summary.entry_matches, [("a.log".to_string(), 1)]);
+        let options = GrepOptions { word: true, ..Default::default() };
+        assert_eq!(search(b"terrible\nerr\n", "a.log", &options).0, [(2, "err".to_string())]);
+    }
+
+    #[test]
+    fn test_binary_and_nested() {
+        let (found, summary) = search(b"error\0\x01", "a.bin", &GrepOptions::default());
+        assert!(found.is_empty());
+        assert_eq!(summary.binary, ["a.bin"]);
+        let options = GrepOptions { binary: true, ..Default::default() };
+        assert_eq!(search(b"error\0\x01", "a.bin", &options).0.len(), 1);
+
+        let mut gz = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        std::io::Write::write_all(&mut gz, b"line\nerror in gz\n").unwrap();
+        let gz = gz.finish().unwrap();
+        assert_eq!(search(&gz, "app.log.gz", &GrepOptions::default()).0, [(2, "error in gz".to_string())]);
+        // 扩展名与魔数不一致时按原样搜索
+        assert_eq!(search(b"error\n", "fake.gz", &GrepOptions::default()).0.len(), 1);
+    }
+
+    #[test]
+    fn test_fixed_strings() {
+        let options = GrepOptions { fixed_strings: true, ..Default::default() };
+        let regex = build_regex("a.b(", &options).unwrap();
+        assert!(regex.is_match(b"x a.b( y") && !regex.is_match(b"axb("));
+        assert!(build_regex("a(", &GrepOptions::default()).is_err());
+    }
+}
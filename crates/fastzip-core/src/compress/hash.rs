@@ -0,0 +1,318 @@
+//! 条目哈希与校验清单：逐条目流式计算 SHA-256 / BLAKE3，读写与 `sha256sum` 兼容的清单
+
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::Path;
+
+use sha2::{Digest, Sha256};
+
+use super::convert::{clean_name, read_entries, Kind};
+use super::{walk_sources, CompressOptions, EntryKind};
+use crate::error::{FastZipError, Result};
+use crate::formats::detect_format;
+
+/// 哈希算法
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HashAlgorithm {
+    #[default]
+    Sha256,
+    Blake3,
+}
+
+impl HashAlgorithm {
+    /// 由名称解析（sha256、blake3，不区分大小写）
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "sha256" | "sha-256" => Some(Self::Sha256),
+            "blake3" | "b3" => Some(Self::Blake3),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Sha256 => "sha256",
+            Self::Blake3 => "blake3",
+        }
+    }
+}
+
+/// 一个文件条目的哈希
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EntryHash {
+    /// 以 `/` 分隔的条目路径
+    pub path: String,
+    /// 小写十六进制摘要
+    pub digest: String,
+}
+
+/// 校验结果
+#[derive(Debug, Clone, Default)]
+pub struct VerifyReport {
+    /// 哈希一致的条目数
+    pub ok: usize,
+    /// 哈希不一致的条目
+    pub mismatched: Vec<String>,
+    /// 清单中有、目标中没有的条目
+    pub missing: Vec<String>,
+    /// 目标中有、清单中没有的文件
+    pub extra: Vec<String>,
+}
+
+impl VerifyReport {
+    /// 没有不一致或缺失的条目（多出的文件不影响结果）
+    pub fn is_ok(&self) -> bool {
+        self.mismatched.is_empty() && self.missing.is_empty()
+    }
+}
+
+/// 计算 `path` 中每个文件条目的哈希，按路径排序
+///
+/// `path` 可为任意可逐条目读取的归档（ZIP、7z、tar.*，启用 unrar 时含 RAR）或目录；
+/// 目录以其内容为根，与压缩该目录得到的条目名一致。目录与符号链接不计入。
+pub fn hash_entries(path: &Path, algorithm: HashAlgorithm, password: Option<&str>) -> Result<Vec<EntryHash>> {
+    let mut hashes = BTreeMap::new();
+    if path.is_dir() {
+        walk_sources(&[path], &CompressOptions::default(), |file, name, kind| {
+            if kind == EntryKind::File {
+                hashes.insert(name.to_string(), digest(algorithm, &mut File::open(file)?)?);
+            }
+            Ok(())
+        })?;
+    } else {
+        let format = detect_format(path)?;
+        read_entries(path, format, password, &mut |entry, data| {
+            if matches!(entry.kind, Kind::File) {
+                hashes.insert(entry.name, digest(algorithm, data)?);
+            }
+            Ok(())
+        })?;
+    }
+    Ok(hashes.into_iter().map(|(path, digest)| EntryHash { path, digest }).collect())
+}
+
+/// 按 `sha256sum` 的格式写出清单：`<摘要>  <路径>`，
+/// 路径含 `\`、换行或回车时转义并在行首加 `\`
+pub fn write_manifest(out: &mut dyn Write, hashes: &[EntryHash]) -> Result<()> {
+    for hash in hashes {
+        if hash.path.contains(['\\', '\n', '\r']) {
+            let escaped = hash.path.replace('\\', "\\\\").replace('\n', "\\n").replace('\r', "\\r");
+            writeln!(out, "\\{}  {}", hash.digest, escaped)?;
+        } else {
+            writeln!(out, "{}  {}", hash.digest, hash.path)?;
+        }
+    }
+    Ok(())
+}
+
+/// 解析 `sha256sum` 格式的清单（文本 `  ` 与二进制 ` *` 两种分隔均可），跳过空行
+pub fn parse_manifest(text: &str) -> Result<Vec<EntryHash>> {
+    let mut hashes = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let invalid = || FastZipError::Other(format!("清单第 {} 行格式无效: {}", i + 1, line));
+        let (escaped, line) = match line.strip_prefix('\\') {
+            Some(rest) => (true, rest),
+            None => (false, line),
+        };
+        let (digest, rest) = line.split_once(' ').ok_or_else(invalid)?;
+        let path = rest.strip_prefix(' ').or_else(|| rest.strip_prefix('*')).ok_or_else(invalid)?;
+        if digest.is_empty() || !digest.bytes().all(|b| b.is_ascii_hexdigit()) || path.is_empty() {
+            return Err(invalid());
+        }
+        let path = if escaped { unescape(path).ok_or_else(invalid)? } else { path.to_string() };
+        let path = path.strip_prefix("./").map(String::from).unwrap_or(path);
+        hashes.push(EntryHash { path, digest: digest.to_ascii_lowercase() });
+    }
+    Ok(hashes)
+}
+
+/// 用清单 `manifest` 校验归档或目录 `target`
+///
+/// 清单中的每个路径都须在 `target` 中存在且哈希一致；`target` 中多出的文件单独列出。
+pub fn verify_manifest(
+    target: &Path,
+    manifest: &Path,
+    algorithm: HashAlgorithm,
+    password: Option<&str>,
+) -> Result<VerifyReport> {
+    let text = fs::read_to_string(manifest).map_err(|_| FastZipError::FileNotFound(manifest.to_path_buf()))?;
+    let expected = parse_manifest(&text)?;
+    let mut actual: BTreeMap<String, String> =
+        hash_entries(target, algorithm, password)?.into_iter().map(|h| (h.path, h.digest)).collect();
+    let mut report = VerifyReport::default();
+    for entry in expected {
+        match actual.remove(&entry.path) {
+            Some(digest) if digest == entry.digest => report.ok += 1,
+            Some(_) => report.mismatched.push(entry.path),
+            None => report.missing.push(entry.path),
+        }
+    }
+    report.extra = actual.into_keys().collect();
+    Ok(report)
+}
+
+/// 读完 `data`，返回小写十六进制摘要
+fn digest(algorithm: HashAlgorithm, data: &mut dyn Read) -> Result<String> {
+    let mut tee = HashTee::new(io::sink(), algorithm);
+    io::copy(data, &mut tee)?;
+    Ok(tee.finish())
+}
+
+/// 增量摘要计算
+pub(crate) enum Hasher {
+    Sha256(Sha256),
+    Blake3(Box<blake3::Hasher>),
+}
+
+impl Hasher {
+    pub(crate) fn new(algorithm: HashAlgorithm) -> Self {
+        match algorithm {
+            HashAlgorithm::Sha256 => Self::Sha256(Sha256::new()),
+            HashAlgorithm::Blake3 => Self::Blake3(Box::default()),
+        }
+    }
+
+    pub(crate) fn update(&mut self, data: &[u8]) {
+        match self {
+            Self::Sha256(hasher) => hasher.update(data),
+            Self::Blake3(hasher) => {
+                hasher.update(data);
+            }
+        }
+    }
+
+    /// 小写十六进制摘要
+    pub(crate) fn finish(self) -> String {
+        match self {
+            Self::Sha256(hasher) => format!("{:x}", hasher.finalize()),
+            Self::Blake3(hasher) => hasher.finalize().to_hex().to_string(),
+        }
+    }
+}
+
+/// 读写时顺带计算经过的数据的摘要
+pub(crate) struct HashTee<T> {
+    inner: T,
+    hasher: Hasher,
+}
+
+impl<T> HashTee<T> {
+    pub(crate) fn new(inner: T, algorithm: HashAlgorithm) -> Self {
+        Self { inner, hasher: Hasher::new(algorithm) }
+    }
+
+    pub(crate) fn finish(self) -> String {
+        self.hasher.finish()
+    }
+}
+
+impl<T: Read> Read for HashTee<T> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.hasher.update(&buf[..n]);
+        Ok(n)
+    }
+}
+
+impl<T: Write> Write for HashTee<T> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.hasher.update(&buf[..n]);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// 解压时随写出记录的文件条目哈希，结果与 [`hash_entries`] 读取归档得到的一致
+pub(crate) struct EntryHashes {
+    algorithm: HashAlgorithm,
+    hashes: BTreeMap<String, String>,
+}
+
+impl EntryHashes {
+    pub(crate) fn new(algorithm: HashAlgorithm) -> Self {
+        Self { algorithm, hashes: BTreeMap::new() }
+    }
+
+    pub(crate) fn algorithm(&self) -> HashAlgorithm {
+        self.algorithm
+    }
+
+    /// 记录条目 `name` 的摘要，条目名按 [`hash_entries`] 的规则统一
+    pub(crate) fn insert(&mut self, name: &str, digest: String) {
+        let name = clean_name(name);
+        if !name.is_empty() {
+            self.hashes.insert(name, digest);
+        }
+    }
+
+    /// 把 `data` 复制到 `out`，同时记录条目 `name` 的摘要
+    pub(crate) fn copy(&mut self, name: &str, data: &mut dyn Read, out: &mut dyn Write) -> io::Result<u64> {
+        let mut tee = HashTee::new(out, self.algorithm);
+        let n = io::copy(data, &mut tee)?;
+        self.insert(name, tee.finish());
+        Ok(n)
+    }
+
+    pub(crate) fn into_vec(self) -> Vec<EntryHash> {
+        self.hashes.into_iter().map(|(path, digest)| EntryHash { path, digest }).collect()
+    }
+}
+
+/// 还原 `\\`、`\n`、`\r` 转义
+fn unescape(path: &str) -> Option<String> {
+    let mut out = String::with_capacity(path.len());
+    let mut chars = path.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next()? {
+            '\\' => out.push('\\'),
+            'n' => out.push('\n'),
+            'r' => out.push('\r'),
+            _ => return None,
+        }
+    }
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(path: &str) -> EntryHash {
+        EntryHash { path: path.into(), digest: "ab".repeat(32) }
+    }
+
+    #[test]
+    fn test_manifest_round_trip() {
+        let hashes = vec![entry("a.txt"), entry("dir/b c.txt"), entry("x\\y"), entry("line\nbreak")];
+        let mut out = Vec::new();
+        write_manifest(&mut out, &hashes).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        assert!(text.starts_with(&format!("{}  a.txt\n", "ab".repeat(32))));
+        assert!(text.contains(&format!("\\{}  x\\\\y\n", "ab".repeat(32))));
+        assert_eq!(parse_manifest(&text).unwrap(), hashes);
+    }
+
+    #[test]
+    fn test_parse_manifest_variants() {
+        let digest = "AB".repeat(32);
+        let text = format!("{digest} *./bin.dat\n\n{digest}  plain.txt\n");
+        let hashes = parse_manifest(&text).unwrap();
+        assert_eq!(hashes[0].path, "bin.dat");
+        assert_eq!(hashes[1].digest, "ab".repeat(32));
+        assert!(parse_manifest("not-hex  a.txt").is_err());
+        assert!(parse_manifest(&format!("{digest}a.txt")).is_err());
+        assert!(parse_manifest(&format!("\\{digest}  bad\\q")).is_err());
+    }
+}
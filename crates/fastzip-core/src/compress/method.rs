@@ -0,0 +1,123 @@
+//! 压缩方法与预设
+
+use crate::formats::Codec;
+
+/// ZIP 条目压缩方法
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ZipMethod {
+    /// 仅存储不压缩
+    #[default]
+    Stored,
+    /// Deflate，级别 0–9
+    Deflate,
+    /// Deflate64（当前仅支持解压，写入时返回错误）
+    Deflate64,
+    /// Bzip2，级别 1–9
+    Bzip2,
+    /// Zstd，级别 1–22
+    Zstd,
+    /// xz（LZMA2，ZIP 方法 95），级别 0–9
+    Xz,
+    /// LZMA（ZIP 方法 14），级别 0–9；不能与 AES 加密同时使用
+    Lzma,
+}
+
+impl ZipMethod {
+    /// 按名称解析（`stored`/`store`、`deflate`、`deflate64`、`bzip2`、`zstd`、`xz`、`lzma`）
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name.to_lowercase().as_str() {
+            "stored" | "store" => Self::Stored,
+            "deflate" | "deflated" => Self::Deflate,
+            "deflate64" => Self::Deflate64,
+            "bzip2" | "bz2" => Self::Bzip2,
+            "zstd" | "zst" => Self::Zstd,
+            "xz" => Self::Xz,
+            "lzma" => Self::Lzma,
+            _ => return None,
+        })
+    }
+
+    /// 级别与之对应的流编码（Stored 无级别）
+    pub(crate) fn level_codec(self) -> Option<Codec> {
+        match self {
+            Self::Stored => None,
+            Self::Deflate | Self::Deflate64 => Some(Codec::Gzip),
+            Self::Bzip2 => Some(Codec::Bzip2),
+            Self::Zstd => Some(Codec::Zstd),
+            Self::Xz | Self::Lzma => Some(Codec::Xz),
+        }
+    }
+
+    pub(crate) fn to_zip(self) -> zip::CompressionMethod {
+        match self {
+            Self::Stored => zip::CompressionMethod::Stored,
+            Self::Deflate => zip::CompressionMethod::Deflated,
+            Self::Deflate64 => zip::CompressionMethod::Deflate64,
+            Self::Bzip2 => zip::CompressionMethod::Bzip2,
+            Self::Zstd => zip::CompressionMethod::Zstd,
+            Self::Xz => zip::CompressionMethod::Xz,
+            Self::Lzma => zip::CompressionMethod::Lzma,
+        }
+    }
+}
+
+/// 压缩预设：未显式指定级别时决定各格式的压缩级别
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CompressionPreset {
+    /// 不压缩（ZIP 存储；其他格式使用最低级别）
+    Store,
+    /// 最快
+    Fastest,
+    /// 常规（各编码默认级别）
+    #[default]
+    Normal,
+    /// 高压缩
+    Max,
+    /// 极限压缩（最慢）
+    Ultra,
+}
+
+impl CompressionPreset {
+    /// 按名称解析（`store`、`fastest`、`normal`、`max`、`ultra`）
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name.to_lowercase().as_str() {
+            "store" => Self::Store,
+            "fastest" | "fast" => Self::Fastest,
+            "normal" => Self::Normal,
+            "max" | "maximum" => Self::Max,
+            "ultra" => Self::Ultra,
+            _ => return None,
+        })
+    }
+
+    /// 预设对应的 ZIP 压缩方法
+    pub fn zip_method(self) -> ZipMethod {
+        match self {
+            Self::Store => ZipMethod::Stored,
+            _ => ZipMethod::Deflate,
+        }
+    }
+
+    /// 预设对应的流编码级别
+    pub fn codec_level(self, codec: Codec) -> u32 {
+        let (min, max) = codec.level_range();
+        match self {
+            Self::Store => min,
+            Self::Fastest => min.max(1),
+            Self::Normal => codec.default_level(),
+            Self::Max if codec == Codec::Zstd => 19,
+            Self::Max | Self::Ultra => max,
+        }
+    }
+
+    /// 预设对应的 7z（LZMA2）级别 0–9
+    pub fn sevenz_level(self) -> u32 {
+        match self {
+            Self::Store => 0,
+            Self::Fastest => 1,
+            Self::Normal => 5,
+            Self::Max => 7,
+            Self::Ultra => 9,
+        }
+    }
+}
@@ -1,25 +1,142 @@
-//! 压缩：创建 ZIP / 7z 归档
+//! 压缩：创建 ZIP / 7z / tar.* 归档，以及单文件 .gz/.xz/.bz2/.zst
 
-use std::fs::File;
+use std::fs::{self, File};
 use std::io::{BufReader, BufWriter, Write};
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::SystemTime;
 
-use walkdir::WalkDir;
-use zip::write::SimpleFileOptions;
-use zip::CompressionMethod;
-use zip::ZipWriter;
+use rayon::prelude::*;
+use ignore::WalkBuilder;
+use sevenz_rust::lzma::LZMA2Options;
+use sevenz_rust::{AesEncoderOptions, SeqReader, SevenZArchiveEntry, SevenZWriter, SourceReader};
+use zip::write::{ExtendedFileOptions, FileOptions, FullFileOptions};
+use tempfile::SpooledTempFile;
+use zip::{ZipArchive, ZipWriter};
 
 use crate::error::{FastZipError, Result};
+use crate::formats::{mark_lzma, zip_datetime, Codec, Encoder, LzmaEncoder};
+use crate::hook::TaskHook;
+
+mod convert;
+mod diff;
+mod edit;
+mod encryption;
+mod filter;
+mod grep;
+mod hash;
+mod method;
+mod reproducible;
+mod tar_rewrite;
+mod update;
+mod volume;
+mod zip_rewrite;
+
+use encryption::{force_ae2, ZipCryptoWriter};
+use filter::EntryFilter;
+use reproducible::Reproducible;
+use volume::{remove_output, write_zip, Output};
+pub use convert::convert_archive;
+pub use diff::{diff_archives, ArchiveDiff, ChangeKind, DiffEntry, DiffEntryInfo, DiffEntryKind};
+pub use edit::{delete_entries, rename_entry, set_zip_comment};
+pub use encryption::ZipEncryption;
+pub use grep::{grep_archives, GrepMatch, GrepOptions, GrepSummary};
+pub use hash::{hash_entries, parse_manifest, verify_manifest, write_manifest, EntryHash, HashAlgorithm, VerifyReport};
+pub use method::{CompressionPreset, ZipMethod};
+pub use update::{update_zip, UpdateMode, UpdateSummary};
+pub use volume::MIN_VOLUME_SIZE;
+pub(crate) use hash::{EntryHashes, HashTee, Hasher};
+pub(crate) use zip_rewrite::{join_split_central, read_split_end};
 
 /// 压缩选项
 #[derive(Debug, Clone)]
 pub struct CompressOptions {
     /// 是否递归包含子目录
     pub recursive: bool,
-    /// 密码（可选，仅 ZIP 支持）
+    /// 密码（可选，ZIP 与 7z 支持）：加密所有文件条目
     pub password: Option<String>,
-    /// 快速模式：ZIP 仅存储不压缩（最快，体积较大）；关闭则使用 Deflate 压缩
-    pub fast: bool,
+    /// ZIP 加密方式（默认 AES-256，ZipCrypto 需显式选择）
+    pub encryption: ZipEncryption,
+    /// ZIP 条目压缩方法（默认仅存储，速度接近纯拷贝）
+    pub method: ZipMethod,
+    /// 压缩预设：未指定 `level` 时决定 ZIP、tar.*、单文件与 7z 的压缩级别
+    pub preset: CompressionPreset,
+    /// 显式压缩级别，按目标方法/编码的范围截断（ZIP Deflate 0–9、zstd 1–22、7z 0–9 等）
+    pub level: Option<u32>,
+    /// 单文件压缩后保留原文件（false 则压缩成功后删除）
+    pub keep_original: bool,
+    /// 单文件压缩时覆盖已存在的输出文件（默认报错）
+    pub overwrite: bool,
+    /// 7z 同时加密文件名等头信息（需设置 `password`）
+    pub encrypt_header: bool,
+    /// 7z 固实压缩：多个文件共用一个压缩流（默认开启）
+    pub solid: bool,
+    /// 7z 固实块大小上限（字节），None 为 4 GiB
+    pub solid_block_size: Option<u64>,
+    /// 7z LZMA2 字典大小（字节），None 取级别默认值
+    pub dict_size: Option<u32>,
+    /// 只包含匹配的文件（glob；含 `/` 的模式匹配条目路径，否则匹配文件名），为空则包含全部
+    pub include: Vec<String>,
+    /// 排除匹配的文件与目录（匹配的目录整体跳过）
+    pub exclude: Vec<String>,
+    /// 只包含不小于该字节数的文件
+    pub min_size: Option<u64>,
+    /// 只包含不大于该字节数的文件
+    pub max_size: Option<u64>,
+    /// 只包含在该时间之后（含）修改的文件
+    pub newer_than: Option<SystemTime>,
+    /// 只包含在该时间之前（含）修改的文件
+    pub older_than: Option<SystemTime>,
+    /// 遵循源目录中的 `.gitignore` / `.ignore`，并跳过 `.git` 目录
+    pub respect_ignore: bool,
+    /// ZIP 与批量单文件压缩的并行线程数（0 = 自动，1 = 单线程）
+    pub threads: usize,
+    /// 可复现输出（ZIP 与 tar.*）：条目按名称排序，固定时间戳，权限规范为 0644/0755，
+    /// 属主为 0，不写入随时间变化的扩展字段；不能与密码同时使用
+    pub reproducible: bool,
+    /// 可复现模式下所有条目的修改时间；None 时取 `SOURCE_DATE_EPOCH`，均未设置为 1980-01-01
+    pub fixed_mtime: Option<SystemTime>,
+    /// 符号链接的处理方式（默认存储链接本身）
+    pub symlinks: SymlinkPolicy,
+    /// ZIP 条目额外写入扩展时间戳（默认开启；0x5455，UTC 秒级精度，DOS 时间为本地时间且仅 2 秒精度）
+    pub extended_timestamp: bool,
+    /// 分卷大小（字节，不小于 [`MIN_VOLUME_SIZE`]）：ZIP 写成 `.z01`…`.zip`，
+    /// 7z 与 tar.* 按字节切分为 `<归档>.001`…；None 不分卷
+    pub volume_size: Option<u64>,
+    /// ZIP 归档注释（不超过 65535 字节）；7z、tar.* 与单文件压缩不支持
+    pub comment: Option<String>,
+}
+
+/// 压缩时符号链接的处理方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SymlinkPolicy {
+    /// 存储为链接（ZIP 为 Unix 链接模式、tar 为链接条目、7z 为 p7zip 链接属性），默认
+    #[default]
+    Store,
+    /// 跟随链接，打包其指向的内容；指向祖先目录的循环链接跳过
+    Follow,
+    /// 跳过所有符号链接
+    Skip,
+}
+
+impl SymlinkPolicy {
+    /// 由名称解析（store、follow、skip）
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name.to_lowercase().as_str() {
+            "store" => Self::Store,
+            "follow" => Self::Follow,
+            "skip" => Self::Skip,
+            _ => return None,
+        })
+    }
+}
+
+/// 遍历得到的条目类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum EntryKind {
+    File,
+    Dir,
+    Symlink,
 }
 
 impl Default for CompressOptions {
@@ -27,86 +144,805 @@ impl Default for CompressOptions {
         Self {
             recursive: true,
             password: None,
-            fast: true,
+            encryption: ZipEncryption::Aes256,
+            method: ZipMethod::Stored,
+            preset: CompressionPreset::Normal,
+            level: None,
+            keep_original: true,
+            overwrite: false,
+            encrypt_header: false,
+            solid: true,
+            solid_block_size: None,
+            dict_size: None,
+            include: Vec::new(),
+            exclude: Vec::new(),
+            min_size: None,
+            max_size: None,
+            newer_than: None,
+            older_than: None,
+            respect_ignore: false,
+            threads: 0,
+            reproducible: false,
+            fixed_mtime: None,
+            symlinks: SymlinkPolicy::Store,
+            extended_timestamp: true,
+            volume_size: None,
+            comment: None,
         }
     }
 }
 
+impl CompressOptions {
+    /// 应用预设：设置 ZIP 压缩方法并清除显式级别
+    pub fn with_preset(mut self, preset: CompressionPreset) -> Self {
+        self.preset = preset;
+        self.method = preset.zip_method();
+        self.level = None;
+        self
+    }
+
+    /// 流编码（tar.*、单文件）使用的级别
+    pub fn codec_level(&self, codec: Codec) -> u32 {
+        let (min, max) = codec.level_range();
+        self.level
+            .map_or_else(|| self.preset.codec_level(codec), |l| l.clamp(min, max))
+    }
+
+    /// 7z（LZMA2）使用的级别
+    pub fn sevenz_level(&self) -> u32 {
+        self.level.map_or_else(|| self.preset.sevenz_level(), |l| l.min(9))
+    }
+
+    /// ZIP 归档注释的字节，超过结束记录的长度上限时报错
+    fn zip_comment(&self) -> Result<Option<&[u8]>> {
+        match &self.comment {
+            Some(comment) => check_zip_comment(comment).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// ZIP 条目使用的级别（Stored 无级别）
+    fn zip_level(&self) -> Option<u32> {
+        self.method.level_codec().map(|codec| self.codec_level(codec))
+    }
+}
+
 /// 将若干路径打包为 ZIP
+///
+/// 文件条目在线程池中并行压缩（`threads`，0 为自动），再按遍历顺序写入，
+/// 输出与线程数无关。设置 `password` 时加密所有文件条目（目录条目不含数据，不加密），
+/// 默认 AES-256（AE-2），`encryption` 为 [`ZipEncryption::ZipCrypto`] 时使用传统加密（顺序写入）。
+/// 设置 `volume_size` 且超过一卷时写成分卷 `.z01`、`.z02`…，最后一卷为 `dest`。
 pub fn compress_to_zip<P: AsRef<Path>>(
     sources: &[P],
     dest: &Path,
     options: &CompressOptions,
 ) -> Result<()> {
-    let file = File::create(dest)?;
-    let mut zip = ZipWriter::new(BufWriter::with_capacity(1024 * 1024, file));
-    let opts = zip_file_options(options.fast);
+    compress_to_zip_hooked(sources, dest, options, &TaskHook::default())
+}
 
+/// 同 [`compress_to_zip`]，每个条目、每块源数据调用 `hook`，取消时以错误结束并删除输出
+pub(crate) fn compress_to_zip_hooked<P: AsRef<Path>>(
+    sources: &[P],
+    dest: &Path,
+    options: &CompressOptions,
+    hook: &TaskHook,
+) -> Result<()> {
+    write_zip(dest, options.volume_size, |dest| write_zip_archive(sources, dest, options, hook))
+}
+
+fn write_zip_archive<P: AsRef<Path>>(
+    sources: &[P],
+    dest: &Path,
+    options: &CompressOptions,
+    hook: &TaskHook,
+) -> Result<()> {
+    reject_aes_lzma(options)?;
+    let reproducible = Reproducible::from_options(options)?;
+    let comment = options.zip_comment()?;
+    let out = BufWriter::with_capacity(1024 * 1024, File::create(dest)?);
+    let result = match (&options.password, options.encryption) {
+        (Some(password), ZipEncryption::ZipCrypto) => {
+            let mut zip = ZipCryptoWriter::new(out, password);
+            zip.set_comment(comment.unwrap_or_default());
+            zip.set_extended_timestamp(options.extended_timestamp);
+            walk_sources(sources, options, |path, name, kind| {
+                hook.entry(name)?;
+                match kind {
+                    EntryKind::Dir => zip.add_directory(&format!("{name}/"), &fs::metadata(path)?),
+                    EntryKind::Symlink => zip.add_symlink(name, &link_target(path)?, &fs::symlink_metadata(path)?),
+                    EntryKind::File => zip.add_file(path, name, options.method, options.zip_level(), hook),
+                }
+            })
+            .and_then(|_| Ok(zip.finish()?.flush()?))
+        }
+        _ => {
+            let dir_opts = zip_file_options(options);
+            let file_opts = match &options.password {
+                Some(password) => dir_opts.clone().with_aes_encryption(zip::AesMode::Aes256, password),
+                None => dir_opts.clone(),
+            };
+            let mut zip = ZipWriter::new(out);
+            zip.set_raw_comment(comment.unwrap_or_default().into());
+            write_zip_parallel(&mut zip, sources, options, reproducible, &file_opts, &dir_opts, hook)
+                .and_then(|_| Ok(zip.finish()?.flush()?))
+                .and_then(|_| match options.password {
+                    Some(_) => force_ae2(dest),
+                    None => Ok(()),
+                })
+                // 合并条目时中央目录的通用标志被重新计算，需再次标记
+                .and_then(|_| match options.method {
+                    ZipMethod::Lzma => mark_lzma(&mut File::options().read(true).write(true).open(dest)?, |f| {
+                        f.compression() == zip::CompressionMethod::Lzma
+                    }),
+                    _ => Ok(()),
+                })
+        }
+    };
+    if result.is_err() {
+        let _ = fs::remove_file(dest);
+    }
+    result
+}
+
+/// 单个条目压缩结果超过该大小时改写到临时文件
+const SPILL_THRESHOLD: usize = 8 * 1024 * 1024;
+
+/// 每个文件压缩为只含该条目的临时 ZIP，按批并行，再按遍历顺序整体合并进输出
+///
+/// 每个临时 ZIP 的内容只取决于文件本身，合并顺序固定，因此输出与线程数、调度无关
+/// （AES 加密条目含随机盐，每次输出本就不同）。
+fn write_zip_parallel<P: AsRef<Path>>(
+    zip: &mut ZipWriter<BufWriter<File>>,
+    sources: &[P],
+    options: &CompressOptions,
+    reproducible: Option<Reproducible>,
+    file_opts: &FullFileOptions<'_>,
+    dir_opts: &FullFileOptions<'_>,
+    hook: &TaskHook,
+) -> Result<()> {
+    let mut entries = Vec::new();
+    walk_sources(sources, options, |path, name, kind| {
+        entries.push((path.to_path_buf(), name.to_string(), kind));
+        Ok(())
+    })?;
+
+    for_each_parallel(
+        &entries,
+        options.threads,
+        |(path, name, kind)| {
+            hook.entry(name)?;
+            match kind {
+                EntryKind::Dir | EntryKind::Symlink => Ok(None),
+                EntryKind::File => {
+                    let opts = entry_options(file_opts, &fs::metadata(path)?, options, reproducible)?;
+                    compress_zip_entry(path, name, opts, options, hook).map(Some)
+                }
+            }
+        },
+        |(path, name, kind), part| {
+            match (part, kind) {
+                (Some(part), _) => zip.merge_archive(ZipArchive::new(part)?)?,
+                (None, EntryKind::Symlink) => {
+                    let opts = entry_options(file_opts, &fs::symlink_metadata(path)?, options, reproducible)?;
+                    zip.add_symlink(name, link_target(path)?, opts.unix_permissions(0o777))?
+                }
+                (None, _) => {
+                    let opts = entry_options(dir_opts, &fs::metadata(path)?, options, reproducible)?;
+                    zip.add_directory(format!("{name}/"), opts)?
+                }
+            }
+            Ok(())
+        },
+    )
+}
+
+/// 按批并行执行 `work`，每批完成后按原顺序把结果依次交给 `sink`
+///
+/// `threads` 为 0 时使用全局线程池，否则建立专用线程池；批大小为线程数的 4 倍，
+/// 限制同时驻留的中间结果。
+fn for_each_parallel<T: Sync, U: Send>(
+    items: &[T],
+    threads: usize,
+    work: impl Fn(&T) -> Result<U> + Sync,
+    mut sink: impl FnMut(&T, U) -> Result<()>,
+) -> Result<()> {
+    let pool = match threads {
+        0 => None,
+        n => Some(
+            rayon::ThreadPoolBuilder::new()
+                .num_threads(n)
+                .build()
+                .map_err(|e| FastZipError::Other(format!("创建压缩线程池失败: {}", e)))?,
+        ),
+    };
+    let batch = pool
+        .as_ref()
+        .map_or_else(rayon::current_num_threads, |p| p.current_num_threads())
+        * 4;
+
+    for chunk in items.chunks(batch) {
+        let run = || -> Vec<Result<U>> { chunk.par_iter().map(&work).collect() };
+        let results = match &pool {
+            Some(pool) => pool.install(run),
+            None => run(),
+        };
+        for (item, result) in chunk.iter().zip(results) {
+            sink(item, result?)?;
+        }
+    }
+    Ok(())
+}
+
+/// 条目选项：记录源文件的修改时间与 Unix 权限，可选扩展时间戳；可复现模式下改为固定值
+fn entry_options<'k>(
+    base: &FullFileOptions<'k>,
+    meta: &fs::Metadata,
+    options: &CompressOptions,
+    reproducible: Option<Reproducible>,
+) -> Result<FullFileOptions<'k>> {
+    if let Some(r) = reproducible {
+        let mut opts = base.clone().last_modified_time(r.zip_time()).unix_permissions(Reproducible::mode(meta));
+        if options.extended_timestamp {
+            opts.add_extra_data(EXTENDED_TIMESTAMP_ID, Box::new(extended_timestamp_data(r.epoch as i64)), false)?;
+        }
+        return Ok(opts);
+    }
+    #[cfg(unix)]
+    let mode = {
+        use std::os::unix::fs::PermissionsExt;
+        Some(meta.permissions().mode())
+    };
+    #[cfg(not(unix))]
+    let mode = None;
+    stamp_options(base.clone(), meta.modified().ok().and_then(unix_secs), mode, options)
+}
+
+/// 写入修改时间（`extended_timestamp` 时另加扩展时间戳）与 Unix 权限
+fn stamp_options<'k>(
+    mut opts: FullFileOptions<'k>,
+    mtime: Option<i64>,
+    mode: Option<u32>,
+    options: &CompressOptions,
+) -> Result<FullFileOptions<'k>> {
+    if let Some(secs) = mtime {
+        opts = opts.last_modified_time(zip_datetime(secs));
+        if options.extended_timestamp {
+            opts.add_extra_data(EXTENDED_TIMESTAMP_ID, Box::new(extended_timestamp_data(secs)), false)?;
+        }
+    }
+    if let Some(mode) = mode {
+        opts = opts.unix_permissions(mode);
+    }
+    Ok(opts)
+}
+
+/// 扩展时间戳扩展字段 ID（0x5455）
+const EXTENDED_TIMESTAMP_ID: u16 = 0x5455;
+
+/// 扩展时间戳的数据：标志位 bit 0，仅含修改时间（有符号 32 位 Unix 秒）
+fn extended_timestamp_data(secs: i64) -> [u8; 5] {
+    let secs = secs.clamp(i32::MIN as i64, i32::MAX as i64) as i32;
+    let mut data = [1u8; 5];
+    data[1..].copy_from_slice(&secs.to_le_bytes());
+    data
+}
+
+fn unix_secs(time: SystemTime) -> Option<i64> {
+    match time.duration_since(std::time::UNIX_EPOCH) {
+        Ok(d) => i64::try_from(d.as_secs()).ok(),
+        Err(e) => i64::try_from(e.duration().as_secs()).ok().map(|s| -s),
+    }
+}
+
+fn compress_zip_entry(
+    path: &Path,
+    name: &str,
+    opts: FullFileOptions<'_>,
+    options: &CompressOptions,
+    hook: &TaskHook,
+) -> Result<SpooledTempFile> {
+    let mut reader = BufReader::with_capacity(1024 * 1024, hook.reader(File::open(path)?));
+    let mut part = ZipWriter::new(tempfile::spooled_tempfile(SPILL_THRESHOLD));
+    part.start_file(name, opts)?;
+    if options.method == ZipMethod::Lzma {
+        write_lzma_entry(&mut part, &mut reader, options.zip_level().unwrap_or_default())?;
+        let mut part = part.finish()?;
+        mark_lzma(&mut part, |_| true)?;
+        return Ok(part);
+    }
+    std::io::copy(&mut reader, &mut part)?;
+    Ok(part.finish()?)
+}
+
+/// 把 `data` 以 LZMA 编码写入已按 Stored 开始的条目，并记下原始数据的大小与 CRC
+///
+/// 条目头中的方法在归档写完后由 [`mark_lzma`] 改写。
+fn write_lzma_entry<W: Write + std::io::Seek>(
+    zip: &mut ZipWriter<W>,
+    data: &mut dyn std::io::Read,
+    level: u32,
+) -> Result<()> {
+    let mut encoder = LzmaEncoder::new(&mut *zip, level)?;
+    let mut crc = crc32fast::Hasher::new();
+    let mut len = 0u64;
+    let mut buf = vec![0u8; 256 * 1024];
+    loop {
+        let n = match data.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e.into()),
+        };
+        crc.update(&buf[..n]);
+        len += n as u64;
+        encoder.write_all(&buf[..n])?;
+    }
+    encoder.finish()?;
+    // SAFETY: 只替换 zip 库按写入字节统计的大小与 CRC，压缩后大小仍按实际写入的位置计算
+    unsafe { zip.set_file_metadata(len, crc.finalize())? };
+    Ok(())
+}
+
+/// LZMA 条目按 Stored 写入后再改写方法号；AES 加密把方法号移入扩展字段，两者不能同时使用
+fn reject_aes_lzma(options: &CompressOptions) -> Result<()> {
+    match (options.method, &options.password, options.encryption) {
+        (ZipMethod::Lzma, Some(_), ZipEncryption::Aes256) => {
+            Err(FastZipError::UnsupportedFormat("LZMA 不能与 AES 加密同时使用".into()))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// 遍历待打包的条目，回调参数为（路径，条目名，条目类型）
+///
+/// 文件源以文件名为条目名；目录源的内容以该目录为根（不含目录自身），
+/// 非递归时只产生一个以目录名命名的目录条目。条目按 `options` 中的筛选条件过滤，
+/// `respect_ignore` 时遵循 `.gitignore`/`.ignore` 并跳过 `.git` 目录。
+/// 符号链接按 `options.symlinks` 存储、跟随或跳过；设备、管道等特殊文件跳过。
+fn walk_sources<P: AsRef<Path>>(
+    sources: &[P],
+    options: &CompressOptions,
+    mut visit: impl FnMut(&Path, &str, EntryKind) -> Result<()>,
+) -> Result<()> {
+    let filter = Arc::new(EntryFilter::new(options)?);
+    let follow = options.symlinks == SymlinkPolicy::Follow;
     for source in sources {
         let src = source.as_ref();
+        let meta = fs::symlink_metadata(src).map_err(|_| FastZipError::FileNotFound(src.to_path_buf()))?;
+        let name = src.file_name().unwrap_or_default().to_string_lossy();
+        if meta.file_type().is_symlink() && !follow {
+            if options.symlinks == SymlinkPolicy::Store && filter.accept(&name, false, Some(&meta)) {
+                visit(src, name.as_ref(), EntryKind::Symlink)?;
+            }
+            continue;
+        }
         if !src.exists() {
             return Err(FastZipError::FileNotFound(src.to_path_buf()));
         }
         if src.is_file() {
-            let name = src.file_name().unwrap_or_default().to_string_lossy();
-            add_file_to_zip(&mut zip, src, name.as_ref(), opts)?;
+            if filter.accept(&name, false, fs::metadata(src).ok().as_ref()) {
+                visit(src, name.as_ref(), EntryKind::File)?;
+            }
         } else if options.recursive {
-            for entry in WalkDir::new(src).into_iter().filter_map(|e| e.ok()) {
+            let root = src.to_path_buf();
+            let prune = Arc::clone(&filter);
+            let respect_ignore = options.respect_ignore;
+            let mut builder = WalkBuilder::new(src);
+            builder
+                .standard_filters(false)
+                .git_ignore(respect_ignore)
+                .git_exclude(respect_ignore)
+                .ignore(respect_ignore)
+                .parents(respect_ignore)
+                .require_git(false)
+                .follow_links(follow)
+                .filter_entry(move |entry| {
+                    if !entry.file_type().is_some_and(|t| t.is_dir()) || entry.depth() == 0 {
+                        return true;
+                    }
+                    if respect_ignore && entry.file_name() == ".git" {
+                        return false;
+                    }
+                    !prune.prune_dir(&entry_name(entry.path(), &root))
+                });
+            if options.reproducible {
+                builder.sort_by_file_name(|a, b| a.cmp(b));
+            }
+            for entry in builder.build() {
+                // 循环链接、无权限等错误只跳过该条目
+                let entry = match entry {
+                    Ok(entry) => entry,
+                    Err(e) => {
+                        tracing::warn!(error = %e, "跳过无法读取的条目");
+                        continue;
+                    }
+                };
                 let path = entry.path();
-                let rel = path.strip_prefix(src).unwrap_or(path);
-                if path.is_dir() {
-                    let name = rel.to_string_lossy().replace('\\', "/") + "/";
-                    zip.add_directory(name, opts)?;
-                } else {
-                    let name = rel.to_string_lossy().replace('\\', "/");
-                    add_file_to_zip(&mut zip, path, name.as_str(), opts)?;
+                if entry.depth() == 0 {
+                    continue;
+                }
+                let kind = match entry.file_type() {
+                    Some(t) if t.is_dir() => EntryKind::Dir,
+                    Some(t) if t.is_file() => EntryKind::File,
+                    Some(t) if t.is_symlink() && options.symlinks == SymlinkPolicy::Store => EntryKind::Symlink,
+                    _ => continue,
+                };
+                let name = entry_name(path, src);
+                if filter.accept(&name, kind == EntryKind::Dir, entry.metadata().ok().as_ref()) {
+                    visit(path, &name, kind)?;
                 }
             }
-        } else {
-            let dir_name = src
-                .file_name()
-                .unwrap_or_default()
-                .to_string_lossy()
-                .to_string()
-                + "/";
-            zip.add_directory(dir_name, opts)?;
+        } else if filter.accept(&name, true, None) {
+            visit(src, name.as_ref(), EntryKind::Dir)?;
         }
     }
-
-    zip.finish()?;
     Ok(())
 }
 
-fn zip_file_options(fast: bool) -> SimpleFileOptions {
-    let opts = if fast {
-        // 仅存储不压缩，速度接近纯拷贝，体积等于原文件之和
-        SimpleFileOptions::default().compression_method(CompressionMethod::Stored)
-    } else {
-        SimpleFileOptions::default()
+/// 符号链接的目标，以 `/` 分隔
+fn link_target(path: &Path) -> Result<String> {
+    Ok(fs::read_link(path)?.to_string_lossy().replace('\\', "/"))
+}
+
+/// 相对 `root` 的条目名，以 `/` 分隔
+fn entry_name(path: &Path, root: &Path) -> String {
+    path.strip_prefix(root)
+        .unwrap_or(path)
+        .to_string_lossy()
+        .replace('\\', "/")
+}
+
+fn zip_file_options(options: &CompressOptions) -> FullFileOptions<'static> {
+    // zip 库不能写入 LZMA：数据由本库编码后按 Stored 写入
+    let (method, level) = match options.method {
+        ZipMethod::Lzma => (zip::CompressionMethod::Stored, None),
+        method => (method.to_zip(), options.zip_level().map(i64::from)),
     };
-    // 启用 ZIP64 支持大文件（> 4GB）
-    opts.large_file(true)
+    FileOptions::<ExtendedFileOptions>::default()
+        .compression_method(method)
+        .compression_level(level)
+        // 启用 ZIP64 支持大文件（> 4GB）
+        .large_file(true)
 }
 
-fn add_file_to_zip<W: Write + std::io::Seek>(
-    zip: &mut ZipWriter<W>,
+/// 不支持加密的格式收到密码时报错，避免静默写出明文归档
+fn reject_password(options: &CompressOptions, format: &str) -> Result<()> {
+    match options.password {
+        Some(_) => Err(FastZipError::UnsupportedFormat(format!("{format} 不支持密码加密"))),
+        None => Ok(()),
+    }
+}
+
+/// ZIP 注释长度不超过 65535 字节
+fn check_zip_comment(comment: &str) -> Result<&[u8]> {
+    match comment.len() > u16::MAX as usize {
+        true => Err(FastZipError::Other(format!("ZIP 注释过长（{} 字节，上限 65535）", comment.len()))),
+        false => Ok(comment.as_bytes()),
+    }
+}
+
+/// 只有 ZIP 支持归档注释，其他格式收到注释时报错
+fn reject_comment(options: &CompressOptions, format: &str) -> Result<()> {
+    match options.comment {
+        Some(_) => Err(FastZipError::UnsupportedFormat(format!("{format} 不支持归档注释"))),
+        None => Ok(()),
+    }
+}
+
+/// 将若干路径打包为 tar，`codec` 为 None 时不压缩
+///
+/// 保留 Unix 权限、修改时间与符号链接（链接本身而非其指向的内容）。
+/// zstd 输出为可寻址格式（独立帧 + seek table），普通 zstd 解码器可直接解压，
+/// FastZip 可借助帧边界只解码目标条目所在的帧。
+/// 设置 `volume_size` 时按字节切分为 `<dest>.001`、`.002`…，不生成 `dest` 本身。
+pub fn compress_to_tar<P: AsRef<Path>>(
+    sources: &[P],
+    dest: &Path,
+    codec: Option<Codec>,
+    options: &CompressOptions,
+) -> Result<()> {
+    reject_password(options, "tar")?;
+    reject_comment(options, "tar")?;
+    let file = BufWriter::with_capacity(1024 * 1024, Output::create(dest, options.volume_size)?);
+    write_tar(file, sources, codec, options)?.flush()?;
+    Ok(())
+}
+
+/// 把 tar（`codec` 不为 None 时外加压缩）写入 `out`，返回 `out`（未 flush）
+pub(crate) fn write_tar<W: Write, P: AsRef<Path>>(
+    out: W,
+    sources: &[P],
+    codec: Option<Codec>,
+    options: &CompressOptions,
+) -> Result<W> {
+    reject_password(options, "tar")?;
+    reject_comment(options, "tar")?;
+    let reproducible = Reproducible::from_options(options)?;
+    Ok(match codec {
+        None => {
+            let mut tar = tar::Builder::new(out);
+            append_sources_to_tar(&mut tar, sources, options, reproducible)?;
+            tar.into_inner()?
+        }
+        Some(codec) => {
+            let mut tar = tar::Builder::new(Encoder::new(out, codec, Some(options.codec_level(codec)))?);
+            append_sources_to_tar(&mut tar, sources, options, reproducible)?;
+            tar.into_inner()?.finish()?
+        }
+    })
+}
+
+fn append_sources_to_tar<W: Write, P: AsRef<Path>>(
+    tar: &mut tar::Builder<W>,
+    sources: &[P],
+    options: &CompressOptions,
+    reproducible: Option<Reproducible>,
+) -> Result<()> {
+    // 链接是否跟随由遍历决定；目录只写入自身条目，内容由遍历逐个写入
+    tar.follow_symlinks(options.symlinks == SymlinkPolicy::Follow);
+    match reproducible {
+        Some(r) => walk_sources(sources, options, |path, name, kind| append_reproducible(tar, path, name, kind, r)),
+        None => walk_sources(sources, options, |path, name, _| Ok(tar.append_path_with_name(path, name)?)),
+    }
+}
+
+/// 以规范化的头写入 tar 条目：固定时间，属主 0、无用户名，权限 0644/0755
+fn append_reproducible<W: Write>(
+    tar: &mut tar::Builder<W>,
     path: &Path,
     name: &str,
-    opts: SimpleFileOptions,
+    kind: EntryKind,
+    r: Reproducible,
 ) -> Result<()> {
-    let f = File::open(path)?;
-    let mut reader = BufReader::with_capacity(1024 * 1024, f);
-    zip.start_file(name, opts)?;
-    std::io::copy(&mut reader, zip)?;
+    let meta = match kind {
+        EntryKind::Symlink => fs::symlink_metadata(path)?,
+        _ => fs::metadata(path)?,
+    };
+    let mut header = tar::Header::new_gnu();
+    header.set_metadata_in_mode(&meta, tar::HeaderMode::Deterministic);
+    header.set_mtime(r.epoch);
+    match kind {
+        EntryKind::Symlink => {
+            header.set_mode(0o777);
+            tar.append_link(&mut header, name, fs::read_link(path)?)?;
+        }
+        EntryKind::Dir => {
+            header.set_mode(0o755);
+            tar.append_data(&mut header, name, std::io::empty())?;
+        }
+        EntryKind::File => {
+            header.set_mode(Reproducible::mode(&meta));
+            tar.append_data(&mut header, name, BufReader::new(File::open(path)?))?;
+        }
+    }
     Ok(())
 }
 
-/// 将单个路径压缩为 7z（目录或文件）
-pub fn compress_to_7z<P: AsRef<Path>>(source: P, dest: &Path) -> Result<()> {
-    let src = source.as_ref();
-    if !src.exists() {
-        return Err(FastZipError::FileNotFound(src.to_path_buf()));
+/// 将单个文件压缩为 .gz/.xz/.bz2/.zst，保留原文件的修改时间与权限
+///
+/// 输出已存在时报错，除非设置 `options.overwrite`。`options.keep_original` 为 false 时
+/// 压缩成功后删除原文件；失败时删除不完整的输出。
+pub fn compress_single(source: &Path, dest: &Path, codec: Codec, options: &CompressOptions) -> Result<()> {
+    if !source.is_file() {
+        return Err(FastZipError::FileNotFound(source.to_path_buf()));
+    }
+    if dest == source {
+        return Err(FastZipError::Other("输出文件不能与源文件相同".into()));
+    }
+    reject_password(options, codec.extension())?;
+    reject_comment(options, codec.extension())?;
+
+    let file = if options.overwrite {
+        File::create(dest)
+    } else {
+        fs::OpenOptions::new().write(true).create_new(true).open(dest)
+    };
+    let file = file.map_err(|e| match e.kind() {
+        std::io::ErrorKind::AlreadyExists => FastZipError::Other(format!("输出文件已存在: {}", dest.display())),
+        _ => e.into(),
+    })?;
+    let write = || -> Result<()> {
+        let meta = fs::metadata(source)?;
+        let mut reader = BufReader::with_capacity(1024 * 1024, File::open(source)?);
+        let out = BufWriter::with_capacity(1024 * 1024, file);
+        let mut encoder = Encoder::new(out, codec, Some(options.codec_level(codec)))?;
+        std::io::copy(&mut reader, &mut encoder)?;
+        let file = encoder.finish()?.into_inner().map_err(|e| e.into_error())?;
+        file.set_permissions(meta.permissions())?;
+        file.set_modified(meta.modified()?)?;
+        Ok(())
+    };
+    if let Err(e) = write() {
+        let _ = fs::remove_file(dest);
+        return Err(e);
+    }
+
+    if !options.keep_original {
+        fs::remove_file(source)?;
     }
-    sevenz_rust::compress_to_path(src, dest).map_err(|e| FastZipError::SevenZ(e.to_string()))?;
     Ok(())
 }
+
+/// 批量原地压缩：每个文件并行压缩为同目录下的 `<文件名>.<扩展名>`
+///
+/// 返回（源文件，压缩结果）。按 `options` 中的 glob（匹配文件名）、大小与时间条件筛选，
+/// 未选中的文件不出现在结果中。线程数取 `options.threads`。
+pub fn compress_single_many<P: AsRef<Path> + Sync>(
+    sources: &[P],
+    codec: Codec,
+    options: &CompressOptions,
+) -> Result<Vec<(PathBuf, Result<PathBuf>)>> {
+    let filter = EntryFilter::new(options)?;
+    let selected: Vec<&Path> = sources.iter().map(|p| p.as_ref()).filter(|p| filter.accept_file(p)).collect();
+    let mut results = Vec::with_capacity(selected.len());
+    for_each_parallel(
+        &selected,
+        options.threads,
+        |src| {
+            let mut name = src.as_os_str().to_os_string();
+            name.push(".");
+            name.push(codec.extension());
+            let dest = PathBuf::from(name);
+            Ok(compress_single(src, &dest, codec, options).map(|_| dest))
+        },
+        |src, result| {
+            results.push((src.to_path_buf(), result));
+            Ok(())
+        },
+    )?;
+    Ok(results)
+}
+
+/// 将若干路径打包为 7z，条目布局与 [`compress_to_zip`] 相同
+///
+/// LZMA2 级别与字典大小取自 `options`；设置 `password` 时以 AES-256 加密内容，
+/// `encrypt_header` 另外加密文件名等头信息。固实模式下文件按 `solid_block_size` 分块压缩。
+/// 设置 `volume_size` 时按字节切分为 `<dest>.001`、`.002`…（与 7-Zip 分卷相同）。
+pub fn compress_to_7z<P: AsRef<Path>>(sources: &[P], dest: &Path, options: &CompressOptions) -> Result<()> {
+    compress_to_7z_hooked(sources, dest, options, &TaskHook::default())
+}
+
+/// 同 [`compress_to_7z`]，每个条目、每块源数据调用 `hook`，取消时以错误结束并删除输出
+pub(crate) fn compress_to_7z_hooked<P: AsRef<Path>>(
+    sources: &[P],
+    dest: &Path,
+    options: &CompressOptions,
+    hook: &TaskHook,
+) -> Result<()> {
+    check_sevenz_options(options)?;
+    let write = || -> Result<()> {
+        let mut writer = sevenz_writer(dest, options)?;
+
+        let block_limit = options.solid_block_size.unwrap_or(MAX_SOLID_BLOCK).clamp(1, MAX_SOLID_BLOCK);
+        let mut block = SolidBlock::default();
+        walk_sources(sources, options, |path, name, kind| {
+            hook.entry(name)?;
+            let mut entry = SevenZArchiveEntry::from_path(path, name.to_string());
+            if kind == EntryKind::Symlink {
+                // p7zip 约定：属性高 16 位为 Unix 模式，链接目标作为内容
+                let target = link_target(path)?.into_bytes();
+                entry.is_directory = false;
+                entry.has_stream = true;
+                entry.has_windows_attributes = true;
+                entry.windows_attributes = 0x8000 | (0o120777 << 16);
+                if !options.solid {
+                    writer.push_archive_entry(entry, Some(target.as_slice())).map_err(seven_err)?;
+                    return Ok(());
+                }
+                block.size += target.len() as u64;
+                block.entries.push(entry);
+                block.readers.push(SourceReader::new(Box::new(std::io::Cursor::new(target))));
+                return Ok(());
+            }
+            let size = if kind == EntryKind::Dir { 0 } else { fs::metadata(path)?.len() };
+            if size == 0 {
+                // 目录与空文件不含数据流
+                writer.push_archive_entry::<&[u8]>(entry, None).map_err(seven_err)?;
+            } else if !options.solid {
+                writer.push_archive_entry(entry, Some(hook.reader(File::open(path)?))).map_err(seven_err)?;
+            } else {
+                if block.size > 0 && block.size + size > block_limit {
+                    block.flush(&mut writer)?;
+                }
+                block.size += size;
+                block.entries.push(entry);
+                block.readers.push(SourceReader::new(Box::new(hook.reader(LazyFile::new(path)))));
+            }
+            Ok(())
+        })?;
+        block.flush(&mut writer)?;
+        writer.finish()?;
+        Ok(())
+    };
+    let result = write();
+    if result.is_err() {
+        remove_output(dest);
+    }
+    result
+}
+
+fn check_sevenz_options(options: &CompressOptions) -> Result<()> {
+    reject_comment(options, "7z")?;
+    match options.encrypt_header && options.password.is_none() {
+        true => Err(FastZipError::Other("加密 7z 文件名需要设置密码".into())),
+        false => Ok(()),
+    }
+}
+
+/// 按 `options` 设置压缩方法（LZMA2，可选 AES-256）与文件名加密的 7z 写入器
+fn sevenz_writer(dest: &Path, options: &CompressOptions) -> Result<SevenZWriter<Output>> {
+    let mut writer = SevenZWriter::new(Output::create(dest, options.volume_size)?).map_err(seven_err)?;
+    let mut lzma2 = LZMA2Options::with_preset(options.sevenz_level());
+    if let Some(size) = options.dict_size {
+        lzma2.dict_size = size.clamp(DICT_SIZE_MIN, DICT_SIZE_MAX);
+    }
+    let methods = match &options.password {
+        Some(password) => vec![AesEncoderOptions::new(password.as_str().into()).into(), lzma2.into()],
+        None => vec![lzma2.into()],
+    };
+    writer.set_content_methods(methods);
+    writer.set_encrypt_header(options.encrypt_header);
+    Ok(writer)
+}
+
+/// sevenz-rust 单个固实块的上限
+const MAX_SOLID_BLOCK: u64 = 4 * 1024 * 1024 * 1024;
+const DICT_SIZE_MIN: u32 = 4096;
+const DICT_SIZE_MAX: u32 = 1536 * 1024 * 1024;
+
+fn seven_err(e: sevenz_rust::Error) -> FastZipError {
+    FastZipError::SevenZ(e.to_string())
+}
+
+/// 待写入的固实块
+#[derive(Default)]
+struct SolidBlock {
+    entries: Vec<SevenZArchiveEntry>,
+    readers: Vec<SourceReader<Box<dyn std::io::Read>>>,
+    size: u64,
+}
+
+impl SolidBlock {
+    fn flush(&mut self, writer: &mut SevenZWriter<Output>) -> Result<()> {
+        if self.entries.is_empty() {
+            return Ok(());
+        }
+        let entries = std::mem::take(&mut self.entries);
+        let readers = SeqReader::new(std::mem::take(&mut self.readers));
+        writer.push_archive_entries(entries, readers).map_err(seven_err)?;
+        self.size = 0;
+        Ok(())
+    }
+}
+
+/// 首次读取时才打开、读完即关闭的文件，避免固实块同时占用大量文件句柄
+struct LazyFile {
+    path: PathBuf,
+    file: Option<File>,
+    done: bool,
+}
+
+impl LazyFile {
+    fn new(path: &Path) -> Self {
+        Self { path: path.to_path_buf(), file: None, done: false }
+    }
+}
+
+impl std::io::Read for LazyFile {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if self.done {
+            return Ok(0);
+        }
+        let file = match &mut self.file {
+            Some(file) => file,
+            None => self.file.insert(File::open(&self.path)?),
+        };
+        let n = file.read(buf)?;
+        if n == 0 {
+            self.done = true;
+            self.file = None;
+        }
+        Ok(n)
+    }
+}
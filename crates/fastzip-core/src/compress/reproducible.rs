@@ -0,0 +1,104 @@
+//! 可复现输出：固定时间戳、规范化权限与属主，同一源树多次压缩得到相同字节
+
+use std::fs::Metadata;
+use std::time::UNIX_EPOCH;
+
+use super::CompressOptions;
+use crate::error::{FastZipError, Result};
+use crate::formats::zip_datetime_utc;
+
+/// 未指定时间时使用 1980-01-01 00:00:00 UTC（ZIP 可表示的最早时间）
+const DEFAULT_EPOCH: u64 = 315_532_800;
+
+/// 可复现模式参数：所有条目使用的修改时间（Unix 秒）
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Reproducible {
+    pub epoch: u64,
+}
+
+impl Reproducible {
+    /// 未开启 `reproducible` 时返回 None；时间依次取 `fixed_mtime`、`SOURCE_DATE_EPOCH`、默认值
+    pub fn from_options(options: &CompressOptions) -> Result<Option<Self>> {
+        if !options.reproducible {
+            return Ok(None);
+        }
+        if options.password.is_some() {
+            return Err(FastZipError::Other("可复现模式不能与密码同时使用（加密条目含随机数据）".into()));
+        }
+        let epoch = match options.fixed_mtime {
+            Some(time) => time.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs()),
+            None => parse_epoch(std::env::var("SOURCE_DATE_EPOCH").ok().as_deref())?,
+        };
+        Ok(Some(Self { epoch }))
+    }
+
+    /// ZIP 条目时间，按 UTC 记录，不随运行环境的时区变化
+    pub fn zip_time(&self) -> zip::DateTime {
+        zip_datetime_utc(self.epoch as i64)
+    }
+
+    /// 规范化的权限：目录与可执行文件 0755，其余 0644
+    pub fn mode(meta: &Metadata) -> u32 {
+        if meta.is_dir() || is_executable(meta) {
+            0o755
+        } else {
+            0o644
+        }
+    }
+}
+
+fn parse_epoch(value: Option<&str>) -> Result<u64> {
+    match value.map(str::trim) {
+        None | Some("") => Ok(DEFAULT_EPOCH),
+        Some(s) => s
+            .parse()
+            .map_err(|_| FastZipError::Other(format!("SOURCE_DATE_EPOCH 无效: {}", s))),
+    }
+}
+
+#[cfg(unix)]
+fn is_executable(meta: &Metadata) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    meta.permissions().mode() & 0o100 != 0
+}
+
+#[cfg(not(unix))]
+fn is_executable(_meta: &Metadata) -> bool {
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_zip_time() {
+        let t = Reproducible { epoch: 1_700_000_000 }.zip_time();
+        // 2023-11-14 22:13:20 UTC
+        assert_eq!(
+            (t.year(), t.month(), t.day(), t.hour(), t.minute(), t.second()),
+            (2023, 11, 14, 22, 13, 20)
+        );
+        let t = Reproducible { epoch: 0 }.zip_time();
+        assert_eq!((t.year(), t.month(), t.day()), (1980, 1, 1));
+        let t = Reproducible { epoch: DEFAULT_EPOCH }.zip_time();
+        assert_eq!((t.year(), t.hour(), t.second()), (1980, 0, 0));
+    }
+
+    #[test]
+    fn test_parse_epoch() {
+        assert_eq!(parse_epoch(None).unwrap(), DEFAULT_EPOCH);
+        assert_eq!(parse_epoch(Some(" 1234 ")).unwrap(), 1234);
+        assert!(parse_epoch(Some("yesterday")).is_err());
+    }
+
+    #[test]
+    fn test_password_rejected() {
+        let options = CompressOptions {
+            reproducible: true,
+            password: Some("x".into()),
+            ..Default::default()
+        };
+        assert!(Reproducible::from_options(&options).is_err());
+    }
+}
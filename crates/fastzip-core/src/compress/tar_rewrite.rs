@@ -0,0 +1,320 @@
+//! tar 记录级复制：修改归档时未改动的条目连同扩展记录按原始字节写出
+//!
+//! tar 库读出条目时会合并 PAX/GNU 扩展记录，重新写入则只保留路径与链接目标，
+//! 丢失 PAX 中的高精度时间、xattr 等。这里按 512 字节块直接读出每个条目的全部记录，
+//! 改名时只替换路径（或链接目标），其余 PAX 记录与头部字段保持不变。
+
+use std::io::{self, Read, Write};
+
+use tar::{EntryType, Header};
+
+use crate::error::{FastZipError, Result};
+
+const BLOCK: usize = 512;
+/// 头部中名称与链接目标字段的长度
+const NAME_LEN: usize = 100;
+
+/// 一个条目的头部记录，数据由 [`RawTarReader::copy_data`] 读出
+pub(super) struct RawEntry {
+    /// 扩展记录、主头部与 GNU 稀疏扩展块的原始字节
+    pub blocks: Vec<u8>,
+    pub header: Header,
+    /// 条目名：依次取 PAX `path`、GNU 长名、头部中的名称
+    pub name: String,
+    /// 链接目标：依次取 PAX `linkpath`、GNU 长链接名、头部中的链接名
+    pub link: Option<String>,
+    /// PAX 扩展记录（键，值），按原顺序
+    pax: Vec<(String, Vec<u8>)>,
+    /// GNU 格式：长名使用 `L`/`K` 记录
+    gnu: bool,
+    /// 稀疏文件（GNU `S` 类型或 PAX `GNU.sparse.*` 记录）
+    pub sparse: bool,
+    /// PAX 全局扩展头（作用于其后所有条目，总是原样保留）
+    pub global: bool,
+}
+
+/// 按记录读出 tar 条目
+pub(super) struct RawTarReader<R> {
+    inner: R,
+    /// 当前条目尚未读出的数据（含补齐）
+    pending: u64,
+}
+
+impl<R: Read> RawTarReader<R> {
+    pub fn new(inner: R) -> Self {
+        Self { inner, pending: 0 }
+    }
+
+    /// 读出下一个条目的头部记录；到达结束块或输入末尾时返回 None
+    pub fn next_entry(&mut self) -> Result<Option<RawEntry>> {
+        io::copy(&mut (&mut self.inner).take(self.pending), &mut io::sink())?;
+        self.pending = 0;
+
+        let mut blocks = Vec::new();
+        let mut pax = Vec::new();
+        let (mut long_name, mut long_link) = (None, None);
+        let mut gnu = false;
+        loop {
+            let mut block = [0u8; BLOCK];
+            if !read_block(&mut self.inner, &mut block)? || block.iter().all(|&b| b == 0) {
+                return match blocks.is_empty() {
+                    true => Ok(None),
+                    false => Err(tar_err("扩展记录后缺少条目头部")),
+                };
+            }
+            let header = Header::from_byte_slice(&block).clone();
+            if header.cksum().ok() != Some(checksum(&block)) {
+                return Err(tar_err("tar 头部校验和错误"));
+            }
+            blocks.extend_from_slice(&block);
+            let kind = header.entry_type();
+            let size = header.entry_size()?;
+
+            if kind.is_pax_local_extensions() || kind.is_gnu_longname() || kind.is_gnu_longlink() {
+                let data = self.read_data(size, &mut blocks)?;
+                if kind.is_pax_local_extensions() {
+                    pax.extend(parse_pax(&data)?);
+                } else {
+                    let value = trim_nul(&data).to_vec();
+                    gnu = true;
+                    match kind.is_gnu_longname() {
+                        true => long_name = Some(value),
+                        false => long_link = Some(value),
+                    }
+                }
+                continue;
+            }
+            if kind.is_pax_global_extensions() {
+                self.read_data(size, &mut blocks)?;
+                let name = String::new();
+                return Ok(Some(RawEntry { blocks, header, name, link: None, pax, gnu, sparse: false, global: true }));
+            }
+
+            // GNU 稀疏条目的头部之后可能还有扩展块，由上一块末尾的标志串起
+            let mut extended = kind.is_gnu_sparse() && header.as_gnu().is_some_and(|h| h.isextended[0] != 0);
+            while extended {
+                if !read_block(&mut self.inner, &mut block)? {
+                    return Err(tar_err("稀疏条目的扩展块不完整"));
+                }
+                blocks.extend_from_slice(&block);
+                extended = block[504] != 0;
+            }
+
+            let value = |key: &str| pax.iter().rev().find(|(k, _)| k == key).map(|(_, v)| v.as_slice());
+            let name = value("GNU.sparse.name")
+                .or_else(|| value("path"))
+                .map(<[u8]>::to_vec)
+                .or(long_name)
+                .unwrap_or_else(|| header.path_bytes().into_owned());
+            let link = value("linkpath")
+                .map(<[u8]>::to_vec)
+                .or(long_link)
+                .or_else(|| header.link_name_bytes().map(|l| l.into_owned()));
+            let sparse = kind.is_gnu_sparse() || pax.iter().any(|(k, _)| k.starts_with("GNU.sparse."));
+            // PAX `size` 覆盖头部中的大小（超过 8 GiB 时头部放不下）
+            let size = match value("size") {
+                Some(size) => std::str::from_utf8(size)
+                    .ok()
+                    .and_then(|s| s.parse().ok())
+                    .ok_or_else(|| tar_err("PAX size 记录无效"))?,
+                None => size,
+            };
+            self.pending = padded(size);
+            let gnu = gnu || header.as_gnu().is_some();
+            return Ok(Some(RawEntry {
+                blocks,
+                header,
+                name: String::from_utf8_lossy(&name).replace('\\', "/"),
+                link: link.map(|l| String::from_utf8_lossy(&l).into_owned()),
+                pax,
+                gnu,
+                sparse,
+                global: false,
+            }));
+        }
+    }
+
+    /// 原样复制当前条目的数据（含补齐）
+    pub fn copy_data<W: Write>(&mut self, out: &mut W) -> Result<()> {
+        let len = self.pending;
+        let copied = io::copy(&mut (&mut self.inner).take(len), out)?;
+        self.pending = 0;
+        match copied == len {
+            true => Ok(()),
+            false => Err(tar_err("tar 条目数据不完整")),
+        }
+    }
+
+    /// 读出扩展记录的数据，原始块追加到 `blocks`
+    fn read_data(&mut self, size: u64, blocks: &mut Vec<u8>) -> Result<Vec<u8>> {
+        let start = blocks.len();
+        let len = usize::try_from(padded(size)).map_err(|_| tar_err("tar 扩展记录过大"))?;
+        blocks.resize(start + len, 0);
+        self.inner.read_exact(&mut blocks[start..]).map_err(|_| tar_err("tar 扩展记录不完整"))?;
+        Ok(blocks[start..start + size as usize].to_vec())
+    }
+}
+
+/// 以新的条目名与链接目标写出头部记录；其余 PAX 记录与头部字段取自原条目
+///
+/// 放不进头部字段的长名沿用原条目的方式：GNU 归档写 `L`/`K` 记录，否则写 PAX 记录。
+pub(super) fn write_renamed<W: Write>(out: &mut W, entry: &RawEntry, name: &str, link: Option<&str>) -> Result<()> {
+    let mut header = entry.header.clone();
+    let mut pax: Vec<(String, Vec<u8>)> = entry.pax.clone();
+    let mut long = Vec::new();
+
+    let had_path = pax.iter().any(|(k, _)| k == "path");
+    pax.retain(|(k, _)| k != "path");
+    if let Some(ustar) = header.as_ustar_mut() {
+        ustar.prefix = [0; 155];
+    }
+    if !set_field(&mut header.as_old_mut().name, name) || had_path {
+        match entry.gnu && !had_path {
+            true => long.push((EntryType::GNULongName, name)),
+            false => pax.push(("path".into(), name.as_bytes().to_vec())),
+        }
+    }
+    if let Some(link) = link {
+        let had_link = pax.iter().any(|(k, _)| k == "linkpath");
+        pax.retain(|(k, _)| k != "linkpath");
+        if !set_field(&mut header.as_old_mut().linkname, link) || had_link {
+            match entry.gnu && !had_link {
+                true => long.push((EntryType::GNULongLink, link)),
+                false => pax.push(("linkpath".into(), link.as_bytes().to_vec())),
+            }
+        }
+    }
+
+    if !pax.is_empty() {
+        let data = encode_pax(&pax);
+        write_record(out, &entry.header, EntryType::XHeader, "././@PaxHeader", &data)?;
+    }
+    for (kind, value) in long {
+        let mut data = value.as_bytes().to_vec();
+        data.push(0);
+        write_record(out, &entry.header, kind, "././@LongLink", &data)?;
+    }
+    header.set_cksum();
+    out.write_all(header.as_bytes())?;
+    Ok(())
+}
+
+/// 写出归档结束标记（两个全零块）
+pub(super) fn write_end<W: Write>(out: &mut W) -> Result<()> {
+    out.write_all(&[0; BLOCK * 2])?;
+    Ok(())
+}
+
+/// 写出一条扩展记录，权限与时间取自所属条目
+fn write_record<W: Write>(out: &mut W, owner: &Header, kind: EntryType, name: &str, data: &[u8]) -> Result<()> {
+    let mut header = match kind {
+        EntryType::XHeader => Header::new_ustar(),
+        _ => Header::new_gnu(),
+    };
+    set_field(&mut header.as_old_mut().name, name);
+    header.set_mode(0o644);
+    header.set_mtime(owner.mtime().unwrap_or(0));
+    header.set_entry_type(kind);
+    header.set_size(data.len() as u64);
+    header.set_cksum();
+    out.write_all(header.as_bytes())?;
+    out.write_all(data)?;
+    out.write_all(&vec![0; padded(data.len() as u64) as usize - data.len()])?;
+    Ok(())
+}
+
+/// 写入定长名称字段，放不下时写入截断的前缀并返回 false
+fn set_field(field: &mut [u8; NAME_LEN], value: &str) -> bool {
+    let bytes = value.as_bytes();
+    let n = bytes.len().min(NAME_LEN);
+    *field = [0; NAME_LEN];
+    field[..n].copy_from_slice(&bytes[..n]);
+    bytes.len() <= NAME_LEN
+}
+
+/// 解析 PAX 记录：`<长度> <键>=<值>\n`，长度含记录自身
+fn parse_pax(mut data: &[u8]) -> Result<Vec<(String, Vec<u8>)>> {
+    let mut records = Vec::new();
+    while !data.is_empty() && data[0] != 0 {
+        let space = data.iter().position(|&b| b == b' ').ok_or_else(|| tar_err("PAX 记录无效"))?;
+        let len: usize = std::str::from_utf8(&data[..space])
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .filter(|&len| len > space + 1 && len <= data.len())
+            .ok_or_else(|| tar_err("PAX 记录长度无效"))?;
+        let record = &data[space + 1..len - 1];
+        let eq = record.iter().position(|&b| b == b'=').ok_or_else(|| tar_err("PAX 记录无效"))?;
+        records.push((String::from_utf8_lossy(&record[..eq]).into_owned(), record[eq + 1..].to_vec()));
+        data = &data[len..];
+    }
+    Ok(records)
+}
+
+fn encode_pax(records: &[(String, Vec<u8>)]) -> Vec<u8> {
+    let mut data = Vec::new();
+    for (key, value) in records {
+        // 长度字段计入自身，位数变化时再加一
+        let body = key.len() + value.len() + 3;
+        let mut len = body + body.to_string().len();
+        if len.to_string().len() != body.to_string().len() {
+            len += 1;
+        }
+        data.extend_from_slice(format!("{len} {key}=").as_bytes());
+        data.extend_from_slice(value);
+        data.push(b'\n');
+    }
+    data
+}
+
+/// 读满一块；输入在块边界结束时返回 false
+fn read_block<R: Read>(input: &mut R, block: &mut [u8; BLOCK]) -> Result<bool> {
+    let mut filled = 0;
+    while filled < BLOCK {
+        match input.read(&mut block[filled..]) {
+            Ok(0) if filled == 0 => return Ok(false),
+            Ok(0) => return Err(tar_err("tar 头部不完整")),
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Ok(true)
+}
+
+/// 头部校验和：校验和字段按空格计
+fn checksum(block: &[u8; BLOCK]) -> u32 {
+    block.iter().enumerate().map(|(i, &b)| if (148..156).contains(&i) { 32 } else { b as u32 }).sum()
+}
+
+fn trim_nul(data: &[u8]) -> &[u8] {
+    let end = data.iter().position(|&b| b == 0).unwrap_or(data.len());
+    &data[..end]
+}
+
+fn padded(size: u64) -> u64 {
+    size.div_ceil(BLOCK as u64) * BLOCK as u64
+}
+
+fn tar_err(msg: &str) -> FastZipError {
+    FastZipError::Tar(msg.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pax_roundtrip() {
+        let records = vec![
+            ("path".to_string(), b"a".repeat(90)),
+            ("mtime".to_string(), b"1600000000.123456789".to_vec()),
+            ("SCHILY.xattr.user.tag".to_string(), b"v".to_vec()),
+        ];
+        let data = encode_pax(&records);
+        assert_eq!(parse_pax(&data).unwrap(), records);
+        // 长度字段恰好从两位变为三位
+        let edge = vec![("k".to_string(), vec![b'x'; 94])];
+        assert_eq!(encode_pax(&edge).len(), 101);
+        assert_eq!(parse_pax(&encode_pax(&edge)).unwrap(), edge);
+    }
+}
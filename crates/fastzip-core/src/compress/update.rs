@@ -0,0 +1,237 @@
+//! 更新已有 ZIP：加入新文件、替换有变化的文件，未变化的条目按原始字节复制，不重新压缩
+
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use tempfile::SpooledTempFile;
+use zip::write::FullFileOptions;
+use zip::{ZipArchive, ZipWriter};
+
+use super::edit::replace_file;
+use super::encryption::{force_ae2_in, ZipCryptoWriter};
+use super::reproducible::Reproducible;
+use super::volume::reject_volumes;
+use super::zip_rewrite::{raw_spans, RawZipWriter};
+use super::{
+    compress_zip_entry, entry_options, for_each_parallel, link_target, reject_aes_lzma, unix_secs, walk_sources,
+    zip_file_options, CompressOptions, EntryKind, ZipEncryption, SPILL_THRESHOLD,
+};
+use crate::error::{FastZipError, Result};
+use crate::formats::entry_mtime;
+use crate::hook::TaskHook;
+
+/// 更新方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UpdateMode {
+    /// 加入新文件并替换有变化的文件；归档不存在时新建
+    Update,
+    /// 只替换归档中已有且有变化的文件
+    Freshen,
+}
+
+/// 更新结果
+#[derive(Debug, Clone, Default)]
+pub struct UpdateSummary {
+    /// 新加入的条目名
+    pub added: Vec<String>,
+    /// 被替换的条目名
+    pub replaced: Vec<String>,
+    /// 未变化、原样保留的已有条目数
+    pub unchanged: usize,
+}
+
+/// 已有条目的比较依据
+struct Existing {
+    index: usize,
+    size: u64,
+    mtime: Option<i64>,
+}
+
+/// 按 `mode` 把 `sources` 更新进已有 ZIP `archive`
+///
+/// 源文件大小不同或修改时间晚于条目时间（DOS 时间有 2 秒误差）即视为有变化；
+/// 目录条目只在缺失时加入。被替换的条目移到归档末尾，其余条目原样保留；
+/// 设置 `comment` 时替换归档注释，否则保留原注释。
+/// 新条目使用 `options` 中的压缩方法与加密方式；没有需要写入的条目且未设置注释时不改动归档。
+pub fn update_zip<P: AsRef<Path>>(
+    archive: &Path,
+    sources: &[P],
+    mode: UpdateMode,
+    options: &CompressOptions,
+) -> Result<UpdateSummary> {
+    reject_aes_lzma(options)?;
+    let reproducible = Reproducible::from_options(options)?;
+    if options.volume_size.is_some() {
+        return Err(FastZipError::Other("更新归档不支持分卷输出".into()));
+    }
+    reject_volumes(archive)?;
+    let mut existing = HashMap::new();
+    let source = match File::open(archive) {
+        Ok(file) => {
+            let mut zip = ZipArchive::new(BufReader::new(file))?;
+            for index in 0..zip.len() {
+                let file = zip.by_index_raw(index)?;
+                let entry = Existing { index, size: file.size(), mtime: entry_mtime(&file) };
+                existing.insert(file.name().to_string(), entry);
+            }
+            Some(zip)
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => match mode {
+            UpdateMode::Update => None,
+            _ => return Err(FastZipError::FileNotFound(archive.to_path_buf())),
+        },
+        Err(e) => return Err(e.into()),
+    };
+
+    let mut summary = UpdateSummary::default();
+    let mut replaced = HashSet::new();
+    let mut queued = HashSet::new();
+    let mut pending = Vec::new();
+    walk_sources(sources, options, |path, name, kind| {
+        let key = match kind {
+            EntryKind::Dir => format!("{name}/"),
+            _ => name.to_string(),
+        };
+        if !queued.insert(key.clone()) {
+            return Err(FastZipError::Other(format!("重复的条目名: {key}")));
+        }
+        match existing.get(&key) {
+            Some(entry) if kind == EntryKind::Dir || !is_changed(path, kind, entry)? => summary.unchanged += 1,
+            Some(entry) => {
+                replaced.insert(entry.index);
+                summary.replaced.push(key);
+                pending.push((path.to_path_buf(), name.to_string(), kind));
+            }
+            None if mode == UpdateMode::Update => {
+                summary.added.push(key);
+                pending.push((path.to_path_buf(), name.to_string(), kind));
+            }
+            None => {}
+        }
+        Ok(())
+    })?;
+    if pending.is_empty() && source.is_some() && options.comment.is_none() {
+        return Ok(summary);
+    }
+
+    match source {
+        Some(mut zip) => {
+            let comment = match options.zip_comment()? {
+                Some(comment) => comment.to_vec(),
+                None => zip.comment().to_vec(),
+            };
+            let spans = raw_spans(&mut zip)?;
+            let mut src = zip.into_inner();
+            replace_file(archive, move |out| {
+                let mut writer = RawZipWriter::new(out);
+                for (index, span) in spans.iter().enumerate() {
+                    if !replaced.contains(&index) {
+                        writer.copy_entry(&mut src, span, None)?;
+                    }
+                }
+                append_entries(&mut writer, &pending, options, reproducible)?;
+                writer.finish(&comment)?;
+                Ok(())
+            })?;
+        }
+        None => {
+            let result = File::create(archive).map_err(FastZipError::from).and_then(|file| {
+                let mut out = RawZipWriter::new(BufWriter::with_capacity(1024 * 1024, file));
+                append_entries(&mut out, &pending, options, reproducible)?;
+                Ok(out.finish(options.zip_comment()?.unwrap_or_default())?.flush()?)
+            });
+            if result.is_err() {
+                let _ = fs::remove_file(archive);
+            }
+            result?;
+        }
+    }
+    Ok(summary)
+}
+
+fn is_changed(path: &Path, kind: EntryKind, entry: &Existing) -> Result<bool> {
+    let (meta, size) = match kind {
+        EntryKind::Symlink => (fs::symlink_metadata(path)?, link_target(path)?.len() as u64),
+        _ => {
+            let meta = fs::metadata(path)?;
+            let size = meta.len();
+            (meta, size)
+        }
+    };
+    if size != entry.size {
+        return Ok(true);
+    }
+    Ok(match (meta.modified().ok().and_then(unix_secs), entry.mtime) {
+        (Some(source), Some(stored)) => source > stored + 1,
+        _ => true,
+    })
+}
+
+/// 每个条目并行压缩为单条目的临时 ZIP，再按遍历顺序原样复制到输出末尾
+fn append_entries<W: Write + std::io::Seek>(
+    out: &mut RawZipWriter<W>,
+    pending: &[(PathBuf, String, EntryKind)],
+    options: &CompressOptions,
+    reproducible: Option<Reproducible>,
+) -> Result<()> {
+    let dir_opts = zip_file_options(options);
+    let file_opts = match &options.password {
+        Some(password) => dir_opts.clone().with_aes_encryption(zip::AesMode::Aes256, password),
+        None => dir_opts.clone(),
+    };
+    for_each_parallel(
+        pending,
+        options.threads,
+        |(path, name, kind)| build_part(path, name, *kind, options, reproducible, &file_opts, &dir_opts),
+        |_, part| out.copy_all(ZipArchive::new(part)?),
+    )
+}
+
+/// 只含一个条目的临时 ZIP，编码方式与 `compress_to_zip` 相同
+fn build_part(
+    path: &Path,
+    name: &str,
+    kind: EntryKind,
+    options: &CompressOptions,
+    reproducible: Option<Reproducible>,
+    file_opts: &FullFileOptions<'_>,
+    dir_opts: &FullFileOptions<'_>,
+) -> Result<SpooledTempFile> {
+    if let (Some(password), ZipEncryption::ZipCrypto) = (&options.password, options.encryption) {
+        let mut part = ZipCryptoWriter::new(tempfile::spooled_tempfile(SPILL_THRESHOLD), password);
+        part.set_extended_timestamp(options.extended_timestamp);
+        match kind {
+            EntryKind::Dir => part.add_directory(&format!("{name}/"), &fs::metadata(path)?)?,
+            EntryKind::Symlink => part.add_symlink(name, &link_target(path)?, &fs::symlink_metadata(path)?)?,
+            EntryKind::File => {
+                part.add_file(path, name, options.method, options.zip_level(), &TaskHook::default())?
+            }
+        }
+        return part.finish();
+    }
+
+    let mut part = match kind {
+        EntryKind::File => {
+            let opts = entry_options(file_opts, &fs::metadata(path)?, options, reproducible)?;
+            compress_zip_entry(path, name, opts, options, &TaskHook::default())?
+        }
+        EntryKind::Symlink => {
+            let mut zip = ZipWriter::new(tempfile::spooled_tempfile(SPILL_THRESHOLD));
+            let opts = entry_options(file_opts, &fs::symlink_metadata(path)?, options, reproducible)?;
+            zip.add_symlink(name, link_target(path)?, opts.unix_permissions(0o777))?;
+            zip.finish()?
+        }
+        EntryKind::Dir => {
+            let mut zip = ZipWriter::new(tempfile::spooled_tempfile(SPILL_THRESHOLD));
+            let opts = entry_options(dir_opts, &fs::metadata(path)?, options, reproducible)?;
+            zip.add_directory(format!("{name}/"), opts)?;
+            zip.finish()?
+        }
+    };
+    if options.password.is_some() {
+        force_ae2_in(&mut part)?;
+    }
+    Ok(part)
+}
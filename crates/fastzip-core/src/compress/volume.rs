@@ -0,0 +1,254 @@
+//! 分卷输出：7z 与 tar.* 按字节切分为 `<归档>.001`、`.002`…；ZIP 写成 PKWARE 分卷 `.z01`…`.zip`
+//!
+//! ZIP 先按普通方式写出，超过一卷时再逐条目原样复制到各卷：头部记录不跨卷，
+//! 中央目录记录各条目所在的卷号，第一卷以分卷签名开头。
+
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use zip::ZipArchive;
+
+use super::zip_rewrite::{RawZipWriter, ZipOut};
+use crate::error::{FastZipError, Result};
+use crate::formats::{byte_volume_path, zip_volume_path, VolumeSet};
+
+/// 分卷大小下限：ZIP 的头部记录与结束记录须能放进一卷
+pub const MIN_VOLUME_SIZE: u64 = 64 * 1024;
+
+/// ZIP 分卷第一卷开头的签名
+const SPLIT_SIG: u32 = 0x0807_4b50;
+
+fn check_volume_size(size: u64) -> Result<()> {
+    match size < MIN_VOLUME_SIZE {
+        true => Err(FastZipError::Other(format!("分卷大小不能小于 {} KiB", MIN_VOLUME_SIZE / 1024))),
+        false => Ok(()),
+    }
+}
+
+/// 删除 `path(1)`、`path(2)`… 直到某一卷不存在（清理上次输出留下的多余分卷）
+fn remove_volumes(path: impl Fn(u32) -> PathBuf) {
+    let mut n = 1;
+    while fs::remove_file(path(n)).is_ok() {
+        n += 1;
+    }
+}
+
+/// 删除输出文件及其字节分卷
+pub(super) fn remove_output(dest: &Path) {
+    let _ = fs::remove_file(dest);
+    remove_volumes(|n| byte_volume_path(dest, n));
+}
+
+/// 原地修改归档前检查：分卷归档不支持原地修改
+pub(super) fn reject_volumes(archive: &Path) -> Result<()> {
+    match VolumeSet::find(archive)? {
+        Some(_) => Err(FastZipError::Other(format!("不支持原地修改分卷归档: {}", archive.display()))),
+        None => Ok(()),
+    }
+}
+
+/// 7z 与 tar.* 的输出：未设置分卷大小时为单个文件
+pub(super) enum Output {
+    Single(File),
+    Split(SplitFile),
+}
+
+impl Output {
+    pub fn create(dest: &Path, volume_size: Option<u64>) -> Result<Self> {
+        match volume_size {
+            None => Ok(Self::Single(File::create(dest)?)),
+            Some(size) => {
+                check_volume_size(size)?;
+                remove_volumes(|n| byte_volume_path(dest, n));
+                Ok(Self::Split(SplitFile { base: dest.to_path_buf(), size, pos: 0, len: 0, files: Vec::new() }))
+            }
+        }
+    }
+}
+
+impl Write for Output {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Self::Single(file) => file.write(buf),
+            Self::Split(split) => split.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Self::Single(file) => file.flush(),
+            Self::Split(split) => split.flush(),
+        }
+    }
+}
+
+impl Seek for Output {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        match self {
+            Self::Single(file) => file.seek(pos),
+            Self::Split(split) => split.seek(pos),
+        }
+    }
+}
+
+/// 按固定大小切分的可定位输出：位置 `pos` 落在第 `pos / size` 卷
+///
+/// 7z 写入器结束时回到开头改写签名头，因此各卷保持打开。
+pub(super) struct SplitFile {
+    base: PathBuf,
+    size: u64,
+    pos: u64,
+    len: u64,
+    /// 已创建的卷及其当前文件位置
+    files: Vec<(File, u64)>,
+}
+
+impl SplitFile {
+    fn volume(&mut self, index: usize) -> io::Result<&mut (File, u64)> {
+        while self.files.len() <= index {
+            // 定位越过末尾后写入：之前的卷补足到整卷大小
+            if let Some((last, _)) = self.files.last() {
+                if last.metadata()?.len() < self.size {
+                    last.set_len(self.size)?;
+                }
+            }
+            let path = byte_volume_path(&self.base, self.files.len() as u32 + 1);
+            self.files.push((File::create(path)?, 0));
+        }
+        Ok(&mut self.files[index])
+    }
+}
+
+impl Write for SplitFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        let (index, offset) = ((self.pos / self.size) as usize, self.pos % self.size);
+        let want = buf.len().min((self.size - offset) as usize);
+        let (file, at) = self.volume(index)?;
+        if *at != offset {
+            file.seek(SeekFrom::Start(offset))?;
+        }
+        let n = file.write(&buf[..want])?;
+        *at = offset + n as u64;
+        self.pos += n as u64;
+        self.len = self.len.max(self.pos);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.files.iter_mut().try_for_each(|(file, _)| file.flush())
+    }
+}
+
+impl Seek for SplitFile {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let target = match pos {
+            SeekFrom::Start(n) => Some(n),
+            SeekFrom::End(d) => self.len.checked_add_signed(d),
+            SeekFrom::Current(d) => self.pos.checked_add_signed(d),
+        };
+        self.pos = target.ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "定位到输出开头之前"))?;
+        Ok(self.pos)
+    }
+}
+
+/// 由 `write` 写出 ZIP；设置分卷大小且结果超过一卷时，再按条目复制为分卷
+///
+/// 分卷依次为 `.z01`、`.z02`…，最后一卷即 `dest` 本身；结果不超过一卷时保持普通 ZIP。
+pub(super) fn write_zip(dest: &Path, volume_size: Option<u64>, write: impl FnOnce(&Path) -> Result<()>) -> Result<()> {
+    let Some(size) = volume_size else {
+        return write(dest);
+    };
+    check_volume_size(size)?;
+    remove_volumes(|n| zip_volume_path(dest, n));
+    write(dest)?;
+    if fs::metadata(dest)?.len() <= size {
+        return Ok(());
+    }
+
+    let result = (|| -> Result<()> {
+        let archive = ZipArchive::new(BufReader::new(File::open(dest)?))?;
+        let comment = archive.comment().to_vec();
+        let mut writer = RawZipWriter::new(ZipVolumes::create(dest, size)?);
+        writer.copy_all(archive)?;
+        writer.finish(&comment)?.finish()
+    })();
+    if result.is_err() {
+        remove_volumes(|n| zip_volume_path(dest, n));
+        let _ = fs::remove_file(dest);
+    }
+    result
+}
+
+/// ZIP 分卷输出：依次写入 `.z01`、`.z02`…，完成后最后一卷改名为 `.zip`
+struct ZipVolumes {
+    dest: PathBuf,
+    size: u64,
+    disk: u32,
+    offset: u64,
+    out: BufWriter<File>,
+}
+
+impl ZipVolumes {
+    fn create(dest: &Path, size: u64) -> Result<Self> {
+        let mut out = BufWriter::with_capacity(1024 * 1024, File::create(zip_volume_path(dest, 1))?);
+        out.write_all(&SPLIT_SIG.to_le_bytes())?;
+        Ok(Self { dest: dest.to_path_buf(), size, disk: 0, offset: 4, out })
+    }
+
+    fn next_disk(&mut self) -> io::Result<()> {
+        self.out.flush()?;
+        if self.disk + 2 >= 0xFFFF {
+            return Err(io::Error::other("ZIP 分卷数量过多"));
+        }
+        self.disk += 1;
+        self.out = BufWriter::with_capacity(1024 * 1024, File::create(zip_volume_path(&self.dest, self.disk + 1))?);
+        self.offset = 0;
+        Ok(())
+    }
+
+    fn finish(mut self) -> Result<()> {
+        self.out.flush()?;
+        drop(self.out);
+        fs::rename(zip_volume_path(&self.dest, self.disk + 1), &self.dest)?;
+        Ok(())
+    }
+}
+
+impl Write for ZipVolumes {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        if self.offset >= self.size {
+            self.next_disk()?;
+        }
+        let want = buf.len().min((self.size - self.offset) as usize);
+        let n = self.out.write(&buf[..want])?;
+        self.offset += n as u64;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+}
+
+impl ZipOut for ZipVolumes {
+    fn position(&mut self) -> io::Result<(u32, u64)> {
+        Ok((self.disk, self.offset))
+    }
+
+    fn keep_together(&mut self, len: u64) -> io::Result<()> {
+        if len > self.size {
+            return Err(io::Error::other("分卷大小不足以容纳 ZIP 头部记录"));
+        }
+        if self.offset + len > self.size {
+            self.next_disk()?;
+        }
+        Ok(())
+    }
+}
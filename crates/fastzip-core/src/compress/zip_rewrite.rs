@@ -0,0 +1,545 @@
+//! ZIP 条目原样复制：本地头与数据、中央目录记录按字节搬运，只改写偏移（及可选的条目名）
+//!
+//! zip 库的 `raw_copy_file` 会按条目选项重建头部，丢失加密标志与扩展字段；
+//! 这里直接复制原始字节，AES/ZipCrypto 条目、扩展时间戳等均保持不变。
+//! 输出可以是分卷（[`ZipOut`]）：头部记录不跨卷，中央目录记录各条目所在的卷号。
+
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::ops::Range;
+
+use zip::ZipArchive;
+
+use crate::error::{FastZipError, Result};
+
+const LOCAL_SIG: u32 = 0x0403_4b50;
+const CENTRAL_SIG: u32 = 0x0201_4b50;
+const END_SIG: u32 = 0x0605_4b50;
+const END64_SIG: u32 = 0x0606_4b50;
+const LOCATOR_SIG: u32 = 0x0706_4b50;
+const ZIP64_EXTRA_ID: u16 = 0x0001;
+/// Info-ZIP Unicode 路径扩展字段，改名后失效
+const UNICODE_PATH_ID: u16 = 0x7075;
+/// 通用标志 bit 11：文件名为 UTF-8
+const UTF8_FLAG: u16 = 1 << 11;
+
+/// 条目在源归档中的原始位置
+#[derive(Debug, Clone)]
+pub(crate) struct RawSpan {
+    /// 本地头起始到下一条目（或中央目录）之前，含数据与数据描述符
+    local: Range<u64>,
+    /// 中央目录记录起始
+    central: u64,
+}
+
+/// 按条目索引列出原始位置
+pub(crate) fn raw_spans<R: Read + Seek>(archive: &mut ZipArchive<R>) -> Result<Vec<RawSpan>> {
+    let mut starts = Vec::with_capacity(archive.len());
+    for i in 0..archive.len() {
+        let file = archive.by_index_raw(i)?;
+        starts.push((file.header_start(), file.central_header_start()));
+    }
+    let mut sorted: Vec<u64> = starts.iter().map(|(s, _)| *s).collect();
+    sorted.sort_unstable();
+    let cd_start = archive.central_directory_start();
+    Ok(starts
+        .into_iter()
+        .map(|(start, central)| {
+            let next = sorted.partition_point(|&s| s <= start);
+            let end = sorted.get(next).copied().unwrap_or(cd_start).min(cd_start);
+            RawSpan { local: start..end, central }
+        })
+        .collect())
+}
+
+/// [`RawZipWriter`] 的输出：普通文件始终位于 0 号卷，分卷输出按大小切换到下一卷
+pub(crate) trait ZipOut: Write {
+    /// 当前写入位置：（卷号，卷内偏移）
+    fn position(&mut self) -> io::Result<(u32, u64)>;
+
+    /// 接下来的 `len` 字节是一条头部记录，不跨卷：当前卷放不下时先切换到下一卷
+    fn keep_together(&mut self, _len: u64) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<W: Write + Seek> ZipOut for W {
+    fn position(&mut self) -> io::Result<(u32, u64)> {
+        Ok((0, self.stream_position()?))
+    }
+}
+
+/// 以原始字节组装新的 ZIP
+pub(crate) struct RawZipWriter<W: ZipOut> {
+    out: W,
+    central: Vec<u8>,
+    /// 各中央目录记录在 `central` 中的结束位置
+    ends: Vec<usize>,
+}
+
+impl<W: ZipOut> RawZipWriter<W> {
+    pub fn new(out: W) -> Self {
+        Self { out, central: Vec::new(), ends: Vec::new() }
+    }
+
+    /// 原样复制 `src` 中的一个条目；`rename` 时改写本地头与中央目录中的条目名
+    pub fn copy_entry<R: Read + Seek>(&mut self, src: &mut R, span: &RawSpan, rename: Option<&str>) -> Result<()> {
+        src.seek(SeekFrom::Start(span.local.start))?;
+        let mut head = [0u8; 30];
+        src.read_exact(&mut head)?;
+        if u32_at(&head, 0) != LOCAL_SIG {
+            return Err(FastZipError::Other("ZIP 本地文件头无效".into()));
+        }
+        let (disk, offset) = match rename {
+            None => {
+                let head_len = 30 + u16_at(&head, 26) as u64 + u16_at(&head, 28) as u64;
+                let position = self.start_record(head_len)?;
+                self.out.write_all(&head)?;
+                copy_exact(src, &mut self.out, span.local.end - span.local.start - 30)?;
+                position
+            }
+            Some(name) => {
+                let (name_len, extra_len) = (u16_at(&head, 26) as u64, u16_at(&head, 28) as usize);
+                src.seek(SeekFrom::Current(name_len as i64))?;
+                let mut extra = vec![0u8; extra_len];
+                src.read_exact(&mut extra)?;
+                let extra = strip_extra(&extra, UNICODE_PATH_ID);
+                let flags = u16_at(&head, 6) | UTF8_FLAG;
+                set_u16(&mut head, 6, flags);
+                set_u16(&mut head, 26, name_len_u16(name)?);
+                set_u16(&mut head, 28, extra.len() as u16);
+                let position = self.start_record(30 + name.len() as u64 + extra.len() as u64)?;
+                self.out.write_all(&head)?;
+                self.out.write_all(name.as_bytes())?;
+                self.out.write_all(&extra)?;
+                let data = span.local.end - span.local.start - 30 - name_len - extra_len as u64;
+                copy_exact(src, &mut self.out, data)?;
+                position
+            }
+        };
+
+        src.seek(SeekFrom::Start(span.central))?;
+        let mut fixed = [0u8; 46];
+        src.read_exact(&mut fixed)?;
+        if u32_at(&fixed, 0) != CENTRAL_SIG {
+            return Err(FastZipError::Other("ZIP 中央目录记录无效".into()));
+        }
+        let (name_len, extra_len, comment_len) =
+            (u16_at(&fixed, 28) as usize, u16_at(&fixed, 30) as usize, u16_at(&fixed, 32) as usize);
+        let mut rest = vec![0u8; name_len + extra_len + comment_len];
+        src.read_exact(&mut rest)?;
+        let (old_name, rest) = rest.split_at(name_len);
+        let (extra, comment) = rest.split_at(extra_len);
+
+        let (name, mut extra) = match rename {
+            Some(name) => {
+                let flags = u16_at(&fixed, 8) | UTF8_FLAG;
+                set_u16(&mut fixed, 8, flags);
+                (name.as_bytes(), strip_extra(extra, UNICODE_PATH_ID))
+            }
+            None => (old_name, extra.to_vec()),
+        };
+        set_offset(&mut fixed, &mut extra, offset)?;
+        // 起始卷号
+        let disk = u16::try_from(disk).ok().filter(|&d| d != 0xFFFF);
+        set_u16(&mut fixed, 34, disk.ok_or_else(|| FastZipError::Other("分卷数量过多".into()))?);
+        // 新条目名长度已在写本地头时检查
+        set_u16(&mut fixed, 28, name.len() as u16);
+        set_u16(&mut fixed, 30, u16::try_from(extra.len()).map_err(|_| extra_too_long())?);
+        self.central.extend_from_slice(&fixed);
+        self.central.extend_from_slice(name);
+        self.central.extend_from_slice(&extra);
+        self.central.extend_from_slice(comment);
+        self.ends.push(self.central.len());
+        Ok(())
+    }
+
+    /// 为一条头部记录定位：保证不跨卷，返回其起始位置
+    fn start_record(&mut self, len: u64) -> Result<(u32, u64)> {
+        self.out.keep_together(len)?;
+        Ok(self.out.position()?)
+    }
+
+    /// 依次复制 `archive` 的全部条目（用于合并单条目的临时归档）
+    pub fn copy_all<R: Read + Seek>(&mut self, mut archive: ZipArchive<R>) -> Result<()> {
+        let spans = raw_spans(&mut archive)?;
+        let mut src = archive.into_inner();
+        for span in &spans {
+            self.copy_entry(&mut src, span, None)?;
+        }
+        Ok(())
+    }
+
+    /// 写出中央目录与结束记录（需要时使用 ZIP64）
+    pub fn finish(mut self, comment: &[u8]) -> Result<W> {
+        let comment = &comment[..comment.len().min(u16::MAX as usize)];
+        let mut cd_start = None;
+        // 结束记录所在卷上的中央目录记录数
+        let (mut last_disk, mut here) = (0, 0);
+        let mut start = 0;
+        for i in 0..self.ends.len() {
+            let end = self.ends[i];
+            let (disk, offset) = self.start_record((end - start) as u64)?;
+            cd_start.get_or_insert((disk, offset));
+            if disk != last_disk {
+                (last_disk, here) = (disk, 0);
+            }
+            here += 1;
+            self.out.write_all(&self.central[start..end])?;
+            start = end;
+        }
+        let (cd_disk, cd_offset) = match cd_start {
+            Some(position) => position,
+            None => self.out.position()?,
+        };
+
+        let mut end = EndRecord {
+            cd_disk,
+            cd_offset,
+            cd_size: self.central.len() as u64,
+            entries: self.ends.len() as u64,
+            entries_here: here,
+        };
+        let (disk, offset) = self.start_record(end.encode(0, 0, comment).len() as u64)?;
+        if disk != last_disk {
+            end.entries_here = 0;
+        }
+        self.out.write_all(&end.encode(disk, offset, comment))?;
+        self.out.flush()?;
+        Ok(self.out)
+    }
+}
+
+/// 中央目录结束记录的内容
+pub(crate) struct EndRecord {
+    /// 中央目录起始所在的卷与卷内偏移
+    pub cd_disk: u32,
+    pub cd_offset: u64,
+    pub cd_size: u64,
+    pub entries: u64,
+    /// 结束记录所在卷上的中央目录记录数
+    pub entries_here: u64,
+}
+
+impl EndRecord {
+    /// 位于 `disk` 卷 `offset` 处的结束记录字节（需要时含 ZIP64 结束记录与定位符）
+    pub fn encode(&self, disk: u32, offset: u64, comment: &[u8]) -> Vec<u8> {
+        let zip64 = self.entries >= 0xFFFF || self.cd_size >= 0xFFFF_FFFF || self.cd_offset >= 0xFFFF_FFFF;
+        let mut r = Vec::with_capacity(56 + 20 + 22 + comment.len());
+        if zip64 {
+            r.extend_from_slice(&END64_SIG.to_le_bytes());
+            r.extend_from_slice(&44u64.to_le_bytes());
+            // 创建版本、所需版本 4.5
+            r.extend_from_slice(&45u16.to_le_bytes());
+            r.extend_from_slice(&45u16.to_le_bytes());
+            r.extend_from_slice(&disk.to_le_bytes());
+            r.extend_from_slice(&self.cd_disk.to_le_bytes());
+            r.extend_from_slice(&self.entries_here.to_le_bytes());
+            r.extend_from_slice(&self.entries.to_le_bytes());
+            r.extend_from_slice(&self.cd_size.to_le_bytes());
+            r.extend_from_slice(&self.cd_offset.to_le_bytes());
+            // ZIP64 结束记录定位符
+            r.extend_from_slice(&LOCATOR_SIG.to_le_bytes());
+            r.extend_from_slice(&disk.to_le_bytes());
+            r.extend_from_slice(&offset.to_le_bytes());
+            r.extend_from_slice(&(disk + 1).to_le_bytes());
+        }
+
+        let small = |v: u64, max: u64| if zip64 { max } else { v.min(max) };
+        r.extend_from_slice(&END_SIG.to_le_bytes());
+        r.extend_from_slice(&(disk.min(0xFFFF) as u16).to_le_bytes());
+        r.extend_from_slice(&(self.cd_disk.min(0xFFFF) as u16).to_le_bytes());
+        r.extend_from_slice(&(small(self.entries_here, 0xFFFF) as u16).to_le_bytes());
+        r.extend_from_slice(&(small(self.entries, 0xFFFF) as u16).to_le_bytes());
+        r.extend_from_slice(&(self.cd_size.min(0xFFFF_FFFF) as u32).to_le_bytes());
+        r.extend_from_slice(&(self.cd_offset.min(0xFFFF_FFFF) as u32).to_le_bytes());
+        r.extend_from_slice(&(comment.len() as u16).to_le_bytes());
+        r.extend_from_slice(comment);
+        r
+    }
+}
+
+/// 从分卷 ZIP 读出的结束记录
+pub(crate) struct SplitEnd {
+    /// 卷数
+    pub disks: u32,
+    pub cd_disk: u32,
+    pub cd_offset: u64,
+    pub cd_size: u64,
+    pub entries: u64,
+    pub comment: Vec<u8>,
+}
+
+/// 读取 ZIP 结束记录：`src` 的末尾为最后一卷的结尾；`starts` 为各卷在 `src` 中的起点，
+/// 用于定位 ZIP64 结束记录（仅读取卷数时传入空切片，ZIP64 字段取自定位符）
+pub(crate) fn read_split_end<R: Read + Seek>(src: &mut R, starts: &[u64]) -> Result<SplitEnd> {
+    let invalid = || FastZipError::Other("找不到 ZIP 中央目录结束记录".into());
+    let len = src.seek(SeekFrom::End(0))?;
+    let tail_len = len.min(22 + u16::MAX as u64);
+    src.seek(SeekFrom::Start(len - tail_len))?;
+    let mut tail = vec![0u8; tail_len as usize];
+    src.read_exact(&mut tail)?;
+    let at = (0..tail.len().saturating_sub(21))
+        .rev()
+        .find(|&i| u32_at(&tail, i) == END_SIG && i + 22 + u16_at(&tail, i + 20) as usize == tail.len())
+        .ok_or_else(invalid)?;
+    let eocd = &tail[at..];
+    let mut end = SplitEnd {
+        disks: u16_at(eocd, 4) as u32 + 1,
+        cd_disk: u16_at(eocd, 6) as u32,
+        cd_offset: u32_at(eocd, 16) as u64,
+        cd_size: u32_at(eocd, 12) as u64,
+        entries: u16_at(eocd, 10) as u64,
+        comment: eocd[22..].to_vec(),
+    };
+
+    // ZIP64 定位符紧接在结束记录之前
+    let eocd_pos = len - tail_len + at as u64;
+    if eocd_pos < 20 {
+        return Ok(end);
+    }
+    let mut locator = [0u8; 20];
+    src.seek(SeekFrom::Start(eocd_pos - 20))?;
+    src.read_exact(&mut locator)?;
+    if u32_at(&locator, 0) != LOCATOR_SIG {
+        return Ok(end);
+    }
+    end.disks = u32_at(&locator, 16);
+    let Some(&disk_start) = starts.get(u32_at(&locator, 4) as usize) else {
+        return Ok(end);
+    };
+    let mut eocd64 = [0u8; 56];
+    src.seek(SeekFrom::Start(disk_start + u64_at(&locator, 8)))?;
+    src.read_exact(&mut eocd64)?;
+    if u32_at(&eocd64, 0) != END64_SIG {
+        return Err(invalid());
+    }
+    end.cd_disk = u32_at(&eocd64, 20);
+    end.entries = u64_at(&eocd64, 32);
+    end.cd_size = u64_at(&eocd64, 40);
+    end.cd_offset = u64_at(&eocd64, 48);
+    Ok(end)
+}
+
+/// 把分卷 ZIP 的中央目录改写为单个连续归档的形式：各卷依次拼接为 `src`，`starts` 为各卷起点
+///
+/// 返回（中央目录在拼接流中的起点，改写后的中央目录与结束记录）；
+/// 拼接流截断到该起点再接上返回的字节，即为偏移均相对开头的普通 ZIP。
+pub(crate) fn join_split_central<R: Read + Seek>(src: &mut R, starts: &[u64]) -> Result<(u64, Vec<u8>)> {
+    let end = read_split_end(src, starts)?;
+    let disk_start = |disk: u32| {
+        starts
+            .get(disk as usize)
+            .copied()
+            .ok_or_else(|| FastZipError::Other(format!("ZIP 分卷缺少第 {} 卷", disk + 1)))
+    };
+    let cd_start = disk_start(end.cd_disk)? + end.cd_offset;
+    src.seek(SeekFrom::Start(cd_start))?;
+    let mut cd = vec![0u8; end.cd_size as usize];
+    src.read_exact(&mut cd)?;
+
+    let mut central = Vec::with_capacity(cd.len() + 64);
+    let mut pos = 0;
+    for _ in 0..end.entries {
+        let record = cd.get(pos..pos + 46).ok_or_else(|| FastZipError::Other("ZIP 中央目录不完整".into()))?;
+        let mut fixed: [u8; 46] = record.try_into().expect("长度为 46");
+        if u32_at(&fixed, 0) != CENTRAL_SIG {
+            return Err(FastZipError::Other("ZIP 中央目录记录无效".into()));
+        }
+        let (name_len, extra_len, comment_len) =
+            (u16_at(&fixed, 28) as usize, u16_at(&fixed, 30) as usize, u16_at(&fixed, 32) as usize);
+        let rest = cd
+            .get(pos + 46..pos + 46 + name_len + extra_len + comment_len)
+            .ok_or_else(|| FastZipError::Other("ZIP 中央目录不完整".into()))?;
+        let (name, rest) = rest.split_at(name_len);
+        let (extra, comment) = rest.split_at(extra_len);
+        let mut extra = extra.to_vec();
+
+        let (disk, offset) = local_position(&fixed, &extra);
+        set_offset(&mut fixed, &mut extra, disk_start(disk)? + offset)?;
+        set_u16(&mut fixed, 34, 0);
+        set_u16(&mut fixed, 30, u16::try_from(extra.len()).map_err(|_| extra_too_long())?);
+        central.extend_from_slice(&fixed);
+        central.extend_from_slice(name);
+        central.extend_from_slice(&extra);
+        central.extend_from_slice(comment);
+        pos += 46 + name_len + extra_len + comment_len;
+    }
+
+    let record = EndRecord {
+        cd_disk: 0,
+        cd_offset: cd_start,
+        cd_size: central.len() as u64,
+        entries: end.entries,
+        entries_here: end.entries,
+    };
+    let end_records = record.encode(0, cd_start + central.len() as u64, &end.comment);
+    central.extend_from_slice(&end_records);
+    Ok((cd_start, central))
+}
+
+/// 中央目录记录中本地头所在的（卷号，卷内偏移），字段为全 1 时取自 ZIP64 扩展字段
+fn local_position(fixed: &[u8; 46], extra: &[u8]) -> (u32, u64) {
+    let mut disk = u16_at(fixed, 34) as u32;
+    let mut offset = u32_at(fixed, 42) as u64;
+    if let Some((pos, len)) = find_extra(extra, ZIP64_EXTRA_ID) {
+        let field = &extra[pos + 4..(pos + 4 + len).min(extra.len())];
+        let mut at = [24, 20].iter().filter(|&&i| u32_at(fixed, i) == 0xFFFF_FFFF).count() * 8;
+        if offset == 0xFFFF_FFFF && field.len() >= at + 8 {
+            offset = u64_at(field, at);
+            at += 8;
+        }
+        if disk == 0xFFFF && field.len() >= at + 4 {
+            disk = u32_at(field, at);
+        }
+    }
+    (disk, offset)
+}
+
+/// 改写中央目录记录中的本地头偏移；超过 4 GiB 时写入 ZIP64 扩展字段
+fn set_offset(fixed: &mut [u8; 46], extra: &mut Vec<u8>, offset: u64) -> Result<()> {
+    let in_zip64 = u32_at(fixed, 42) == 0xFFFF_FFFF;
+    if !in_zip64 && offset < 0xFFFF_FFFF {
+        set_u32(fixed, 42, offset as u32);
+        return Ok(());
+    }
+    // ZIP64 扩展字段依次为：原始大小、压缩大小、本地头偏移，各自仅在对应字段为 0xFFFFFFFF 时出现
+    let skip = [24, 20].iter().filter(|&&at| u32_at(fixed, at) == 0xFFFF_FFFF).count() * 8;
+    match find_extra(extra, ZIP64_EXTRA_ID) {
+        Some((pos, len)) if in_zip64 && len >= skip + 8 => {
+            extra[pos + 4 + skip..pos + 4 + skip + 8].copy_from_slice(&offset.to_le_bytes());
+        }
+        Some((pos, len)) => {
+            // 已有 ZIP64 字段但不含偏移：在大小字段之后插入
+            let at = pos + 4 + skip.min(len);
+            extra.splice(at..at, offset.to_le_bytes());
+            set_u16(extra, pos + 2, (len + 8) as u16);
+        }
+        None => {
+            let mut field = Vec::with_capacity(12);
+            field.extend_from_slice(&ZIP64_EXTRA_ID.to_le_bytes());
+            field.extend_from_slice(&8u16.to_le_bytes());
+            field.extend_from_slice(&offset.to_le_bytes());
+            extra.splice(0..0, field);
+        }
+    }
+    set_u32(fixed, 42, 0xFFFF_FFFF);
+    // 所需解压版本至少 4.5
+    if u16_at(fixed, 6) & 0xFF < 45 {
+        set_u16(fixed, 6, 45);
+    }
+    Ok(())
+}
+
+/// 查找扩展字段，返回（起始位置，数据长度）
+fn find_extra(extra: &[u8], id: u16) -> Option<(usize, usize)> {
+    let mut pos = 0;
+    while pos + 4 <= extra.len() {
+        let len = u16_at(extra, pos + 2) as usize;
+        if u16_at(extra, pos) == id {
+            return Some((pos, len));
+        }
+        pos += 4 + len;
+    }
+    None
+}
+
+fn strip_extra(extra: &[u8], id: u16) -> Vec<u8> {
+    let mut out = Vec::with_capacity(extra.len());
+    let mut pos = 0;
+    while pos + 4 <= extra.len() {
+        let end = (pos + 4 + u16_at(extra, pos + 2) as usize).min(extra.len());
+        if u16_at(extra, pos) != id {
+            out.extend_from_slice(&extra[pos..end]);
+        }
+        pos = end;
+    }
+    out
+}
+
+fn copy_exact<R: Read, W: Write>(src: &mut R, dst: &mut W, len: u64) -> Result<()> {
+    let copied = io::copy(&mut src.take(len), dst)?;
+    if copied != len {
+        return Err(FastZipError::Other("ZIP 条目数据不完整".into()));
+    }
+    Ok(())
+}
+
+fn name_len_u16(name: &str) -> Result<u16> {
+    u16::try_from(name.len()).map_err(|_| FastZipError::Other(format!("条目名过长: {}", name)))
+}
+
+fn extra_too_long() -> FastZipError {
+    FastZipError::Other("ZIP 扩展字段过长".into())
+}
+
+fn u16_at(b: &[u8], at: usize) -> u16 {
+    u16::from_le_bytes([b[at], b[at + 1]])
+}
+
+fn u32_at(b: &[u8], at: usize) -> u32 {
+    u32::from_le_bytes([b[at], b[at + 1], b[at + 2], b[at + 3]])
+}
+
+fn u64_at(b: &[u8], at: usize) -> u64 {
+    u64::from_le_bytes(b[at..at + 8].try_into().expect("长度为 8"))
+}
+
+fn set_u16(b: &mut [u8], at: usize, v: u16) {
+    b[at..at + 2].copy_from_slice(&v.to_le_bytes());
+}
+
+fn set_u32(b: &mut [u8], at: usize, v: u32) {
+    b[at..at + 4].copy_from_slice(&v.to_le_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn central_with(sizes_in_zip64: bool, extra: Vec<u8>) -> ([u8; 46], Vec<u8>) {
+        let mut fixed = [0u8; 46];
+        set_u32(&mut fixed, 0, CENTRAL_SIG);
+        set_u16(&mut fixed, 6, 20);
+        if sizes_in_zip64 {
+            set_u32(&mut fixed, 20, 0xFFFF_FFFF);
+            set_u32(&mut fixed, 24, 0xFFFF_FFFF);
+        }
+        (fixed, extra)
+    }
+
+    #[test]
+    fn test_set_offset_small_and_large() {
+        let (mut fixed, mut extra) = central_with(false, Vec::new());
+        set_offset(&mut fixed, &mut extra, 1234).unwrap();
+        assert_eq!(u32_at(&fixed, 42), 1234);
+        assert!(extra.is_empty());
+
+        // 超过 4 GiB：新增仅含偏移的 ZIP64 字段
+        set_offset(&mut fixed, &mut extra, 5 << 30).unwrap();
+        assert_eq!(u32_at(&fixed, 42), 0xFFFF_FFFF);
+        assert_eq!(find_extra(&extra, ZIP64_EXTRA_ID), Some((0, 8)));
+        assert_eq!(u64::from_le_bytes(extra[4..12].try_into().unwrap()), 5 << 30);
+        assert_eq!(u16_at(&fixed, 6), 45);
+
+        // 已在 ZIP64 字段中：原位更新
+        set_offset(&mut fixed, &mut extra, 7).unwrap();
+        assert_eq!(u64::from_le_bytes(extra[4..12].try_into().unwrap()), 7);
+    }
+
+    #[test]
+    fn test_set_offset_after_zip64_sizes() {
+        let mut zip64 = vec![0x01, 0x00, 16, 0];
+        zip64.extend_from_slice(&[0xAA; 16]);
+        let (mut fixed, mut extra) = central_with(true, zip64);
+        set_offset(&mut fixed, &mut extra, 6 << 30).unwrap();
+        assert_eq!(find_extra(&extra, ZIP64_EXTRA_ID), Some((0, 24)));
+        assert_eq!(&extra[4..20], &[0xAA; 16]);
+        assert_eq!(u64::from_le_bytes(extra[20..28].try_into().unwrap()), 6 << 30);
+    }
+
+    #[test]
+    fn test_strip_extra() {
+        let extra = [0x75, 0x70, 1, 0, 9, 0x55, 0x54, 1, 0, 1];
+        assert_eq!(strip_extra(&extra, UNICODE_PATH_ID), vec![0x55, 0x54, 1, 0, 1]);
+    }
+}
@@ -1,18 +1,21 @@
 //! 解压引擎：统一调度各格式解压
 
-use std::path::Path;
+use std::io::Read;
+use std::path::{Path, PathBuf};
 
 use rayon::prelude::*;
 use tracing::{debug, info};
 
-use crate::error::Result;
+use crate::compress::{EntryHash, EntryHashes, HashAlgorithm};
+use crate::error::{FastZipError, Result};
 use crate::formats::{
-    detect_format, extract_single_compressed, ArchiveFormat, SevenZExtractor, TarExtractor,
+    detect_format, extract_single_compressed, logical_path, ArchiveFormat, SevenZExtractor, TarExtractor,
     ZipExtractor,
 };
 #[cfg(feature = "unrar")]
 use crate::formats::RarExtractor;
-use crate::smart_dest::resolve_smart_dest;
+use crate::hook::TaskHook;
+use crate::smart_dest::{extract_staged, resolve_smart_dest};
 
 /// 解压选项
 #[derive(Debug, Clone)]
@@ -25,6 +28,8 @@ pub struct ExtractOptions {
     pub overwrite: bool,
     /// 密码（可选）
     pub password: Option<String>,
+    /// gzip/xz/zstd 流解码线程数（0 = 自动，1 = 单线程）
+    pub threads: usize,
 }
 
 impl Default for ExtractOptions {
@@ -34,12 +39,46 @@ impl Default for ExtractOptions {
             smart: true,
             overwrite: false,
             password: None,
+            threads: 0,
         }
     }
 }
 
 /// 解压单个文件
 pub fn extract_one(archive_path: &Path, options: &ExtractOptions) -> Result<std::path::PathBuf> {
+    extract_impl(archive_path, options, None, &TaskHook::default())
+}
+
+/// 同 [`extract_one`]，解压 ZIP、7z 时每个条目、每块数据调用 `hook`
+#[cfg_attr(not(feature = "async"), allow(dead_code))]
+pub(crate) fn extract_hooked(archive_path: &Path, options: &ExtractOptions, hook: &TaskHook) -> Result<PathBuf> {
+    extract_impl(archive_path, options, None, hook)
+}
+
+/// 解压单个归档，同时计算每个文件条目的哈希，按路径排序
+///
+/// 摘要在写出条目时计算，结果与对同一归档调用 [`crate::hash_entries`] 一致，无需再读一遍归档。
+/// 只支持可逐条目读取的归档（ZIP、7z、tar.*，启用 unrar 时含 RAR）。
+pub fn extract_with_hashes(
+    archive_path: &Path,
+    options: &ExtractOptions,
+    algorithm: HashAlgorithm,
+) -> Result<(PathBuf, Vec<EntryHash>)> {
+    let format = detect_format(archive_path)?;
+    if !format.is_archive() {
+        return Err(FastZipError::UnsupportedFormat(format!("{:?} 不支持逐条目读取", format)));
+    }
+    let mut hashes = EntryHashes::new(algorithm);
+    let dest_dir = extract_impl(archive_path, options, Some(&mut hashes), &TaskHook::default())?;
+    Ok((dest_dir, hashes.into_vec()))
+}
+
+fn extract_impl(
+    archive_path: &Path,
+    options: &ExtractOptions,
+    hashes: Option<&mut EntryHashes>,
+    hook: &TaskHook,
+) -> Result<PathBuf> {
     info!(path = %archive_path.display(), "开始解压");
     let format = detect_format(archive_path)?;
     debug!(format = ?format, "格式已检测");
@@ -51,24 +90,19 @@ pub fn extract_one(archive_path: &Path, options: &ExtractOptions) -> Result<std:
 
     std::fs::create_dir_all(&base_dir)?;
 
+    // tar.* 列出条目需完整解压一遍：改为解压到临时目录后再决定放置位置，只解压一次
+    if options.smart && format.is_tar() {
+        let dest_dir = extract_staged(archive_path, &base_dir, |staging| {
+            TarExtractor::extract_hashed(archive_path, staging, format, options.threads, hashes)
+        })?;
+        info!(path = %archive_path.display(), dest = %dest_dir.display(), "解压完成");
+        return Ok(dest_dir);
+    }
+
     let dest_dir = if options.smart {
         resolve_smart_dest(archive_path, &base_dir, format)?
     } else {
-        // flat 模式：解压到 base_dir，对于多文件归档使用 archive_stem 子目录
-        if format.is_archive() {
-            let stem = archive_path
-                .file_stem()
-                .and_then(|s| s.to_str())
-                .unwrap_or("extracted");
-            let stem = if stem.ends_with(".tar") {
-                stem.strip_suffix(".tar").unwrap_or(stem)
-            } else {
-                stem
-            };
-            base_dir.join(stem)
-        } else {
-            base_dir.clone()
-        }
+        flat_dest(archive_path, &base_dir, format)
     };
 
     std::fs::create_dir_all(&dest_dir)?;
@@ -78,20 +112,20 @@ pub fn extract_one(archive_path: &Path, options: &ExtractOptions) -> Result<std:
 
     match format {
         ArchiveFormat::Zip => {
-            ZipExtractor::extract(archive_path, &dest_dir, password)?;
+            ZipExtractor::extract_hashed(archive_path, &dest_dir, password, hashes, hook)?;
         }
         ArchiveFormat::SevenZ => {
-            SevenZExtractor::extract(archive_path, &dest_dir, password)?;
+            SevenZExtractor::extract_hashed(archive_path, &dest_dir, password, hashes, hook)?;
         }
         #[cfg(feature = "unrar")]
         ArchiveFormat::Rar => {
-            RarExtractor::extract(archive_path, &dest_dir, password)?;
+            RarExtractor::extract_hashed(archive_path, &dest_dir, password, hashes)?;
         }
         ArchiveFormat::Tar | ArchiveFormat::TarGz | ArchiveFormat::TarXz | ArchiveFormat::TarBz2 | ArchiveFormat::TarZst => {
-            TarExtractor::extract(archive_path, &dest_dir, format)?;
+            TarExtractor::extract_hashed(archive_path, &dest_dir, format, options.threads, hashes)?;
         }
         ArchiveFormat::Gz | ArchiveFormat::Xz | ArchiveFormat::Bz2 | ArchiveFormat::Zst => {
-            extract_single_compressed(archive_path, &dest_dir, format, options.overwrite)?;
+            extract_single_compressed(archive_path, &dest_dir, format, options.overwrite, options.threads)?;
         }
         #[cfg(not(feature = "unrar"))]
         ArchiveFormat::Rar => {
@@ -105,6 +139,38 @@ pub fn extract_one(archive_path: &Path, options: &ExtractOptions) -> Result<std:
     Ok(dest_dir)
 }
 
+/// 从已解码的 tar 流解压，放置规则同 [`extract_one`]；`archive_path` 只用于决定目标目录名
+///
+/// 用于输入不是本地文件的场景（如异步接口），`options.dest` 须已指定。
+#[cfg_attr(not(feature = "async"), allow(dead_code))]
+pub(crate) fn extract_tar_stream(
+    reader: impl Read,
+    archive_path: &Path,
+    options: &ExtractOptions,
+    on_entry: &mut dyn FnMut(&str) -> Result<()>,
+) -> Result<PathBuf> {
+    let base_dir = options.dest.clone().ok_or_else(|| FastZipError::Other("未指定目标目录".into()))?;
+    std::fs::create_dir_all(&base_dir)?;
+    if options.smart {
+        return extract_staged(archive_path, &base_dir, |staging| {
+            TarExtractor::unpack(reader, staging, on_entry, None)
+        });
+    }
+    let dest_dir = flat_dest(archive_path, &base_dir, ArchiveFormat::Tar);
+    TarExtractor::unpack(reader, &dest_dir, on_entry, None)?;
+    Ok(dest_dir)
+}
+
+/// flat 模式的目标目录：多文件归档使用 archive_stem 子目录，单文件压缩直接解压到 base_dir
+fn flat_dest(archive_path: &Path, base_dir: &Path, format: ArchiveFormat) -> PathBuf {
+    if !format.is_archive() {
+        return base_dir.to_path_buf();
+    }
+    let logical = logical_path(archive_path);
+    let stem = logical.file_stem().and_then(|s| s.to_str()).unwrap_or("extracted");
+    base_dir.join(stem.strip_suffix(".tar").unwrap_or(stem))
+}
+
 /// 并行解压多个文件
 pub fn extract_many<P: AsRef<Path> + Sync>(
     archive_paths: &[P],
@@ -0,0 +1,1326 @@
+//! 压缩流编解码（gzip / xz / bzip2 / zstd）
+//!
+//! 可切分为独立成员的流（BGZF 块、带大小信息的 xz 块、zstd 帧）按批并行解码，
+//! 其余情况退回到顺序解码器。顺序解码器均支持多成员/多流拼接。
+//! 编码端（[`Encoder`]）产出的 xz 与 zstd 均分块/分帧，解压时可并行、可随机访问。
+
+use std::collections::VecDeque;
+use std::io::{self, BufRead, BufReader, Chain, Cursor, Read, Seek, SeekFrom, Take, Write};
+
+use bzip2::read::MultiBzDecoder;
+use bzip2::write::BzEncoder;
+use flate2::read::{GzDecoder, MultiGzDecoder};
+use flate2::write::GzEncoder;
+use rayon::prelude::*;
+use xz2::read::XzDecoder;
+use xz2::stream::{Check, MtStreamBuilder};
+use xz2::write::XzEncoder;
+
+use crate::error::{FastZipError, Result};
+
+/// 单个成员的压缩数据或声明的解压后大小超过该值时不再缓冲，改为流式解码
+const MEMBER_LIMIT: usize = 64 * 1024 * 1024;
+
+/// 每批成员声明的解压后大小之和达到该值时停止切分，限制并行解码占用的内存
+const BATCH_LIMIT: u64 = 256 * 1024 * 1024;
+
+/// zstd 编码时每帧的解压前大小
+const ZSTD_FRAME_SIZE: usize = 4 * 1024 * 1024;
+
+/// BGZF 每块的解压前大小（与 samtools/htslib 相同）
+const BGZF_BLOCK_SIZE: usize = 0xff00;
+
+/// 流压缩编码
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Codec {
+    Gzip,
+    Xz,
+    Bzip2,
+    Zstd,
+}
+
+impl Codec {
+    /// 单文件压缩的扩展名
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Gzip => "gz",
+            Self::Xz => "xz",
+            Self::Bzip2 => "bz2",
+            Self::Zstd => "zst",
+        }
+    }
+
+    /// 按名称或扩展名解析（`gz`/`gzip`、`xz`、`bz2`/`bzip2`、`zst`/`zstd`）
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim_start_matches('.').to_lowercase().as_str() {
+            "gz" | "gzip" => Some(Self::Gzip),
+            "xz" => Some(Self::Xz),
+            "bz2" | "bzip2" => Some(Self::Bzip2),
+            "zst" | "zstd" => Some(Self::Zstd),
+            _ => None,
+        }
+    }
+
+    /// 默认压缩级别
+    pub fn default_level(self) -> u32 {
+        match self {
+            Self::Gzip => 6,
+            Self::Xz => 6,
+            Self::Bzip2 => 9,
+            Self::Zstd => 3,
+        }
+    }
+
+    /// 压缩级别范围（含两端）
+    pub fn level_range(self) -> (u32, u32) {
+        match self {
+            Self::Gzip | Self::Xz => (0, 9),
+            Self::Bzip2 => (1, 9),
+            Self::Zstd => (1, 22),
+        }
+    }
+}
+
+/// 打开解码器
+///
+/// `threads`：0 为自动（使用全局线程池），1 为单线程顺序解码，其他值使用独立线程池
+pub fn open_decoder<R: Read + Send + 'static>(
+    reader: R,
+    codec: Codec,
+    threads: usize,
+) -> Result<Box<dyn Read + Send>> {
+    let reader = BufReader::with_capacity(1024 * 1024, reader);
+    if threads == 1 || codec == Codec::Bzip2 {
+        return Ok(sequential_decoder(codec, Box::new(reader))?);
+    }
+
+    let splitter: Box<dyn Splitter> = match codec {
+        Codec::Gzip => Box::new(BgzfSplitter),
+        Codec::Xz => Box::new(XzSplitter::default()),
+        Codec::Zstd => Box::new(ZstdSplitter),
+        Codec::Bzip2 => unreachable!(),
+    };
+    let pool = if threads == 0 {
+        None
+    } else {
+        Some(
+            rayon::ThreadPoolBuilder::new()
+                .num_threads(threads)
+                .build()
+                .map_err(|e| FastZipError::Other(format!("创建解码线程池失败: {}", e)))?,
+        )
+    };
+    let batch = pool
+        .as_ref()
+        .map_or_else(rayon::current_num_threads, |p| p.current_num_threads())
+        * 2;
+
+    Ok(Box::new(ParallelDecoder {
+        codec,
+        feeder: Feeder {
+            input: Some(Box::new(reader)),
+            splitter,
+            pending_fallback: None,
+            pending_large: None,
+            done: false,
+        },
+        pool,
+        batch,
+        queued: Vec::new(),
+        out: VecDeque::new(),
+        pos: 0,
+        large: None,
+        fallback: None,
+    }))
+}
+
+/// 顺序解码器（支持拼接的多成员/多流）
+fn sequential_decoder(codec: Codec, reader: Box<dyn Read + Send>) -> io::Result<Box<dyn Read + Send>> {
+    Ok(match codec {
+        Codec::Gzip => Box::new(MultiGzDecoder::new(reader)),
+        Codec::Xz => Box::new(XzDecoder::new_multi_decoder(reader)),
+        Codec::Bzip2 => Box::new(MultiBzDecoder::new(reader)),
+        Codec::Zstd => Box::new(zstd::Decoder::new(reader)?),
+    })
+}
+
+/// 解码一个独立成员，输出不超过声明的解压后大小 `size`
+fn decode_member(codec: Codec, member: &[u8], size: u64) -> io::Result<Vec<u8>> {
+    let decoder: Box<dyn Read + '_> = match codec {
+        Codec::Gzip => Box::new(GzDecoder::new(member)),
+        Codec::Xz => Box::new(XzDecoder::new(member)),
+        Codec::Zstd => Box::new(zstd::Decoder::new(member)?),
+        Codec::Bzip2 => Box::new(MultiBzDecoder::new(member)),
+    };
+    // 多读一个字节以发现超出声明大小的成员
+    let mut out = Vec::with_capacity(size as usize);
+    decoder.take(size + 1).read_to_end(&mut out)?;
+    if out.len() as u64 != size {
+        return Err(invalid("成员解压后大小与声明不符"));
+    }
+    Ok(out)
+}
+
+/// 切分结果
+enum Split {
+    /// 可独立解码的完整成员及其声明的解压后大小
+    Member(Vec<u8>, u64),
+    /// 需要流式解码的 xz 块，解完后继续切分
+    Large(LargeBlock),
+    /// 无法继续切分：已读取的字节（从可重新开始解码的位置起）与剩余输入交给顺序解码器
+    Fallback(Vec<u8>),
+    End,
+}
+
+/// 流式解码的 xz 块
+enum LargeBlock {
+    /// 过大的块：`head` + 输入中接下来的 `body` 字节 + `tail`
+    Sized { head: Vec<u8>, body: u64, tail: Vec<u8> },
+    /// 块头不带大小：`head` 之后边读边解析 LZMA2 数据以确定块的结尾
+    Unsized { head: Vec<u8>, header_len: u64 },
+}
+
+/// 从输入中依次切出可独立解码的成员
+trait Splitter: Send {
+    fn next_member(&mut self, input: &mut dyn BufRead) -> io::Result<Split>;
+}
+
+/// 输入端：持有原始流并按批切分成员
+struct Feeder {
+    input: Option<Box<dyn BufRead + Send>>,
+    splitter: Box<dyn Splitter>,
+    pending_fallback: Option<Vec<u8>>,
+    pending_large: Option<LargeBlock>,
+    done: bool,
+}
+
+impl Feeder {
+    /// 切出至多 `n` 个成员，声明的解压后大小之和不超过 [`BATCH_LIMIT`]（至少一个）
+    fn collect(&mut self, n: usize) -> io::Result<Vec<(Vec<u8>, u64)>> {
+        let mut members = Vec::new();
+        let Some(input) = self.input.as_mut() else {
+            return Ok(members);
+        };
+        let mut total = 0;
+        while !self.done && self.pending_large.is_none() && members.len() < n && total < BATCH_LIMIT {
+            match self.splitter.next_member(input.as_mut())? {
+                Split::Member(m, size) => {
+                    total += size;
+                    members.push((m, size));
+                }
+                Split::Large(block) => self.pending_large = Some(block),
+                Split::Fallback(buf) => {
+                    self.pending_fallback = Some(buf);
+                    self.done = true;
+                }
+                Split::End => self.done = true,
+            }
+        }
+        Ok(members)
+    }
+}
+
+/// 缓冲的头部 + 原始输入中的一段 + 补出的尾部
+type SizedInput = Chain<Chain<Cursor<Vec<u8>>, Take<Box<dyn BufRead + Send>>>, Cursor<Vec<u8>>>;
+
+/// 流式解码的 xz 块的输入
+enum LargeInput {
+    Sized(SizedInput),
+    Unsized(UnsizedBlock),
+}
+
+impl LargeInput {
+    /// 块已读完：取回原始输入
+    fn into_input(self) -> io::Result<Box<dyn BufRead + Send>> {
+        let finished = match &self {
+            LargeInput::Sized(chain) => chain.get_ref().0.get_ref().1.limit() == 0,
+            LargeInput::Unsized(block) => {
+                block.done && block.pending.position() == block.pending.get_ref().len() as u64
+            }
+        };
+        if !finished {
+            return Err(invalid("xz 块长度与块头不符"));
+        }
+        Ok(match self {
+            LargeInput::Sized(chain) => chain.into_inner().0.into_inner().1.into_inner(),
+            LargeInput::Unsized(block) => block.input,
+        })
+    }
+}
+
+impl Read for LargeInput {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            LargeInput::Sized(chain) => chain.read(buf),
+            LargeInput::Unsized(block) => block.read(buf),
+        }
+    }
+}
+
+/// 块头不带大小的 xz 块
+///
+/// 按 LZMA2 数据块头逐块转发输入并累计压缩与解压后大小，遇到结束标记后读入填充与校验值，
+/// 再补出索引与流尾，使其成为可独立解码的单块流。
+struct UnsizedBlock {
+    input: Box<dyn BufRead + Send>,
+    /// 待转发的字节
+    pending: Cursor<Vec<u8>>,
+    stream_flags: [u8; 2],
+    header_len: u64,
+    compressed: u64,
+    uncompressed: u64,
+    done: bool,
+}
+
+impl UnsizedBlock {
+    fn new(input: Box<dyn BufRead + Send>, head: Vec<u8>, header_len: u64) -> Self {
+        let stream_flags = [head[6], head[7]];
+        Self {
+            input,
+            pending: Cursor::new(head),
+            stream_flags,
+            header_len,
+            compressed: 0,
+            uncompressed: 0,
+            done: false,
+        }
+    }
+
+    /// 读入下一个 LZMA2 数据块
+    fn next_chunk(&mut self) -> io::Result<()> {
+        let mut buf = vec![0u8];
+        self.input.read_exact(&mut buf)?;
+        let control = buf[0];
+        let (data, uncompressed) = match control {
+            0x00 => {
+                self.compressed += 1;
+                let check_len = xz_check_len(self.stream_flags[1] & 0x0F) as u64;
+                let pad = (4 - self.compressed % 4) % 4;
+                read_append(self.input.as_mut(), &mut buf, (pad + check_len) as usize)?;
+                let unpadded = self.header_len + self.compressed + check_len;
+                append_xz_tail(&mut buf, &self.stream_flags, unpadded, self.uncompressed);
+                self.pending = Cursor::new(buf);
+                self.done = true;
+                return Ok(());
+            }
+            // 未压缩块：2 字节大小
+            0x01 | 0x02 => {
+                read_append(self.input.as_mut(), &mut buf, 2)?;
+                let size = u16::from_be_bytes([buf[1], buf[2]]) as u64 + 1;
+                (size, size)
+            }
+            // LZMA 块：解压后大小的低 16 位、压缩后大小，重置状态时另带 1 字节属性
+            0x80.. => {
+                read_append(self.input.as_mut(), &mut buf, if control >= 0xC0 { 5 } else { 4 })?;
+                let low = u16::from_be_bytes([buf[1], buf[2]]);
+                let uncompressed = (u64::from(control & 0x1F) << 16 | u64::from(low)) + 1;
+                (u16::from_be_bytes([buf[3], buf[4]]) as u64 + 1, uncompressed)
+            }
+            _ => return Err(invalid("无效的 LZMA2 数据块")),
+        };
+        read_append(self.input.as_mut(), &mut buf, data as usize)?;
+        self.compressed += buf.len() as u64;
+        self.uncompressed += uncompressed;
+        self.pending = Cursor::new(buf);
+        Ok(())
+    }
+}
+
+impl Read for UnsizedBlock {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            let n = self.pending.read(buf)?;
+            if n > 0 || buf.is_empty() || self.done {
+                return Ok(n);
+            }
+            self.next_chunk()?;
+        }
+    }
+}
+
+/// 并行解码器：切分与解码流水线进行，输出保持原始顺序
+struct ParallelDecoder {
+    codec: Codec,
+    feeder: Feeder,
+    pool: Option<rayon::ThreadPool>,
+    batch: usize,
+    queued: Vec<(Vec<u8>, u64)>,
+    out: VecDeque<Vec<u8>>,
+    pos: usize,
+    /// 正在流式解码的 xz 块，解完后把输入交还给切分端
+    large: Option<XzDecoder<LargeInput>>,
+    fallback: Option<Box<dyn Read + Send>>,
+}
+
+impl ParallelDecoder {
+    /// 解码当前批次，同时切分下一批
+    fn decode_batch(&mut self) -> io::Result<()> {
+        let batch = std::mem::take(&mut self.queued);
+        let codec = self.codec;
+        let n = self.batch;
+        let feeder = &mut self.feeder;
+        let work = move || {
+            rayon::join(
+                || {
+                    batch
+                        .into_par_iter()
+                        .map(|(m, size)| decode_member(codec, &m, size))
+                        .collect::<io::Result<Vec<_>>>()
+                },
+                || feeder.collect(n),
+            )
+        };
+        let (decoded, next) = match &self.pool {
+            Some(pool) => pool.install(work),
+            None => work(),
+        };
+        self.out.extend(decoded?);
+        self.queued = next?;
+        Ok(())
+    }
+
+    /// 切换到顺序解码：已缓冲字节 + 剩余输入
+    fn start_fallback(&mut self, buf: Vec<u8>) -> io::Result<()> {
+        let rest: Box<dyn Read + Send> = match self.feeder.input.take() {
+            Some(input) => Box::new(Cursor::new(buf).chain(input)),
+            None => Box::new(Cursor::new(buf)),
+        };
+        self.fallback = Some(sequential_decoder(self.codec, rest)?);
+        Ok(())
+    }
+
+    /// 开始流式解码 xz 块：暂时从切分端取走输入
+    fn start_large(&mut self, block: LargeBlock) -> io::Result<()> {
+        let input = self.feeder.input.take().ok_or_else(|| invalid("输入已结束"))?;
+        let member = match block {
+            LargeBlock::Sized { head, body, tail } => {
+                LargeInput::Sized(Cursor::new(head).chain(input.take(body)).chain(Cursor::new(tail)))
+            }
+            LargeBlock::Unsized { head, header_len } => LargeInput::Unsized(UnsizedBlock::new(input, head, header_len)),
+        };
+        self.large = Some(XzDecoder::new(member));
+        Ok(())
+    }
+
+    /// xz 块解完：把剩余输入交还给切分端
+    fn finish_large(&mut self, decoder: XzDecoder<LargeInput>) -> io::Result<()> {
+        self.feeder.input = Some(decoder.into_inner().into_input()?);
+        Ok(())
+    }
+}
+
+impl Read for ParallelDecoder {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        loop {
+            if let Some(front) = self.out.front() {
+                if self.pos < front.len() {
+                    let n = (front.len() - self.pos).min(buf.len());
+                    buf[..n].copy_from_slice(&front[self.pos..self.pos + n]);
+                    self.pos += n;
+                    return Ok(n);
+                }
+                self.out.pop_front();
+                self.pos = 0;
+                continue;
+            }
+            if let Some(fallback) = self.fallback.as_mut() {
+                return fallback.read(buf);
+            }
+            if let Some(large) = self.large.as_mut() {
+                let n = large.read(buf)?;
+                if n > 0 {
+                    return Ok(n);
+                }
+                let large = self.large.take().expect("上面已检查");
+                self.finish_large(large)?;
+                continue;
+            }
+            if !self.queued.is_empty() {
+                self.decode_batch()?;
+                continue;
+            }
+            if let Some(pending) = self.feeder.pending_fallback.take() {
+                self.start_fallback(pending)?;
+                continue;
+            }
+            if let Some(block) = self.feeder.pending_large.take() {
+                self.start_large(block)?;
+                continue;
+            }
+            if self.feeder.done {
+                return Ok(0);
+            }
+            self.queued = self.feeder.collect(self.batch)?;
+        }
+    }
+}
+
+pub(crate) fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.to_string())
+}
+
+/// 读满 `buf`；若在读取任何字节前遇到 EOF 则返回 false
+fn read_exact_or_eof(input: &mut dyn BufRead, buf: &mut [u8]) -> io::Result<bool> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match input.read(&mut buf[filled..]) {
+            Ok(0) if filled == 0 => return Ok(false),
+            Ok(0) => return Err(io::ErrorKind::UnexpectedEof.into()),
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(true)
+}
+
+/// 追加读取 `n` 字节到 `buf` 末尾
+fn read_append(input: &mut dyn BufRead, buf: &mut Vec<u8>, n: usize) -> io::Result<()> {
+    let start = buf.len();
+    buf.resize(start + n, 0);
+    input.read_exact(&mut buf[start..])
+}
+
+/// BGZF（每个 gzip 成员在 `BC` 扩展字段中记录自身大小）
+struct BgzfSplitter;
+
+impl Splitter for BgzfSplitter {
+    fn next_member(&mut self, input: &mut dyn BufRead) -> io::Result<Split> {
+        let mut buf = vec![0u8; 10];
+        if !read_exact_or_eof(input, &mut buf)? {
+            return Ok(Split::End);
+        }
+        if buf[0] != 0x1f || buf[1] != 0x8b || buf[3] & 0x04 == 0 {
+            return Ok(Split::Fallback(buf));
+        }
+        read_append(input, &mut buf, 2)?;
+        let xlen = u16::from_le_bytes([buf[10], buf[11]]) as usize;
+        read_append(input, &mut buf, xlen)?;
+
+        let Some(total) = bgzf_block_size(&buf[12..]) else {
+            return Ok(Split::Fallback(buf));
+        };
+        if total < buf.len() {
+            return Err(invalid("BGZF 块大小无效"));
+        }
+        let remaining = total - buf.len();
+        read_append(input, &mut buf, remaining)?;
+        // 成员末尾的 ISIZE 为解压后大小；BGZF 块不超过 64 KiB，过大时按普通 gzip 顺序解码
+        let isize = u32::from_le_bytes(buf[buf.len() - 4..].try_into().unwrap());
+        if isize as usize > MEMBER_LIMIT {
+            return Ok(Split::Fallback(buf));
+        }
+        Ok(Split::Member(buf, u64::from(isize)))
+    }
+}
+
+/// 从 gzip 扩展字段中取出 BGZF `BC` 子字段记录的成员总大小
+fn bgzf_block_size(mut extra: &[u8]) -> Option<usize> {
+    while extra.len() >= 4 {
+        let slen = u16::from_le_bytes([extra[2], extra[3]]) as usize;
+        if extra[0] == b'B' && extra[1] == b'C' && slen == 2 && extra.len() >= 6 {
+            return Some(u16::from_le_bytes([extra[4], extra[5]]) as usize + 1);
+        }
+        extra = &extra[(4 + slen).min(extra.len())..];
+    }
+    None
+}
+
+/// zstd 帧（帧尾位置由块头推算，无需解码）
+///
+/// 只切分帧头声明了解压后大小（Frame_Content_Size）且不超过 [`MEMBER_LIMIT`] 的帧。
+struct ZstdSplitter;
+
+const ZSTD_MAGIC: u32 = 0xFD2F_B528;
+
+impl Splitter for ZstdSplitter {
+    fn next_member(&mut self, input: &mut dyn BufRead) -> io::Result<Split> {
+        loop {
+            let mut buf = vec![0u8; 4];
+            if !read_exact_or_eof(input, &mut buf)? {
+                return Ok(Split::End);
+            }
+            let magic = u32::from_le_bytes([buf[0], buf[1], buf[2], buf[3]]);
+            if magic & 0xFFFF_FFF0 == 0x184D_2A50 {
+                // 可跳过帧：不含数据
+                let mut size = [0u8; 4];
+                input.read_exact(&mut size)?;
+                let size = u32::from_le_bytes(size) as u64;
+                io::copy(&mut input.take(size), &mut io::sink())?;
+                continue;
+            }
+            if magic != ZSTD_MAGIC {
+                return Err(invalid("无效的 zstd 帧"));
+            }
+
+            read_append(input, &mut buf, 1)?;
+            let fhd = buf[4];
+            let single_segment = fhd & 0x20 != 0;
+            let has_checksum = fhd & 0x04 != 0;
+            let dict_len = [0, 1, 2, 4][(fhd & 0x03) as usize];
+            let fcs_len = match fhd >> 6 {
+                0 if single_segment => 1,
+                0 => 0,
+                1 => 2,
+                2 => 4,
+                _ => 8,
+            };
+            let window_len = usize::from(!single_segment);
+            read_append(input, &mut buf, window_len + dict_len + fcs_len)?;
+            let fcs = &buf[5 + window_len + dict_len..];
+            let size = match fcs_len {
+                1 => u64::from(fcs[0]),
+                2 => u64::from(u16::from_le_bytes([fcs[0], fcs[1]])) + 256,
+                4 => u64::from(u32::from_le_bytes(fcs.try_into().unwrap())),
+                8 => u64::from_le_bytes(fcs.try_into().unwrap()),
+                _ => u64::MAX,
+            };
+            if size > MEMBER_LIMIT as u64 {
+                return Ok(Split::Fallback(buf));
+            }
+
+            loop {
+                if buf.len() > MEMBER_LIMIT {
+                    return Ok(Split::Fallback(buf));
+                }
+                let start = buf.len();
+                read_append(input, &mut buf, 3)?;
+                let header = u32::from_le_bytes([buf[start], buf[start + 1], buf[start + 2], 0]);
+                let last = header & 1 != 0;
+                let size = match (header >> 1) & 0x03 {
+                    0 | 2 => (header >> 3) as usize,
+                    1 => 1,
+                    _ => return Err(invalid("zstd 块类型无效")),
+                };
+                read_append(input, &mut buf, size)?;
+                if last {
+                    break;
+                }
+            }
+            if has_checksum {
+                read_append(input, &mut buf, 4)?;
+            }
+            return Ok(Split::Member(buf, size));
+        }
+    }
+}
+
+/// xz 块（多线程 xz 编码器会在块头中写入压缩/未压缩大小）
+///
+/// 每个块被重新包装为只含一个块的完整 xz 流，以便独立解码并校验；
+/// 压缩或解压后大小超过 [`MEMBER_LIMIT`] 的块，以及流中间不带大小的块不缓冲，交给解码端流式解码。
+#[derive(Default)]
+struct XzSplitter {
+    /// 当前流的流头（12 字节）；None 表示期待新的流头
+    header: Option<[u8; 12]>,
+    /// 当前流已切出的块数
+    blocks: usize,
+}
+
+const XZ_MAGIC: &[u8; 6] = b"\xfd7zXZ\x00";
+
+impl Splitter for XzSplitter {
+    fn next_member(&mut self, input: &mut dyn BufRead) -> io::Result<Split> {
+        loop {
+            let Some(header) = self.header else {
+                // 跳过流之间的填充（4 字节对齐的 0）
+                let mut head = [0u8; 12];
+                loop {
+                    if !read_exact_or_eof(input, &mut head[..4])? {
+                        return Ok(Split::End);
+                    }
+                    if head[..4] != [0, 0, 0, 0] {
+                        break;
+                    }
+                }
+                input.read_exact(&mut head[4..])?;
+                if &head[..6] != XZ_MAGIC {
+                    return Err(invalid("无效的 xz 流头"));
+                }
+                self.header = Some(head);
+                self.blocks = 0;
+                continue;
+            };
+
+            let mut first = [0u8; 1];
+            input.read_exact(&mut first)?;
+            if first[0] == 0 {
+                // 索引：跳过索引与流尾，等待下一个流
+                skip_xz_index(input)?;
+                self.header = None;
+                continue;
+            }
+
+            let header_len = (first[0] as usize + 1) * 4;
+            let mut block = Vec::with_capacity(header_len);
+            block.push(first[0]);
+            read_append(input, &mut block, header_len - 1)?;
+
+            let flags = block[1];
+            let mut fields = &block[2..];
+            let compressed = if flags & 0x40 != 0 { Some(read_varint(&mut fields)?) } else { None };
+            let uncompressed = if flags & 0x80 != 0 { Some(read_varint(&mut fields)?) } else { None };
+            let (Some(compressed), Some(uncompressed)) = (compressed, uncompressed) else {
+                if self.blocks == 0 {
+                    // 单线程 xz 的块不带大小：从流头开始顺序解码
+                    let mut buf = header.to_vec();
+                    buf.extend_from_slice(&block);
+                    return Ok(Split::Fallback(buf));
+                }
+                // 流中间的块不带大小：流式解码该块，之后继续切分
+                self.blocks += 1;
+                let mut head = header.to_vec();
+                head.extend_from_slice(&block);
+                return Ok(Split::Large(LargeBlock::Unsized { head, header_len: header_len as u64 }));
+            };
+
+            let check_len = xz_check_len(header[7] & 0x0F);
+            let padded = compressed.div_ceil(4) * 4 + check_len as u64;
+            let unpadded = header_len as u64 + compressed + check_len as u64;
+            self.blocks += 1;
+
+            let mut member = header.to_vec();
+            member.extend_from_slice(&block);
+            if compressed > MEMBER_LIMIT as u64 || uncompressed > MEMBER_LIMIT as u64 {
+                let mut tail = Vec::with_capacity(48);
+                append_xz_tail(&mut tail, &header[6..8], unpadded, uncompressed);
+                return Ok(Split::Large(LargeBlock::Sized { head: member, body: padded, tail }));
+            }
+            read_append(input, &mut member, padded as usize)?;
+            append_xz_tail(&mut member, &header[6..8], unpadded, uncompressed);
+            return Ok(Split::Member(member, uncompressed));
+        }
+    }
+}
+
+fn xz_check_len(check: u8) -> usize {
+    match check {
+        0 => 0,
+        1..=3 => 4,
+        4..=6 => 8,
+        7..=9 => 16,
+        10..=12 => 32,
+        _ => 64,
+    }
+}
+
+/// 读取 xz 变长整数
+pub(crate) fn read_varint(buf: &mut &[u8]) -> io::Result<u64> {
+    let mut value = 0u64;
+    for i in 0..9 {
+        let Some((&b, rest)) = buf.split_first() else {
+            break;
+        };
+        *buf = rest;
+        value |= u64::from(b & 0x7F) << (7 * i);
+        if b & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(invalid("xz 变长整数无效"))
+}
+
+fn read_varint_from(input: &mut dyn BufRead, raw: &mut Vec<u8>) -> io::Result<u64> {
+    let mut value = 0u64;
+    for i in 0..9 {
+        let mut b = [0u8; 1];
+        input.read_exact(&mut b)?;
+        raw.push(b[0]);
+        value |= u64::from(b[0] & 0x7F) << (7 * i);
+        if b[0] & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(invalid("xz 变长整数无效"))
+}
+
+fn write_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+/// 跳过索引（指示字节已读取）与随后的流尾
+fn skip_xz_index(input: &mut dyn BufRead) -> io::Result<()> {
+    let mut raw = vec![0u8];
+    let records = read_varint_from(input, &mut raw)?;
+    for _ in 0..records * 2 {
+        read_varint_from(input, &mut raw)?;
+    }
+    let pad = (4 - raw.len() % 4) % 4;
+    // 填充 + CRC32 + 流尾
+    io::copy(&mut input.take((pad + 4 + 12) as u64), &mut io::sink())?;
+    Ok(())
+}
+
+/// 为单块流追加索引与流尾
+pub(crate) fn append_xz_tail(out: &mut Vec<u8>, stream_flags: &[u8], unpadded: u64, uncompressed: u64) {
+    let mut index = vec![0u8];
+    write_varint(&mut index, 1);
+    write_varint(&mut index, unpadded);
+    write_varint(&mut index, uncompressed);
+    while index.len() % 4 != 0 {
+        index.push(0);
+    }
+    let crc = crc32fast::hash(&index);
+    index.extend_from_slice(&crc.to_le_bytes());
+
+    let backward = (index.len() / 4 - 1) as u32;
+    let mut footer = Vec::with_capacity(12);
+    footer.extend_from_slice(&backward.to_le_bytes());
+    footer.extend_from_slice(stream_flags);
+    let crc = crc32fast::hash(&footer);
+
+    out.extend_from_slice(&index);
+    out.extend_from_slice(&crc.to_le_bytes());
+    out.extend_from_slice(&footer);
+    out.extend_from_slice(b"YZ");
+}
+
+/// zstd 可寻址格式（seekable format）写入器
+///
+/// 每 `frame_size` 字节输入独立压缩为一帧，结束时追加 seek table 可跳过帧，
+/// 读取方可从任意帧起点开始解码。
+pub struct SeekableZstdWriter<W: Write> {
+    inner: W,
+    level: i32,
+    frame_size: usize,
+    buf: Vec<u8>,
+    frames: Vec<(u32, u32)>,
+}
+
+/// seek table 可跳过帧的魔数
+const SEEK_TABLE_MAGIC: u32 = 0x184D_2A5E;
+/// seek table 尾部魔数
+const SEEKABLE_MAGIC: u32 = 0x8F92_EAB1;
+
+impl<W: Write> SeekableZstdWriter<W> {
+    pub fn new(inner: W, level: i32, frame_size: usize) -> Self {
+        Self {
+            inner,
+            level,
+            frame_size: frame_size.max(1),
+            buf: Vec::with_capacity(frame_size),
+            frames: Vec::new(),
+        }
+    }
+
+    fn write_frame(&mut self) -> io::Result<()> {
+        if self.buf.is_empty() {
+            return Ok(());
+        }
+        let frame = zstd::bulk::compress(&self.buf, self.level)?;
+        self.inner.write_all(&frame)?;
+        self.frames.push((frame.len() as u32, self.buf.len() as u32));
+        self.buf.clear();
+        Ok(())
+    }
+
+    /// 写出剩余数据与 seek table，返回内部写入器
+    pub fn finish(mut self) -> io::Result<W> {
+        self.write_frame()?;
+        let mut table = Vec::with_capacity(self.frames.len() * 8 + 17);
+        table.extend_from_slice(&SEEK_TABLE_MAGIC.to_le_bytes());
+        table.extend_from_slice(&((self.frames.len() * 8 + 9) as u32).to_le_bytes());
+        for (compressed, decompressed) in &self.frames {
+            table.extend_from_slice(&compressed.to_le_bytes());
+            table.extend_from_slice(&decompressed.to_le_bytes());
+        }
+        table.extend_from_slice(&(self.frames.len() as u32).to_le_bytes());
+        table.push(0);
+        table.extend_from_slice(&SEEKABLE_MAGIC.to_le_bytes());
+        self.inner.write_all(&table)?;
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+}
+
+impl<W: Write> Write for SeekableZstdWriter<W> {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        let n = (self.frame_size - self.buf.len()).min(data.len());
+        self.buf.extend_from_slice(&data[..n]);
+        if self.buf.len() == self.frame_size {
+            self.write_frame()?;
+        }
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// BGZF 写入器
+///
+/// 每 64 KiB 以内的输入压缩为一个在 `BC` 扩展字段中记录自身大小的 gzip 成员，
+/// 结束时追加空成员作为 EOF 标记；普通 gzip 解码器也可读取。
+pub struct BgzfWriter<W: Write> {
+    inner: W,
+    level: flate2::Compression,
+    buf: Vec<u8>,
+}
+
+impl<W: Write> BgzfWriter<W> {
+    pub fn new(inner: W, level: u32) -> Self {
+        Self { inner, level: flate2::Compression::new(level), buf: Vec::with_capacity(BGZF_BLOCK_SIZE) }
+    }
+
+    fn write_block(&mut self) -> io::Result<()> {
+        let mut data = deflate(&self.buf, self.level)?;
+        // 成员总大小须能写入 16 位字段，不可压缩的数据改为存储
+        if data.len() + 26 > 1 << 16 {
+            data = deflate(&self.buf, flate2::Compression::none())?;
+        }
+        let mut crc = flate2::Crc::new();
+        crc.update(&self.buf);
+        let bsize = (data.len() + 25) as u16;
+        let mut header = [0x1f, 0x8b, 8, 4, 0, 0, 0, 0, 0, 0xff, 6, 0, b'B', b'C', 2, 0, 0, 0];
+        header[16..].copy_from_slice(&bsize.to_le_bytes());
+        self.inner.write_all(&header)?;
+        self.inner.write_all(&data)?;
+        self.inner.write_all(&crc.sum().to_le_bytes())?;
+        self.inner.write_all(&(self.buf.len() as u32).to_le_bytes())?;
+        self.buf.clear();
+        Ok(())
+    }
+
+    /// 写出剩余数据与 EOF 块，返回内部写入器
+    pub fn finish(mut self) -> io::Result<W> {
+        if !self.buf.is_empty() {
+            self.write_block()?;
+        }
+        self.write_block()?;
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+}
+
+impl<W: Write> Write for BgzfWriter<W> {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        let n = (BGZF_BLOCK_SIZE - self.buf.len()).min(data.len());
+        self.buf.extend_from_slice(&data[..n]);
+        if self.buf.len() == BGZF_BLOCK_SIZE {
+            self.write_block()?;
+        }
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+fn deflate(data: &[u8], level: flate2::Compression) -> io::Result<Vec<u8>> {
+    let mut encoder = flate2::write::DeflateEncoder::new(Vec::new(), level);
+    encoder.write_all(data)?;
+    encoder.finish()
+}
+
+/// 已有压缩流的分帧方式，修改归档后按原方式重新编码
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Framing {
+    /// 与 [`Encoder::new`] 相同
+    Default,
+    /// gzip 按 BGZF 分块
+    Bgzf,
+    /// 不带 seek table 的 zstd 流
+    ZstdStream,
+    /// zstd 可寻址格式，值为每帧的解压前大小
+    ZstdSeekable(usize),
+}
+
+impl Framing {
+    /// 检查流头（gzip）或流尾（zstd），结束后回到开头
+    pub(crate) fn detect<R: Read + Seek>(input: &mut R, codec: Codec) -> io::Result<Self> {
+        let framing = match codec {
+            Codec::Gzip => {
+                let mut head = [0u8; 12];
+                input.seek(SeekFrom::Start(0))?;
+                let mut extra = Vec::new();
+                if input.read_exact(&mut head).is_ok() && head[3] & 0x04 != 0 {
+                    let xlen = u16::from_le_bytes([head[10], head[11]]) as u64;
+                    input.take(xlen).read_to_end(&mut extra)?;
+                }
+                match bgzf_block_size(&extra) {
+                    Some(_) => Self::Bgzf,
+                    None => Self::Default,
+                }
+            }
+            Codec::Zstd => Self::detect_zstd(input)?,
+            Codec::Xz | Codec::Bzip2 => Self::Default,
+        };
+        input.seek(SeekFrom::Start(0))?;
+        Ok(framing)
+    }
+
+    /// seek table 尾部：帧数、描述符、魔数；每项为压缩后大小、解压前大小与可选的校验和
+    fn detect_zstd<R: Read + Seek>(input: &mut R) -> io::Result<Self> {
+        let mut footer = [0u8; 9];
+        if input.seek(SeekFrom::End(-9)).is_err() || input.read_exact(&mut footer).is_err() {
+            return Ok(Self::ZstdStream);
+        }
+        if u32::from_le_bytes(footer[5..].try_into().unwrap()) != SEEKABLE_MAGIC {
+            return Ok(Self::ZstdStream);
+        }
+        let frames = u32::from_le_bytes(footer[..4].try_into().unwrap()) as i64;
+        if frames < 2 {
+            // 只有一帧时看不出帧大小
+            return Ok(Self::ZstdSeekable(ZSTD_FRAME_SIZE));
+        }
+        let entry = if footer[4] & 0x80 != 0 { 12 } else { 8 };
+        let mut size = [0u8; 4];
+        input.seek(SeekFrom::End(-9 - frames * entry + 4))?;
+        input.read_exact(&mut size)?;
+        match u32::from_le_bytes(size) as usize {
+            0 => Ok(Self::ZstdSeekable(ZSTD_FRAME_SIZE)),
+            size => Ok(Self::ZstdSeekable(size)),
+        }
+    }
+}
+
+/// 压缩写入器
+pub enum Encoder<W: Write> {
+    Gzip(GzEncoder<W>),
+    /// BGZF 分块 gzip
+    Bgzf(BgzfWriter<W>),
+    /// 多线程 xz，分块输出
+    Xz(XzEncoder<W>),
+    Bzip2(BzEncoder<W>),
+    /// zstd 可寻址格式（独立帧 + seek table）
+    Zstd(SeekableZstdWriter<W>),
+    /// 单个 zstd 流
+    ZstdStream(zstd::stream::write::Encoder<'static, W>),
+}
+
+impl<W: Write> Encoder<W> {
+    /// 创建编码器；`level` 为 None 时使用 [`Codec::default_level`]，超出范围的值被截断
+    pub fn new(inner: W, codec: Codec, level: Option<u32>) -> Result<Self> {
+        let (min, max) = codec.level_range();
+        let level = level.unwrap_or(codec.default_level()).clamp(min, max);
+        Ok(match codec {
+            Codec::Gzip => Self::Gzip(GzEncoder::new(inner, flate2::Compression::new(level))),
+            Codec::Xz => {
+                let threads = std::thread::available_parallelism().map_or(1, |n| n.get()) as u32;
+                let stream = MtStreamBuilder::new()
+                    .preset(level)
+                    .check(Check::Crc64)
+                    .threads(threads)
+                    .encoder()
+                    .map_err(|e| FastZipError::Io(e.into()))?;
+                Self::Xz(XzEncoder::new_stream(inner, stream))
+            }
+            Codec::Bzip2 => Self::Bzip2(BzEncoder::new(inner, bzip2::Compression::new(level))),
+            Codec::Zstd => Self::Zstd(SeekableZstdWriter::new(inner, level as i32, ZSTD_FRAME_SIZE)),
+        })
+    }
+
+    /// 按 `framing` 创建编码器，其余同 [`Encoder::new`]
+    pub(crate) fn with_framing(inner: W, codec: Codec, level: Option<u32>, framing: Framing) -> Result<Self> {
+        let (min, max) = codec.level_range();
+        let level = level.unwrap_or(codec.default_level()).clamp(min, max);
+        Ok(match (codec, framing) {
+            (Codec::Gzip, Framing::Bgzf) => Self::Bgzf(BgzfWriter::new(inner, level)),
+            (Codec::Zstd, Framing::ZstdStream) => {
+                Self::ZstdStream(zstd::stream::write::Encoder::new(inner, level as i32)?)
+            }
+            (Codec::Zstd, Framing::ZstdSeekable(size)) => {
+                Self::Zstd(SeekableZstdWriter::new(inner, level as i32, size))
+            }
+            _ => Self::new(inner, codec, Some(level))?,
+        })
+    }
+
+    /// 写出剩余数据与流尾，返回内部写入器
+    pub fn finish(self) -> io::Result<W> {
+        match self {
+            Self::Gzip(w) => w.finish(),
+            Self::Bgzf(w) => w.finish(),
+            Self::Xz(w) => w.finish(),
+            Self::Bzip2(w) => w.finish(),
+            Self::Zstd(w) => w.finish(),
+            Self::ZstdStream(w) => w.finish(),
+        }
+    }
+}
+
+impl<W: Write> Write for Encoder<W> {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        match self {
+            Self::Gzip(w) => w.write(data),
+            Self::Bgzf(w) => w.write(data),
+            Self::Xz(w) => w.write(data),
+            Self::Bzip2(w) => w.write(data),
+            Self::Zstd(w) => w.write(data),
+            Self::ZstdStream(w) => w.write(data),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Self::Gzip(w) => w.flush(),
+            Self::Bgzf(w) => w.flush(),
+            Self::Xz(w) => w.flush(),
+            Self::Bzip2(w) => w.flush(),
+            Self::Zstd(w) => w.flush(),
+            Self::ZstdStream(w) => w.flush(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn sample(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i * 31 % 251) as u8).collect()
+    }
+
+    fn decode(data: Vec<u8>, codec: Codec, threads: usize) -> Vec<u8> {
+        let mut out = Vec::new();
+        open_decoder(Cursor::new(data), codec, threads)
+            .unwrap()
+            .read_to_end(&mut out)
+            .unwrap();
+        out
+    }
+
+    #[test]
+    fn test_zstd_multi_frame_parallel() {
+        let data = sample(300_000);
+        let mut compressed = Vec::new();
+        for chunk in data.chunks(50_000) {
+            compressed.extend(zstd::bulk::compress(chunk, 3).unwrap());
+        }
+        assert_eq!(decode(compressed.clone(), Codec::Zstd, 4), data);
+        assert_eq!(decode(compressed, Codec::Zstd, 1), data);
+    }
+
+    #[test]
+    fn test_zstd_without_content_size_falls_back() {
+        // 流式编码的帧不声明解压后大小，从该帧起顺序解码
+        let data = sample(300_000);
+        let mut compressed = zstd::bulk::compress(&data[..1000], 3).unwrap();
+        compressed.extend(zstd::encode_all(&data[1000..], 3).unwrap());
+
+        let mut splitter = ZstdSplitter;
+        let mut input = Cursor::new(&compressed[..]);
+        assert!(matches!(splitter.next_member(&mut input).unwrap(), Split::Member(_, 1000)));
+        assert!(matches!(splitter.next_member(&mut input).unwrap(), Split::Fallback(_)));
+        assert_eq!(decode(compressed, Codec::Zstd, 4), data);
+    }
+
+    #[test]
+    fn test_member_larger_than_declared() {
+        let member = zstd::bulk::compress(&sample(1000), 3).unwrap();
+        assert!(decode_member(Codec::Zstd, &member, 1000).is_ok());
+        assert!(decode_member(Codec::Zstd, &member, 999).is_err());
+    }
+
+    #[test]
+    fn test_xz_multi_block_parallel() {
+        let data = sample(400_000);
+        let stream = xz2::stream::MtStreamBuilder::new()
+            .threads(2)
+            .block_size(64 * 1024)
+            .preset(1)
+            .encoder()
+            .unwrap();
+        let mut enc = xz2::write::XzEncoder::new_stream(Vec::new(), stream);
+        enc.write_all(&data).unwrap();
+        let mut compressed = enc.finish().unwrap();
+
+        let mut splitter = XzSplitter::default();
+        let mut input = Cursor::new(&compressed[..]);
+        let mut members = 0;
+        while let Split::Member(..) = splitter.next_member(&mut input).unwrap() {
+            members += 1;
+        }
+        assert!(members > 1, "多线程 xz 应切分出多个块");
+
+        // 拼接第二个流
+        compressed.extend(xz(&data[..1000]));
+
+        let mut expected = data.clone();
+        expected.extend_from_slice(&data[..1000]);
+        assert_eq!(decode(compressed, Codec::Xz, 4), expected);
+    }
+
+    #[test]
+    fn test_xz_large_block_streamed() {
+        // 第一块解压后超过 MEMBER_LIMIT，流式解码后继续切分第二块
+        let data = sample(MEMBER_LIMIT + 8 * 1024 * 1024);
+        let stream = xz2::stream::MtStreamBuilder::new()
+            .threads(2)
+            .block_size((MEMBER_LIMIT + 4 * 1024 * 1024) as u64)
+            .preset(0)
+            .encoder()
+            .unwrap();
+        let mut enc = xz2::write::XzEncoder::new_stream(Vec::new(), stream);
+        enc.write_all(&data).unwrap();
+        let compressed = enc.finish().unwrap();
+
+        let mut splitter = XzSplitter::default();
+        assert!(matches!(
+            splitter.next_member(&mut Cursor::new(&compressed[..])).unwrap(),
+            Split::Large(LargeBlock::Sized { .. })
+        ));
+        assert!(decode(compressed, Codec::Xz, 4) == data);
+    }
+
+    #[test]
+    fn test_xz_unsized_block_after_sized() {
+        // 第一块带大小（多线程编码），第二块不带大小（单线程编码），合并为一个流
+        let data = sample(300_000);
+        let stream = xz2::stream::MtStreamBuilder::new()
+            .threads(2)
+            .preset(1)
+            .check(Check::Crc64)
+            .encoder()
+            .unwrap();
+        let mut enc = xz2::write::XzEncoder::new_stream(Vec::new(), stream);
+        enc.write_all(&data[..100_000]).unwrap();
+        let sized = enc.finish().unwrap();
+        let unsized_ = xz(&data[100_000..]);
+
+        // 拆出单块流的块数据与索引记录
+        let split = |stream: &[u8]| {
+            let footer = &stream[stream.len() - 12..];
+            let backward = u32::from_le_bytes(footer[4..8].try_into().unwrap());
+            let index_start = stream.len() - 12 - (backward as usize + 1) * 4;
+            let mut fields = &stream[index_start + 2..];
+            let record = (read_varint(&mut fields).unwrap(), read_varint(&mut fields).unwrap());
+            (stream[12..index_start].to_vec(), record)
+        };
+        let (block_a, (unpadded_a, size_a)) = split(&sized);
+        let (block_b, (unpadded_b, size_b)) = split(&unsized_);
+
+        let mut index = vec![0u8, 2];
+        for value in [unpadded_a, size_a, unpadded_b, size_b] {
+            write_varint(&mut index, value);
+        }
+        while index.len() % 4 != 0 {
+            index.push(0);
+        }
+        index.extend_from_slice(&crc32fast::hash(&index).to_le_bytes());
+        let mut footer = ((index.len() / 4 - 1) as u32).to_le_bytes().to_vec();
+        footer.extend_from_slice(&sized[6..8]);
+
+        let mut compressed = sized[..12].to_vec();
+        compressed.extend(block_a);
+        compressed.extend(block_b);
+        compressed.extend(index);
+        compressed.extend(crc32fast::hash(&footer).to_le_bytes());
+        compressed.extend(footer);
+        compressed.extend(b"YZ");
+
+        let mut splitter = XzSplitter::default();
+        let mut input = Cursor::new(&compressed[..]);
+        assert!(matches!(splitter.next_member(&mut input).unwrap(), Split::Member(_, 100_000)));
+        assert!(matches!(
+            splitter.next_member(&mut input).unwrap(),
+            Split::Large(LargeBlock::Unsized { .. })
+        ));
+        assert_eq!(decode(compressed.clone(), Codec::Xz, 4), data);
+        assert_eq!(decode(compressed, Codec::Xz, 1), data);
+    }
+
+    fn xz(data: &[u8]) -> Vec<u8> {
+        let mut enc = xz2::write::XzEncoder::new(Vec::new(), 6);
+        enc.write_all(data).unwrap();
+        enc.finish().unwrap()
+    }
+
+    #[test]
+    fn test_xz_single_block_fallback() {
+        let data = sample(100_000);
+        assert_eq!(decode(xz(&data), Codec::Xz, 4), data);
+    }
+
+    #[test]
+    fn test_gzip_concatenated_members() {
+        let mut compressed = Vec::new();
+        for part in [&b"hello "[..], &b"world"[..]] {
+            let mut enc = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+            enc.write_all(part).unwrap();
+            compressed.extend(enc.finish().unwrap());
+        }
+        assert_eq!(decode(compressed.clone(), Codec::Gzip, 4), b"hello world");
+        assert_eq!(decode(compressed, Codec::Gzip, 1), b"hello world");
+    }
+
+    #[test]
+    fn test_bgzf_blocks_parallel() {
+        let data = sample(200_000);
+        let mut compressed = Vec::new();
+        for chunk in data.chunks(60_000) {
+            let mut enc = flate2::GzBuilder::new()
+                .extra(vec![b'B', b'C', 2, 0, 0, 0])
+                .write(Vec::new(), flate2::Compression::fast());
+            enc.write_all(chunk).unwrap();
+            let mut member = enc.finish().unwrap();
+            let bsize = (member.len() - 1) as u16;
+            member[16..18].copy_from_slice(&bsize.to_le_bytes());
+            compressed.extend(member);
+        }
+        assert_eq!(decode(compressed, Codec::Gzip, 4), data);
+    }
+
+    #[test]
+    fn test_bgzf_writer_and_framing() {
+        let data = sample(200_000);
+        let mut writer = BgzfWriter::new(Vec::new(), 6);
+        writer.write_all(&data).unwrap();
+        let compressed = writer.finish().unwrap();
+        let mut splitter = BgzfSplitter;
+        let mut input = Cursor::new(&compressed[..]);
+        let mut sizes = Vec::new();
+        while let Split::Member(_, size) = splitter.next_member(&mut input).unwrap() {
+            sizes.push(size);
+        }
+        assert_eq!(sizes, [0xff00, 0xff00, 0xff00, 200_000 - 3 * 0xff00, 0]);
+        assert_eq!(decode(compressed.clone(), Codec::Gzip, 4), data);
+        assert_eq!(Framing::detect(&mut Cursor::new(compressed), Codec::Gzip).unwrap(), Framing::Bgzf);
+
+        let mut writer = SeekableZstdWriter::new(Vec::new(), 3, 16 * 1024);
+        writer.write_all(&data).unwrap();
+        let seekable = writer.finish().unwrap();
+        let framing = Framing::detect(&mut Cursor::new(seekable), Codec::Zstd).unwrap();
+        assert_eq!(framing, Framing::ZstdSeekable(16 * 1024));
+        let stream = zstd::encode_all(&data[..], 3).unwrap();
+        assert_eq!(Framing::detect(&mut Cursor::new(stream), Codec::Zstd).unwrap(), Framing::ZstdStream);
+    }
+
+    #[test]
+    fn test_seekable_zstd_frames() {
+        let data = sample(100_000);
+        let mut writer = SeekableZstdWriter::new(Vec::new(), 3, 16 * 1024);
+        writer.write_all(&data).unwrap();
+        let compressed = writer.finish().unwrap();
+
+        let mut splitter = ZstdSplitter;
+        let mut input = Cursor::new(&compressed[..]);
+        let mut frames = 0;
+        while let Split::Member(..) = splitter.next_member(&mut input).unwrap() {
+            frames += 1;
+        }
+        assert_eq!(frames, 7);
+        assert_eq!(decode(compressed, Codec::Zstd, 4), data);
+    }
+
+    #[test]
+    fn test_bzip2_concatenated_streams() {
+        let mut compressed = Vec::new();
+        for part in [&b"foo"[..], &b"bar"[..]] {
+            let mut enc = bzip2::write::BzEncoder::new(Vec::new(), bzip2::Compression::default());
+            enc.write_all(part).unwrap();
+            compressed.extend(enc.finish().unwrap());
+        }
+        assert_eq!(decode(compressed, Codec::Bzip2, 0), b"foobar");
+    }
+}
@@ -5,6 +5,7 @@ use std::io::Read;
 use std::path::Path;
 
 use crate::error::{FastZipError, Result};
+use crate::formats::{logical_path, Codec};
 
 /// 支持的压缩/归档格式
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -24,8 +25,12 @@ pub enum ArchiveFormat {
 }
 
 impl ArchiveFormat {
-    /// 根据扩展名检测格式
+    /// 根据扩展名检测格式；分卷（`a.7z.001`、`a.z01`）按所属归档检测
     pub fn from_extension(path: &Path) -> Option<Self> {
+        let logical = logical_path(path);
+        if logical != path {
+            return Self::from_extension(&logical);
+        }
         let ext = path.extension().and_then(|e| e.to_str())?.to_lowercase();
         let stem = path
             .file_stem()
@@ -101,10 +106,8 @@ impl ArchiveFormat {
             return Some(Self::Zst);
         }
         // tar 无魔术，但前 257 字节后可能有多字节 ustar
-        if buf.len() >= 262 {
-            if &buf[257..262] == b"ustar" {
-                return Some(Self::Tar);
-            }
+        if buf.len() >= 262 && &buf[257..262] == b"ustar" {
+            return Some(Self::Tar);
         }
         None
     }
@@ -124,10 +127,29 @@ impl ArchiveFormat {
         )
     }
 
+    /// 是否为 tar 系列（含 tar.gz 等外层压缩）
+    pub fn is_tar(&self) -> bool {
+        matches!(
+            self,
+            Self::Tar | Self::TarGz | Self::TarXz | Self::TarBz2 | Self::TarZst
+        )
+    }
+
     /// 是否为单文件压缩格式
     pub fn is_single_compressed(&self) -> bool {
         matches!(self, Self::Gz | Self::Xz | Self::Bz2 | Self::Zst)
     }
+
+    /// 外层流压缩编码（tar.* 与单文件压缩格式）
+    pub fn codec(&self) -> Option<Codec> {
+        match self {
+            Self::TarGz | Self::Gz => Some(Codec::Gzip),
+            Self::TarXz | Self::Xz => Some(Codec::Xz),
+            Self::TarBz2 | Self::Bz2 => Some(Codec::Bzip2),
+            Self::TarZst | Self::Zst => Some(Codec::Zstd),
+            _ => None,
+        }
+    }
 }
 
 /// 检测文件格式：优先扩展名，扩展名无法识别时用魔数
@@ -183,5 +205,13 @@ mod tests {
             ArchiveFormat::from_extension(Path::new("a.zst")),
             Some(ArchiveFormat::Zst)
         );
+        assert_eq!(
+            ArchiveFormat::from_extension(Path::new("a.tar.gz.003")),
+            Some(ArchiveFormat::TarGz)
+        );
+        assert_eq!(
+            ArchiveFormat::from_extension(Path::new("a.z01")),
+            Some(ArchiveFormat::Zip)
+        );
     }
 }
@@ -0,0 +1,273 @@
+//! 单个条目的随机读取（`Read + Seek`）
+//!
+//! 未加密的存储 ZIP 条目与 tar 条目直接按偏移读；Deflate ZIP 条目边解码边在块边界记录断点，
+//! 向回定位时从最近的断点恢复；tar.* 借助 [`TarIndex`] 的解码重启点重新定位；
+//! 其余条目（7z、加密或其他压缩方法的 ZIP）打开时整体解到临时缓存。
+
+use std::io::{self, Read, Seek, SeekFrom, Take};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use tempfile::SpooledTempFile;
+
+use super::inflate::{DeflateCheckpoint, Inflater};
+use super::{detect_format, ArchiveFormat, InputReader, SevenZExtractor, TarExtractor, TarIndex, ZipExtractor};
+use crate::error::{FastZipError, Result};
+
+/// 缓存的条目超过该大小时落到临时文件
+const SPILL_THRESHOLD: usize = 16 * 1024 * 1024;
+
+/// Deflate 条目每解码约该字节数记录一个断点（每个断点保存 32 KiB 窗口）
+const CHECKPOINT_SPAN: u64 = 1024 * 1024;
+
+/// 单个条目的可定位读取器，由 [`open_entry`] 或各格式的 `open_entry` 返回
+pub struct EntryReader {
+    inner: Inner,
+    size: u64,
+    pos: u64,
+}
+
+enum Inner {
+    /// 条目数据在归档中连续存放，`start` 为数据起点
+    Direct { reader: InputReader, start: u64, cursor: u64 },
+    Deflate(SeekableInflate),
+    Tar { path: PathBuf, index: Arc<TarIndex>, offset: u64, reader: Option<Box<dyn Read + Send>>, cursor: u64 },
+    Cached(SpooledTempFile),
+}
+
+impl EntryReader {
+    pub(crate) fn direct(reader: InputReader, start: u64, size: u64) -> Self {
+        Self::new(Inner::Direct { reader, start, cursor: u64::MAX }, size)
+    }
+
+    pub(crate) fn deflate(input: InputReader, start: u64, compressed: u64, size: u64) -> Self {
+        let inflate = SeekableInflate { input, start, compressed, decoder: None, decoded: 0, checkpoints: Vec::new() };
+        Self::new(Inner::Deflate(inflate), size)
+    }
+
+    pub(crate) fn tar(path: &Path, index: Arc<TarIndex>, offset: u64, size: u64) -> Self {
+        Self::new(Inner::Tar { path: path.to_path_buf(), index, offset, reader: None, cursor: 0 }, size)
+    }
+
+    /// 把 `data` 整体解到缓存
+    pub(crate) fn cached(data: &mut dyn Read) -> Result<Self> {
+        let mut cached = tempfile::spooled_tempfile(SPILL_THRESHOLD);
+        let size = io::copy(data, &mut cached)?;
+        cached.rewind()?;
+        Ok(Self::new(Inner::Cached(cached), size))
+    }
+
+    fn new(inner: Inner, size: u64) -> Self {
+        Self { inner, size, pos: 0 }
+    }
+
+    /// 条目解压后的大小
+    pub fn len(&self) -> u64 {
+        self.size
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.size == 0
+    }
+}
+
+impl Read for EntryReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let remaining = self.size.saturating_sub(self.pos);
+        let len = buf.len().min(usize::try_from(remaining).unwrap_or(usize::MAX));
+        if len == 0 {
+            return Ok(0);
+        }
+        let buf = &mut buf[..len];
+        let n = match &mut self.inner {
+            Inner::Direct { reader, start, cursor } => {
+                if *cursor != self.pos {
+                    reader.seek(SeekFrom::Start(*start + self.pos))?;
+                }
+                let n = reader.read(buf)?;
+                *cursor = self.pos + n as u64;
+                n
+            }
+            Inner::Deflate(inflate) => inflate.read_at(self.pos, buf)?,
+            Inner::Tar { path, index, offset, reader, cursor } => {
+                // 向回或远距离向前定位时经索引重新打开，近距离向前直接跳过
+                let stale = self.pos < *cursor || self.pos - *cursor > CHECKPOINT_SPAN;
+                if reader.is_none() || stale {
+                    *reader = Some(index.open_at(path, *offset + self.pos).map_err(io::Error::other)?);
+                    *cursor = self.pos;
+                }
+                let reader = reader.as_mut().expect("上面已打开");
+                *cursor += io::copy(&mut reader.take(self.pos - *cursor), &mut io::sink())?;
+                let n = reader.read(buf)?;
+                *cursor += n as u64;
+                n
+            }
+            Inner::Cached(cached) => {
+                cached.seek(SeekFrom::Start(self.pos))?;
+                cached.read(buf)?
+            }
+        };
+        if n == 0 {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        self.pos += n as u64;
+        Ok(n)
+    }
+}
+
+impl Seek for EntryReader {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let target = match pos {
+            SeekFrom::Start(n) => Some(n),
+            SeekFrom::End(n) => self.size.checked_add_signed(n),
+            SeekFrom::Current(n) => self.pos.checked_add_signed(n),
+        };
+        self.pos = target.ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "定位到条目起点之前"))?;
+        Ok(self.pos)
+    }
+}
+
+/// 带断点的 Deflate 条目解码
+struct SeekableInflate {
+    input: InputReader,
+    /// 压缩数据在归档中的起点与长度
+    start: u64,
+    compressed: u64,
+    decoder: Option<Box<Inflater<Take<InputReader>>>>,
+    /// 解码器下一个输出字节对应的解压后偏移
+    decoded: u64,
+    /// 按解压后偏移递增
+    checkpoints: Vec<DeflateCheckpoint>,
+}
+
+impl SeekableInflate {
+    fn read_at(&mut self, pos: u64, buf: &mut [u8]) -> io::Result<usize> {
+        // 向回定位时必须重新开始；目标前有比当前位置更近的断点时也从断点恢复
+        let behind = self.decoder.is_none() || pos < self.decoded;
+        match self.checkpoints.iter().rev().find(|c| c.uncompressed <= pos).cloned() {
+            Some(cp) if behind || cp.uncompressed > self.decoded => self.resume(&cp)?,
+            None if behind => self.restart()?,
+            _ => {}
+        }
+        let decoder = self.decoder.as_mut().expect("上面已打开");
+        self.decoded += io::copy(&mut decoder.take(pos - self.decoded), &mut io::sink())?;
+        let n = decoder.read(buf)?;
+        self.decoded += n as u64;
+        let latest = self.checkpoints.last().map_or(0, |c| c.uncompressed);
+        self.checkpoints.extend(decoder.take_checkpoints().into_iter().filter(|c| c.uncompressed > latest));
+        Ok(n)
+    }
+
+    /// 从压缩数据起点重新开始解码
+    fn restart(&mut self) -> io::Result<()> {
+        let mut input = self.input.clone();
+        input.seek(SeekFrom::Start(self.start))?;
+        let decoder = Inflater::new(input.take(self.compressed)).raw();
+        self.decoder = Some(Box::new(decoder.record_checkpoints(CHECKPOINT_SPAN)));
+        self.decoded = 0;
+        Ok(())
+    }
+
+    fn resume(&mut self, cp: &DeflateCheckpoint) -> io::Result<()> {
+        let mut input = self.input.clone();
+        input.seek(SeekFrom::Start(self.start + cp.compressed))?;
+        let input = input.take(self.compressed - cp.compressed);
+        let decoder = Inflater::resume(input, cp).raw();
+        self.decoder = Some(Box::new(decoder.record_checkpoints(CHECKPOINT_SPAN)));
+        self.decoded = cp.uncompressed;
+        Ok(())
+    }
+}
+
+/// 打开归档中的单个文件条目，返回可定位的读取器
+///
+/// 支持 ZIP、7z 与 tar.*；条目名以 `/` 分隔，开头的 `/` 与 `./` 忽略。
+pub fn open_entry(archive: &Path, name: &str, password: Option<&str>) -> Result<EntryReader> {
+    let name = entry_name(name);
+    match detect_format(archive)? {
+        ArchiveFormat::Zip => ZipExtractor::open_entry(archive, name, password),
+        ArchiveFormat::SevenZ => SevenZExtractor::open_entry(archive, name, password),
+        format if format.is_tar() => TarExtractor::open_entry(archive, format, name),
+        format => Err(FastZipError::UnsupportedFormat(format!("{:?} 不支持按条目读取", format))),
+    }
+}
+
+/// 按 `归档!/条目路径` 打开条目，如 `data.zip!/db/app.sqlite`
+///
+/// 路径中有多个 `!/` 时，以第一个左侧为已存在文件的为分隔。
+pub fn open_entry_path(spec: &str, password: Option<&str>) -> Result<EntryReader> {
+    let (archive, name) = spec
+        .match_indices("!/")
+        .map(|(i, _)| (&spec[..i], &spec[i + 2..]))
+        .find(|(archive, _)| Path::new(archive).is_file())
+        .ok_or_else(|| FastZipError::Other(format!("无效的条目路径（应为 归档!/条目）: {}", spec)))?;
+    open_entry(Path::new(archive), name, password)
+}
+
+fn entry_name(name: &str) -> &str {
+    let name = name.trim_start_matches('/');
+    name.strip_prefix("./").unwrap_or(name)
+}
+
+/// 条目是目录时的错误
+pub(crate) fn not_a_file(name: &str) -> FastZipError {
+    FastZipError::Other(format!("条目不是文件: {}", name))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::*;
+    use crate::formats::ArchiveInput;
+
+    #[test]
+    fn test_deflate_seek_uses_checkpoints() {
+        // 可压缩但不重复的数据，解压后约 6 MiB
+        let mut state = 1u32;
+        let words = ["alpha ", "beta ", "gamma ", "delta\n", "epsilon "];
+        let data: Vec<u8> = (0..1_000_000)
+            .flat_map(|_| {
+                state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+                words[(state >> 16) as usize % words.len()].bytes()
+            })
+            .collect();
+        let mut encoder = flate2::write::DeflateEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(&data).unwrap();
+        let compressed = encoder.finish().unwrap();
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(b"header").unwrap();
+        file.write_all(&compressed).unwrap();
+        file.flush().unwrap();
+
+        let input = ArchiveInput::open(file.path()).unwrap();
+        let mut reader = EntryReader::deflate(input.reader().unwrap(), 6, compressed.len() as u64, data.len() as u64);
+        let mut all = Vec::new();
+        reader.read_to_end(&mut all).unwrap();
+        assert!(all == data);
+        let Inner::Deflate(inflate) = &reader.inner else { unreachable!() };
+        assert!(inflate.checkpoints.len() >= 4);
+
+        let len = data.len() as u64;
+        for offset in [len - 100, 10, 3_000_000, 2_999_000, 5_500_000, 0] {
+            reader.seek(SeekFrom::Start(offset)).unwrap();
+            let mut buf = [0u8; 100];
+            reader.read_exact(&mut buf).unwrap();
+            assert_eq!(buf[..], data[offset as usize..offset as usize + 100], "@{offset}");
+            let Inner::Deflate(inflate) = &reader.inner else { unreachable!() };
+            // 从不早于目标前最近断点的位置开始解码
+            let nearest = inflate.checkpoints.iter().rev().find(|c| c.uncompressed <= offset);
+            assert!(inflate.decoded - 100 >= nearest.map_or(0, |c| c.uncompressed));
+        }
+        assert_eq!(reader.seek(SeekFrom::End(0)).unwrap(), len);
+        assert_eq!(reader.read(&mut [0u8; 10]).unwrap(), 0);
+        assert!(reader.seek(SeekFrom::Current(-(len as i64) - 1)).is_err());
+    }
+
+    #[test]
+    fn test_entry_name() {
+        assert_eq!(entry_name("/a/b.txt"), "a/b.txt");
+        assert_eq!(entry_name("./a"), "a");
+        assert_eq!(entry_name("a"), "a");
+    }
+}
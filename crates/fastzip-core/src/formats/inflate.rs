@@ -0,0 +1,333 @@
+//! 可记录/恢复断点的 deflate 解码（多成员 gzip 或裸 deflate 流）
+//!
+//! 基于 miniz_oxide 的块边界状态：在 deflate 块之间记录剩余位与 32 KiB 窗口，
+//! 之后可从该压缩偏移直接恢复解码，无需从头解压。
+
+use std::io::{self, Read};
+
+use miniz_oxide::inflate::core::inflate_flags::{
+    TINFL_FLAG_HAS_MORE_INPUT, TINFL_FLAG_STOP_ON_BLOCK_BOUNDARY,
+};
+use miniz_oxide::inflate::core::{decompress, BlockBoundaryState, DecompressorOxide, TINFL_LZ_DICT_SIZE};
+use miniz_oxide::inflate::TINFLStatus;
+
+use crate::formats::codec::invalid;
+
+const DICT_MASK: usize = TINFL_LZ_DICT_SIZE - 1;
+
+/// deflate 块边界断点
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeflateCheckpoint {
+    /// 压缩数据中的字节偏移（从该字节继续读取）
+    pub compressed: u64,
+    /// 对应的解压后偏移
+    pub uncompressed: u64,
+    /// 上一个已读字节中尚未消费的位数（0..=7）
+    pub bits: u8,
+    /// 尚未消费的位（低位有效）
+    pub bit_buf: u8,
+    /// 此前最多 32 KiB 的输出（解码窗口）
+    pub window: Vec<u8>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Header,
+    Inflate,
+    Trailer,
+    Done,
+}
+
+/// 可记录断点的 gzip / 裸 deflate 解码器
+pub(crate) struct Inflater<R> {
+    input: R,
+    buf: Box<[u8]>,
+    start: usize,
+    end: usize,
+    eof: bool,
+    /// buf[start] 在压缩数据中的偏移
+    compressed: u64,
+    state: State,
+    /// 裸 deflate 流（如 ZIP 条目），无 gzip 头尾
+    raw: bool,
+    decomp: Box<DecompressorOxide>,
+    dict: Box<[u8]>,
+    dict_ofs: usize,
+    dict_filled: usize,
+    uncompressed: u64,
+    /// 当前 gzip 成员的 CRC 与长度；从断点恢复时为 None（无法校验）
+    member: Option<(crc32fast::Hasher, u32)>,
+    pending: usize,
+    pending_len: usize,
+    span: Option<u64>,
+    last_checkpoint: u64,
+    checkpoints: Vec<DeflateCheckpoint>,
+}
+
+impl<R: Read> Inflater<R> {
+    /// 从压缩数据起点开始解码
+    pub(crate) fn new(input: R) -> Self {
+        Self {
+            input,
+            buf: vec![0u8; 256 * 1024].into_boxed_slice(),
+            start: 0,
+            end: 0,
+            eof: false,
+            compressed: 0,
+            state: State::Header,
+            raw: false,
+            decomp: Box::default(),
+            dict: vec![0u8; TINFL_LZ_DICT_SIZE].into_boxed_slice(),
+            dict_ofs: 0,
+            dict_filled: 0,
+            uncompressed: 0,
+            member: None,
+            pending: 0,
+            pending_len: 0,
+            span: None,
+            last_checkpoint: 0,
+            checkpoints: Vec::new(),
+        }
+    }
+
+    /// 从断点恢复解码；`input` 须已定位到 `checkpoint.compressed`
+    pub(crate) fn resume(input: R, checkpoint: &DeflateCheckpoint) -> Self {
+        let mut inflater = Self::new(input);
+        inflater.compressed = checkpoint.compressed;
+        inflater.uncompressed = checkpoint.uncompressed;
+        inflater.state = State::Inflate;
+        *inflater.decomp = DecompressorOxide::from_block_boundary_state(&BlockBoundaryState {
+            num_bits: checkpoint.bits,
+            bit_buf: checkpoint.bit_buf,
+            ..Default::default()
+        });
+        let window = &checkpoint.window[checkpoint.window.len().saturating_sub(TINFL_LZ_DICT_SIZE)..];
+        inflater.dict[..window.len()].copy_from_slice(window);
+        inflater.dict_ofs = window.len() & DICT_MASK;
+        inflater.dict_filled = window.len();
+        inflater.last_checkpoint = checkpoint.uncompressed;
+        inflater
+    }
+
+    /// 每输出约 `span` 字节在块边界记录一个断点
+    pub(crate) fn record_checkpoints(mut self, span: u64) -> Self {
+        self.span = Some(span);
+        self
+    }
+
+    /// 按裸 deflate 流解码（无 gzip 头尾，流结束即结束）
+    pub(crate) fn raw(mut self) -> Self {
+        self.raw = true;
+        if self.state == State::Header {
+            self.state = State::Inflate;
+        }
+        self
+    }
+
+    /// 取出目前记录的断点
+    pub(crate) fn take_checkpoints(&mut self) -> Vec<DeflateCheckpoint> {
+        std::mem::take(&mut self.checkpoints)
+    }
+
+    pub(crate) fn into_checkpoints(self) -> Vec<DeflateCheckpoint> {
+        self.checkpoints
+    }
+
+    fn fill(&mut self) -> io::Result<()> {
+        if self.start > 0 {
+            self.buf.copy_within(self.start..self.end, 0);
+            self.end -= self.start;
+            self.start = 0;
+        }
+        if self.end < self.buf.len() {
+            let n = self.input.read(&mut self.buf[self.end..])?;
+            if n == 0 {
+                self.eof = true;
+            }
+            self.end += n;
+        }
+        Ok(())
+    }
+
+    fn next_byte(&mut self) -> io::Result<Option<u8>> {
+        if self.start == self.end && !self.eof {
+            self.fill()?;
+        }
+        if self.start == self.end {
+            return Ok(None);
+        }
+        let b = self.buf[self.start];
+        self.start += 1;
+        self.compressed += 1;
+        Ok(Some(b))
+    }
+
+    fn byte(&mut self) -> io::Result<u8> {
+        self.next_byte()?
+            .ok_or_else(|| io::Error::from(io::ErrorKind::UnexpectedEof))
+    }
+
+    fn skip(&mut self, n: usize) -> io::Result<()> {
+        for _ in 0..n {
+            self.byte()?;
+        }
+        Ok(())
+    }
+
+    fn skip_cstr(&mut self) -> io::Result<()> {
+        while self.byte()? != 0 {}
+        Ok(())
+    }
+
+    /// 解析 gzip 成员头；输入结束（或后跟非 gzip 数据）时返回 false
+    fn read_gzip_header(&mut self) -> io::Result<bool> {
+        match self.next_byte()? {
+            Some(0x1f) => {}
+            _ => return Ok(false),
+        }
+        if self.byte()? != 0x8b || self.byte()? != 8 {
+            return Err(invalid("无效的 gzip 头"));
+        }
+        let flags = self.byte()?;
+        self.skip(6)?;
+        if flags & 0x04 != 0 {
+            let xlen = u16::from_le_bytes([self.byte()?, self.byte()?]) as usize;
+            self.skip(xlen)?;
+        }
+        if flags & 0x08 != 0 {
+            self.skip_cstr()?;
+        }
+        if flags & 0x10 != 0 {
+            self.skip_cstr()?;
+        }
+        if flags & 0x02 != 0 {
+            self.skip(2)?;
+        }
+        Ok(true)
+    }
+
+    fn read_gzip_trailer(&mut self) -> io::Result<()> {
+        let mut trailer = [0u8; 8];
+        for b in trailer.iter_mut() {
+            *b = self.byte()?;
+        }
+        if let Some((hasher, size)) = self.member.take() {
+            let crc = u32::from_le_bytes([trailer[0], trailer[1], trailer[2], trailer[3]]);
+            let isize = u32::from_le_bytes([trailer[4], trailer[5], trailer[6], trailer[7]]);
+            if hasher.finalize() != crc || size != isize {
+                return Err(invalid("gzip 校验失败"));
+            }
+        }
+        Ok(())
+    }
+
+    fn window(&self) -> Vec<u8> {
+        if self.dict_filled < TINFL_LZ_DICT_SIZE {
+            self.dict[..self.dict_ofs].to_vec()
+        } else {
+            let mut w = self.dict[self.dict_ofs..].to_vec();
+            w.extend_from_slice(&self.dict[..self.dict_ofs]);
+            w
+        }
+    }
+
+    fn inflate_step(&mut self) -> io::Result<()> {
+        if self.start == self.end && !self.eof {
+            self.fill()?;
+        }
+        let mut flags = 0;
+        if !self.eof {
+            flags |= TINFL_FLAG_HAS_MORE_INPUT;
+        }
+        if self.span.is_some() {
+            flags |= TINFL_FLAG_STOP_ON_BLOCK_BOUNDARY;
+        }
+        let (status, n_in, n_out) = decompress(
+            &mut self.decomp,
+            &self.buf[self.start..self.end],
+            &mut self.dict,
+            self.dict_ofs,
+            flags,
+        );
+        self.start += n_in;
+        self.compressed += n_in as u64;
+        if n_out > 0 {
+            let out = &self.dict[self.dict_ofs..self.dict_ofs + n_out];
+            if let Some((hasher, size)) = self.member.as_mut() {
+                hasher.update(out);
+                *size = size.wrapping_add(n_out as u32);
+            }
+            self.pending = self.dict_ofs;
+            self.pending_len = n_out;
+            self.uncompressed += n_out as u64;
+            self.dict_ofs = (self.dict_ofs + n_out) & DICT_MASK;
+            self.dict_filled = (self.dict_filled + n_out).min(TINFL_LZ_DICT_SIZE);
+        }
+
+        match status {
+            TINFLStatus::Done => {
+                self.state = if self.raw { State::Done } else { State::Trailer };
+            }
+            TINFLStatus::BlockBoundary => {
+                let due = self
+                    .span
+                    .is_some_and(|span| self.uncompressed - self.last_checkpoint >= span);
+                if due {
+                    if let Some(st) = self.decomp.block_boundary_state() {
+                        self.checkpoints.push(DeflateCheckpoint {
+                            compressed: self.compressed,
+                            uncompressed: self.uncompressed,
+                            bits: st.num_bits,
+                            bit_buf: st.bit_buf,
+                            window: self.window(),
+                        });
+                        self.last_checkpoint = self.uncompressed;
+                    }
+                }
+            }
+            TINFLStatus::NeedsMoreInput => {
+                if self.eof {
+                    return Err(io::ErrorKind::UnexpectedEof.into());
+                }
+                self.fill()?;
+            }
+            TINFLStatus::HasMoreOutput => {}
+            _ => return Err(invalid("deflate 数据损坏")),
+        }
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for Inflater<R> {
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        if out.is_empty() {
+            return Ok(0);
+        }
+        loop {
+            if self.pending_len > 0 {
+                let n = self.pending_len.min(out.len());
+                out[..n].copy_from_slice(&self.dict[self.pending..self.pending + n]);
+                self.pending += n;
+                self.pending_len -= n;
+                return Ok(n);
+            }
+            match self.state {
+                State::Done => return Ok(0),
+                State::Header => {
+                    if self.read_gzip_header()? {
+                        *self.decomp = DecompressorOxide::new();
+                        self.member = Some((crc32fast::Hasher::new(), 0));
+                        self.state = State::Inflate;
+                    } else {
+                        self.state = State::Done;
+                    }
+                }
+                State::Inflate => self.inflate_step()?,
+                State::Trailer => {
+                    self.read_gzip_trailer()?;
+                    self.state = State::Header;
+                }
+            }
+        }
+    }
+}
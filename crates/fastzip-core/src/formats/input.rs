@@ -0,0 +1,308 @@
+//! 归档输入源：优先内存映射，管道、网络文件系统等场景回退为缓冲读取
+//!
+//! [`ArchiveInput`] 可廉价克隆并跨线程共享；每个读取器拥有独立的读取位置，
+//! 缓冲读取按位置读（pread），并行读取条目时无需争用同一个文件偏移。分卷归档按各卷拼接后的视图读取。
+
+use std::fs::File;
+use std::io::{self, Cursor, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use memmap2::Mmap;
+
+use crate::error::Result;
+use crate::formats::volume::{VolumeReader, VolumeSet};
+
+const BUF_SIZE: usize = 256 * 1024;
+
+/// 设置后（非空）禁用内存映射，始终使用缓冲读取
+pub const NO_MMAP_ENV: &str = "FASTZIP_NO_MMAP";
+
+/// 归档输入源
+#[derive(Debug, Clone)]
+pub enum ArchiveInput {
+    /// 整个文件映射到内存
+    Mapped(Arc<Mmap>),
+    /// 每次 [`ArchiveInput::reader`] 打开文件，其克隆共享句柄并缓冲读取
+    Buffered(PathBuf),
+    /// 分卷归档（`.001`…、`.z01`…`.zip`）
+    Volumes(Arc<VolumeSet>),
+}
+
+impl ArchiveInput {
+    /// 打开归档：普通本地文件使用内存映射，否则（或映射失败时）回退为缓冲读取
+    ///
+    /// `path` 属于分卷归档时读取全部分卷。
+    pub fn open(path: &Path) -> Result<Self> {
+        if let Some(set) = VolumeSet::find(path)? {
+            return Ok(Self::Volumes(Arc::new(set)));
+        }
+        let file = File::open(path)?;
+        if mmap_allowed(&file) {
+            // SAFETY: 映射只读；解压期间归档被其他进程截断属于调用方须避免的情形，
+            // 与 unzip/7z 等工具一致
+            match unsafe { Mmap::map(&file) } {
+                Ok(map) => return Ok(Self::Mapped(Arc::new(map))),
+                Err(e) => tracing::debug!(path = %path.display(), error = %e, "内存映射失败，使用缓冲读取"),
+            }
+        }
+        Ok(Self::Buffered(path.to_path_buf()))
+    }
+
+    /// 始终使用缓冲读取
+    pub fn open_buffered(path: &Path) -> Self {
+        Self::Buffered(path.to_path_buf())
+    }
+
+    pub fn is_mapped(&self) -> bool {
+        matches!(self, Self::Mapped(_))
+    }
+
+    /// 归档字节数
+    pub fn size(&self) -> Result<u64> {
+        Ok(match self {
+            Self::Mapped(map) => map.len() as u64,
+            Self::Buffered(path) => std::fs::metadata(path)?.len(),
+            Self::Volumes(set) => set.len(),
+        })
+    }
+
+    /// 创建一个从头开始的独立读取器
+    pub fn reader(&self) -> Result<InputReader> {
+        Ok(match self {
+            Self::Mapped(map) => InputReader::Mapped(Cursor::new(MappedBytes(Arc::clone(map)))),
+            Self::Buffered(path) => InputReader::Buffered(SharedFile::new(File::open(path)?)),
+            Self::Volumes(set) => InputReader::Volumes(VolumeReader::new(Arc::clone(set))),
+        })
+    }
+}
+
+/// 共享映射的字节视图
+#[derive(Debug, Clone)]
+pub struct MappedBytes(Arc<Mmap>);
+
+impl AsRef<[u8]> for MappedBytes {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+/// [`ArchiveInput`] 的读取器；克隆共享底层数据，保留当前位置，之后各自独立定位
+#[derive(Debug, Clone)]
+pub enum InputReader {
+    Mapped(Cursor<MappedBytes>),
+    Buffered(SharedFile),
+    Volumes(VolumeReader),
+}
+
+impl Read for InputReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Self::Mapped(r) => r.read(buf),
+            Self::Buffered(r) => r.read(buf),
+            Self::Volumes(r) => r.read(buf),
+        }
+    }
+}
+
+impl Seek for InputReader {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        match self {
+            Self::Mapped(r) => r.seek(pos),
+            Self::Buffered(r) => r.seek(pos),
+            Self::Volumes(r) => r.seek(pos),
+        }
+    }
+}
+
+/// 共享文件句柄的缓冲读取器：按位置读取，不改变句柄的文件偏移
+#[derive(Debug)]
+pub struct SharedFile {
+    file: Arc<File>,
+    /// `buf[cursor]` 对应的文件位置
+    pos: u64,
+    buf: Vec<u8>,
+    cursor: usize,
+}
+
+impl SharedFile {
+    fn new(file: File) -> Self {
+        Self { file: Arc::new(file), pos: 0, buf: Vec::new(), cursor: 0 }
+    }
+}
+
+impl Read for SharedFile {
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        if self.cursor == self.buf.len() {
+            // 大块读取绕过缓冲
+            if out.len() >= BUF_SIZE {
+                let n = read_at(&self.file, out, self.pos)?;
+                self.pos += n as u64;
+                return Ok(n);
+            }
+            self.buf.resize(BUF_SIZE, 0);
+            let n = read_at(&self.file, &mut self.buf, self.pos)?;
+            self.buf.truncate(n);
+            self.cursor = 0;
+        }
+        let n = out.len().min(self.buf.len() - self.cursor);
+        out[..n].copy_from_slice(&self.buf[self.cursor..self.cursor + n]);
+        self.cursor += n;
+        self.pos += n as u64;
+        Ok(n)
+    }
+}
+
+impl Seek for SharedFile {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let target = match pos {
+            SeekFrom::Start(n) => Some(n),
+            SeekFrom::End(n) => self.file.metadata()?.len().checked_add_signed(n),
+            SeekFrom::Current(n) => self.pos.checked_add_signed(n),
+        };
+        let target = target.ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "定位到文件起点之前"))?;
+        // 目标仍在缓冲区内时只移动游标
+        let buf_start = self.pos - self.cursor as u64;
+        if target >= buf_start && target <= buf_start + self.buf.len() as u64 {
+            self.cursor = (target - buf_start) as usize;
+        } else {
+            self.buf.clear();
+            self.cursor = 0;
+        }
+        self.pos = target;
+        Ok(target)
+    }
+}
+
+impl Clone for SharedFile {
+    /// 共享文件句柄，不复制缓冲区
+    fn clone(&self) -> Self {
+        Self { file: Arc::clone(&self.file), pos: self.pos, buf: Vec::new(), cursor: 0 }
+    }
+}
+
+#[cfg(unix)]
+fn read_at(file: &File, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+    std::os::unix::fs::FileExt::read_at(file, buf, offset)
+}
+
+#[cfg(windows)]
+fn read_at(file: &File, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+    std::os::windows::fs::FileExt::seek_read(file, buf, offset)
+}
+
+fn mmap_allowed(file: &File) -> bool {
+    if std::env::var_os(NO_MMAP_ENV).is_some_and(|v| !v.is_empty()) {
+        return false;
+    }
+    match file.metadata() {
+        // 空文件无法映射；管道、设备等非普通文件只能顺序读取
+        Ok(meta) if meta.is_file() && meta.len() > 0 => !is_network_fs(file),
+        _ => false,
+    }
+}
+
+/// 网络文件系统上映射的页面可能被远端修改或在断连时触发 SIGBUS
+#[cfg(target_os = "linux")]
+fn is_network_fs(file: &File) -> bool {
+    use std::os::unix::io::AsRawFd;
+
+    const NFS: u32 = 0x6969;
+    const SMB: u32 = 0x517B;
+    const CIFS: u32 = 0xFF53_4D42;
+    const SMB2: u32 = 0xFE53_4D42;
+    const FUSE: u32 = 0x6573_5546;
+    const CEPH: u32 = 0x00C3_6400;
+    const AFS: u32 = 0x5346_414F;
+
+    let mut st = std::mem::MaybeUninit::<libc::statfs>::uninit();
+    // SAFETY: fd 在 file 存活期间有效，st 由 fstatfs 填充
+    if unsafe { libc::fstatfs(file.as_raw_fd(), st.as_mut_ptr()) } != 0 {
+        return false;
+    }
+    let kind = unsafe { st.assume_init() }.f_type as u32;
+    matches!(kind, NFS | SMB | CIFS | SMB2 | FUSE | CEPH | AFS)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn is_network_fs(_file: &File) -> bool {
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn sample_file() -> (tempfile::TempDir, PathBuf) {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("data.bin");
+        let data: Vec<u8> = (0..600_000u32).map(|i| (i % 251) as u8).collect();
+        File::create(&path).unwrap().write_all(&data).unwrap();
+        (dir, path)
+    }
+
+    fn read_at(reader: &mut InputReader, pos: u64, len: usize) -> Vec<u8> {
+        reader.seek(SeekFrom::Start(pos)).unwrap();
+        let mut buf = vec![0u8; len];
+        reader.read_exact(&mut buf).unwrap();
+        buf
+    }
+
+    #[test]
+    fn test_mapped_and_buffered_agree() {
+        let (_dir, path) = sample_file();
+        let mapped = ArchiveInput::open(&path).unwrap();
+        let buffered = ArchiveInput::open_buffered(&path);
+        assert_eq!(mapped.size().unwrap(), buffered.size().unwrap());
+
+        let (mut a, mut b) = (mapped.reader().unwrap(), buffered.reader().unwrap());
+        for pos in [0, 1, 300_001, 599_990] {
+            assert_eq!(read_at(&mut a, pos, 10), read_at(&mut b, pos, 10));
+        }
+        assert_eq!(a.seek(SeekFrom::End(-1)).unwrap(), 599_999);
+    }
+
+    #[test]
+    fn test_cloned_readers_independent() {
+        let (_dir, path) = sample_file();
+        for input in [ArchiveInput::open(&path).unwrap(), ArchiveInput::open_buffered(&path)] {
+            let mut first = input.reader().unwrap();
+            let expected = read_at(&mut first, 1000, 4);
+            let mut second = first.clone();
+
+            // 克隆保留当前位置，之后互不影响
+            let next = read_at(&mut first.clone(), 1004, 4);
+            let mut buf = [0u8; 4];
+            second.read_exact(&mut buf).unwrap();
+            assert_eq!(buf.to_vec(), next);
+            first.seek(SeekFrom::Start(0)).unwrap();
+            second.read_exact(&mut buf).unwrap();
+            assert_eq!(buf.to_vec(), read_at(&mut input.reader().unwrap(), 1008, 4));
+            assert_ne!(expected, buf.to_vec());
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_clone_shares_handle() {
+        // 克隆不重新打开文件：归档被删除后克隆仍可读取
+        let (_dir, path) = sample_file();
+        let mut reader = ArchiveInput::open_buffered(&path).reader().unwrap();
+        let expected = read_at(&mut reader, 500_000, 8);
+        std::fs::remove_file(&path).unwrap();
+        let mut cloned = reader.clone();
+        assert_eq!(read_at(&mut cloned, 500_000, 8), expected);
+        assert_eq!(cloned.seek(SeekFrom::End(0)).unwrap(), 600_000);
+    }
+
+    #[test]
+    fn test_empty_file_falls_back() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("empty");
+        File::create(&path).unwrap();
+        let input = ArchiveInput::open(&path).unwrap();
+        assert!(!input.is_mapped());
+        assert_eq!(input.size().unwrap(), 0);
+    }
+}
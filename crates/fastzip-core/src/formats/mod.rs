@@ -1,19 +1,38 @@
 //! 各压缩格式适配器
 
+mod codec;
 mod detect;
+mod entry_reader;
+mod inflate;
+mod input;
 mod single;
 mod zip_format;
 mod sevenz_format;
 mod tar_format;
+mod tar_index;
+mod volume;
+mod zip_lzma;
 
 #[cfg(feature = "unrar")]
 mod rar_format;
 
+pub use codec::{open_decoder, BgzfWriter, Codec, Encoder, SeekableZstdWriter};
+pub(crate) use codec::Framing;
 pub use detect::{detect_format, ArchiveFormat};
+pub use entry_reader::{open_entry, open_entry_path, EntryReader};
+pub use inflate::DeflateCheckpoint;
+pub use input::{ArchiveInput, InputReader, MappedBytes, SharedFile, NO_MMAP_ENV};
 pub use single::extract_single_compressed;
 pub use zip_format::ZipExtractor;
+pub(crate) use zip_format::{entry_mtime, open_zip_entry, zip_datetime, zip_datetime_utc};
+pub(crate) use zip_lzma::{mark_lzma, LzmaEncoder};
+#[cfg(feature = "unrar")]
+pub(crate) use zip_format::dos_unix_secs;
 pub use sevenz_format::SevenZExtractor;
+pub(crate) use sevenz_format::{seven_err, unix_mode};
 pub use tar_format::TarExtractor;
+pub use tar_index::{Checkpoint, IndexEntry, TarIndex};
+pub use volume::{byte_volume_path, logical_path, open_sequential, zip_volume_path, VolumeReader, VolumeSet};
 
 #[cfg(feature = "unrar")]
 pub use rar_format::RarExtractor;
@@ -2,10 +2,13 @@
 
 #![cfg(feature = "unrar")]
 
+use std::fs::File;
+use std::io;
 use std::path::Path;
 
 use unrar::Archive;
 
+use crate::compress::EntryHashes;
 use crate::error::{FastZipError, Result};
 use crate::smart_dest::TopLevelEntries;
 
@@ -60,11 +63,22 @@ impl RarExtractor {
             entries: top_level,
             single_root_dir,
             single_file,
+            ..Default::default()
         })
     }
 
     /// 解压到指定目录
     pub fn extract(path: &Path, dest: &Path, password: Option<&str>) -> Result<()> {
+        Self::extract_hashed(path, dest, password, None)
+    }
+
+    /// 同 [`Self::extract`]，提供 `hashes` 时记录每个文件的摘要
+    pub(crate) fn extract_hashed(
+        path: &Path,
+        dest: &Path,
+        password: Option<&str>,
+        mut hashes: Option<&mut EntryHashes>,
+    ) -> Result<()> {
         let archive = if let Some(pwd) = password {
             Archive::with_password(path, pwd.as_bytes())
         } else {
@@ -83,9 +97,15 @@ impl RarExtractor {
                 Ok(None) => break,
                 Err(e) => return Err(FastZipError::Other(format!("RAR 读取: {}", e))),
             };
+            let (name, is_file) = (next.entry().filename.clone(), !next.entry().is_directory());
             open = next
                 .extract_with_base(dest)
                 .map_err(|e| FastZipError::Other(format!("RAR 解压: {}", e)))?;
+            // unrar 直接写出文件，无法接管写入：写完后立即读回该文件计算摘要
+            if let Some(hashes) = hashes.as_deref_mut().filter(|_| is_file) {
+                let name = name.to_string_lossy();
+                hashes.copy(&name, &mut File::open(dest.join(&*name))?, &mut io::sink())?;
+            }
         }
 
         Ok(())
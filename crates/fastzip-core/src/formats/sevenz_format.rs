@@ -1,10 +1,18 @@
 //! 7z 格式解压
 
-use std::path::Path;
+use std::io;
+use std::path::{Path, PathBuf};
 
-use sevenz_rust::{decompress_file, decompress_file_with_password, Archive, Password};
+use sevenz_rust::{
+    decompress, decompress_with_extract_fn_and_password, decompress_with_password, default_entry_extract_fn, Archive,
+    Password, SevenZArchiveEntry, SevenZReader,
+};
 
+use crate::compress::{EntryHashes, HashTee};
 use crate::error::{FastZipError, Result};
+use crate::formats::entry_reader::not_a_file;
+use crate::formats::{ArchiveInput, EntryReader};
+use crate::hook::TaskHook;
 use crate::smart_dest::TopLevelEntries;
 
 /// 7z 格式解压器
@@ -13,7 +21,8 @@ pub struct SevenZExtractor;
 impl SevenZExtractor {
     /// 列出顶层条目
     pub fn list_top_level(path: &Path) -> Result<TopLevelEntries> {
-        let archive = Archive::open(path)
+        let input = ArchiveInput::open(path)?;
+        let archive = Archive::read(&mut input.reader()?, input.size()?, &[])
             .map_err(|e| FastZipError::SevenZ(e.to_string()))?;
 
         let mut top_level: Vec<String> = archive
@@ -46,17 +55,90 @@ impl SevenZExtractor {
             entries: top_level,
             single_root_dir,
             single_file,
+            ..Default::default()
         })
     }
 
+    /// 打开单个文件条目供随机读取：7z 无法按偏移定位，条目整体解到临时缓存
+    pub fn open_entry(path: &Path, name: &str, password: Option<&str>) -> Result<EntryReader> {
+        let input = ArchiveInput::open(path)?;
+        let password = password.map_or_else(Password::empty, Password::from);
+        let mut reader = SevenZReader::new(input.reader()?, input.size()?, password).map_err(seven_err)?;
+        let entry = reader.archive().files.iter().find(|e| e.name() == name);
+        match entry {
+            None => return Err(FastZipError::FileNotFound(PathBuf::from(name))),
+            Some(entry) if entry.is_directory() => return Err(not_a_file(name)),
+            Some(entry) if entry.size() == 0 => return EntryReader::cached(&mut io::empty()),
+            Some(_) => {}
+        }
+        let mut opened = None;
+        reader
+            .for_each_entries(|entry, data| {
+                // 固实块内的条目须按顺序读完，才能继续解码后面的条目
+                if entry.name() != name {
+                    io::copy(data, &mut io::sink())?;
+                    return Ok(true);
+                }
+                opened = Some(EntryReader::cached(data));
+                Ok(false)
+            })
+            .map_err(seven_err)?;
+        opened.ok_or_else(|| FastZipError::FileNotFound(PathBuf::from(name)))?
+    }
+
     /// 解压到指定目录
     pub fn extract(path: &Path, dest: &Path, password: Option<&str>) -> Result<()> {
-        if let Some(pwd) = password {
-            decompress_file_with_password(path, dest, Password::from(pwd))
+        Self::extract_hashed(path, dest, password, None, &TaskHook::default())
+    }
+
+    /// 同 [`Self::extract`]，提供 `hashes` 时在写出每个文件的同时记录其摘要（符号链接不计入）；
+    /// 每个条目、每块数据调用 `hook`
+    pub(crate) fn extract_hashed(
+        path: &Path,
+        dest: &Path,
+        password: Option<&str>,
+        mut hashes: Option<&mut EntryHashes>,
+        hook: &TaskHook,
+    ) -> Result<()> {
+        let reader = ArchiveInput::open(path)?.reader()?;
+        if hashes.is_some() || hook.is_set() {
+            let password = password.map_or_else(Password::empty, Password::from);
+            decompress_with_extract_fn_and_password(reader, dest, password, |entry, data, path| {
+                hook.entry(entry.name())?;
+                let mut data = hook.reader(data);
+                let link = unix_mode(entry).is_some_and(|m| m & 0o170000 == 0o120000);
+                match hashes.as_deref_mut() {
+                    Some(hashes) if !entry.is_directory() && !link => {
+                        let mut tee = HashTee::new(&mut data, hashes.algorithm());
+                        let extracted = default_entry_extract_fn(entry, &mut tee, path)?;
+                        hashes.insert(entry.name(), tee.finish());
+                        Ok(extracted)
+                    }
+                    _ => default_entry_extract_fn(entry, &mut data, path),
+                }
+            })
+            .map_err(|e: sevenz_rust::Error| FastZipError::SevenZ(e.to_string()))?;
+        } else if let Some(pwd) = password {
+            decompress_with_password(reader, dest, Password::from(pwd))
                 .map_err(|e: sevenz_rust::Error| FastZipError::SevenZ(e.to_string()))?;
         } else {
-            decompress_file(path, dest).map_err(|e: sevenz_rust::Error| FastZipError::SevenZ(e.to_string()))?;
+            decompress(reader, dest).map_err(|e: sevenz_rust::Error| FastZipError::SevenZ(e.to_string()))?;
         }
         Ok(())
     }
 }
+
+/// 条目的 Unix 模式；p7zip 约定属性 bit 15 表示高 16 位为 Unix 模式
+pub(crate) fn unix_mode(entry: &SevenZArchiveEntry) -> Option<u32> {
+    (entry.has_windows_attributes && entry.windows_attributes & 0x8000 != 0).then_some(entry.windows_attributes >> 16)
+}
+
+/// 7z 错误；密码缺失或错误统一为 [`FastZipError::PasswordRequired`]
+pub(crate) fn seven_err(e: sevenz_rust::Error) -> FastZipError {
+    match e {
+        sevenz_rust::Error::PasswordRequired | sevenz_rust::Error::MaybeBadPassword(_) => {
+            FastZipError::PasswordRequired
+        }
+        e => FastZipError::SevenZ(e.to_string()),
+    }
+}
@@ -1,24 +1,20 @@
 //! 单文件压缩格式解压（.gz, .xz, .bz2, .zst）
 
 use std::fs::File;
-use std::io::{BufReader, Read, Write};
+use std::io::Write;
 use std::path::Path;
 
-use bzip2::read::BzDecoder;
-use flate2::read::GzDecoder;
-use xz2::read::XzDecoder;
-use zstd::Decoder;
-
 use crate::error::{FastZipError, Result};
-use crate::formats::ArchiveFormat;
+use crate::formats::{open_decoder, ArchiveFormat};
 
 /// 解压单文件压缩格式到指定目录
-/// 输出文件名 = 输入文件名去掉压缩扩展名
+/// 输出文件名 = 输入文件名去掉压缩扩展名；`threads` 为解码线程数（0 = 自动）
 pub fn extract_single_compressed(
     path: &Path,
     dest_dir: &Path,
     format: ArchiveFormat,
     overwrite: bool,
+    threads: usize,
 ) -> Result<std::path::PathBuf> {
     let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("output");
     // 对于 .tar.gz，file_stem 可能是 "archive.tar"，需要再去掉 .tar
@@ -38,16 +34,11 @@ pub fn extract_single_compressed(
         return Ok(out_path);
     }
 
-    let file = File::open(path)?;
-    let reader = BufReader::new(file);
-
-    let mut decoder: Box<dyn Read> = match format {
-        ArchiveFormat::Gz => Box::new(GzDecoder::new(reader)),
-        ArchiveFormat::Xz => Box::new(XzDecoder::new(reader)),
-        ArchiveFormat::Bz2 => Box::new(BzDecoder::new(reader)),
-        ArchiveFormat::Zst => Box::new(Decoder::new(reader)?),
+    let codec = match format.codec() {
+        Some(codec) if format.is_single_compressed() => codec,
         _ => return Err(FastZipError::UnsupportedFormat(format!("{:?}", format))),
     };
+    let mut decoder = open_decoder(File::open(path)?, codec, threads)?;
 
     let mut out_file = File::create(&out_path)?;
     std::io::copy(&mut decoder, &mut out_file)?;
@@ -1,89 +1,210 @@
 //! TAR 格式解压（含 tar.gz, tar.xz, tar.bz2, tar.zst）
 
-use std::fs::File;
-use std::io::{BufReader, Read};
-use std::path::Path;
+use std::cell::RefCell;
+use std::io::{BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::Arc;
 
-use bzip2::read::BzDecoder;
-use flate2::read::GzDecoder;
-use tar::Archive;
-use xz2::read::XzDecoder;
-use zstd::Decoder;
+use tar::{Archive, EntryType};
 
+use crate::compress::{EntryHashes, Hasher};
 use crate::error::{FastZipError, Result};
-use crate::formats::ArchiveFormat;
+use crate::formats::entry_reader::not_a_file;
+use crate::formats::{open_decoder, open_sequential, ArchiveFormat, ArchiveInput, EntryReader, TarIndex};
 use crate::smart_dest::TopLevelEntries;
 
 /// TAR 格式解压器
 pub struct TarExtractor;
 
 impl TarExtractor {
-    /// 列出顶层条目
+    /// 列出顶层条目；存在有效的 sidecar 索引时无需解压
     pub fn list_top_level(path: &Path, format: ArchiveFormat) -> Result<TopLevelEntries> {
-        let reader = Self::open_decoder(path, format)?;
-        let mut archive = Archive::new(reader);
+        if let Some(index) = TarIndex::load(path, format)? {
+            return Ok(index.top_level());
+        }
 
-        let mut top_level: Vec<String> = Vec::new();
-        let mut count = 0;
-        let mut has_single_file_at_root = false;
+        let reader = Self::open_decoder(path, format, 0)?;
+        let mut archive = Archive::new(reader);
 
+        let mut paths = Vec::new();
         for entry in archive.entries().map_err(|e| FastZipError::Tar(e.to_string()))? {
             let e = entry.map_err(|e| FastZipError::Tar(e.to_string()))?;
-            count += 1;
             let path = e.path().map_err(|e| FastZipError::Tar(e.to_string()))?;
-            let path_str = path.to_string_lossy();
-            let parts: Vec<&str> = path_str.split(['/', '\\']).collect();
-            if parts.is_empty() || parts[0].is_empty() {
-                continue;
-            }
-            let top = parts[0].to_string();
-            if !top_level.contains(&top) {
-                top_level.push(top);
-            }
-            if count == 1 && parts.len() == 1 && !path_str.ends_with('/') {
-                has_single_file_at_root = true;
-            } else if count > 1 {
-                has_single_file_at_root = false;
-            }
+            paths.push(path.to_string_lossy().into_owned());
         }
+        Ok(top_level_from_paths(paths.iter().map(String::as_str)))
+    }
 
-        top_level.sort();
-        top_level.dedup();
+    /// 读取 sidecar 索引；不存在或已过期时重新构建并保存
+    pub fn index(path: &Path, format: ArchiveFormat) -> Result<TarIndex> {
+        if let Some(index) = TarIndex::load(path, format)? {
+            return Ok(index);
+        }
+        let index = TarIndex::build(path, format)?;
+        if let Err(e) = index.save(path) {
+            tracing::warn!(path = %path.display(), error = %e, "无法写入索引文件");
+        }
+        Ok(index)
+    }
 
-        let single_file = count == 1 && has_single_file_at_root;
-        let single_root_dir = if top_level.len() == 1 && count > 1 {
-            Some(top_level[0].clone())
-        } else {
-            None
-        };
+    /// 借助索引读取单个条目，写入 `out`，返回字节数
+    pub fn extract_entry(path: &Path, format: ArchiveFormat, name: &str, out: &mut dyn Write) -> Result<u64> {
+        Self::index(path, format)?.read_entry(path, name, out)
+    }
+
+    /// 借助索引打开单个文件条目供随机读取：tar 直接按偏移读，tar.* 经索引的解码重启点定位
+    pub fn open_entry(path: &Path, format: ArchiveFormat, name: &str) -> Result<EntryReader> {
+        let index = Self::index(path, format)?;
+        let not_found = || FastZipError::FileNotFound(PathBuf::from(name));
+        let mut entry = index.find(name).ok_or_else(not_found)?;
+        // 硬链接共用先前条目的数据
+        if entry.entry_type == b'1' {
+            entry = entry.link_name.as_deref().and_then(|link| index.find(link)).ok_or_else(not_found)?;
+        }
+        if !matches!(entry.entry_type, b'0' | b'\0' | b'7') {
+            return Err(not_a_file(name));
+        }
+        let (offset, size) = (entry.data_offset, entry.size);
+        Self::open_indexed(path, Arc::new(index), offset, size)
+    }
 
-        Ok(TopLevelEntries {
-            entries: top_level,
-            single_root_dir,
-            single_file,
-        })
+    /// 打开解压后 tar 流中 `offset` 处、长 `size` 的条目数据；tar 直接按偏移读，tar.* 经索引定位
+    pub(crate) fn open_indexed(path: &Path, index: Arc<TarIndex>, offset: u64, size: u64) -> Result<EntryReader> {
+        if index.format == ArchiveFormat::Tar {
+            return Ok(EntryReader::direct(ArchiveInput::open(path)?.reader()?, offset, size));
+        }
+        Ok(EntryReader::tar(path, index, offset, size))
     }
 
-    fn open_decoder(path: &Path, format: ArchiveFormat) -> Result<Box<dyn Read + Send>> {
-        let file = File::open(path)?;
-        let reader = BufReader::new(file);
+    fn open_decoder(path: &Path, format: ArchiveFormat, threads: usize) -> Result<Box<dyn Read + Send>> {
+        let file = open_sequential(path)?;
 
         let decoder: Box<dyn Read + Send> = match format {
-            ArchiveFormat::Tar => Box::new(reader),
-            ArchiveFormat::TarGz => Box::new(GzDecoder::new(reader)),
-            ArchiveFormat::TarXz => Box::new(XzDecoder::new(reader)),
-            ArchiveFormat::TarBz2 => Box::new(BzDecoder::new(reader)),
-            ArchiveFormat::TarZst => Box::new(Decoder::new(reader)?),
+            ArchiveFormat::Tar => Box::new(BufReader::new(file)),
+            ArchiveFormat::TarGz | ArchiveFormat::TarXz | ArchiveFormat::TarBz2 | ArchiveFormat::TarZst => {
+                let codec = format.codec().expect("tar.* 格式均有外层编码");
+                open_decoder(file, codec, threads)?
+            }
             _ => return Err(FastZipError::UnsupportedFormat(format!("{:?}", format))),
         };
         Ok(decoder)
     }
 
-    /// 解压到指定目录
-    pub fn extract(path: &Path, dest: &Path, format: ArchiveFormat) -> Result<()> {
-        let reader = Self::open_decoder(path, format)?;
-        let mut archive = Archive::new(reader);
-        archive.unpack(dest).map_err(|e| FastZipError::Tar(e.to_string()))?;
+    /// 解压到指定目录；`threads` 为解码线程数（0 = 自动）
+    pub fn extract(path: &Path, dest: &Path, format: ArchiveFormat, threads: usize) -> Result<()> {
+        Self::extract_hashed(path, dest, format, threads, None)
+    }
+
+    /// 同 [`Self::extract`]，提供 `hashes` 时在写出每个文件的同时记录其摘要
+    pub(crate) fn extract_hashed(
+        path: &Path,
+        dest: &Path,
+        format: ArchiveFormat,
+        threads: usize,
+        hashes: Option<&mut EntryHashes>,
+    ) -> Result<()> {
+        Self::unpack(Self::open_decoder(path, format, threads)?, dest, &mut |_| Ok(()), hashes)
+    }
+
+    /// 从已解码的 tar 流解压到 `dest`，每个条目写出前调用 `on_entry(条目路径)`
+    ///
+    /// 与 `tar::Archive::unpack` 相同：目录条目推迟到最后由深到浅创建，避免目录权限妨碍写入其中的文件。
+    /// 提供 `hashes` 时记录普通文件条目的摘要（硬链接等不计入）。
+    pub(crate) fn unpack(
+        reader: impl Read,
+        dest: &Path,
+        on_entry: &mut dyn FnMut(&str) -> Result<()>,
+        mut hashes: Option<&mut EntryHashes>,
+    ) -> Result<()> {
+        let tar_err = |e: std::io::Error| FastZipError::Tar(e.to_string());
+        std::fs::create_dir_all(dest)?;
+        let dest = &dest.canonicalize().unwrap_or_else(|_| dest.to_path_buf());
+        let hasher = Rc::new(RefCell::new(None));
+        let mut archive = Archive::new(EntryTee { inner: reader, hasher: Rc::clone(&hasher) });
+        let mut directories = Vec::new();
+        for entry in archive.entries().map_err(tar_err)? {
+            let mut entry = entry.map_err(tar_err)?;
+            let name = String::from_utf8_lossy(&entry.path_bytes()).into_owned();
+            on_entry(&name)?;
+            let entry_type = entry.header().entry_type();
+            if entry_type == EntryType::Directory {
+                directories.push(entry);
+                continue;
+            }
+            // 条目数据只在 unpack_in 中读出，期间经过读取器的字节即文件内容
+            if let Some(hashes) = &hashes {
+                if entry_type.is_file() || entry_type.is_contiguous() {
+                    *hasher.borrow_mut() = Some(Hasher::new(hashes.algorithm()));
+                }
+            }
+            let unpacked = entry.unpack_in(dest).map_err(tar_err)?;
+            let digest = hasher.borrow_mut().take();
+            if let (Some(hashes), Some(digest), true) = (hashes.as_deref_mut(), digest, unpacked) {
+                hashes.insert(&name, digest.finish());
+            }
+        }
+        directories.sort_by(|a, b| b.path_bytes().cmp(&a.path_bytes()));
+        for mut dir in directories {
+            dir.unpack_in(dest).map_err(tar_err)?;
+        }
         Ok(())
     }
 }
+
+/// tar 流的读取器：设置了摘要时顺带计算读出的字节
+struct EntryTee<R> {
+    inner: R,
+    hasher: Rc<RefCell<Option<Hasher>>>,
+}
+
+impl<R: Read> Read for EntryTee<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        if let Some(hasher) = self.hasher.borrow_mut().as_mut() {
+            hasher.update(&buf[..n]);
+        }
+        Ok(n)
+    }
+}
+
+/// 由条目路径计算顶层条目
+pub(crate) fn top_level_from_paths<'a>(paths: impl IntoIterator<Item = &'a str>) -> TopLevelEntries {
+    let mut top_level: Vec<String> = Vec::new();
+    let mut count = 0;
+    let mut has_single_file_at_root = false;
+
+    for path_str in paths {
+        count += 1;
+        let parts: Vec<&str> = path_str.split(['/', '\\']).collect();
+        if parts.is_empty() || parts[0].is_empty() {
+            continue;
+        }
+        let top = parts[0].to_string();
+        if !top_level.contains(&top) {
+            top_level.push(top);
+        }
+        if count == 1 && parts.len() == 1 && !path_str.ends_with('/') {
+            has_single_file_at_root = true;
+        } else if count > 1 {
+            has_single_file_at_root = false;
+        }
+    }
+
+    top_level.sort();
+    top_level.dedup();
+
+    let single_file = count == 1 && has_single_file_at_root;
+    let single_root_dir = if top_level.len() == 1 && count > 1 {
+        Some(top_level[0].clone())
+    } else {
+        None
+    };
+
+    TopLevelEntries {
+        entries: top_level,
+        single_root_dir,
+        single_file,
+        ..Default::default()
+    }
+}
@@ -0,0 +1,637 @@
+//! tar.* 随机访问索引（sidecar 文件 `<archive>.fzidx`）
+//!
+//! 记录每个条目在解压后 tar 流中的偏移，以及解码重启点：zstd 帧起点、xz 块、
+//! gzip deflate 块边界断点。构建一次后，列出条目无需解压，读取单个条目只需
+//! 从最近的重启点开始解码。
+
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Chain, Cursor, Read, Seek, SeekFrom, Take, Write};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use tar::Archive;
+use xz2::read::XzDecoder;
+use zstd::stream::raw::{Decoder as RawZstdDecoder, InBuffer, Operation, OutBuffer};
+
+use crate::error::{FastZipError, Result};
+use crate::formats::codec::{append_xz_tail, invalid, read_varint};
+use crate::formats::inflate::Inflater;
+use crate::formats::tar_format::top_level_from_paths;
+use crate::formats::{open_decoder, ArchiveFormat, DeflateCheckpoint};
+use crate::smart_dest::TopLevelEntries;
+
+/// gzip 断点间隔（每个断点需保存 32 KiB 窗口）
+const GZIP_SPAN: u64 = 4 * 1024 * 1024;
+/// zstd 帧起点记录间隔
+const ZSTD_SPAN: u64 = 1024 * 1024;
+
+const INDEX_MAGIC: &[u8; 8] = b"FZIDX\x01\x00\x00";
+
+/// 索引中的一个 tar 条目
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexEntry {
+    /// 归档内原始路径（目录通常以 `/` 结尾）
+    pub path: String,
+    /// tar 条目类型字节（`0` 文件、`5` 目录、`2` 符号链接等）
+    pub entry_type: u8,
+    pub size: u64,
+    pub mode: u32,
+    pub mtime: u64,
+    /// 条目头在解压后 tar 流中的偏移
+    pub header_offset: u64,
+    /// 条目数据在解压后 tar 流中的偏移
+    pub data_offset: u64,
+    /// 链接目标（符号链接/硬链接）
+    pub link_name: Option<String>,
+}
+
+impl IndexEntry {
+    pub fn is_dir(&self) -> bool {
+        self.entry_type == b'5'
+    }
+}
+
+/// 解码重启点
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Checkpoint {
+    /// zstd 帧起点：从该压缩偏移可直接开始解码
+    Frame { compressed: u64, uncompressed: u64 },
+    /// gzip deflate 块边界
+    Deflate(DeflateCheckpoint),
+    /// xz 块（每个块可单独包装为完整 xz 流解码）
+    XzBlock {
+        compressed: u64,
+        uncompressed: u64,
+        stream_flags: [u8; 2],
+        unpadded: u64,
+        size: u64,
+    },
+}
+
+impl Checkpoint {
+    /// 在解压后 tar 流中的偏移
+    pub fn uncompressed(&self) -> u64 {
+        match self {
+            Self::Frame { uncompressed, .. } | Self::XzBlock { uncompressed, .. } => *uncompressed,
+            Self::Deflate(cp) => cp.uncompressed,
+        }
+    }
+}
+
+/// tar.* 随机访问索引
+#[derive(Debug, Clone)]
+pub struct TarIndex {
+    pub format: ArchiveFormat,
+    pub entries: Vec<IndexEntry>,
+    /// 按解压后偏移升序排列
+    pub checkpoints: Vec<Checkpoint>,
+    /// 归档文件大小与修改时间，用于判断索引是否过期
+    stamp: (u64, u64, u32),
+}
+
+impl TarIndex {
+    /// sidecar 索引文件路径
+    pub fn sidecar_path(archive: &Path) -> PathBuf {
+        let mut name = archive.as_os_str().to_os_string();
+        name.push(".fzidx");
+        PathBuf::from(name)
+    }
+
+    /// 完整解码一遍归档，记录条目偏移与重启点
+    pub fn build(path: &Path, format: ArchiveFormat) -> Result<Self> {
+        let file = File::open(path)?;
+        let stamp = file_stamp(&file)?;
+
+        let source = match format {
+            ArchiveFormat::Tar => Source::Plain(BufReader::new(file)),
+            ArchiveFormat::TarGz => {
+                Source::Gzip(Box::new(Inflater::new(file).record_checkpoints(GZIP_SPAN)))
+            }
+            ArchiveFormat::TarZst => Source::Zstd(ZstdFrames::new(BufReader::new(file))?),
+            ArchiveFormat::TarXz | ArchiveFormat::TarBz2 => {
+                Source::Stream(open_decoder(file, format.codec().expect("tar.* 格式均有外层编码"), 0)?)
+            }
+            _ => return Err(FastZipError::UnsupportedFormat(format!("{:?}", format))),
+        };
+
+        let mut archive = Archive::new(source);
+        let mut entries = Vec::new();
+        for entry in archive.entries().map_err(|e| FastZipError::Tar(e.to_string()))? {
+            let e = entry.map_err(|e| FastZipError::Tar(e.to_string()))?;
+            let header = e.header();
+            entries.push(IndexEntry {
+                path: String::from_utf8_lossy(&e.path_bytes()).into_owned(),
+                entry_type: header.entry_type().as_byte(),
+                size: e.size(),
+                mode: header.mode().unwrap_or(0o644),
+                mtime: header.mtime().unwrap_or(0),
+                header_offset: e.raw_header_position(),
+                data_offset: e.raw_file_position(),
+                link_name: e
+                    .link_name_bytes()
+                    .map(|l| String::from_utf8_lossy(&l).into_owned()),
+            });
+        }
+
+        let checkpoints = match archive.into_inner() {
+            Source::Gzip(inflater) => inflater
+                .into_checkpoints()
+                .into_iter()
+                .map(Checkpoint::Deflate)
+                .collect(),
+            Source::Zstd(frames) => frames.checkpoints,
+            _ if format == ArchiveFormat::TarXz => xz_blocks(&mut File::open(path)?)?,
+            _ => Vec::new(),
+        };
+
+        Ok(Self {
+            format,
+            entries,
+            checkpoints,
+            stamp,
+        })
+    }
+
+    /// 读取 sidecar 索引；不存在、已损坏或与归档不匹配时返回 None
+    pub fn load(archive: &Path, format: ArchiveFormat) -> Result<Option<Self>> {
+        let sidecar = Self::sidecar_path(archive);
+        if !sidecar.exists() {
+            return Ok(None);
+        }
+        let stamp = file_stamp(&File::open(archive)?)?;
+        match Self::read_from(&sidecar) {
+            Ok(index) if index.stamp == stamp && index.format == format => Ok(Some(index)),
+            Ok(_) => Ok(None),
+            Err(e) => {
+                tracing::warn!(path = %sidecar.display(), error = %e, "索引文件无效，已忽略");
+                Ok(None)
+            }
+        }
+    }
+
+    /// 写入 sidecar 索引
+    pub fn save(&self, archive: &Path) -> Result<()> {
+        let sidecar = Self::sidecar_path(archive);
+        let mut file = io::BufWriter::new(File::create(&sidecar)?);
+        file.write_all(INDEX_MAGIC)?;
+        let mut w = zstd::Encoder::new(file, 3)?;
+        self.write_payload(&mut w)?;
+        w.finish()?.flush()?;
+        Ok(())
+    }
+
+    /// 顶层条目（规则同 [`crate::formats::TarExtractor::list_top_level`]）
+    pub fn top_level(&self) -> TopLevelEntries {
+        top_level_from_paths(self.entries.iter().map(|e| e.path.as_str()))
+    }
+
+    /// 按归档内路径查找条目（忽略开头的 `./` 与结尾的 `/`）
+    pub fn find(&self, name: &str) -> Option<&IndexEntry> {
+        let name = normalize(name);
+        self.entries.iter().find(|e| normalize(&e.path) == name)
+    }
+
+    /// 打开一个定位到解压后 tar 流 `offset` 处的读取器
+    pub fn open_at(&self, archive: &Path, offset: u64) -> Result<Box<dyn Read + Send>> {
+        let mut file = File::open(archive)?;
+        if self.format == ArchiveFormat::Tar {
+            file.seek(SeekFrom::Start(offset))?;
+            return Ok(Box::new(BufReader::new(file)));
+        }
+
+        let i = self.checkpoints.partition_point(|c| c.uncompressed() <= offset);
+        let (mut reader, start): (Box<dyn Read + Send>, u64) = match i.checked_sub(1).map(|i| (i, &self.checkpoints[i])) {
+            None => {
+                let codec = self.format.codec().expect("tar.* 格式均有外层编码");
+                (open_decoder(file, codec, 0)?, 0)
+            }
+            Some((_, Checkpoint::Frame { compressed, uncompressed })) => {
+                file.seek(SeekFrom::Start(*compressed))?;
+                (Box::new(zstd::Decoder::new(file)?), *uncompressed)
+            }
+            Some((_, Checkpoint::Deflate(cp))) => {
+                file.seek(SeekFrom::Start(cp.compressed))?;
+                (Box::new(Inflater::resume(file, cp)), cp.uncompressed)
+            }
+            Some((i, Checkpoint::XzBlock { uncompressed, .. })) => {
+                (Box::new(XzBlocks::new(file, self.checkpoints[i..].to_vec())), *uncompressed)
+            }
+        };
+        io::copy(&mut (&mut reader).take(offset - start), &mut io::sink())?;
+        Ok(reader)
+    }
+
+    /// 读取单个条目的内容写入 `out`，返回字节数
+    pub fn read_entry(&self, archive: &Path, name: &str, out: &mut dyn Write) -> Result<u64> {
+        let entry = self
+            .find(name)
+            .ok_or_else(|| FastZipError::FileNotFound(PathBuf::from(name)))?;
+        let reader = self.open_at(archive, entry.data_offset)?;
+        Ok(io::copy(&mut reader.take(entry.size), out)?)
+    }
+
+    fn write_payload(&self, w: &mut dyn Write) -> io::Result<()> {
+        w.write_all(&[format_tag(self.format)])?;
+        w.write_all(&self.stamp.0.to_le_bytes())?;
+        w.write_all(&self.stamp.1.to_le_bytes())?;
+        w.write_all(&self.stamp.2.to_le_bytes())?;
+
+        w.write_all(&(self.entries.len() as u64).to_le_bytes())?;
+        for e in &self.entries {
+            write_bytes(w, e.path.as_bytes())?;
+            w.write_all(&[e.entry_type])?;
+            w.write_all(&e.size.to_le_bytes())?;
+            w.write_all(&e.mode.to_le_bytes())?;
+            w.write_all(&e.mtime.to_le_bytes())?;
+            w.write_all(&e.header_offset.to_le_bytes())?;
+            w.write_all(&e.data_offset.to_le_bytes())?;
+            match &e.link_name {
+                Some(link) => {
+                    w.write_all(&[1])?;
+                    write_bytes(w, link.as_bytes())?;
+                }
+                None => w.write_all(&[0])?,
+            }
+        }
+
+        w.write_all(&(self.checkpoints.len() as u64).to_le_bytes())?;
+        for cp in &self.checkpoints {
+            match cp {
+                Checkpoint::Frame { compressed, uncompressed } => {
+                    w.write_all(&[0])?;
+                    w.write_all(&compressed.to_le_bytes())?;
+                    w.write_all(&uncompressed.to_le_bytes())?;
+                }
+                Checkpoint::Deflate(cp) => {
+                    w.write_all(&[1])?;
+                    w.write_all(&cp.compressed.to_le_bytes())?;
+                    w.write_all(&cp.uncompressed.to_le_bytes())?;
+                    w.write_all(&[cp.bits, cp.bit_buf])?;
+                    write_bytes(w, &cp.window)?;
+                }
+                Checkpoint::XzBlock {
+                    compressed,
+                    uncompressed,
+                    stream_flags,
+                    unpadded,
+                    size,
+                } => {
+                    w.write_all(&[2])?;
+                    w.write_all(&compressed.to_le_bytes())?;
+                    w.write_all(&uncompressed.to_le_bytes())?;
+                    w.write_all(stream_flags)?;
+                    w.write_all(&unpadded.to_le_bytes())?;
+                    w.write_all(&size.to_le_bytes())?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn read_from(sidecar: &Path) -> io::Result<Self> {
+        let mut file = BufReader::new(File::open(sidecar)?);
+        let mut magic = [0u8; 8];
+        file.read_exact(&mut magic)?;
+        if &magic != INDEX_MAGIC {
+            return Err(invalid("索引文件头无效"));
+        }
+        let r = &mut zstd::Decoder::new(file)?;
+
+        let format = format_from_tag(read_u8(r)?).ok_or_else(|| invalid("索引格式无效"))?;
+        let stamp = (read_u64(r)?, read_u64(r)?, read_u32(r)?);
+
+        let count = read_u64(r)?;
+        let mut entries = Vec::new();
+        for _ in 0..count {
+            entries.push(IndexEntry {
+                path: read_string(r)?,
+                entry_type: read_u8(r)?,
+                size: read_u64(r)?,
+                mode: read_u32(r)?,
+                mtime: read_u64(r)?,
+                header_offset: read_u64(r)?,
+                data_offset: read_u64(r)?,
+                link_name: match read_u8(r)? {
+                    0 => None,
+                    _ => Some(read_string(r)?),
+                },
+            });
+        }
+
+        let count = read_u64(r)?;
+        let mut checkpoints = Vec::new();
+        for _ in 0..count {
+            checkpoints.push(match read_u8(r)? {
+                0 => Checkpoint::Frame {
+                    compressed: read_u64(r)?,
+                    uncompressed: read_u64(r)?,
+                },
+                1 => Checkpoint::Deflate(DeflateCheckpoint {
+                    compressed: read_u64(r)?,
+                    uncompressed: read_u64(r)?,
+                    bits: read_u8(r)?,
+                    bit_buf: read_u8(r)?,
+                    window: read_bytes(r)?,
+                }),
+                2 => Checkpoint::XzBlock {
+                    compressed: read_u64(r)?,
+                    uncompressed: read_u64(r)?,
+                    stream_flags: [read_u8(r)?, read_u8(r)?],
+                    unpadded: read_u64(r)?,
+                    size: read_u64(r)?,
+                },
+                _ => return Err(invalid("索引重启点类型无效")),
+            });
+        }
+
+        Ok(Self {
+            format,
+            entries,
+            checkpoints,
+            stamp,
+        })
+    }
+}
+
+/// 构建索引时的解码源
+enum Source {
+    Plain(BufReader<File>),
+    Gzip(Box<Inflater<File>>),
+    Zstd(ZstdFrames<BufReader<File>>),
+    Stream(Box<dyn Read + Send>),
+}
+
+impl Read for Source {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Self::Plain(r) => r.read(buf),
+            Self::Gzip(r) => r.read(buf),
+            Self::Zstd(r) => r.read(buf),
+            Self::Stream(r) => r.read(buf),
+        }
+    }
+}
+
+/// 逐帧解码 zstd，记录帧起点
+struct ZstdFrames<R> {
+    input: R,
+    decoder: RawZstdDecoder<'static>,
+    compressed: u64,
+    uncompressed: u64,
+    at_boundary: bool,
+    checkpoints: Vec<Checkpoint>,
+}
+
+impl<R: BufRead> ZstdFrames<R> {
+    fn new(input: R) -> io::Result<Self> {
+        Ok(Self {
+            input,
+            decoder: RawZstdDecoder::new()?,
+            compressed: 0,
+            uncompressed: 0,
+            at_boundary: true,
+            checkpoints: Vec::new(),
+        })
+    }
+}
+
+impl<R: BufRead> Read for ZstdFrames<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        loop {
+            let data = self.input.fill_buf()?;
+            if data.is_empty() && self.at_boundary {
+                return Ok(0);
+            }
+            let mut input = InBuffer::around(data);
+            let mut output = OutBuffer::around(&mut *buf);
+            let hint = self.decoder.run(&mut input, &mut output)?;
+            let (consumed, written) = (input.pos(), output.pos());
+            self.input.consume(consumed);
+            self.compressed += consumed as u64;
+            self.uncompressed += written as u64;
+
+            self.at_boundary = hint == 0;
+            if self.at_boundary {
+                let last = self.checkpoints.last().map_or(0, Checkpoint::uncompressed);
+                if self.uncompressed - last >= ZSTD_SPAN {
+                    self.checkpoints.push(Checkpoint::Frame {
+                        compressed: self.compressed,
+                        uncompressed: self.uncompressed,
+                    });
+                }
+            } else if consumed == 0 && written == 0 && data_exhausted(&mut self.input)? {
+                return Err(io::ErrorKind::UnexpectedEof.into());
+            }
+            if written > 0 {
+                return Ok(written);
+            }
+        }
+    }
+}
+
+fn data_exhausted(input: &mut dyn BufRead) -> io::Result<bool> {
+    Ok(input.fill_buf()?.is_empty())
+}
+
+/// 从 xz 文件尾部的索引读出所有块的位置与大小
+fn xz_blocks(file: &mut File) -> Result<Vec<Checkpoint>> {
+    let mut streams = Vec::new();
+    let mut pos = file.seek(SeekFrom::End(0))?;
+    let mut word = [0u8; 4];
+    while pos > 0 {
+        // 跳过流填充
+        file.seek(SeekFrom::Start(pos.checked_sub(4).ok_or_else(|| invalid("xz 流尾无效"))?))?;
+        file.read_exact(&mut word)?;
+        if word == [0; 4] {
+            pos -= 4;
+            continue;
+        }
+
+        let mut footer = [0u8; 12];
+        let footer_start = pos.checked_sub(12).ok_or_else(|| invalid("xz 流尾无效"))?;
+        file.seek(SeekFrom::Start(footer_start))?;
+        file.read_exact(&mut footer)?;
+        if &footer[10..] != b"YZ" {
+            return Err(FastZipError::Tar("xz 流尾无效".into()));
+        }
+        let backward = (u32::from_le_bytes([footer[4], footer[5], footer[6], footer[7]]) as u64 + 1) * 4;
+        let stream_flags = [footer[8], footer[9]];
+
+        let index_start = footer_start.checked_sub(backward).ok_or_else(|| invalid("xz 索引大小超出文件"))?;
+        let mut index = vec![0u8; backward as usize];
+        file.seek(SeekFrom::Start(index_start))?;
+        file.read_exact(&mut index)?;
+        let mut fields = &index[1..];
+        let count = read_varint(&mut fields)?;
+        let mut records = Vec::new();
+        for _ in 0..count {
+            records.push((read_varint(&mut fields)?, read_varint(&mut fields)?));
+        }
+
+        let blocks_len: u64 = records.iter().map(|(unpadded, _)| unpadded.div_ceil(4) * 4).sum();
+        let stream_start = index_start
+            .checked_sub(blocks_len + 12)
+            .ok_or_else(|| FastZipError::Tar("xz 索引无效".into()))?;
+        streams.push((stream_start + 12, stream_flags, records));
+        pos = stream_start;
+    }
+
+    let mut checkpoints = Vec::new();
+    let mut uncompressed = 0;
+    for (mut compressed, stream_flags, records) in streams.into_iter().rev() {
+        for (unpadded, size) in records {
+            checkpoints.push(Checkpoint::XzBlock {
+                compressed,
+                uncompressed,
+                stream_flags,
+                unpadded,
+                size,
+            });
+            compressed += unpadded.div_ceil(4) * 4;
+            uncompressed += size;
+        }
+    }
+    Ok(checkpoints)
+}
+
+/// 单个 xz 块的输入：补出的流头 + 文件中的块 + 补出的索引与流尾
+type BlockInput = Chain<Chain<Cursor<Vec<u8>>, Take<File>>, Cursor<Vec<u8>>>;
+
+/// 从某个 xz 块开始逐块流式解码
+struct XzBlocks {
+    file: File,
+    blocks: std::vec::IntoIter<Checkpoint>,
+    decoder: Option<XzDecoder<BlockInput>>,
+}
+
+impl XzBlocks {
+    fn new(file: File, blocks: Vec<Checkpoint>) -> Self {
+        Self {
+            file,
+            blocks: blocks.into_iter(),
+            decoder: None,
+        }
+    }
+
+    /// 把下一块包装为只含一个块的 xz 流；没有更多块时返回 false
+    fn next_block(&mut self) -> io::Result<bool> {
+        self.decoder = None;
+        let Some(Checkpoint::XzBlock {
+            compressed,
+            stream_flags,
+            unpadded,
+            size,
+            ..
+        }) = self.blocks.next()
+        else {
+            return Ok(false);
+        };
+
+        let mut head = Vec::with_capacity(12);
+        head.extend_from_slice(b"\xfd7zXZ\x00");
+        head.extend_from_slice(&stream_flags);
+        head.extend_from_slice(&crc32fast::hash(&stream_flags).to_le_bytes());
+        let mut tail = Vec::new();
+        append_xz_tail(&mut tail, &stream_flags, unpadded, size);
+
+        let mut file = self.file.try_clone()?;
+        file.seek(SeekFrom::Start(compressed))?;
+        let block = Cursor::new(head).chain(file.take(unpadded.div_ceil(4) * 4)).chain(Cursor::new(tail));
+        self.decoder = Some(XzDecoder::new(block));
+        Ok(true)
+    }
+}
+
+impl Read for XzBlocks {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        loop {
+            if let Some(decoder) = self.decoder.as_mut() {
+                let n = decoder.read(buf)?;
+                if n > 0 {
+                    return Ok(n);
+                }
+            }
+            if !self.next_block()? {
+                return Ok(0);
+            }
+        }
+    }
+}
+
+fn normalize(name: &str) -> &str {
+    name.trim_start_matches("./").trim_end_matches('/')
+}
+
+fn file_stamp(file: &File) -> io::Result<(u64, u64, u32)> {
+    let meta = file.metadata()?;
+    let mtime = meta
+        .modified()?
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    Ok((meta.len(), mtime.as_secs(), mtime.subsec_nanos()))
+}
+
+fn format_tag(format: ArchiveFormat) -> u8 {
+    match format {
+        ArchiveFormat::TarGz => 1,
+        ArchiveFormat::TarXz => 2,
+        ArchiveFormat::TarBz2 => 3,
+        ArchiveFormat::TarZst => 4,
+        _ => 0,
+    }
+}
+
+fn format_from_tag(tag: u8) -> Option<ArchiveFormat> {
+    Some(match tag {
+        0 => ArchiveFormat::Tar,
+        1 => ArchiveFormat::TarGz,
+        2 => ArchiveFormat::TarXz,
+        3 => ArchiveFormat::TarBz2,
+        4 => ArchiveFormat::TarZst,
+        _ => return None,
+    })
+}
+
+fn write_bytes(w: &mut dyn Write, bytes: &[u8]) -> io::Result<()> {
+    w.write_all(&(bytes.len() as u64).to_le_bytes())?;
+    w.write_all(bytes)
+}
+
+fn read_u8(r: &mut dyn Read) -> io::Result<u8> {
+    let mut b = [0u8; 1];
+    r.read_exact(&mut b)?;
+    Ok(b[0])
+}
+
+fn read_u32(r: &mut dyn Read) -> io::Result<u32> {
+    let mut b = [0u8; 4];
+    r.read_exact(&mut b)?;
+    Ok(u32::from_le_bytes(b))
+}
+
+fn read_u64(r: &mut dyn Read) -> io::Result<u64> {
+    let mut b = [0u8; 8];
+    r.read_exact(&mut b)?;
+    Ok(u64::from_le_bytes(b))
+}
+
+fn read_bytes(r: &mut dyn Read) -> io::Result<Vec<u8>> {
+    let len = read_u64(r)?;
+    let mut buf = Vec::new();
+    r.take(len).read_to_end(&mut buf)?;
+    if buf.len() as u64 != len {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+    Ok(buf)
+}
+
+fn read_string(r: &mut dyn Read) -> io::Result<String> {
+    String::from_utf8(read_bytes(r)?).map_err(|_| invalid("索引路径不是 UTF-8"))
+}
+
@@ -0,0 +1,301 @@
+//! 分卷归档：按字节切分的 `<归档>.001`、`.002`…（7z、tar.*）与 ZIP 分卷 `.z01`…`.zip`
+//!
+//! 各卷依次拼接为一个只读视图。ZIP 分卷的偏移相对各卷起点，视图末尾换成
+//! 改写为绝对偏移的中央目录，zip 库即可按普通 ZIP 读取。
+
+use std::ffi::OsString;
+use std::fs::{self, File};
+use std::io::{self, BufReader, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::compress::{join_split_central, read_split_end};
+use crate::error::{FastZipError, Result};
+
+const BUF_SIZE: usize = 256 * 1024;
+
+/// 第 `n` 个字节分卷（从 1 开始）：`a.7z` → `a.7z.001`
+pub fn byte_volume_path(base: &Path, n: u32) -> PathBuf {
+    let mut name = OsString::from(base.as_os_str());
+    name.push(format!(".{:03}", n));
+    PathBuf::from(name)
+}
+
+/// 第 `n` 个 ZIP 分卷（从 1 开始，末卷为 `.zip` 本身）：`a.zip` → `a.z01`
+pub fn zip_volume_path(base: &Path, n: u32) -> PathBuf {
+    base.with_extension(format!("z{:02}", n))
+}
+
+/// 分卷所属归档的逻辑路径：`a.tar.gz.002` → `a.tar.gz`，`a.z01` → `a.zip`，其余原样返回
+pub fn logical_path(path: &Path) -> PathBuf {
+    match volume_suffix(path) {
+        Some(Suffix::Bytes) => path.with_extension(""),
+        Some(Suffix::Zip) => path.with_extension("zip"),
+        None => path.to_path_buf(),
+    }
+}
+
+enum Suffix {
+    /// `.001` 等
+    Bytes,
+    /// `.z01` 等
+    Zip,
+}
+
+fn volume_suffix(path: &Path) -> Option<Suffix> {
+    let ext = path.extension()?.to_str()?;
+    let digits = |s: &str| !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit());
+    if ext.len() >= 3 && digits(ext) {
+        return Some(Suffix::Bytes);
+    }
+    match ext.strip_prefix(['z', 'Z']) {
+        Some(n) if n.len() >= 2 && digits(n) => Some(Suffix::Zip),
+        _ => None,
+    }
+}
+
+/// 分卷的拼接视图
+#[derive(Debug)]
+pub struct VolumeSet {
+    pieces: Vec<Piece>,
+    /// 各片段在视图中的起点
+    starts: Vec<u64>,
+    len: u64,
+}
+
+#[derive(Debug)]
+enum Piece {
+    /// 文件开头的 `len` 字节
+    File { path: PathBuf, len: u64 },
+    Bytes(Vec<u8>),
+}
+
+impl Piece {
+    fn len(&self) -> u64 {
+        match self {
+            Self::File { len, .. } => *len,
+            Self::Bytes(bytes) => bytes.len() as u64,
+        }
+    }
+}
+
+impl VolumeSet {
+    /// 查找 `path` 所属的分卷，不是分卷归档时返回 None
+    ///
+    /// `path` 可以是任意一卷（`a.7z.002`、`a.z01`）、ZIP 分卷的末卷 `a.zip`，
+    /// 或存在 `.001` 分卷的逻辑名（`a.tar.gz`）。
+    pub fn find(path: &Path) -> Result<Option<Self>> {
+        match volume_suffix(path) {
+            Some(Suffix::Bytes) => return Self::bytes(&logical_path(path)).map(Some),
+            Some(Suffix::Zip) => return Self::zip(&logical_path(path)).map(Some),
+            None => {}
+        }
+        let is_zip = path.extension().is_some_and(|e| e.eq_ignore_ascii_case("zip"));
+        if is_zip && zip_volume_path(path, 1).is_file() {
+            return Self::zip(path).map(Some);
+        }
+        if !path.exists() && byte_volume_path(path, 1).is_file() {
+            return Self::bytes(path).map(Some);
+        }
+        Ok(None)
+    }
+
+    /// 按字节切分的分卷：从 `.001` 起依次拼接，直到缺少下一卷
+    fn bytes(base: &Path) -> Result<Self> {
+        let mut paths = Vec::new();
+        loop {
+            let path = byte_volume_path(base, paths.len() as u32 + 1);
+            if !path.is_file() {
+                break;
+            }
+            paths.push(path);
+        }
+        if paths.is_empty() {
+            return Err(FastZipError::FileNotFound(byte_volume_path(base, 1)));
+        }
+        Self::files(paths)
+    }
+
+    /// ZIP 分卷：卷数取自末卷的结束记录，中央目录改写为绝对偏移
+    fn zip(base: &Path) -> Result<Self> {
+        if !base.is_file() {
+            return Err(FastZipError::FileNotFound(base.to_path_buf()));
+        }
+        let disks = read_split_end(&mut File::open(base)?, &[])?.disks.max(1);
+        let mut paths: Vec<PathBuf> = (1..disks).map(|n| zip_volume_path(base, n)).collect();
+        if let Some(missing) = paths.iter().find(|p| !p.is_file()) {
+            return Err(FastZipError::FileNotFound(missing.clone()));
+        }
+        paths.push(base.to_path_buf());
+
+        let full = Arc::new(Self::files(paths)?);
+        let (cd_start, tail) = join_split_central(&mut VolumeReader::new(Arc::clone(&full)), &full.starts)?;
+        let full = Arc::try_unwrap(full).expect("读取器已释放");
+        let mut pieces = Vec::with_capacity(full.pieces.len() + 1);
+        for (piece, start) in full.pieces.into_iter().zip(full.starts) {
+            if let Piece::File { path, len } = piece {
+                let len = len.min(cd_start.saturating_sub(start));
+                if len > 0 {
+                    pieces.push(Piece::File { path, len });
+                }
+            }
+        }
+        pieces.push(Piece::Bytes(tail));
+        Ok(Self::from_pieces(pieces))
+    }
+
+    fn files(paths: Vec<PathBuf>) -> Result<Self> {
+        let pieces = paths
+            .into_iter()
+            .map(|path| Ok(Piece::File { len: fs::metadata(&path)?.len(), path }))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self::from_pieces(pieces))
+    }
+
+    fn from_pieces(mut pieces: Vec<Piece>) -> Self {
+        pieces.retain(|p| p.len() > 0);
+        let mut starts = Vec::with_capacity(pieces.len());
+        let mut len = 0;
+        for piece in &pieces {
+            starts.push(len);
+            len += piece.len();
+        }
+        Self { pieces, starts, len }
+    }
+
+    /// 视图的总字节数
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// 卷数
+    pub fn volumes(&self) -> usize {
+        self.pieces.iter().filter(|p| matches!(p, Piece::File { .. })).count()
+    }
+}
+
+/// 分卷视图的读取器：只打开当前所在的卷；克隆得到位置相同的独立读取器
+#[derive(Debug)]
+pub struct VolumeReader {
+    set: Arc<VolumeSet>,
+    pos: u64,
+    /// 当前打开的片段序号、文件内位置与文件
+    open: Option<(usize, u64, BufReader<File>)>,
+}
+
+impl VolumeReader {
+    pub fn new(set: Arc<VolumeSet>) -> Self {
+        Self { set, pos: 0, open: None }
+    }
+}
+
+impl Clone for VolumeReader {
+    fn clone(&self) -> Self {
+        Self { set: Arc::clone(&self.set), pos: self.pos, open: None }
+    }
+}
+
+impl Read for VolumeReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.pos >= self.set.len || buf.is_empty() {
+            return Ok(0);
+        }
+        let index = self.set.starts.partition_point(|&s| s <= self.pos) - 1;
+        let offset = self.pos - self.set.starts[index];
+        let piece = &self.set.pieces[index];
+        let want = buf.len().min((piece.len() - offset) as usize);
+        let n = match piece {
+            Piece::Bytes(bytes) => {
+                buf[..want].copy_from_slice(&bytes[offset as usize..offset as usize + want]);
+                want
+            }
+            Piece::File { path, .. } => {
+                if !matches!(self.open, Some((i, _, _)) if i == index) {
+                    self.open = Some((index, 0, BufReader::with_capacity(BUF_SIZE, File::open(path)?)));
+                }
+                let (_, at, reader) = self.open.as_mut().expect("已打开");
+                if *at != offset {
+                    reader.seek(SeekFrom::Start(offset))?;
+                }
+                let n = reader.read(&mut buf[..want])?;
+                if n == 0 {
+                    return Err(io::Error::new(
+                        io::ErrorKind::UnexpectedEof,
+                        format!("分卷被截断: {}", path.display()),
+                    ));
+                }
+                *at = offset + n as u64;
+                n
+            }
+        };
+        self.pos += n as u64;
+        Ok(n)
+    }
+}
+
+impl Seek for VolumeReader {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let target = match pos {
+            SeekFrom::Start(n) => Some(n),
+            SeekFrom::End(d) => self.set.len.checked_add_signed(d),
+            SeekFrom::Current(d) => self.pos.checked_add_signed(d),
+        };
+        self.pos = target.ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "定位到归档开头之前"))?;
+        Ok(self.pos)
+    }
+}
+
+/// 顺序读取归档文件：分卷时依次读取各卷
+pub fn open_sequential(path: &Path) -> Result<Box<dyn Read + Send>> {
+    Ok(match VolumeSet::find(path)? {
+        Some(set) => Box::new(VolumeReader::new(Arc::new(set))),
+        None => Box::new(File::open(path)?),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_volume_names() {
+        assert_eq!(byte_volume_path(Path::new("a.tar.gz"), 2), Path::new("a.tar.gz.002"));
+        assert_eq!(zip_volume_path(Path::new("d/a.zip"), 3), Path::new("d/a.z03"));
+        assert_eq!(logical_path(Path::new("a.7z.001")), Path::new("a.7z"));
+        assert_eq!(logical_path(Path::new("a.z12")), Path::new("a.zip"));
+        assert_eq!(logical_path(Path::new("a.zip")), Path::new("a.zip"));
+        assert_eq!(logical_path(Path::new("a.zst")), Path::new("a.zst"));
+    }
+
+    #[test]
+    fn test_byte_volumes_read_and_seek() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let base = dir.path().join("data.tar");
+        let data: Vec<u8> = (0..1000u32).map(|i| (i % 251) as u8).collect();
+        for (n, chunk) in data.chunks(300).enumerate() {
+            fs::write(byte_volume_path(&base, n as u32 + 1), chunk).unwrap();
+        }
+        for path in [base.clone(), byte_volume_path(&base, 3)] {
+            let set = VolumeSet::find(&path).unwrap().unwrap();
+            assert_eq!((set.len(), set.volumes()), (1000, 4));
+            let mut reader = VolumeReader::new(Arc::new(set));
+            let mut all = Vec::new();
+            reader.read_to_end(&mut all).unwrap();
+            assert_eq!(all, data);
+
+            // 跨卷读取
+            reader.seek(SeekFrom::Start(295)).unwrap();
+            let mut buf = [0u8; 10];
+            reader.read_exact(&mut buf).unwrap();
+            assert_eq!(buf[..], data[295..305]);
+            let mut clone = reader.clone();
+            clone.read_exact(&mut buf).unwrap();
+            assert_eq!(buf[..], data[305..315]);
+        }
+        assert!(VolumeSet::find(&dir.path().join("other.tar")).unwrap().is_none());
+    }
+}
@@ -1,35 +1,41 @@
 //! ZIP 格式解压（含加密 ZIP 密码解压）
 
 use std::fs::{self, File};
-use std::io::Write;
-use std::path::Path;
+use std::io::{self, Read, Seek, Write};
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
 
-use zip::ZipArchive;
+use chrono::{Local, LocalResult, TimeZone};
+use zip::extra_fields::ExtraField;
+use zip::read::ZipFile;
+use zip::{CompressionMethod, ZipArchive};
 
+use crate::compress::EntryHashes;
 use crate::error::{FastZipError, Result};
+use crate::formats::entry_reader::not_a_file;
+use crate::formats::zip_lzma::LzmaDecoder;
+use crate::formats::{ArchiveInput, EntryReader, InputReader};
+use crate::hook::TaskHook;
 use crate::smart_dest::TopLevelEntries;
 
 /// ZIP 格式解压器
 pub struct ZipExtractor;
 
 impl ZipExtractor {
-    /// 列出顶层条目（用于智能解压决策）
-    /// 若归档加密且未提供密码，可能无法正确列出，调用方需处理
+    /// 列出顶层条目（用于智能解压决策）与归档、条目注释
+    /// 条目名与注释不加密，加密归档无需密码即可列出
     pub fn list_top_level(path: &Path) -> Result<TopLevelEntries> {
-        let file = File::open(path)?;
-        let mut archive = ZipArchive::new(file)?;
+        let mut archive = ZipArchive::new(ArchiveInput::open(path)?.reader()?)?;
         let len = archive.len();
         let mut entries = Vec::new();
+        let mut entry_comments = Vec::new();
 
         for i in 0..len {
-            let entry = archive.by_index(i).or_else(|e| {
-                if let zip::result::ZipError::InvalidPassword = e {
-                    Err(FastZipError::PasswordRequired)
-                } else {
-                    Err(e.into())
-                }
-            })?;
+            let entry = archive.by_index_raw(i)?;
             let name = entry.name().to_string();
+            if !entry.comment().is_empty() {
+                entry_comments.push((name.clone(), entry.comment().to_string()));
+            }
             let trimmed = name.trim_end_matches('/');
             if trimmed.is_empty() {
                 continue;
@@ -44,14 +50,7 @@ impl ZipExtractor {
         entries.dedup();
 
         let single_file = if len == 1 {
-            let mut a = ZipArchive::new(File::open(path)?)?;
-            let e = a.by_index(0).or_else(|e| {
-                if let zip::result::ZipError::InvalidPassword = e {
-                    Err(FastZipError::PasswordRequired)
-                } else {
-                    Err(e.into())
-                }
-            })?;
+            let e = archive.by_index_raw(0)?;
             !e.name().contains('/') && !e.name().ends_with('/')
         } else {
             false
@@ -63,43 +62,95 @@ impl ZipExtractor {
             None
         };
 
+        let comment = archive.comment();
         Ok(TopLevelEntries {
             entries,
             single_root_dir,
             single_file,
+            comment: (!comment.is_empty()).then(|| String::from_utf8_lossy(comment).into_owned()),
+            entry_comments,
         })
     }
 
     /// 解压到指定目录；若提供 password 则支持加密 ZIP（ZipCrypto/AES）
     pub fn extract(path: &Path, dest: &Path, password: Option<&str>) -> Result<()> {
-        let file = File::open(path)?;
-        let mut archive = ZipArchive::new(file)?;
+        Self::extract_hashed(path, dest, password, None, &TaskHook::default())
+    }
 
-        if let Some(pwd) = password {
-            Self::extract_with_password(&mut archive, dest, pwd)
+    /// 同 [`Self::extract`]，提供 `hashes` 时在写出每个文件的同时记录其摘要；
+    /// 每个条目、每块数据调用 `hook`
+    pub(crate) fn extract_hashed(
+        path: &Path,
+        dest: &Path,
+        password: Option<&str>,
+        hashes: Option<&mut EntryHashes>,
+        hook: &TaskHook,
+    ) -> Result<()> {
+        let mut archive = ZipArchive::new(ArchiveInput::open(path)?.reader()?)?;
+        // zip 库解不了 LZMA 条目，含有时逐条目解压
+        let len = archive.len();
+        let lzma = (0..len).any(|i| archive.by_index_raw(i).is_ok_and(|f| f.compression() == CompressionMethod::Lzma));
+
+        if password.is_some() || hashes.is_some() || lzma || hook.is_set() {
+            Self::extract_entries(&mut archive, dest, password, hashes, hook)?;
         } else {
             archive.extract(dest)?;
-            Ok(())
         }
+        restore_metadata(&mut archive, dest)
+    }
+
+    /// 打开单个文件条目供随机读取
+    ///
+    /// 未加密的存储条目直接按偏移读，未加密的 Deflate 条目经带断点的解码器定位，
+    /// 其余条目（加密或其他压缩方法）整体解到临时缓存。
+    pub fn open_entry(path: &Path, name: &str, password: Option<&str>) -> Result<EntryReader> {
+        let input = ArchiveInput::open(path)?;
+        let mut archive = ZipArchive::new(input.reader()?)?;
+        let index = archive.index_for_name(name).ok_or_else(|| FastZipError::FileNotFound(PathBuf::from(name)))?;
+        if archive.by_index_raw(index)?.is_dir() {
+            return Err(not_a_file(name));
+        }
+        Self::open_index(&input, &mut archive, index, password)
     }
 
-    /// 使用密码逐条解压（兼容加密与非加密条目）
-    fn extract_with_password(
-        archive: &mut ZipArchive<File>,
+    /// 同 [`Self::open_entry`]，按下标打开由 `input` 读出的 `archive` 中的条目
+    pub(crate) fn open_index(
+        input: &ArchiveInput,
+        archive: &mut ZipArchive<InputReader>,
+        index: usize,
+        password: Option<&str>,
+    ) -> Result<EntryReader> {
+        let file = archive.by_index_raw(index)?;
+        if !file.encrypted() {
+            let (start, compressed, size) = (file.data_start(), file.compressed_size(), file.size());
+            match file.compression() {
+                CompressionMethod::Stored => return Ok(EntryReader::direct(input.reader()?, start, size)),
+                CompressionMethod::Deflated => {
+                    return Ok(EntryReader::deflate(input.reader()?, start, compressed, size));
+                }
+                _ => {}
+            }
+        }
+        drop(file);
+        // 先绑定结果，使条目读取器在 archive 之前释放
+        let reader = EntryReader::cached(&mut open_zip_entry(archive, index, password)?);
+        reader
+    }
+
+    /// 逐条解压（兼容加密与非加密条目）
+    ///
+    /// 符号链接在其余条目写完后再创建，避免后续条目经由链接写到目标目录之外。
+    fn extract_entries<R: Read + Seek>(
+        archive: &mut ZipArchive<R>,
         dest: &Path,
-        password: &str,
+        password: Option<&str>,
+        mut hashes: Option<&mut EntryHashes>,
+        hook: &TaskHook,
     ) -> Result<()> {
-        let pwd_bytes = password.as_bytes();
+        let mut links = Vec::new();
         for i in 0..archive.len() {
-            let mut file = archive
-                .by_index_decrypt(i, pwd_bytes)
-                .or_else(|e| {
-                    if let zip::result::ZipError::InvalidPassword = e {
-                        Err(FastZipError::PasswordRequired)
-                    } else {
-                        Err(e.into())
-                    }
-                })?;
+            let mut file = open_zip_entry(archive, i, password)?;
+            hook.entry(file.name())?;
 
             let out_path = if let Some(enclosed) = file.enclosed_name() {
                 dest.join(enclosed)
@@ -109,15 +160,222 @@ impl ZipExtractor {
 
             if file.is_dir() {
                 fs::create_dir_all(&out_path)?;
+            } else if file.is_symlink() {
+                let mut target = String::new();
+                file.read_to_string(&mut target)?;
+                links.push((out_path, target));
             } else {
                 if let Some(p) = out_path.parent() {
                     fs::create_dir_all(p)?;
                 }
                 let mut out_file = File::create(&out_path)?;
-                std::io::copy(&mut file, &mut out_file)?;
+                let name = file.name().to_string();
+                let mut data = hook.reader(&mut file);
+                match hashes.as_deref_mut() {
+                    Some(hashes) => {
+                        hashes.copy(&name, &mut data, &mut out_file)?;
+                    }
+                    None => {
+                        std::io::copy(&mut data, &mut out_file)?;
+                    }
+                }
                 out_file.flush()?;
             }
         }
+        for (path, target) in links {
+            if let Some(p) = path.parent() {
+                fs::create_dir_all(p)?;
+            }
+            make_symlink(&target, &path)?;
+        }
         Ok(())
     }
 }
+
+/// 打开条目的解码读取器，加密条目需要密码
+pub(crate) fn open_zip_entry<'a, R: Read + Seek>(
+    archive: &'a mut ZipArchive<R>,
+    index: usize,
+    password: Option<&str>,
+) -> Result<ZipEntry<'a>> {
+    let (method, encrypted) = {
+        let raw = archive.by_index_raw(index)?;
+        (raw.compression(), raw.encrypted())
+    };
+    match password {
+        // zip 库按 .lzma 文件解析 LZMA 条目，改由本库解码原始数据
+        _ if method == CompressionMethod::Lzma => {
+            if encrypted {
+                return Err(FastZipError::UnsupportedFormat("加密的 LZMA 条目".into()));
+            }
+            let raw = archive.by_index_raw(index)?;
+            let size = raw.size();
+            Ok(ZipEntry::Lzma(LzmaDecoder::new(raw, size)?))
+        }
+        Some(pw) => archive.by_index_decrypt(index, pw.as_bytes()).map(ZipEntry::Zip).map_err(|e| match e {
+            zip::result::ZipError::InvalidPassword => FastZipError::PasswordRequired,
+            e => e.into(),
+        }),
+        None if encrypted => Err(FastZipError::PasswordRequired),
+        None => Ok(ZipEntry::Zip(archive.by_index(index)?)),
+    }
+}
+
+/// 已打开的 ZIP 条目，按解压后内容读取
+// 条目逐个打开、不成批存放，两种变体都不装箱
+#[allow(clippy::large_enum_variant)]
+pub(crate) enum ZipEntry<'a> {
+    Zip(ZipFile<'a>),
+    Lzma(LzmaDecoder<ZipFile<'a>>),
+}
+
+impl<'a> Deref for ZipEntry<'a> {
+    type Target = ZipFile<'a>;
+
+    fn deref(&self) -> &ZipFile<'a> {
+        match self {
+            ZipEntry::Zip(file) => file,
+            ZipEntry::Lzma(decoder) => decoder.get_ref(),
+        }
+    }
+}
+
+impl Read for ZipEntry<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            ZipEntry::Zip(file) => file.read(buf),
+            ZipEntry::Lzma(decoder) => decoder.read(buf),
+        }
+    }
+}
+
+#[cfg(unix)]
+fn make_symlink(target: &str, path: &Path) -> Result<()> {
+    let _ = fs::remove_file(path);
+    std::os::unix::fs::symlink(target, path)?;
+    Ok(())
+}
+
+/// 非 Unix 平台将链接目标写为普通文件
+#[cfg(not(unix))]
+fn make_symlink(target: &str, path: &Path) -> Result<()> {
+    fs::write(path, target)?;
+    Ok(())
+}
+
+/// 按条目记录恢复修改时间与 Unix 权限
+///
+/// 文件先于目录处理，目录由深到浅，避免写入文件改动目录时间或只读目录阻止后续设置；
+/// 符号链接保持原样。
+fn restore_metadata<R: Read + Seek>(archive: &mut ZipArchive<R>, dest: &Path) -> Result<()> {
+    let mut dirs = Vec::new();
+    for i in 0..archive.len() {
+        let file = archive.by_index_raw(i)?;
+        let Some(path) = file.enclosed_name().map(|name| dest.join(name)) else { continue };
+        if file.is_symlink() {
+            continue;
+        }
+        let meta = (entry_mtime(&file), file.unix_mode());
+        if file.is_dir() {
+            dirs.push((path, meta));
+        } else {
+            apply_metadata(&path, meta)?;
+        }
+    }
+    for (path, meta) in dirs.into_iter().rev() {
+        apply_metadata(&path, meta)?;
+    }
+    Ok(())
+}
+
+fn apply_metadata(path: &Path, (mtime, mode): (Option<i64>, Option<u32>)) -> Result<()> {
+    if let Some(secs) = mtime {
+        filetime::set_file_mtime(path, filetime::FileTime::from_unix_time(secs, 0))?;
+    }
+    #[cfg(unix)]
+    if let Some(mode) = mode {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(path, fs::Permissions::from_mode(mode & 0o7777))?;
+    }
+    #[cfg(not(unix))]
+    let _ = mode;
+    Ok(())
+}
+
+/// Unix 秒转换为 ZIP 的 DOS 时间（按本地时间记录，与其他 ZIP 工具一致；超出 1980–2107 时截断到边界）
+pub(crate) fn zip_datetime(secs: i64) -> zip::DateTime {
+    zip_datetime_utc(secs + local_offset(secs))
+}
+
+/// 同 [`zip_datetime`]，按 UTC 记录（可复现输出不随时区变化）
+pub(crate) fn zip_datetime_utc(secs: i64) -> zip::DateTime {
+    let days = secs.div_euclid(86_400);
+    let rem = secs.rem_euclid(86_400);
+    let (year, month, day) = civil_from_days(days);
+    let time = match year {
+        ..1980 => zip::DateTime::from_date_and_time(1980, 1, 1, 0, 0, 0),
+        2108.. => zip::DateTime::from_date_and_time(2107, 12, 31, 23, 59, 58),
+        _ => zip::DateTime::from_date_and_time(
+            year as u16,
+            month,
+            day,
+            (rem / 3600) as u8,
+            (rem / 60 % 60) as u8,
+            (rem % 60) as u8,
+        ),
+    };
+    time.unwrap_or_default()
+}
+
+/// ZIP 条目的修改时间（Unix 秒）：优先取扩展时间戳，否则按本地时间解释 DOS 时间
+pub(crate) fn entry_mtime(file: &zip::read::ZipFile<'_>) -> Option<i64> {
+    let extended = file.extra_data_fields().find_map(|field| match field {
+        ExtraField::ExtendedTimestamp(ts) => ts.mod_time(),
+        _ => None,
+    });
+    if let Some(secs) = extended {
+        return Some(secs as i64);
+    }
+    file.last_modified().map(dos_unix_secs)
+}
+
+/// DOS 时间按本地时间解释为 Unix 秒
+///
+/// 夏令时切换造成的重复或缺失时刻按切换前后的偏移近似。
+pub(crate) fn dos_unix_secs(t: zip::DateTime) -> i64 {
+    let days = days_from_civil(t.year() as i64, t.month(), t.day());
+    let local = days * 86_400 + t.hour() as i64 * 3600 + t.minute() as i64 * 60 + t.second() as i64;
+    local - local_offset(local - local_offset(local))
+}
+
+/// `secs` 时刻本地时间相对 UTC 的偏移（秒）
+fn local_offset(secs: i64) -> i64 {
+    match Local.timestamp_opt(secs, 0) {
+        LocalResult::Single(t) | LocalResult::Ambiguous(t, _) => t.offset().local_minus_utc() as i64,
+        LocalResult::None => 0,
+    }
+}
+
+/// 自 1970-01-01 起的天数转换为（年, 月, 日）
+fn civil_from_days(days: i64) -> (i64, u8, u8) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u8;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u8;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+fn days_from_civil(year: i64, month: u8, day: u8) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let mp = (month as i64 + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
@@ -0,0 +1,198 @@
+//! ZIP 中的 LZMA（方法 14）
+//!
+//! 条目数据以 4 字节头（LZMA SDK 版本、属性长度）与 5 字节属性开头，随后是裸 LZMA1 数据流。
+//! zip 库不能写入该方法，读取时又把数据当作 `.lzma` 文件解析，因此编解码均由本模块完成：
+//! 借助 liblzma 的 `.lzma` 编解码器，在其 13 字节文件头（属性 + 解压后大小）与 ZIP 的属性头之间转换。
+
+use std::io::{self, Chain, Cursor, Read, Seek, SeekFrom, Write};
+
+use xz2::read::XzDecoder;
+use xz2::stream::{LzmaOptions, Stream};
+use xz2::write::XzEncoder;
+use zip::read::ZipFile;
+use zip::ZipArchive;
+
+use crate::error::Result;
+use crate::formats::codec::invalid;
+
+/// ZIP 方法号
+pub(crate) const LZMA_METHOD: u16 = 14;
+/// 通用标志 bit 1：LZMA 数据以结束标记收尾
+const EOS_FLAG: u16 = 1 << 1;
+/// 写入属性头的 LZMA SDK 版本
+const SDK_VERSION: [u8; 2] = [9, 20];
+/// `.lzma` 文件头：5 字节属性 + 8 字节解压后大小
+const ALONE_HEADER_LEN: usize = 13;
+
+/// ZIP LZMA 编码器，数据以结束标记收尾
+pub(crate) struct LzmaEncoder<W: Write> {
+    inner: XzEncoder<HeaderWriter<W>>,
+}
+
+impl<W: Write> LzmaEncoder<W> {
+    /// `level` 为 0–9
+    pub fn new(inner: W, level: u32) -> io::Result<Self> {
+        let options = LzmaOptions::new_preset(level.min(9))?;
+        let stream = Stream::new_lzma_encoder(&options)?;
+        let header = HeaderWriter { inner, head: Vec::with_capacity(ALONE_HEADER_LEN) };
+        Ok(Self { inner: XzEncoder::new_stream(header, stream) })
+    }
+
+    pub fn finish(self) -> io::Result<W> {
+        Ok(self.inner.finish()?.inner)
+    }
+}
+
+impl<W: Write> Write for LzmaEncoder<W> {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        self.inner.write(data)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// 把 `.lzma` 文件头改写为 ZIP 的属性头，其余数据原样写出
+struct HeaderWriter<W> {
+    inner: W,
+    head: Vec<u8>,
+}
+
+impl<W: Write> Write for HeaderWriter<W> {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        if self.head.len() == ALONE_HEADER_LEN {
+            return self.inner.write(data);
+        }
+        let n = (ALONE_HEADER_LEN - self.head.len()).min(data.len());
+        self.head.extend_from_slice(&data[..n]);
+        if self.head.len() == ALONE_HEADER_LEN {
+            let mut header = SDK_VERSION.to_vec();
+            header.extend_from_slice(&5u16.to_le_bytes());
+            header.extend_from_slice(&self.head[..5]);
+            self.inner.write_all(&header)?;
+        }
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// ZIP LZMA 解码器
+///
+/// 按未知大小解码，输出满 `size` 字节即停止，故有无结束标记的数据都能读取。
+pub(crate) struct LzmaDecoder<R: Read> {
+    inner: XzDecoder<Chain<Cursor<Vec<u8>>, R>>,
+    remaining: u64,
+}
+
+impl<R: Read> LzmaDecoder<R> {
+    /// `raw` 为条目的原始数据，`size` 为解压后大小
+    pub fn new(mut raw: R, size: u64) -> io::Result<Self> {
+        let mut head = [0u8; 4];
+        raw.read_exact(&mut head)?;
+        if u16::from_le_bytes([head[2], head[3]]) != 5 {
+            return Err(invalid("LZMA 属性头无效"));
+        }
+        let mut alone = vec![0u8; 5];
+        raw.read_exact(&mut alone)?;
+        alone.extend_from_slice(&u64::MAX.to_le_bytes());
+        let stream = Stream::new_lzma_decoder(u64::MAX)?;
+        Ok(Self { inner: XzDecoder::new_stream(Cursor::new(alone).chain(raw), stream), remaining: size })
+    }
+
+    pub fn get_ref(&self) -> &R {
+        self.inner.get_ref().get_ref().1
+    }
+}
+
+impl<R: Read> Read for LzmaDecoder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = buf.len().min(usize::try_from(self.remaining).unwrap_or(usize::MAX));
+        if len == 0 {
+            return Ok(0);
+        }
+        let n = self.inner.read(&mut buf[..len])?;
+        if n == 0 {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        self.remaining -= n as u64;
+        Ok(n)
+    }
+}
+
+/// 把选中的条目标为 LZMA：本地头与中央目录中的方法改为 14，并置结束标记标志
+///
+/// 写入端以 Stored 写入已编码的数据，写完后由此改写条目头。
+pub(crate) fn mark_lzma<F: Read + Write + Seek>(
+    file: &mut F,
+    select: impl Fn(&ZipFile<'_>) -> bool,
+) -> Result<()> {
+    let mut headers = Vec::new();
+    {
+        let mut archive = ZipArchive::new(&mut *file)?;
+        for i in 0..archive.len() {
+            let entry = archive.by_index_raw(i)?;
+            if select(&entry) {
+                headers.push((entry.header_start(), entry.central_header_start()));
+            }
+        }
+    }
+
+    for (local, central) in headers {
+        // 本地文件头：标志位于 +6，方法 +8；中央目录：标志 +8，方法 +10
+        patch_method(file, local + 6)?;
+        patch_method(file, central + 8)?;
+    }
+    file.flush()?;
+    Ok(())
+}
+
+/// 在 `at` 处的通用标志中置结束标记标志，并把其后的方法改为 LZMA
+fn patch_method<F: Read + Write + Seek>(file: &mut F, at: u64) -> Result<()> {
+    let mut flags = [0u8; 2];
+    file.seek(SeekFrom::Start(at))?;
+    file.read_exact(&mut flags)?;
+    let flags = u16::from_le_bytes(flags) | EOS_FLAG;
+    file.seek(SeekFrom::Start(at))?;
+    file.write_all(&flags.to_le_bytes())?;
+    file.write_all(&LZMA_METHOD.to_le_bytes())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_roundtrip() {
+        let data: Vec<u8> = (0..200_000).map(|i| (i * 7 % 251) as u8).collect();
+        let mut encoder = LzmaEncoder::new(Vec::new(), 6).unwrap();
+        encoder.write_all(&data).unwrap();
+        let encoded = encoder.finish().unwrap();
+        assert_eq!(&encoded[..4], &[9, 20, 5, 0]);
+
+        let mut out = Vec::new();
+        LzmaDecoder::new(&encoded[..], data.len() as u64)
+            .unwrap()
+            .read_to_end(&mut out)
+            .unwrap();
+        assert_eq!(out, data);
+    }
+
+    #[test]
+    fn test_decode_python_zipfile_entry() {
+        // Python zipfile（ZIP_LZMA）写入的 `b"hello world " * 1000`
+        let raw = "090405005d0000800000341949ee8de917893a335ffd827c64d39cabf237033db007c0fc9abc29cbad\
+                   1008efcfe332b3c4f071dbfbcf884f9843f52d6af68efe59b583b1d9dbfffe918800";
+        let raw: Vec<u8> = (0..raw.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&raw[i..i + 2], 16).unwrap())
+            .collect();
+        let mut out = Vec::new();
+        LzmaDecoder::new(&raw[..], 12_000).unwrap().read_to_end(&mut out).unwrap();
+        assert_eq!(out, b"hello world ".repeat(1000));
+    }
+}
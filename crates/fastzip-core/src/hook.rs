@@ -0,0 +1,94 @@
+//! 后台任务的取消与进度回调：解压缩循环在每个条目、每块数据处检查
+//!
+//! 同步接口使用不做任何事的默认值；异步接口（`async` feature）借此在任务被丢弃后尽快停止。
+
+use std::io::{self, Read};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// 回调收到的事件
+#[cfg_attr(not(feature = "async"), allow(dead_code))]
+pub(crate) enum HookEvent<'a> {
+    /// 开始处理的条目
+    Entry(&'a str),
+    /// 处理了若干字节的条目数据
+    Data(usize),
+}
+
+/// 取消标志与进度回调；可跨线程共享（ZIP 并行压缩时在线程池中调用）
+#[derive(Clone, Default)]
+pub(crate) struct TaskHook(Option<Arc<Inner>>);
+
+struct Inner {
+    cancel: Arc<AtomicBool>,
+    on_event: Box<dyn Fn(HookEvent<'_>) + Send + Sync>,
+}
+
+#[cfg_attr(not(feature = "async"), allow(dead_code))]
+impl TaskHook {
+    pub fn new(cancel: Arc<AtomicBool>, on_event: impl Fn(HookEvent<'_>) + Send + Sync + 'static) -> Self {
+        Self(Some(Arc::new(Inner { cancel, on_event: Box::new(on_event) })))
+    }
+
+    /// 是否设置了回调；未设置时调用方可走不逐条目处理的快速路径
+    pub fn is_set(&self) -> bool {
+        self.0.is_some()
+    }
+
+    /// 开始处理条目 `name`；已取消时返回错误
+    pub fn entry(&self, name: &str) -> io::Result<()> {
+        self.event(HookEvent::Entry(name))
+    }
+
+    /// 处理了 `len` 字节数据；已取消时返回错误
+    pub fn data(&self, len: usize) -> io::Result<()> {
+        self.event(HookEvent::Data(len))
+    }
+
+    /// 包装读取器：每读出一块都经过 [`Self::data`]
+    pub fn reader<R: Read>(&self, inner: R) -> HookReader<R> {
+        HookReader { inner, hook: self.clone(), report: true }
+    }
+
+    /// 同 [`Self::reader`]，但只检查取消、不报告进度（用于同一数据的重复读取）
+    pub fn guard<R: Read>(&self, inner: R) -> HookReader<R> {
+        HookReader { inner, hook: self.clone(), report: false }
+    }
+
+    fn check(&self) -> io::Result<()> {
+        match &self.0 {
+            Some(inner) if inner.cancel.load(Ordering::Relaxed) => Err(cancelled()),
+            _ => Ok(()),
+        }
+    }
+
+    fn event(&self, event: HookEvent<'_>) -> io::Result<()> {
+        self.check()?;
+        if let Some(inner) = &self.0 {
+            (inner.on_event)(event);
+        }
+        Ok(())
+    }
+}
+
+/// 见 [`TaskHook::reader`]
+pub(crate) struct HookReader<R> {
+    inner: R,
+    hook: TaskHook,
+    report: bool,
+}
+
+impl<R: Read> Read for HookReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        match self.report && n > 0 {
+            true => self.hook.data(n)?,
+            false => self.hook.check()?,
+        }
+        Ok(n)
+    }
+}
+
+pub(crate) fn cancelled() -> io::Error {
+    io::Error::other("操作已取消")
+}
@@ -3,14 +3,28 @@
 pub mod error;
 pub mod extractor;
 pub mod formats;
+mod hook;
+pub mod mount;
 pub mod path_utils;
 pub mod smart_dest;
 
 pub mod compress;
 
-pub use compress::{compress_to_zip, compress_to_7z, CompressOptions};
+#[cfg(feature = "async")]
+pub mod async_api;
+
+pub use compress::{
+    compress_single, compress_single_many, compress_to_tar, compress_to_zip, compress_to_7z, convert_archive, delete_entries,
+    diff_archives, grep_archives, hash_entries, parse_manifest, rename_entry, set_zip_comment, update_zip,
+    verify_manifest, write_manifest, ArchiveDiff, ChangeKind, CompressOptions, CompressionPreset, DiffEntry,
+    DiffEntryInfo, DiffEntryKind, EntryHash, GrepMatch, GrepOptions, GrepSummary, HashAlgorithm, SymlinkPolicy,
+    UpdateMode, UpdateSummary, VerifyReport, ZipEncryption, ZipMethod,
+};
+#[cfg(feature = "async")]
+pub use async_api::{compress_async, extract_async, AsyncTask, Progress};
 pub use error::{FastZipError, Result};
-pub use extractor::{extract_many, extract_one, ExtractOptions};
-pub use formats::{detect_format, ArchiveFormat};
+pub use extractor::{extract_many, extract_one, extract_with_hashes, ExtractOptions};
+pub use formats::{detect_format, open_entry, open_entry_path, ArchiveFormat, Codec, EntryReader};
+pub use mount::{mount_archive, unmount, MountOptions};
 pub use path_utils::normalize_entry_path;
-pub use smart_dest::{list_archive_top_level, resolve_smart_dest, TopLevelEntries};
+pub use smart_dest::{extract_staged, list_archive_top_level, resolve_smart_dest, TopLevelEntries};
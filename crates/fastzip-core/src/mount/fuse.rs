@@ -0,0 +1,500 @@
+//! FUSE 内核协议的只读实现：直接读写 `/dev/fuse`，单线程逐个处理请求
+
+use std::ffi::{CString, OsStr};
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd, RawFd};
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::OpenOptionsExt;
+use std::os::unix::thread::JoinHandleExt;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use super::{ArchiveFs, NodeKind};
+use crate::error::{FastZipError, Result};
+
+/// 单次读取的最大字节数；请求缓冲区需再留出请求头的空间
+const MAX_READ: usize = 128 * 1024;
+const BUFFER_SIZE: usize = MAX_READ + 4096;
+
+/// 属性与目录项的缓存时间（内容不会变化）
+const TTL_SECS: u64 = 3600;
+
+const FUSE_KERNEL_VERSION: u32 = 7;
+const FUSE_KERNEL_MINOR_VERSION: u32 = 31;
+const FOPEN_KEEP_CACHE: u32 = 1 << 1;
+
+mod opcode {
+    pub const LOOKUP: u32 = 1;
+    pub const FORGET: u32 = 2;
+    pub const GETATTR: u32 = 3;
+    pub const SETATTR: u32 = 4;
+    pub const READLINK: u32 = 5;
+    pub const SYMLINK: u32 = 6;
+    pub const MKNOD: u32 = 8;
+    pub const MKDIR: u32 = 9;
+    pub const UNLINK: u32 = 10;
+    pub const RMDIR: u32 = 11;
+    pub const RENAME: u32 = 12;
+    pub const LINK: u32 = 13;
+    pub const OPEN: u32 = 14;
+    pub const READ: u32 = 15;
+    pub const WRITE: u32 = 16;
+    pub const STATFS: u32 = 17;
+    pub const RELEASE: u32 = 18;
+    pub const FSYNC: u32 = 20;
+    pub const SETXATTR: u32 = 21;
+    pub const REMOVEXATTR: u32 = 24;
+    pub const FLUSH: u32 = 25;
+    pub const INIT: u32 = 26;
+    pub const OPENDIR: u32 = 27;
+    pub const READDIR: u32 = 28;
+    pub const RELEASEDIR: u32 = 29;
+    pub const FSYNCDIR: u32 = 30;
+    pub const ACCESS: u32 = 34;
+    pub const CREATE: u32 = 35;
+    pub const INTERRUPT: u32 = 36;
+    pub const DESTROY: u32 = 38;
+    pub const BATCH_FORGET: u32 = 42;
+    pub const FALLOCATE: u32 = 43;
+    pub const RENAME2: u32 = 45;
+}
+
+/// 挂载并处理请求，直到被卸载或收到终止信号
+pub(super) fn serve(mut fs: ArchiveFs, archive: &Path, mountpoint: &Path, allow_other: bool) -> Result<()> {
+    if !mountpoint.is_dir() {
+        return Err(FastZipError::Other(format!("挂载点不是目录: {}", mountpoint.display())));
+    }
+    let fsname = archive.file_name().map_or_else(|| "fastzip".into(), |n| n.to_string_lossy().replace(',', "_"));
+    let device = mount(mountpoint, &fsname, allow_other)?;
+    tracing::info!(archive = %archive.display(), mountpoint = %mountpoint.display(), "已挂载");
+
+    let watcher = SignalWatcher::start(mountpoint.to_path_buf());
+    let result = Session { device, uid: unsafe { libc::getuid() }, gid: unsafe { libc::getgid() } }.run(&mut fs);
+    watcher.stop();
+    if result.is_err() {
+        let _ = unmount(mountpoint);
+    }
+    result
+}
+
+/// 卸载挂载点：root 直接卸载，否则交给 fusermount
+pub(super) fn unmount(mountpoint: &Path) -> Result<()> {
+    let target = c_path(mountpoint)?;
+    // SAFETY: target 为有效的 C 字符串
+    if unsafe { libc::umount2(target.as_ptr(), libc::MNT_DETACH) } == 0 {
+        return Ok(());
+    }
+    let err = io::Error::last_os_error();
+    if err.raw_os_error() != Some(libc::EPERM) {
+        return Err(err.into());
+    }
+    for program in ["fusermount3", "fusermount"] {
+        if let Ok(status) = Command::new(program).arg("-u").arg("-z").arg("--").arg(mountpoint).status() {
+            return match status.success() {
+                true => Ok(()),
+                false => Err(FastZipError::Other(format!("{} 卸载失败: {}", program, mountpoint.display()))),
+            };
+        }
+    }
+    Err(err.into())
+}
+
+/// 打开 `/dev/fuse` 并挂载；没有权限时改用 fusermount
+fn mount(mountpoint: &Path, fsname: &str, allow_other: bool) -> Result<File> {
+    let device = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .custom_flags(libc::O_CLOEXEC)
+        .open("/dev/fuse")
+        .map_err(|e| FastZipError::Other(format!("无法打开 /dev/fuse（需要 FUSE 支持）: {}", e)))?;
+    // SAFETY: getuid/getgid 总是成功
+    let (uid, gid) = unsafe { (libc::getuid(), libc::getgid()) };
+    let mut data =
+        format!("fd={},rootmode=40000,user_id={},group_id={},default_permissions", device.as_raw_fd(), uid, gid);
+    if allow_other {
+        data.push_str(",allow_other");
+    }
+    let source = CString::new(fsname).map_err(|_| FastZipError::Other("归档名无效".into()))?;
+    let target = c_path(mountpoint)?;
+    let data = CString::new(data).expect("挂载参数不含 NUL");
+    // SAFETY: 所有指针均指向有效的 C 字符串
+    let rc = unsafe {
+        libc::mount(
+            source.as_ptr(),
+            target.as_ptr(),
+            c"fuse.fastzip".as_ptr(),
+            libc::MS_RDONLY | libc::MS_NOSUID | libc::MS_NODEV,
+            data.as_ptr().cast(),
+        )
+    };
+    if rc == 0 {
+        return Ok(device);
+    }
+    let err = io::Error::last_os_error();
+    if err.raw_os_error() != Some(libc::EPERM) {
+        return Err(FastZipError::Other(format!("挂载失败: {}", err)));
+    }
+    drop(device);
+    mount_with_fusermount(mountpoint, fsname, allow_other)
+}
+
+/// 经 setuid 的 fusermount 挂载：通过 `_FUSE_COMMFD` 指定的 Unix 套接字取回 `/dev/fuse` 的描述符
+fn mount_with_fusermount(mountpoint: &Path, fsname: &str, allow_other: bool) -> Result<File> {
+    let mut fds = [0 as RawFd; 2];
+    // SAFETY: fds 可写入两个描述符
+    if unsafe { libc::socketpair(libc::AF_UNIX, libc::SOCK_STREAM, 0, fds.as_mut_ptr()) } != 0 {
+        return Err(io::Error::last_os_error().into());
+    }
+    // SAFETY: socketpair 成功后两个描述符归本函数所有
+    let (local, remote) = unsafe { (OwnedFd::from_raw_fd(fds[0]), OwnedFd::from_raw_fd(fds[1])) };
+    // SAFETY: local 为有效描述符；子进程只应继承 remote
+    unsafe { libc::fcntl(local.as_raw_fd(), libc::F_SETFD, libc::FD_CLOEXEC) };
+
+    let mut options = format!("ro,nosuid,nodev,default_permissions,fsname={},subtype=fastzip", fsname);
+    if allow_other {
+        options.push_str(",allow_other");
+    }
+    let mut status = None;
+    for program in ["fusermount3", "fusermount"] {
+        let result = Command::new(program)
+            .env("_FUSE_COMMFD", remote.as_raw_fd().to_string())
+            .arg("-o")
+            .arg(&options)
+            .arg("--")
+            .arg(mountpoint)
+            .status();
+        if let Ok(s) = result {
+            status = Some((program, s));
+            break;
+        }
+    }
+    drop(remote);
+    match status {
+        None => return Err(FastZipError::Other("没有挂载权限，且未找到 fusermount3/fusermount".into())),
+        Some((program, s)) if !s.success() => {
+            return Err(FastZipError::Other(format!("{} 挂载失败: {}", program, mountpoint.display())))
+        }
+        Some(_) => {}
+    }
+    receive_fd(&local).map(File::from)
+}
+
+/// 从套接字接收一个 SCM_RIGHTS 描述符
+fn receive_fd(socket: &OwnedFd) -> Result<OwnedFd> {
+    let mut byte = [0u8; 1];
+    let mut iov = libc::iovec { iov_base: byte.as_mut_ptr().cast(), iov_len: 1 };
+    // SAFETY: CMSG_SPACE 只做长度计算
+    let space = unsafe { libc::CMSG_SPACE(std::mem::size_of::<RawFd>() as u32) } as usize;
+    let mut control = vec![0u8; space];
+    // SAFETY: msghdr 全零是有效的初始值
+    let mut msg: libc::msghdr = unsafe { std::mem::zeroed() };
+    msg.msg_iov = &mut iov;
+    msg.msg_iovlen = 1;
+    msg.msg_control = control.as_mut_ptr().cast();
+    msg.msg_controllen = space as _;
+    // SAFETY: msg 中的缓冲区在调用期间有效
+    if unsafe { libc::recvmsg(socket.as_raw_fd(), &mut msg, 0) } < 0 {
+        return Err(io::Error::last_os_error().into());
+    }
+    // SAFETY: recvmsg 已填好 msg，CMSG_* 只在其控制缓冲区内访问
+    unsafe {
+        let cmsg = libc::CMSG_FIRSTHDR(&msg);
+        if cmsg.is_null() || (*cmsg).cmsg_level != libc::SOL_SOCKET || (*cmsg).cmsg_type != libc::SCM_RIGHTS {
+            return Err(FastZipError::Other("fusermount 未返回 /dev/fuse 描述符".into()));
+        }
+        let fd = std::ptr::read_unaligned(libc::CMSG_DATA(cmsg).cast::<RawFd>());
+        libc::fcntl(fd, libc::F_SETFD, libc::FD_CLOEXEC);
+        Ok(OwnedFd::from_raw_fd(fd))
+    }
+}
+
+fn c_path(path: &Path) -> Result<CString> {
+    CString::new(path.as_os_str().as_bytes()).map_err(|_| FastZipError::Other(format!("路径无效: {}", path.display())))
+}
+
+/// 在独立线程中等待 SIGINT/SIGTERM/SIGHUP，收到后卸载挂载点
+struct SignalWatcher {
+    thread: std::thread::JoinHandle<()>,
+    done: Arc<AtomicBool>,
+    previous: libc::sigset_t,
+}
+
+impl SignalWatcher {
+    fn start(mountpoint: PathBuf) -> Self {
+        // SAFETY: 信号集只在本函数内初始化和使用
+        let (set, previous) = unsafe {
+            let mut set: libc::sigset_t = std::mem::zeroed();
+            libc::sigemptyset(&mut set);
+            for signal in [libc::SIGINT, libc::SIGTERM, libc::SIGHUP] {
+                libc::sigaddset(&mut set, signal);
+            }
+            let mut previous: libc::sigset_t = std::mem::zeroed();
+            // 在当前线程屏蔽，之后创建的等待线程继承该屏蔽字，由 sigwait 同步接收
+            libc::pthread_sigmask(libc::SIG_BLOCK, &set, &mut previous);
+            (set, previous)
+        };
+        let done = Arc::new(AtomicBool::new(false));
+        let stop = Arc::clone(&done);
+        let thread = std::thread::spawn(move || {
+            let mut signal = 0;
+            // SAFETY: set 为已初始化的信号集
+            unsafe { libc::sigwait(&set, &mut signal) };
+            if !stop.load(Ordering::SeqCst) {
+                tracing::info!(mountpoint = %mountpoint.display(), "收到信号，正在卸载");
+                if let Err(e) = unmount(&mountpoint) {
+                    tracing::warn!(error = %e, "卸载失败");
+                }
+            }
+        });
+        Self { thread, done, previous }
+    }
+
+    /// 结束等待线程并恢复信号屏蔽字
+    fn stop(self) {
+        self.done.store(true, Ordering::SeqCst);
+        if !self.thread.is_finished() {
+            // SAFETY: 线程仍在运行，pthread_t 有效；该信号被其屏蔽，只会唤醒 sigwait
+            unsafe { libc::pthread_kill(self.thread.as_pthread_t(), libc::SIGTERM) };
+        }
+        let _ = self.thread.join();
+        // SAFETY: previous 为此前保存的屏蔽字
+        unsafe { libc::pthread_sigmask(libc::SIG_SETMASK, &self.previous, std::ptr::null_mut()) };
+    }
+}
+
+struct Session {
+    device: File,
+    uid: u32,
+    gid: u32,
+}
+
+/// 请求头之后的参数
+struct Args<'a>(&'a [u8]);
+
+impl Args<'_> {
+    fn u32(&self, at: usize) -> u32 {
+        self.0.get(at..at + 4).map_or(0, |b| u32::from_le_bytes(b.try_into().expect("4 字节")))
+    }
+
+    fn u64(&self, at: usize) -> u64 {
+        self.0.get(at..at + 8).map_or(0, |b| u64::from_le_bytes(b.try_into().expect("8 字节")))
+    }
+
+    /// 以 NUL 结尾的名称
+    fn name(&self) -> &OsStr {
+        let end = self.0.iter().position(|&b| b == 0).unwrap_or(self.0.len());
+        OsStr::from_bytes(&self.0[..end])
+    }
+}
+
+/// 回复内容
+#[derive(Default)]
+struct Out(Vec<u8>);
+
+impl Out {
+    fn u32(&mut self, v: u32) -> &mut Self {
+        self.0.extend_from_slice(&v.to_le_bytes());
+        self
+    }
+
+    fn u64(&mut self, v: u64) -> &mut Self {
+        self.0.extend_from_slice(&v.to_le_bytes());
+        self
+    }
+}
+
+impl Session {
+    fn run(&mut self, fs: &mut ArchiveFs) -> Result<()> {
+        let mut buf = vec![0u8; BUFFER_SIZE];
+        loop {
+            let n = match self.device.read(&mut buf) {
+                Ok(n) => n,
+                // 已卸载
+                Err(e) if e.raw_os_error() == Some(libc::ENODEV) => return Ok(()),
+                // 请求被中断或暂无请求
+                Err(e) if matches!(e.raw_os_error(), Some(libc::ENOENT | libc::EINTR | libc::EAGAIN)) => continue,
+                Err(e) => return Err(e.into()),
+            };
+            if n < 40 {
+                continue;
+            }
+            let header = Args(&buf[..40]);
+            let (op, unique, ino) = (header.u32(4), header.u64(8), header.u64(16));
+            let args = Args(&buf[40..n]);
+            let reply = self.dispatch(fs, op, ino, &args);
+            match reply {
+                Some(Ok(out)) => self.send(unique, 0, &out.0)?,
+                Some(Err(errno)) => self.send(unique, -errno, &[])?,
+                None => {}
+            }
+            if op == opcode::DESTROY {
+                return Ok(());
+            }
+        }
+    }
+
+    /// 处理一个请求；返回 None 表示不回复
+    fn dispatch(&self, fs: &mut ArchiveFs, op: u32, ino: u64, args: &Args) -> Option<std::result::Result<Out, i32>> {
+        use opcode::*;
+        let mut out = Out::default();
+        let result = match op {
+            INIT => {
+                let (major, minor, readahead) = (args.u32(0), args.u32(4), args.u32(8));
+                if major < FUSE_KERNEL_VERSION {
+                    return Some(Err(libc::EPROTO));
+                }
+                let minor = minor.min(FUSE_KERNEL_MINOR_VERSION);
+                out.u32(FUSE_KERNEL_VERSION).u32(minor).u32(readahead).u32(0);
+                // max_background、congestion_threshold、max_write、time_gran
+                out.0.extend_from_slice(&16u16.to_le_bytes());
+                out.0.extend_from_slice(&12u16.to_le_bytes());
+                out.u32(MAX_READ as u32).u32(1);
+                // 7.23 之前的内核只接受 24 字节的回复
+                out.0.resize(if minor < 23 { 24 } else { 64 }, 0);
+                Ok(())
+            }
+            LOOKUP => match args.name().to_str().and_then(|name| fs.lookup(ino, name)) {
+                Some(child) => {
+                    self.entry(fs, child, &mut out);
+                    Ok(())
+                }
+                None => Err(libc::ENOENT),
+            },
+            FORGET | BATCH_FORGET | INTERRUPT => return None,
+            GETATTR => match fs.node(ino) {
+                Some(_) => {
+                    out.u64(TTL_SECS).u32(0).u32(0);
+                    self.attr(fs, ino, &mut out);
+                    Ok(())
+                }
+                None => Err(libc::ENOENT),
+            },
+            READLINK => match fs.node(ino).map(|n| &n.kind) {
+                Some(NodeKind::Symlink(target)) => {
+                    out.0.extend_from_slice(target.as_bytes());
+                    Ok(())
+                }
+                _ => Err(libc::EINVAL),
+            },
+            OPEN => {
+                if args.u32(0) as i32 & libc::O_ACCMODE != libc::O_RDONLY {
+                    return Some(Err(libc::EROFS));
+                }
+                match fs.open_file(ino) {
+                    Ok(fh) => {
+                        out.u64(fh).u32(FOPEN_KEEP_CACHE).u32(0);
+                        Ok(())
+                    }
+                    Err(e) => Err(errno(&e)),
+                }
+            }
+            READ => match fs.read(args.u64(0), args.u64(8), (args.u32(16) as usize).min(MAX_READ)) {
+                Ok(data) => {
+                    out.0 = data;
+                    Ok(())
+                }
+                Err(e) => Err(errno(&e)),
+            },
+            RELEASE => {
+                fs.release(args.u64(0));
+                Ok(())
+            }
+            OPENDIR => match fs.node(ino).map(|n| &n.kind) {
+                Some(NodeKind::Dir(_)) => {
+                    out.u64(0).u32(0).u32(0);
+                    Ok(())
+                }
+                Some(_) => Err(libc::ENOTDIR),
+                None => Err(libc::ENOENT),
+            },
+            READDIR => {
+                self.readdir(fs, ino, args.u64(8), args.u32(16) as usize, &mut out);
+                Ok(())
+            }
+            STATFS => {
+                let blocks = fs.total_size().div_ceil(4096);
+                out.u64(blocks).u64(0).u64(0).u64(fs.len() as u64).u64(0);
+                // bsize、namelen、frsize、padding 与 6 个保留字段
+                out.u32(4096).u32(255).u32(4096).u32(0);
+                out.0.resize(80, 0);
+                Ok(())
+            }
+            RELEASEDIR | FLUSH | FSYNC | FSYNCDIR | ACCESS | DESTROY => Ok(()),
+            SETATTR | SYMLINK | MKNOD | MKDIR | UNLINK | RMDIR | RENAME | LINK | WRITE | SETXATTR | REMOVEXATTR
+            | CREATE | FALLOCATE | RENAME2 => Err(libc::EROFS),
+            _ => Err(libc::ENOSYS),
+        };
+        Some(result.map(|()| out))
+    }
+
+    /// fuse_entry_out
+    fn entry(&self, fs: &ArchiveFs, ino: u64, out: &mut Out) {
+        out.u64(ino).u64(0).u64(TTL_SECS).u64(TTL_SECS).u32(0).u32(0);
+        self.attr(fs, ino, out);
+    }
+
+    /// fuse_attr
+    fn attr(&self, fs: &ArchiveFs, ino: u64, out: &mut Out) {
+        let node = fs.node(ino).expect("调用方已检查节点存在");
+        let (kind, nlink) = match node.kind {
+            NodeKind::Dir(_) => (libc::S_IFDIR, 2),
+            NodeKind::File(_) => (libc::S_IFREG, 1),
+            NodeKind::Symlink(_) => (libc::S_IFLNK, 1),
+        };
+        let mtime = node.mtime.max(0) as u64;
+        out.u64(ino).u64(node.size).u64(node.size.div_ceil(512));
+        out.u64(mtime).u64(mtime).u64(mtime).u32(0).u32(0).u32(0);
+        out.u32(kind | node.mode).u32(nlink).u32(self.uid).u32(self.gid).u32(0).u32(4096).u32(0);
+    }
+
+    /// 从第 `offset` 项开始填入 fuse_dirent，直到超过 `size`
+    fn readdir(&self, fs: &ArchiveFs, ino: u64, offset: u64, size: usize, out: &mut Out) {
+        let Some(node) = fs.node(ino) else {
+            return;
+        };
+        let NodeKind::Dir(children) = &node.kind else {
+            return;
+        };
+        let entries = [(".", ino), ("..", node.parent)]
+            .into_iter()
+            .chain(children.iter().map(|(name, &child)| (name.as_str(), child)));
+        for (i, (name, child)) in entries.enumerate().skip(offset as usize) {
+            let len = (24 + name.len()).next_multiple_of(8);
+            if out.0.len() + len > size {
+                break;
+            }
+            let kind = match fs.node(child).map(|n| &n.kind) {
+                Some(NodeKind::Dir(_)) => libc::DT_DIR,
+                Some(NodeKind::Symlink(_)) => libc::DT_LNK,
+                _ => libc::DT_REG,
+            };
+            out.u64(child).u64(i as u64 + 1).u32(name.len() as u32).u32(kind as u32);
+            out.0.extend_from_slice(name.as_bytes());
+            out.0.resize(out.0.len().next_multiple_of(8), 0);
+        }
+    }
+
+    fn send(&mut self, unique: u64, error: i32, payload: &[u8]) -> Result<()> {
+        let mut reply = Out::default();
+        reply.u32((16 + payload.len()) as u32).u32(error as u32).u64(unique);
+        reply.0.extend_from_slice(payload);
+        match self.device.write(&reply.0) {
+            Ok(_) => Ok(()),
+            // 请求已被中断
+            Err(e) if e.raw_os_error() == Some(libc::ENOENT) => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+fn errno(e: &FastZipError) -> i32 {
+    tracing::debug!(error = %e, "请求失败");
+    match e {
+        FastZipError::PasswordRequired => libc::EACCES,
+        FastZipError::Io(e) => e.raw_os_error().unwrap_or(libc::EIO),
+        _ => libc::EIO,
+    }
+}
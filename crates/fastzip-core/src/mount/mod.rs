@@ -0,0 +1,479 @@
+//! 只读挂载归档（Linux FUSE）
+//!
+//! 挂载时只读取目录信息建立文件树；每个打开的文件对应一个 [`EntryReader`]，内容按需读取，
+//! 定位方式与 [`open_entry`](crate::open_entry) 相同。
+
+// 文件树与读取逻辑与平台无关，只有 Linux 上由 FUSE 使用
+#![cfg_attr(not(target_os = "linux"), allow(dead_code))]
+
+#[cfg(target_os = "linux")]
+mod fuse;
+
+use std::collections::{BTreeMap, HashMap};
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use sevenz_rust::{Password, SevenZArchiveEntry, SevenZReader};
+use zip::ZipArchive;
+
+use crate::error::{FastZipError, Result};
+use crate::formats::{
+    detect_format, entry_mtime, open_zip_entry, seven_err, ArchiveFormat, ArchiveInput, EntryReader, InputReader,
+    SevenZExtractor, TarExtractor, TarIndex, ZipExtractor,
+};
+use crate::path_utils::normalize_entry_path;
+
+/// 根目录的 inode（FUSE 约定为 1）
+pub(crate) const ROOT: u64 = 1;
+
+/// 挂载选项
+#[derive(Debug, Clone, Default)]
+pub struct MountOptions {
+    /// 加密归档的密码
+    pub password: Option<String>,
+    /// 允许其他用户访问挂载点（非 root 用户需在 /etc/fuse.conf 中启用 user_allow_other）
+    pub allow_other: bool,
+}
+
+/// 把 `archive` 只读挂载到目录 `mountpoint`，阻塞直到被卸载
+///
+/// 支持 ZIP、7z 与 tar.*；tar.* 优先使用已有的 `.fzidx` 索引，否则挂载前在内存中建立。
+/// 收到 SIGINT、SIGTERM 或 SIGHUP 时卸载并返回。root 直接挂载，其他用户经 `fusermount3`/`fusermount`。
+///
+/// 请求在单个线程上逐个处理：需要整体解到缓存的条目（7z 等）打开时会阻塞其余请求直到解压完成。
+pub fn mount_archive(archive: &Path, mountpoint: &Path, options: &MountOptions) -> Result<()> {
+    #[cfg(target_os = "linux")]
+    {
+        let fs = ArchiveFs::open(archive, options.password.as_deref())?;
+        fuse::serve(fs, archive, mountpoint, options.allow_other)
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = (archive, mountpoint, options);
+        Err(FastZipError::Other("挂载仅支持 Linux".into()))
+    }
+}
+
+/// 卸载 [`mount_archive`] 挂载的目录
+pub fn unmount(mountpoint: &Path) -> Result<()> {
+    #[cfg(target_os = "linux")]
+    {
+        fuse::unmount(mountpoint)
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = mountpoint;
+        Err(FastZipError::Other("挂载仅支持 Linux".into()))
+    }
+}
+
+/// 文件树中的节点
+#[derive(Debug)]
+pub(crate) struct Node {
+    pub parent: u64,
+    pub kind: NodeKind,
+    pub size: u64,
+    /// 修改时间（Unix 秒）
+    pub mtime: i64,
+    /// 权限位（0o7777）
+    pub mode: u32,
+}
+
+#[derive(Debug)]
+pub(crate) enum NodeKind {
+    /// 子节点名 -> inode
+    Dir(BTreeMap<String, u64>),
+    File(Data),
+    Symlink(String),
+}
+
+/// 文件内容的位置
+#[derive(Debug, Clone)]
+pub(crate) enum Data {
+    Empty,
+    /// ZIP 第 `index` 个条目
+    Zip(usize),
+    /// 7z 中名为 `name` 的条目
+    SevenZ(String),
+    /// 解压后 tar 流中的数据偏移
+    Tar(u64),
+}
+
+enum Source {
+    Zip { input: ArchiveInput, zip: ZipArchive<InputReader> },
+    SevenZ(PathBuf),
+    Tar { path: PathBuf, index: Arc<TarIndex> },
+}
+
+/// 归档的只读文件系统视图（与 FUSE 协议无关）
+pub(crate) struct ArchiveFs {
+    source: Source,
+    password: Option<String>,
+    /// 下标为 inode - 1
+    nodes: Vec<Node>,
+    /// 句柄 -> 条目读取器
+    handles: HashMap<u64, EntryReader>,
+    next_handle: u64,
+}
+
+impl ArchiveFs {
+    /// 读取归档目录建立文件树
+    pub fn open(archive: &Path, password: Option<&str>) -> Result<Self> {
+        let format = detect_format(archive)?;
+        let mtime = std::fs::metadata(archive)?
+            .modified()
+            .ok()
+            .and_then(|t| t.duration_since(SystemTime::UNIX_EPOCH).ok())
+            .map_or(0, |d| d.as_secs() as i64);
+        let root = Node { parent: ROOT, kind: NodeKind::Dir(BTreeMap::new()), size: 0, mtime, mode: 0o755 };
+        let mut nodes = vec![root];
+
+        let source = match format {
+            ArchiveFormat::Zip => {
+                let input = ArchiveInput::open(archive)?;
+                let mut zip = ZipArchive::new(input.reader()?)?;
+                for i in 0..zip.len() {
+                    let file = zip.by_index_raw(i)?;
+                    let (name, size) = (file.name().to_string(), file.size());
+                    let time = entry_mtime(&file).unwrap_or(mtime);
+                    let mode = file.unix_mode().map(|m| m & 0o7777);
+                    let kind = if file.is_dir() {
+                        NodeKind::Dir(BTreeMap::new())
+                    } else if file.is_symlink() {
+                        drop(file);
+                        let mut target = String::new();
+                        open_zip_entry(&mut zip, i, password)?.read_to_string(&mut target)?;
+                        NodeKind::Symlink(target)
+                    } else if size == 0 {
+                        NodeKind::File(Data::Empty)
+                    } else {
+                        NodeKind::File(Data::Zip(i))
+                    };
+                    insert(&mut nodes, &name, kind, size, time, mode);
+                }
+                Source::Zip { input, zip }
+            }
+            ArchiveFormat::SevenZ => {
+                let input = ArchiveInput::open(archive)?;
+                let password = password.map_or_else(Password::empty, Password::from);
+                let mut reader = SevenZReader::new(input.reader()?, input.size()?, password).map_err(seven_err)?;
+                // p7zip 约定：属性 bit 15 表示高 16 位为 Unix 模式
+                let unix = |entry: &SevenZArchiveEntry| {
+                    (entry.has_windows_attributes && entry.windows_attributes & 0x8000 != 0)
+                        .then_some(entry.windows_attributes >> 16)
+                };
+                let is_link = |entry: &SevenZArchiveEntry| unix(entry).is_some_and(|m| m & 0o170000 == 0o120000);
+                // 链接目标存放在条目内容中，需解码一遍
+                let mut targets = HashMap::new();
+                if reader.archive().files.iter().any(is_link) {
+                    reader
+                        .for_each_entries(|entry, data| {
+                            let mut content = Vec::new();
+                            data.read_to_end(&mut content)?;
+                            if is_link(entry) {
+                                targets.insert(entry.name.clone(), String::from_utf8_lossy(&content).into_owned());
+                            }
+                            Ok(true)
+                        })
+                        .map_err(seven_err)?;
+                }
+                for entry in &reader.archive().files {
+                    let unix = unix(entry);
+                    let kind = if entry.is_directory {
+                        NodeKind::Dir(BTreeMap::new())
+                    } else if let Some(target) = targets.remove(&entry.name) {
+                        NodeKind::Symlink(target)
+                    } else if entry.size == 0 {
+                        NodeKind::File(Data::Empty)
+                    } else {
+                        NodeKind::File(Data::SevenZ(entry.name.clone()))
+                    };
+                    let time = entry.has_last_modified_date.then(|| entry.last_modified_date.to_unix_time());
+                    insert(&mut nodes, &entry.name, kind, entry.size, time.unwrap_or(mtime), unix.map(|m| m & 0o7777));
+                }
+                Source::SevenZ(archive.to_path_buf())
+            }
+            format if format.is_tar() => {
+                let index = match TarIndex::load(archive, format)? {
+                    Some(index) => index,
+                    None => TarIndex::build(archive, format)?,
+                };
+                for entry in &index.entries {
+                    let (kind, size) = match entry.entry_type {
+                        b'5' => (NodeKind::Dir(BTreeMap::new()), 0),
+                        b'2' => (NodeKind::Symlink(entry.link_name.clone().unwrap_or_default()), 0),
+                        // 硬链接共用先前条目的数据
+                        b'1' => match entry.link_name.as_deref().and_then(|link| index.find(link)) {
+                            Some(target) if target.size == 0 => (NodeKind::File(Data::Empty), 0),
+                            Some(target) => (NodeKind::File(Data::Tar(target.data_offset)), target.size),
+                            None => continue,
+                        },
+                        b'0' | b'\0' | b'7' if entry.size == 0 => (NodeKind::File(Data::Empty), 0),
+                        b'0' | b'\0' | b'7' => (NodeKind::File(Data::Tar(entry.data_offset)), entry.size),
+                        _ => continue,
+                    };
+                    insert(&mut nodes, &entry.path, kind, size, entry.mtime as i64, Some(entry.mode & 0o7777));
+                }
+                Source::Tar { path: archive.to_path_buf(), index: Arc::new(index) }
+            }
+            format => return Err(FastZipError::UnsupportedFormat(format!("{:?} 不支持挂载", format))),
+        };
+
+        Ok(Self {
+            source,
+            password: password.map(String::from),
+            nodes,
+            handles: HashMap::new(),
+            next_handle: 1,
+        })
+    }
+
+    pub fn node(&self, ino: u64) -> Option<&Node> {
+        ino.checked_sub(1).and_then(|i| self.nodes.get(i as usize))
+    }
+
+    /// 节点总数
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// 所有文件内容的字节数
+    pub fn total_size(&self) -> u64 {
+        self.nodes.iter().filter(|n| matches!(n.kind, NodeKind::File(_))).map(|n| n.size).sum()
+    }
+
+    pub fn lookup(&self, parent: u64, name: &str) -> Option<u64> {
+        match &self.node(parent)?.kind {
+            NodeKind::Dir(children) => children.get(name).copied(),
+            _ => None,
+        }
+    }
+
+    /// 打开文件，返回句柄
+    ///
+    /// 需要整体解到缓存的条目（7z、加密或其他压缩方法的 ZIP）在此解压，每个句柄各自缓存。
+    pub fn open_file(&mut self, ino: u64) -> Result<u64> {
+        let (data, size) = match self.node(ino) {
+            Some(Node { kind: NodeKind::File(data), size, .. }) => (data.clone(), *size),
+            _ => return Err(FastZipError::Other(format!("inode {} 不是文件", ino))),
+        };
+        let password = self.password.as_deref();
+        let reader = match (&mut self.source, &data) {
+            (_, Data::Empty) => EntryReader::cached(&mut io::empty())?,
+            (Source::Zip { input, zip }, Data::Zip(index)) => ZipExtractor::open_index(input, zip, *index, password)?,
+            (Source::SevenZ(path), Data::SevenZ(name)) => SevenZExtractor::open_entry(path, name, password)?,
+            (Source::Tar { path, index }, Data::Tar(offset)) => {
+                TarExtractor::open_indexed(path, index.clone(), *offset, size)?
+            }
+            _ => return Err(FastZipError::Other("条目与归档格式不符".into())),
+        };
+        let fh = self.next_handle;
+        self.next_handle += 1;
+        self.handles.insert(fh, reader);
+        Ok(fh)
+    }
+
+    /// 从句柄 `fh` 的 `offset` 处读取最多 `size` 字节
+    pub fn read(&mut self, fh: u64, offset: u64, size: usize) -> Result<Vec<u8>> {
+        let reader = self.handles.get_mut(&fh).ok_or_else(|| FastZipError::Other("无效的句柄".into()))?;
+        let len = reader.len().saturating_sub(offset).min(size as u64) as usize;
+        let mut buf = vec![0u8; len];
+        reader.seek(SeekFrom::Start(offset))?;
+        let n = read_full(reader, &mut buf)?;
+        buf.truncate(n);
+        Ok(buf)
+    }
+
+    /// 关闭句柄，释放其读取器与缓存
+    pub fn release(&mut self, fh: u64) {
+        self.handles.remove(&fh);
+    }
+}
+
+/// 把条目加入文件树，按需补齐上级目录；同名条目以后出现的为准
+fn insert(nodes: &mut Vec<Node>, name: &str, kind: NodeKind, size: u64, mtime: i64, mode: Option<u32>) {
+    let path = normalize_entry_path(&name.replace('\\', "/"));
+    let parts: Vec<&str> = path
+        .components()
+        .filter_map(|c| match c {
+            Component::Normal(s) => s.to_str(),
+            _ => None,
+        })
+        .collect();
+    let Some((leaf, dirs)) = parts.split_last() else {
+        return;
+    };
+    let mut parent = ROOT;
+    for dir in dirs {
+        parent = match child(nodes, parent, dir) {
+            Some(ino) if matches!(nodes[ino as usize - 1].kind, NodeKind::Dir(_)) => ino,
+            // 同名的文件被目录取代
+            Some(ino) => {
+                nodes[ino as usize - 1].kind = NodeKind::Dir(BTreeMap::new());
+                ino
+            }
+            None => {
+                let dir_node = Node { parent, kind: NodeKind::Dir(BTreeMap::new()), size: 0, mtime, mode: 0o755 };
+                add_child(nodes, parent, dir, dir_node)
+            }
+        };
+    }
+    let default_mode = match kind {
+        NodeKind::Dir(_) => 0o755,
+        NodeKind::File(_) => 0o644,
+        NodeKind::Symlink(_) => 0o777,
+    };
+    let size = match &kind {
+        NodeKind::Symlink(target) => target.len() as u64,
+        _ => size,
+    };
+    let node = Node { parent, kind, size, mtime, mode: mode.filter(|&m| m != 0).unwrap_or(default_mode) };
+    match child(nodes, parent, leaf) {
+        Some(ino) => {
+            let existing = &mut nodes[ino as usize - 1];
+            match (&existing.kind, &node.kind) {
+                // 已有目录保留其子节点，只更新属性
+                (NodeKind::Dir(_), NodeKind::Dir(_)) => {
+                    existing.mtime = node.mtime;
+                    existing.mode = node.mode;
+                }
+                (NodeKind::Dir(children), _) if !children.is_empty() => {}
+                _ => *existing = node,
+            }
+        }
+        None => {
+            add_child(nodes, parent, leaf, node);
+        }
+    }
+}
+
+fn child(nodes: &[Node], parent: u64, name: &str) -> Option<u64> {
+    match &nodes[parent as usize - 1].kind {
+        NodeKind::Dir(children) => children.get(name).copied(),
+        _ => None,
+    }
+}
+
+fn add_child(nodes: &mut Vec<Node>, parent: u64, name: &str, node: Node) -> u64 {
+    nodes.push(node);
+    let ino = nodes.len() as u64;
+    if let NodeKind::Dir(children) = &mut nodes[parent as usize - 1].kind {
+        children.insert(name.to_string(), ino);
+    }
+    ino
+}
+
+/// 读满 `buf` 或读到末尾，返回读到的字节数
+fn read_full(reader: &mut dyn Read, buf: &mut [u8]) -> io::Result<usize> {
+    let mut n = 0;
+    while n < buf.len() {
+        match reader.read(&mut buf[n..]) {
+            Ok(0) => break,
+            Ok(read) => n += read,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(n)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file() -> NodeKind {
+        NodeKind::File(Data::Empty)
+    }
+
+    #[test]
+    fn test_insert_builds_tree() {
+        let root = Node { parent: ROOT, kind: NodeKind::Dir(BTreeMap::new()), size: 0, mtime: 0, mode: 0o755 };
+        let mut nodes = vec![root];
+        insert(&mut nodes, "a/b/c.txt", file(), 3, 10, Some(0o600));
+        insert(&mut nodes, "./a/", NodeKind::Dir(BTreeMap::new()), 0, 20, Some(0o700));
+        insert(&mut nodes, "../../evil", file(), 1, 0, None);
+        insert(&mut nodes, "a\\win.txt", file(), 1, 0, Some(0));
+        insert(&mut nodes, "/", NodeKind::Dir(BTreeMap::new()), 0, 0, None);
+
+        let a = child(&nodes, ROOT, "a").unwrap();
+        assert_eq!((nodes[a as usize - 1].mtime, nodes[a as usize - 1].mode), (20, 0o700));
+        let b = child(&nodes, a, "b").unwrap();
+        assert_eq!(nodes[b as usize - 1].mode, 0o755);
+        let c = child(&nodes, b, "c.txt").unwrap();
+        assert_eq!((nodes[c as usize - 1].parent, nodes[c as usize - 1].mode), (b, 0o600));
+        // `..` 不会越出根目录，反斜杠按分隔符处理，权限为 0 时用默认值
+        assert!(child(&nodes, ROOT, "evil").is_some());
+        let win = child(&nodes, a, "win.txt").unwrap();
+        assert_eq!(nodes[win as usize - 1].mode, 0o644);
+        assert_eq!(nodes.len(), 6);
+    }
+
+    /// 按路径查找 inode
+    fn resolve(fs: &ArchiveFs, path: &str) -> u64 {
+        path.split('/').fold(ROOT, |ino, name| fs.lookup(ino, name).unwrap_or_else(|| panic!("{path}")))
+    }
+
+    #[test]
+    fn test_read_formats() {
+        use crate::compress::{compress_to_7z, compress_to_tar, compress_to_zip, CompressOptions, ZipMethod};
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let src = dir.path().join("src");
+        std::fs::create_dir_all(src.join("sub")).unwrap();
+        let big: Vec<u8> = (0..300_000u32).flat_map(|i| i.to_le_bytes()).collect();
+        std::fs::write(src.join("big.bin"), &big).unwrap();
+        std::fs::write(src.join("sub/small.txt"), "small").unwrap();
+        std::fs::write(src.join("empty"), "").unwrap();
+
+        let deflate = CompressOptions { method: ZipMethod::Deflate, ..Default::default() };
+        let encrypted = CompressOptions { password: Some("pw".into()), ..deflate.clone() };
+        let archives = [
+            ("stored.zip", CompressOptions::default(), None),
+            ("deflate.zip", deflate, None),
+            ("zstd.zip", CompressOptions { method: ZipMethod::Zstd, ..Default::default() }, None),
+            ("secret.zip", encrypted, Some("pw")),
+            ("plain.tar", CompressOptions::default(), None),
+            ("packed.tar.zst", CompressOptions::default(), None),
+            ("packed.tar.gz", CompressOptions::default(), None),
+            ("packed.7z", CompressOptions::default(), None),
+        ];
+        for (name, options, password) in archives {
+            let archive = dir.path().join(name);
+            match name {
+                n if n.ends_with(".zip") => compress_to_zip(&[&src], &archive, &options).unwrap(),
+                n if n.ends_with(".7z") => compress_to_7z(&[&src], &archive, &options).unwrap(),
+                _ => {
+                    let codec = ArchiveFormat::from_extension(&archive).and_then(|f| f.codec());
+                    compress_to_tar(&[&src], &archive, codec, &options).unwrap()
+                }
+            }
+            let mut fs = ArchiveFs::open(&archive, password).unwrap();
+            let ino = resolve(&fs, "big.bin");
+            assert_eq!(fs.node(ino).unwrap().size, big.len() as u64, "{name}");
+            let fh = fs.open_file(ino).unwrap();
+            // 顺序读、向前跳、向回定位、越过末尾
+            let ranges = [(0, 4096), (4096, 4096), (700_000, 10_000), (10, 100), (1_199_990, 100), (2_000_000, 10)];
+            for (offset, size) in ranges {
+                let end = (offset + size).min(big.len());
+                let expected = big.get(offset..end).unwrap_or_default();
+                assert_eq!(fs.read(fh, offset as u64, size).unwrap(), expected, "{name} @{offset}");
+            }
+            let small = fs.open_file(resolve(&fs, "sub/small.txt")).unwrap();
+            assert_eq!(fs.read(small, 0, 100).unwrap(), b"small", "{name}");
+            let empty = fs.open_file(resolve(&fs, "empty")).unwrap();
+            assert!(fs.read(empty, 0, 100).unwrap().is_empty());
+            for fh in [fh, small, empty] {
+                fs.release(fh);
+            }
+            assert!(fs.handles.is_empty(), "{name}");
+        }
+
+        let secret = dir.path().join("secret.zip");
+        let mut fs = ArchiveFs::open(&secret, None).unwrap();
+        let ino = resolve(&fs, "big.bin");
+        assert!(matches!(fs.open_file(ino), Err(FastZipError::PasswordRequired)));
+        assert!(fs.open_file(ROOT).is_err());
+    }
+}
@@ -1,9 +1,11 @@
 //! 智能解压目标路径决策（参考 Bandizip Extract Here Smart）
 
+use std::fs;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, Ordering};
 
 use crate::error::Result;
-use crate::formats::{detect_format, ArchiveFormat, SevenZExtractor, TarExtractor, ZipExtractor};
+use crate::formats::{detect_format, logical_path, ArchiveFormat, SevenZExtractor, TarExtractor, ZipExtractor};
 
 #[cfg(feature = "unrar")]
 use crate::formats::RarExtractor;
@@ -15,8 +17,8 @@ pub fn list_archive_top_level(path: &Path) -> Result<(ArchiveFormat, TopLevelEnt
     Ok((format, entries))
 }
 
-/// 顶层条目信息（用于智能解压决策）
-#[derive(Debug, Clone)]
+/// 顶层条目信息（用于智能解压决策）与归档注释
+#[derive(Debug, Clone, Default)]
 pub struct TopLevelEntries {
     /// 顶层条目名称列表（文件或目录）
     pub entries: Vec<String>,
@@ -24,6 +26,12 @@ pub struct TopLevelEntries {
     pub single_root_dir: Option<String>,
     /// 是否为单文件归档（归档内只有一个文件且位于根）
     pub single_file: bool,
+    /// 归档注释（仅 ZIP）
+    ///
+    /// 7z 的注释（kComment 属性）暂不读取：读取库解析头部时跳过该属性，7z 总是为 None；tar.* 无注释。
+    pub comment: Option<String>,
+    /// 带注释的条目（仅 ZIP，7z 同上）：（条目名，注释），按归档内顺序
+    pub entry_comments: Vec<(String, String)>,
 }
 
 /// 计算智能解压的目标目录
@@ -46,7 +54,101 @@ pub fn resolve_smart_dest(archive_path: &Path, base_dir: &Path, format: ArchiveF
     }
 
     // 3. 其他情况：创建 archive_stem 文件夹，重名则 (2), (3)...
-    let stem = archive_path
+    Ok(unique_stem_dir(archive_path, base_dir))
+}
+
+/// 单遍智能解压：先由 `extract` 解压到 base_dir 下的临时目录，
+/// 再按实际写出的顶层内容决定放置位置（规则同 [`resolve_smart_dest`]），最后移动到位
+///
+/// 适用于列出条目需要完整解压一遍的流式格式（tar.*），返回最终目标目录
+pub fn extract_staged<F>(archive_path: &Path, base_dir: &Path, extract: F) -> Result<PathBuf>
+where
+    F: FnOnce(&Path) -> Result<()>,
+{
+    let staging = create_staging_dir(base_dir)?;
+    let result = extract(&staging).and_then(|()| place_staged(archive_path, base_dir, &staging));
+    if staging.exists() {
+        let _ = fs::remove_dir_all(&staging);
+    }
+    result
+}
+
+/// 在 base_dir 下创建隐藏的临时目录（与目标同一文件系统，保证可直接 rename）
+fn create_staging_dir(base_dir: &Path) -> Result<PathBuf> {
+    static NEXT: AtomicU32 = AtomicU32::new(0);
+    loop {
+        let n = NEXT.fetch_add(1, Ordering::Relaxed);
+        let dir = base_dir.join(format!(".fastzip-staging-{}-{}", std::process::id(), n));
+        match fs::create_dir(&dir) {
+            Ok(()) => return Ok(dir),
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e.into()),
+        }
+    }
+}
+
+/// 根据临时目录中的实际内容移动到最终位置
+fn place_staged(archive_path: &Path, base_dir: &Path, staging: &Path) -> Result<PathBuf> {
+    let entries = scan_top_level(staging)?;
+    if entries.single_file || entries.single_root_dir.is_some() {
+        for name in &entries.entries {
+            merge_move(&staging.join(name), &base_dir.join(name))?;
+        }
+        return Ok(base_dir.to_path_buf());
+    }
+    let dest = unique_stem_dir(archive_path, base_dir);
+    fs::rename(staging, &dest)?;
+    Ok(dest)
+}
+
+/// 从已解压的目录读取顶层条目
+fn scan_top_level(dir: &Path) -> Result<TopLevelEntries> {
+    let mut entries = Vec::new();
+    let mut is_dir = false;
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        is_dir = entry.file_type()?.is_dir();
+        entries.push(entry.file_name().to_string_lossy().into_owned());
+    }
+    entries.sort();
+
+    let single = entries.len() == 1;
+    Ok(TopLevelEntries {
+        single_root_dir: if single && is_dir { Some(entries[0].clone()) } else { None },
+        single_file: single && !is_dir,
+        entries,
+        ..Default::default()
+    })
+}
+
+/// 移动到目标位置；目标目录已存在时逐项合并，已存在的文件被覆盖（与直接解压一致）
+fn merge_move(src: &Path, dst: &Path) -> Result<()> {
+    let dst_meta = match fs::symlink_metadata(dst) {
+        Ok(m) => m,
+        Err(_) => {
+            fs::rename(src, dst)?;
+            return Ok(());
+        }
+    };
+    if fs::symlink_metadata(src)?.is_dir() && dst_meta.is_dir() {
+        for entry in fs::read_dir(src)? {
+            let entry = entry?;
+            merge_move(&entry.path(), &dst.join(entry.file_name()))?;
+        }
+        return Ok(());
+    }
+    if dst_meta.is_dir() {
+        fs::remove_dir_all(dst)?;
+    } else {
+        fs::remove_file(dst)?;
+    }
+    fs::rename(src, dst)?;
+    Ok(())
+}
+
+/// base_dir / archive_stem，重名则 (2), (3)...；分卷按所属归档命名
+fn unique_stem_dir(archive_path: &Path, base_dir: &Path) -> PathBuf {
+    let stem = logical_path(archive_path)
         .file_stem()
         .and_then(|s| s.to_str())
         .unwrap_or("extracted")
@@ -61,14 +163,14 @@ pub fn resolve_smart_dest(archive_path: &Path, base_dir: &Path, format: ArchiveF
 
     let candidate = base_dir.join(&stem);
     if !candidate.exists() {
-        return Ok(candidate);
+        return candidate;
     }
 
     let mut k = 2u32;
     loop {
         let next = base_dir.join(format!("{} ({})", stem, k));
         if !next.exists() {
-            return Ok(next);
+            return next;
         }
         k += 1;
     }
@@ -82,6 +184,7 @@ fn list_top_level_entries(path: &Path, format: ArchiveFormat) -> Result<TopLevel
             entries: vec![],
             single_root_dir: None,
             single_file: true,
+            ..Default::default()
         });
     }
 
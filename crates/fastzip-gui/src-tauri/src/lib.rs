@@ -3,8 +3,8 @@
 use std::path::PathBuf;
 
 use fastzip_core::{
-    compress_to_7z, compress_to_zip, extract_one, list_archive_top_level, ArchiveFormat,
-    CompressOptions, ExtractOptions,
+    compress_to_7z, compress_to_tar, compress_to_zip, extract_one, list_archive_top_level, ArchiveFormat,
+    CompressOptions, CompressionPreset, ExtractOptions,
 };
 use tauri::command;
 
@@ -79,6 +79,7 @@ fn extract(
         smart,
         overwrite: false,
         password,
+        ..Default::default()
     };
     let result_path = extract_one(&archive_path, &opts).map_err(|e| e.to_string())?;
     Ok(result_path.display().to_string())
@@ -90,25 +91,26 @@ fn compress(
     dest: String,
     format_zip: bool,
     recursive: bool,
+    preset: Option<String>,
+    password: Option<String>,
 ) -> Result<(), String> {
     let dest_path = PathBuf::from(&dest);
     let sources: Vec<PathBuf> = sources.into_iter().map(PathBuf::from).collect();
-    if format_zip {
-        compress_to_zip(
-            &sources,
-            &dest_path,
-            &CompressOptions {
-                recursive,
-                password: None,
-                fast: true,
-            },
-        )
-        .map_err(|e| e.to_string())
+    let mut options = match preset.as_deref().filter(|p| !p.is_empty()) {
+        Some(name) => CompressOptions::default().with_preset(
+            CompressionPreset::from_name(name).ok_or_else(|| format!("未知的压缩预设: {}", name))?,
+        ),
+        None => CompressOptions::default(),
+    };
+    options.recursive = recursive;
+    options.password = password.filter(|p| !p.is_empty());
+
+    if let Some(format) = ArchiveFormat::from_extension(&dest_path).filter(|f| f.is_tar()) {
+        compress_to_tar(&sources, &dest_path, format.codec(), &options).map_err(|e| e.to_string())
+    } else if format_zip {
+        compress_to_zip(&sources, &dest_path, &options).map_err(|e| e.to_string())
     } else {
-        if sources.len() > 1 {
-            return Err("7z 仅支持单一路径".to_string());
-        }
-        compress_to_7z(&sources[0], &dest_path).map_err(|e| e.to_string())
+        compress_to_7z(&sources, &dest_path, &options).map_err(|e| e.to_string())
     }
 }
 
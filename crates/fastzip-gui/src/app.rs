@@ -39,6 +39,8 @@ pub fn App() -> impl IntoView {
     let (compress_dest, set_compress_dest) = create_signal(String::new());
     let (compress_recursive, set_compress_recursive) = create_signal(true);
     let (compress_format_zip, set_compress_format_zip) = create_signal(true);
+    let (compress_preset, set_compress_preset) = create_signal(String::new());
+    let (compress_password, set_compress_password) = create_signal(String::new());
     
     let (status, set_status) = create_signal(String::new());
     let (status_type, set_status_type) = create_signal("info");
@@ -51,19 +53,15 @@ pub fn App() -> impl IntoView {
 
     let on_pick_file = move |_| {
         spawn_local(async move {
-            match invoke_no_args::<Option<String>>("pick_file").await {
-                Ok(Some(p)) => {
-                    set_archive_path.set(p.clone());
-                    set_status_with_type(String::new(), "info");
-                    match invoke::<_, Result<(String, Vec<String>), String>>("list_archive", (p,)).await {
-                        Ok(Ok((fmt, entries))) => {
-                            set_preview_format.set(fmt);
-                            set_preview_entries.set(entries);
-                        }
-                        _ => {}
-                    }
+            if let Ok(Some(p)) = invoke_no_args::<Option<String>>("pick_file").await {
+                set_archive_path.set(p.clone());
+                set_status_with_type(String::new(), "info");
+                if let Ok(Ok((fmt, entries))) =
+                    invoke::<_, Result<(String, Vec<String>), String>>("list_archive", (p,)).await
+                {
+                    set_preview_format.set(fmt);
+                    set_preview_entries.set(entries);
                 }
-                _ => {}
             }
         });
     };
@@ -124,9 +122,10 @@ pub fn App() -> impl IntoView {
         set_running.set(true);
         set_status_with_type("正在压缩...".to_string(), "running");
         
+        let pw = if compress_password.get().is_empty() { None } else { Some(compress_password.get()) };
         spawn_local(async move {
             let result: Result<(), String> = invoke("compress", 
-                (sources, dest, compress_format_zip.get(), compress_recursive.get())).await;
+                (sources, dest, compress_format_zip.get(), compress_recursive.get(), compress_preset.get(), pw)).await;
             set_running.set(false);
             match result {
                 Ok(()) => set_status_with_type("压缩完成".to_string(), "success"),
@@ -188,6 +187,10 @@ pub fn App() -> impl IntoView {
                             set_recursive=set_compress_recursive
                             format_zip=compress_format_zip
                             set_format_zip=set_compress_format_zip
+                            preset=compress_preset
+                            set_preset=set_compress_preset
+                            password=compress_password
+                            set_password=set_compress_password
                             on_pick_files=on_pick_files
                             on_save_file=on_save_file
                             on_compress=on_compress
@@ -343,6 +346,10 @@ fn CompressView(
     set_recursive: WriteSignal<bool>,
     format_zip: ReadSignal<bool>,
     set_format_zip: WriteSignal<bool>,
+    preset: ReadSignal<String>,
+    set_preset: WriteSignal<String>,
+    password: ReadSignal<String>,
+    set_password: WriteSignal<String>,
     on_pick_files: impl Fn(leptos::ev::MouseEvent) + 'static + Clone,
     on_save_file: impl Fn(leptos::ev::MouseEvent) + 'static + Clone,
     on_compress: impl Fn(leptos::ev::MouseEvent) + 'static + Clone,
@@ -398,6 +405,26 @@ fn CompressView(
                 </div>
             </div>
 
+            <div class="input-group">
+                <label class="input-label">"压缩级别"</label>
+                <select class="input-field" prop:value=move || preset.get()
+                    on:change=move |ev| { if let Ok(v) = event_target_select_value(&ev) { set_preset.set(v); } }>
+                    <option value="">"默认（ZIP 仅存储）"</option>
+                    <option value="store">"仅存储"</option>
+                    <option value="fastest">"最快"</option>
+                    <option value="normal">"标准"</option>
+                    <option value="max">"高压缩"</option>
+                    <option value="ultra">"极限"</option>
+                </select>
+            </div>
+
+            <div class="input-group">
+                <label class="input-label">"加密密码（可选，ZIP / 7z）"</label>
+                <input type="password" class="input-field" prop:value=password.get()
+                    on:input=move |ev| { if let Ok(v) = event_target_value(&ev) { set_password.set(v); } }
+                    placeholder="设置后使用 AES-256 加密所有文件" />
+            </div>
+
             <div class="options-grid">
                 <label class="option-item">
                     <input type="checkbox" class="option-checkbox" prop:checked=format_zip.get()
@@ -440,3 +467,9 @@ fn event_target_value(ev: &web_sys::Event) -> Result<String, JsValue> {
     let input: web_sys::HtmlInputElement = target.dyn_into()?;
     Ok(input.value())
 }
+
+fn event_target_select_value(ev: &web_sys::Event) -> Result<String, JsValue> {
+    let target = ev.target().ok_or("no target")?;
+    let select: web_sys::HtmlSelectElement = target.dyn_into()?;
+    Ok(select.value())
+}